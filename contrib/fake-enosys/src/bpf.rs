@@ -9,79 +9,182 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use anyhow::{Context, Error};
+use anyhow::Error;
+use bpfvm::seccomp::{FieldOffset, SeccompReturn};
 use syscalls::Sysno;
 
-pub(crate) fn compile_filter(
-    syscalls: impl AsRef<[Sysno]>,
-) -> Result<Vec<libc::sock_filter>, Error> {
-    use bpfvm::{
-        asm::{self, Operation::*},
-        bpf::{JmpOp::*, Mode::*, Src::*},
-        seccomp::{FieldOffset, SeccompReturn},
-    };
-
-    // Generate a very basic seccomp-bpf profile:
-    asm::compile(
-        &vec![
-            // TODO: Check that the architecture is native...
-            // load [0] (syscall number)
-            Load(ABS, FieldOffset::Syscall.offset()),
-        ]
-        .into_iter()
-        // jeq [$sysno1],[ENOSYS]
-        // jeq [$sysno2],[ENOSYS]
-        // ...
-        .chain(
-            syscalls
-                .as_ref()
-                .iter()
-                .flat_map(|sysno| Some(Jump(JEQ, sysno.id() as u32, Some("ENOSYS"), None))),
-        )
-        // ret [0]
-        // 'ENOSYS:
-        // ret [ENOSYS]
-        .chain(vec![
-            Label("ALLOW"),
-            Return(Const, SeccompReturn::Allow.into()),
-            Label("ENOSYS"),
-            Return(Const, SeccompReturn::Errno(libc::ENOSYS as u32).into()),
-        ])
-        .collect::<Vec<_>>(),
-    )
-    .context("failed to compile seccomp-bpf filter")
+/// A mask/compare predicate applied to one of a syscall's `seccomp_data.args`
+/// registers, used to only trap a syscall when its arguments match some
+/// condition (rather than unconditionally masking the whole syscall).
+///
+/// Only the low 32 bits of the argument are compared -- good enough for the
+/// small bitmask flags (such as `openat2`'s `RESOLVE_*` flags) this is
+/// intended for, but not suitable for comparing a full 64-bit argument.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ArgPredicate {
+    pub(crate) arg_index: u8,
+    pub(crate) mask: u32,
+    pub(crate) value: u32,
+    /// If `true`, the rule triggers when `(arg & mask) != value` rather than
+    /// `(arg & mask) == value`.
+    pub(crate) negate: bool,
+}
+
+/// A single `--syscall` rule: which syscall to trap, the errno to return
+/// (instead of actually running it), and an optional argument predicate that
+/// narrows the rule to only specific calls of that syscall.
+#[derive(Debug, Clone)]
+pub(crate) struct SyscallRule {
+    pub(crate) sysno: Sysno,
+    pub(crate) errno: i32,
+    pub(crate) predicate: Option<ArgPredicate>,
+}
+
+// `struct seccomp_data.args` immediately follows `instruction_pointer`, as a
+// `__u64 args[6]` array:
+//
+//   struct seccomp_data {
+//       int nr;
+//       __u32 arch;
+//       __u64 instruction_pointer;
+//       __u64 args[6];
+//   };
+//
+// bpfvm's `FieldOffset` only covers `nr`/`arch`/`instruction_pointer`, so we
+// compute the `args[N]` offsets ourselves. We only ever load the low 32-bit
+// word of a given arg (this tool is little-endian-only, like the rest of
+// this crate), which is all that's needed to test small bitmask flags.
+const ARGS_OFFSET: u32 = 16;
+
+fn arg_offset(arg_index: u8) -> u32 {
+    ARGS_OFFSET + (arg_index as u32) * 8
+}
+
+fn stmt(code: u16, k: u32) -> libc::sock_filter {
+    libc::sock_filter {
+        code,
+        jt: 0,
+        jf: 0,
+        k,
+    }
+}
+
+fn jump(code: u16, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+    libc::sock_filter { code, jt, jf, k }
+}
+
+/// Compile `rules` into a seccomp-bpf program: each rule is checked in order,
+/// and the first one whose syscall number (and, if present, argument
+/// predicate) matches causes `SECCOMP_RET_ERRNO | errno` to be returned.
+/// Syscalls that don't match any rule (or whose predicate doesn't hold) are
+/// allowed through unmodified.
+pub(crate) fn compile_filter(rules: impl AsRef<[SyscallRule]>) -> Result<Vec<libc::sock_filter>, Error> {
+    let mut prog = Vec::new();
+
+    for rule in rules.as_ref() {
+        let ret_errno: u32 = SeccompReturn::Errno(rule.errno as u32).into();
+
+        // load [0] (syscall number)
+        prog.push(stmt(
+            (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
+            FieldOffset::Syscall.offset(),
+        ));
+
+        match rule.predicate {
+            None => {
+                // jeq sysno, jt=0 (fall into ret), jf=1 (skip ret)
+                prog.push(jump(
+                    (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16,
+                    rule.sysno.id() as u32,
+                    0,
+                    1,
+                ));
+                prog.push(stmt((libc::BPF_RET | libc::BPF_K) as u16, ret_errno));
+            }
+            Some(pred) => {
+                // jeq sysno, jt=0 (check the predicate next), jf=4 (skip the
+                // 4-instruction predicate+ret block below entirely)
+                prog.push(jump(
+                    (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16,
+                    rule.sysno.id() as u32,
+                    0,
+                    4,
+                ));
+                // load args[arg_index] (low word)
+                prog.push(stmt(
+                    (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
+                    arg_offset(pred.arg_index),
+                ));
+                // A &= mask
+                prog.push(stmt(
+                    (libc::BPF_ALU | libc::BPF_AND | libc::BPF_K) as u16,
+                    pred.mask,
+                ));
+                // jeq value, {jt, jf} depend on whether this is == or !=
+                let (jt, jf) = if pred.negate {
+                    // trigger when (A & mask) != value: equal -> skip ret,
+                    // not-equal -> fall into ret.
+                    (1, 0)
+                } else {
+                    // trigger when (A & mask) == value: equal -> fall into
+                    // ret, not-equal -> skip ret.
+                    (0, 1)
+                };
+                prog.push(jump(
+                    (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16,
+                    pred.value,
+                    jt,
+                    jf,
+                ));
+                prog.push(stmt((libc::BPF_RET | libc::BPF_K) as u16, ret_errno));
+            }
+        }
+    }
+
+    // Nothing matched: allow the syscall through unmodified.
+    prog.push(stmt(
+        (libc::BPF_RET | libc::BPF_K) as u16,
+        SeccompReturn::Allow.into(),
+    ));
+
+    Ok(prog)
 }
 
 #[cfg(test)]
-#[cfg(target_arch = "x86_64")] // for SECCOMP_ARCH_NATIVE
+#[cfg(target_arch = "x86_64")] // for SECCOMP_ARCH_NATIVE and argument offsets
 mod test {
     use super::*;
 
     use anyhow::Error;
-    use bpfvm::{
-        seccomp::SeccompReturn,
-        vm::{self, BpfVM},
-    };
+    use bpfvm::vm::{self, BpfVM};
     use pretty_assertions::assert_eq;
     use syscalls::Sysno;
 
-    fn syscall_data(sysno: Sysno) -> libc::seccomp_data {
+    fn syscall_data(sysno: Sysno, args: [u64; 6]) -> libc::seccomp_data {
         libc::seccomp_data {
             nr: sysno.id(),
             arch: SECCOMP_ARCH_NATIVE,
             instruction_pointer: 0xdeadbeefcafe,
-            args: [0x0, 0x0, 0x0, 0x0, 0x0, 0x0],
+            args,
         }
     }
 
     #[cfg(target_arch = "x86_64")]
     const SECCOMP_ARCH_NATIVE: u32 = bpfvm::seccomp::AUDIT_ARCH_X86_64;
 
+    fn rule(sysno: Sysno) -> SyscallRule {
+        SyscallRule {
+            sysno,
+            errno: libc::ENOSYS,
+            predicate: None,
+        }
+    }
+
     #[test]
     fn test_single_filter_allow() -> Result<(), Error> {
-        let filter = compile_filter([Sysno::openat])?;
+        let filter = compile_filter([rule(Sysno::openat)])?;
 
-        let data = syscall_data(Sysno::link);
+        let data = syscall_data(Sysno::link, [0; 6]);
         let ret: SeccompReturn = BpfVM::new(&filter)?
             .run(vm::any_to_data(&data))?
             .try_into()?;
@@ -92,9 +195,9 @@ mod test {
 
     #[test]
     fn test_multi_filter_allow() -> Result<(), Error> {
-        let filter = compile_filter([Sysno::openat2, Sysno::statx])?;
+        let filter = compile_filter([rule(Sysno::openat2), rule(Sysno::statx)])?;
 
-        let data = syscall_data(Sysno::openat);
+        let data = syscall_data(Sysno::openat, [0; 6]);
         let ret: SeccompReturn = BpfVM::new(&filter)?
             .run(vm::any_to_data(&data))?
             .try_into()?;
@@ -105,19 +208,19 @@ mod test {
 
     #[test]
     fn test_single_filter_enosys() -> Result<(), Error> {
-        let filter = compile_filter([Sysno::openat2])?;
+        let filter = compile_filter([rule(Sysno::openat2)])?;
 
-        let data = syscall_data(Sysno::openat2);
+        let data = syscall_data(Sysno::openat2, [0; 6]);
         let ret: SeccompReturn = BpfVM::new(&filter)?
             .run(vm::any_to_data(&data))?
             .try_into()?;
         assert_eq!(
             ret,
             SeccompReturn::Errno(libc::ENOSYS as u32),
-            "errno should be ENOSYS for statx"
+            "errno should be ENOSYS for openat2"
         );
 
-        let data = syscall_data(Sysno::openat);
+        let data = syscall_data(Sysno::openat, [0; 6]);
         let ret: SeccompReturn = BpfVM::new(&filter)?
             .run(vm::any_to_data(&data))?
             .try_into()?;
@@ -128,9 +231,9 @@ mod test {
 
     #[test]
     fn test_multi_filter_enosys() -> Result<(), Error> {
-        let filter = compile_filter([Sysno::openat2, Sysno::statx])?;
+        let filter = compile_filter([rule(Sysno::openat2), rule(Sysno::statx)])?;
 
-        let data = syscall_data(Sysno::statx);
+        let data = syscall_data(Sysno::statx, [0; 6]);
         let ret: SeccompReturn = BpfVM::new(&filter)?
             .run(vm::any_to_data(&data))?
             .try_into()?;
@@ -140,7 +243,7 @@ mod test {
             "errno should be ENOSYS for statx"
         );
 
-        let data = syscall_data(Sysno::openat2);
+        let data = syscall_data(Sysno::openat2, [0; 6]);
         let ret: SeccompReturn = BpfVM::new(&filter)?
             .run(vm::any_to_data(&data))?
             .try_into()?;
@@ -152,4 +255,65 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_custom_errno() -> Result<(), Error> {
+        let filter = compile_filter([SyscallRule {
+            sysno: Sysno::openat2,
+            errno: libc::EINVAL,
+            predicate: None,
+        }])?;
+
+        let data = syscall_data(Sysno::openat2, [0; 6]);
+        let ret: SeccompReturn = BpfVM::new(&filter)?
+            .run(vm::any_to_data(&data))?
+            .try_into()?;
+        assert_eq!(
+            ret,
+            SeccompReturn::Errno(libc::EINVAL as u32),
+            "errno should be the custom EINVAL"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_argument_predicate() -> Result<(), Error> {
+        const RESOLVE_IN_ROOT: u32 = 0x10;
+
+        let filter = compile_filter([SyscallRule {
+            sysno: Sysno::openat2,
+            errno: libc::EINVAL,
+            predicate: Some(ArgPredicate {
+                arg_index: 4,
+                mask: RESOLVE_IN_ROOT,
+                value: 0,
+                negate: true,
+            }),
+        }])?;
+
+        let mut args = [0u64; 6];
+        args[4] = RESOLVE_IN_ROOT as u64;
+        let data = syscall_data(Sysno::openat2, args);
+        let ret: SeccompReturn = BpfVM::new(&filter)?
+            .run(vm::any_to_data(&data))?
+            .try_into()?;
+        assert_eq!(
+            ret,
+            SeccompReturn::Errno(libc::EINVAL as u32),
+            "openat2 with RESOLVE_IN_ROOT set should be trapped"
+        );
+
+        let data = syscall_data(Sysno::openat2, [0; 6]);
+        let ret: SeccompReturn = BpfVM::new(&filter)?
+            .run(vm::any_to_data(&data))?
+            .try_into()?;
+        assert_eq!(
+            ret,
+            SeccompReturn::Allow,
+            "openat2 without RESOLVE_IN_ROOT should be allowed"
+        );
+
+        Ok(())
+    }
 }
@@ -11,6 +11,8 @@
 
 mod bpf;
 
+use bpf::{ArgPredicate, SyscallRule};
+
 use std::{os::unix::process::CommandExt, process::Command as StdCmd};
 
 use anyhow::{anyhow, Context, Error};
@@ -21,12 +23,17 @@ use syscalls::Sysno;
 fn cli() -> Command {
     Command::new("fake-enosys")
         .author("Aleksa Sarai <cyphar@cyphar.com>")
-        .about("Runs a subcommand with certain syscalls disabled (returning -ENOSYS).")
+        .about("Runs a subcommand with certain syscalls disabled (returning a chosen errno).")
         .arg(
             Arg::new("syscalls")
                 .long("syscall")
                 .short('s')
-                .help("syscall name (or number) to mask (comma-separated or passed multiple times)")
+                .help(
+                    "syscall rule to apply (comma-separated or passed multiple times): \
+                     NAME, NAME:ERRNO, or NAME[argN & MASK (==|!=) VALUE]:ERRNO, where NAME \
+                     is a syscall name or number, ERRNO defaults to ENOSYS, and MASK/VALUE \
+                     are numbers or RESOLVE_* flag names",
+                )
                 .action(ArgAction::Append),
         )
         .arg(
@@ -44,6 +51,127 @@ fn verify_app() {
     cli().debug_assert();
 }
 
+/// Named `openat2(2)` `RESOLVE_*` flags, so `--syscall` predicates can refer
+/// to them by name instead of having to spell out the raw bitmask.
+const RESOLVE_FLAGS: &[(&str, u32)] = &[
+    ("RESOLVE_NO_XDEV", 0x01),
+    ("RESOLVE_NO_MAGICLINKS", 0x02),
+    ("RESOLVE_NO_SYMLINKS", 0x04),
+    ("RESOLVE_BENEATH", 0x08),
+    ("RESOLVE_IN_ROOT", 0x10),
+    ("RESOLVE_CACHED", 0x20),
+];
+
+/// Common errno names, so `--syscall foo:EINVAL` doesn't require the caller
+/// to know (or look up) the raw numeric value.
+const ERRNO_NAMES: &[(&str, i32)] = &[
+    ("EPERM", libc::EPERM),
+    ("ENOENT", libc::ENOENT),
+    ("EIO", libc::EIO),
+    ("EACCES", libc::EACCES),
+    ("EEXIST", libc::EEXIST),
+    ("ENOTDIR", libc::ENOTDIR),
+    ("EISDIR", libc::EISDIR),
+    ("EINVAL", libc::EINVAL),
+    ("ENOSYS", libc::ENOSYS),
+    ("ENOTEMPTY", libc::ENOTEMPTY),
+    ("ELOOP", libc::ELOOP),
+    ("EXDEV", libc::EXDEV),
+    ("EOPNOTSUPP", libc::EOPNOTSUPP),
+    ("EBADF", libc::EBADF),
+];
+
+fn parse_mask(s: &str) -> Result<u32, Error> {
+    if let Some((_, value)) = RESOLVE_FLAGS.iter().find(|(name, _)| *name == s) {
+        return Ok(*value);
+    }
+    if let Some(hex) = s.strip_prefix("0x") {
+        return u32::from_str_radix(hex, 16).map_err(|_| anyhow!("{s:?} is not a valid hex mask"));
+    }
+    s.parse::<u32>()
+        .map_err(|_| anyhow!("{s:?} is not a known RESOLVE_* flag or a valid mask"))
+}
+
+fn parse_errno(s: &str) -> Result<i32, Error> {
+    if let Some((_, errno)) = ERRNO_NAMES.iter().find(|(name, _)| *name == s) {
+        return Ok(*errno);
+    }
+    s.parse::<i32>()
+        .map_err(|_| anyhow!("{s:?} is not a known errno name or a valid errno number"))
+}
+
+fn parse_sysno(s: &str) -> Result<Sysno, Error> {
+    s.parse::<Sysno>().or_else(|_| {
+        s.parse::<usize>()
+            .map_err(|_| anyhow!("syscall {s:?} is not a known syscall"))
+            .and_then(|sysno| Sysno::new(sysno).ok_or_else(|| anyhow!("syscall #{sysno} is not a known syscall")))
+    })
+}
+
+/// Parse an `arg4 & MASK (==|!=) VALUE` predicate (the contents of a
+/// `NAME[...]` rule's brackets).
+fn parse_predicate(s: &str) -> Result<ArgPredicate, Error> {
+    let parts: Vec<&str> = s.split_whitespace().collect();
+    let [arg, amp, mask, cmp, value] = parts[..] else {
+        return Err(anyhow!(
+            "predicate {s:?} is not of the form 'argN & MASK (==|!=) VALUE'"
+        ));
+    };
+    if amp != "&" {
+        return Err(anyhow!("predicate {s:?} must use '&' to mask the argument"));
+    }
+    let arg_index = arg
+        .strip_prefix("arg")
+        .and_then(|n| n.parse::<u8>().ok())
+        .ok_or_else(|| anyhow!("{arg:?} is not a valid 'argN' reference"))?;
+    let negate = match cmp {
+        "==" => false,
+        "!=" => true,
+        _ => return Err(anyhow!("{cmp:?} is not a supported comparison (expected '==' or '!=')")),
+    };
+
+    Ok(ArgPredicate {
+        arg_index,
+        mask: parse_mask(mask)?,
+        value: parse_mask(value)?,
+        negate,
+    })
+}
+
+/// Parse a single `--syscall` rule: `NAME`, `NAME:ERRNO`, or
+/// `NAME[argN & MASK (==|!=) VALUE]:ERRNO`.
+fn parse_rule(s: &str) -> Result<SyscallRule, Error> {
+    let (name_and_predicate, errno) = match s.split_once(']') {
+        // A bracketed predicate may itself contain no ':', but split on the
+        // closing ']' first regardless, so the errno is only ever taken from
+        // what follows the predicate.
+        Some((head, tail)) => (
+            format!("{head}]"),
+            tail.strip_prefix(':').map(str::to_string),
+        ),
+        None => match s.split_once(':') {
+            Some((name, errno)) => (name.to_string(), Some(errno.to_string())),
+            None => (s.to_string(), None),
+        },
+    };
+
+    let (name, predicate) = match name_and_predicate.split_once('[') {
+        Some((name, bracketed)) => {
+            let pred_str = bracketed
+                .strip_suffix(']')
+                .ok_or_else(|| anyhow!("rule {s:?} has an unterminated '['"))?;
+            (name, Some(parse_predicate(pred_str)?))
+        }
+        None => (name_and_predicate.as_str(), None),
+    };
+
+    Ok(SyscallRule {
+        sysno: parse_sysno(name)?,
+        errno: errno.as_deref().map(parse_errno).transpose()?.unwrap_or(libc::ENOSYS),
+        predicate,
+    })
+}
+
 fn seccomp_set_filter(mut filter: impl AsMut<[libc::sock_filter]>) -> Result<(), Error> {
     let filter = filter.as_mut();
 
@@ -71,23 +199,12 @@ fn seccomp_set_filter(mut filter: impl AsMut<[libc::sock_filter]>) -> Result<(),
 fn main() -> Result<(), Error> {
     let m = cli().get_matches();
 
-    let syscalls: Vec<Sysno> = m
+    let rules: Vec<SyscallRule> = m
         .get_many::<String>("syscalls")
         .map(|iter| {
             iter.flat_map(|s| s.split(","))
                 .filter(|&s| !s.is_empty())
-                .map(|syscall| -> Result<_, Error> {
-                    syscall.parse::<Sysno>().or_else(|_| {
-                        syscall
-                            .parse::<usize>()
-                            .map_err(|_| anyhow!("syscall {syscall:?} is not a known syscall"))
-                            .and_then(|sysno| {
-                                Sysno::new(sysno).ok_or_else(|| {
-                                    anyhow!("syscall #{sysno} is not a known syscall")
-                                })
-                            })
-                    })
-                })
+                .map(parse_rule)
                 .collect::<Result<Vec<_>, _>>()
         })
         .unwrap_or_else(|| Ok(vec![]))?;
@@ -101,8 +218,8 @@ fn main() -> Result<(), Error> {
         .split_first()
         .context("command-line must have at least one element")?;
 
-    if !syscalls.is_empty() {
-        let mut filter = bpf::compile_filter(&syscalls)?;
+    if !rules.is_empty() {
+        let mut filter = bpf::compile_filter(&rules)?;
 
         // Unprivileged processes cannot enable seccomp-bpf unless they also set the
         // no-new-privs bit (to stop them from being able to trick setuid binaries).
@@ -0,0 +1,311 @@
+// SPDX-License-Identifier: MPL-2.0
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use pathrs::{flags::OpenFlags, FileType, InodeType, Metadata, Root, RootRef};
+
+use std::{
+    ffi::OsStr,
+    fs::Permissions,
+    io::{self, Read, Write},
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Error};
+use clap::{builder::TypedValueParser, error::ErrorKind as ClapErrorKind, Arg, ArgMatches, Command};
+use rustix::fs as rustix_fs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    None,
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CompressionParser;
+
+impl TypedValueParser for CompressionParser {
+    type Value = Compression;
+
+    fn parse_ref(
+        &self,
+        _cmd: &Command,
+        _arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let value = value.to_str().ok_or_else(|| {
+            clap::Error::raw(
+                ClapErrorKind::InvalidUtf8,
+                "compress contained invalid utf8 characters",
+            )
+        })?;
+
+        match value {
+            "none" => Ok(Compression::None),
+            "gzip" => Ok(Compression::Gzip),
+            "xz" => Ok(Compression::Xz),
+            "zstd" => Ok(Compression::Zstd),
+            _ => Err(clap::Error::raw(
+                ClapErrorKind::ValueValidation,
+                format!("{value} is not a valid compression (expected one of none, gzip, xz, zstd)"),
+            )),
+        }
+    }
+}
+
+fn decoder(compress: Compression, r: impl Read + 'static) -> Box<dyn Read> {
+    match compress {
+        Compression::None => Box::new(r),
+        Compression::Gzip => Box::new(flate2::read::GzDecoder::new(r)),
+        Compression::Xz => Box::new(xz2::read::XzDecoder::new(r)),
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(r).expect("zstd decoder")),
+    }
+}
+
+fn encoder(compress: Compression, xz_preset: u32, w: impl Write + 'static) -> Box<dyn Write> {
+    match compress {
+        Compression::None => Box::new(w),
+        Compression::Gzip => Box::new(flate2::write::GzEncoder::new(w, flate2::Compression::default())),
+        Compression::Xz => Box::new(xz2::write::XzEncoder::new(w, xz_preset)),
+        Compression::Zstd => Box::new(zstd::stream::write::Encoder::new(w, 0).expect("zstd encoder").auto_finish()),
+    }
+}
+
+fn extract_cli() -> Command {
+    Command::new("extract")
+        .about("safely extract a tar stream (read from stdin) into the root")
+        .arg(
+            Arg::new("compress")
+                .long("compress")
+                .help("compression the input stream is wrapped in")
+                .default_value("none")
+                .value_parser(CompressionParser),
+        )
+        .arg(
+            crate::utils::subpath_arg("subpath")
+                .help("directory inside the root to extract into")
+                .default_value("."),
+        )
+}
+
+fn archive_cli() -> Command {
+    Command::new("archive")
+        .about("safely create a tar stream (written to stdout) from a subtree of the root")
+        .arg(
+            Arg::new("compress")
+                .long("compress")
+                .help("compression to wrap the output stream in")
+                .default_value("none")
+                .value_parser(CompressionParser),
+        )
+        .arg(
+            Arg::new("xz-preset")
+                .long("xz-preset")
+                .help("xz compression preset (0 = fastest/smallest window, 9 = slowest/largest window)")
+                .default_value("6")
+                .value_parser(0..=9),
+        )
+        .arg(
+            crate::utils::subpath_arg("subpath")
+                .help("directory inside the root to archive")
+                .default_value("."),
+        )
+}
+
+pub(crate) fn cli() -> Command {
+    Command::new("archive")
+        .about("tar stream import/export for a root")
+        .arg(
+            Arg::new("root")
+                .long("root")
+                .value_parser(clap::value_parser!(PathBuf))
+                .required(true),
+        )
+        .subcommand(extract_cli())
+        .subcommand(archive_cli())
+}
+
+pub(crate) fn subcommand(matches: &ArgMatches) -> Result<(), Error> {
+    let root = Root::open(
+        matches
+            .get_one::<PathBuf>("root")
+            .expect("root should be set"),
+    )?;
+
+    match matches.subcommand() {
+        Some(("extract", sub_matches)) => extract(root.as_ref(), sub_matches),
+        Some(("archive", sub_matches)) => archive(root.as_ref(), sub_matches),
+        Some((subcommand, _)) => Err(anyhow!("unknown 'archive' subcommand '{subcommand}'")),
+        None => Err(anyhow!("no 'archive' subcommand specified")),
+    }
+}
+
+// Ensure that the parent directory of `path` (and all of its own parents)
+// exists inside `root`, using
+// only the root's race-free primitives -- never a raw path operation on the
+// host filesystem.
+fn ensure_parent(root: RootRef<'_>, path: &Path) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        if parent != Path::new("") {
+            root.mkdir_all(parent, &Permissions::from_mode(0o755))?;
+        }
+    }
+    Ok(())
+}
+
+fn extract(root: RootRef<'_>, matches: &ArgMatches) -> Result<(), Error> {
+    let compress = *matches
+        .get_one::<Compression>("compress")
+        .expect("compress should always be set");
+    let subpath = matches
+        .get_one::<PathBuf>("subpath")
+        .expect("subpath should always be set");
+
+    root.mkdir_all(subpath, &Permissions::from_mode(0o755))?;
+
+    let stream = decoder(compress, io::stdin());
+    let mut archive = tar::Archive::new(stream);
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = subpath.join(entry.path()?.as_ref());
+        let perm = Permissions::from_mode(entry.header().mode()?);
+
+        ensure_parent(root, &entry_path)?;
+
+        // Every leaf below is created by resolving its parent through the
+        // root and creating (or replacing) the leaf itself -- the same
+        // RESOLVE_NO_SYMLINKS-style semantics `Root::create`/`create_file`
+        // already use -- so a malicious "../" entry or a symlink planted
+        // earlier in the stream can never be followed out of the root.
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => {
+                root.mkdir_all(&entry_path, &perm)?;
+            }
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                let mut f = root.create_file(
+                    &entry_path,
+                    OpenFlags::O_WRONLY | OpenFlags::O_CREAT | OpenFlags::O_TRUNC,
+                    &perm,
+                )?;
+                io::copy(&mut entry, &mut f)?;
+            }
+            tar::EntryType::Symlink => {
+                let target = entry
+                    .link_name()?
+                    .ok_or_else(|| anyhow!("symlink entry {entry_path:?} has no link target"))?
+                    .into_owned();
+                root.create(&entry_path, &InodeType::Symlink(target))?;
+            }
+            tar::EntryType::Link => {
+                let target = entry
+                    .link_name()?
+                    .ok_or_else(|| anyhow!("hardlink entry {entry_path:?} has no link target"))?
+                    .into_owned();
+                root.create(&entry_path, &InodeType::Hardlink(target))?;
+            }
+            tar::EntryType::Fifo => {
+                root.create(&entry_path, &InodeType::Fifo(perm))?;
+            }
+            tar::EntryType::Char | tar::EntryType::Block => {
+                let dev = rustix_fs::makedev(
+                    entry.header().device_major()?.unwrap_or(0),
+                    entry.header().device_minor()?.unwrap_or(0),
+                );
+                let inode_type = if entry.header().entry_type() == tar::EntryType::Char {
+                    InodeType::CharacterDevice(perm, dev)
+                } else {
+                    InodeType::BlockDevice(perm, dev)
+                };
+                root.create(&entry_path, &inode_type)?;
+            }
+            other => return Err(anyhow!("unsupported tar entry type {other:?} for {entry_path:?}")),
+        }
+    }
+
+    Ok(())
+}
+
+fn archive_dir(root: RootRef<'_>, builder: &mut tar::Builder<impl Write>, rel_path: &Path) -> Result<(), Error> {
+    for entry in root.read_dir(rel_path)? {
+        let entry = entry?;
+        let entry_path = rel_path.join(entry.file_name());
+        let meta = root.metadata_nofollow(&entry_path)?;
+
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(meta.mode());
+        header.set_size(0);
+
+        match meta.file_type() {
+            FileType::Directory => {
+                header.set_entry_type(tar::EntryType::Directory);
+                builder.append_data(&mut header, &entry_path, io::empty())?;
+                archive_dir(root, builder, &entry_path)?;
+            }
+            FileType::Symlink => {
+                let target = root.readlink(&entry_path)?;
+                header.set_entry_type(tar::EntryType::Symlink);
+                builder.append_link(&mut header, &entry_path, &target)?;
+            }
+            FileType::File => {
+                header.set_size(meta.len());
+                header.set_entry_type(tar::EntryType::Regular);
+                let f = root.open_subpath(&entry_path, OpenFlags::O_RDONLY)?;
+                builder.append_data(&mut header, &entry_path, f)?;
+            }
+            file_type => {
+                set_special_header(&mut header, file_type, &meta);
+                builder.append_data(&mut header, &entry_path, io::empty())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn set_special_header(header: &mut tar::Header, file_type: FileType, meta: &Metadata) {
+    match file_type {
+        FileType::Fifo => header.set_entry_type(tar::EntryType::Fifo),
+        FileType::BlockDevice | FileType::CharacterDevice => {
+            header.set_entry_type(if file_type == FileType::BlockDevice {
+                tar::EntryType::Block
+            } else {
+                tar::EntryType::Char
+            });
+            // We don't have a statx-based accessor for rdev major/minor in
+            // this crate, so device nodes are archived without their
+            // major/minor numbers -- good enough for round-tripping the tree
+            // structure, but a restored device node will need `mknod`.
+            let _ = meta;
+        }
+        _ => header.set_entry_type(tar::EntryType::Regular),
+    }
+}
+
+fn archive(root: RootRef<'_>, matches: &ArgMatches) -> Result<(), Error> {
+    let compress = *matches
+        .get_one::<Compression>("compress")
+        .expect("compress should always be set");
+    let xz_preset = *matches
+        .get_one::<i64>("xz-preset")
+        .expect("xz-preset should always be set") as u32;
+    let subpath = matches
+        .get_one::<PathBuf>("subpath")
+        .expect("subpath should always be set");
+
+    let stream = encoder(compress, xz_preset, io::stdout());
+    let mut builder = tar::Builder::new(stream);
+    archive_dir(root, &mut builder, subpath)?;
+    builder.finish()?;
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: MPL-2.0
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! A tiny flat-object JSON writer, just enough for `--json`'s structured
+//! output -- this is not a general-purpose JSON library.
+
+#[derive(Debug, Clone)]
+pub(crate) enum Value {
+    Str(String),
+    Int(i64),
+    Null,
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Str(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Str(s)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(v: i32) -> Self {
+        Value::Int(v as i64)
+    }
+}
+
+impl From<Option<i32>> for Value {
+    fn from(v: Option<i32>) -> Self {
+        v.map(Value::from).unwrap_or(Value::Null)
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Print a flat JSON object with the given `(key, value)` fields to stdout.
+pub(crate) fn print_object(fields: &[(&str, Value)]) {
+    let body = fields
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::Str(s) => format!("\"{}\"", escape(s)),
+                Value::Int(v) => v.to_string(),
+                Value::Null => "null".to_string(),
+            };
+            format!("\"{}\":{value}", escape(key))
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    println!("{{{body}}}");
+}
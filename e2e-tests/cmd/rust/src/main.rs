@@ -9,23 +9,38 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use pathrs::error::{Error as PathrsError, ErrorKind as PathrsErrorKind};
+use pathrs::error::Error as PathrsError;
+
+use json::Value as J;
 
 use std::process::ExitCode;
 
 use anyhow::{anyhow, Error};
-use clap::Command;
+use clap::{Arg, ArgAction, Command};
 use errno::Errno;
 
+mod archive;
+mod identify;
+mod json;
 mod procfs;
+mod resolve;
 mod root;
 mod utils;
 
 fn cli() -> Command {
     Command::new("pathrs-cmd")
         .author("Aleksa Sarai <cyphar@cyphar.com>")
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("emit machine-readable JSON instead of free-form text")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
         .subcommand(root::cli())
         .subcommand(procfs::cli())
+        .subcommand(archive::cli())
+        .subcommand(resolve::cli())
 }
 
 #[test]
@@ -33,31 +48,37 @@ fn verify_app() {
     cli().debug_assert();
 }
 
-fn handle_error(func: impl FnOnce() -> Result<(), Error>) -> ExitCode {
+fn handle_error(json: bool, func: impl FnOnce() -> Result<(), Error>) -> ExitCode {
     if let Err(err) = func() {
         let mut desc = err.to_string();
+        let mut found = None;
         for cause in err.chain() {
             if let Some(err) = cause.downcast_ref::<PathrsError>() {
-                // This is basically ErrorKind::errno (which isn't exported
-                // currently). This is necessary in order to emulate the
-                // behaviour of the binding test programs, as they see the
-                // converted errnos as well as the legitimate OsError ones.
-                let errno = match err.kind() {
-                    PathrsErrorKind::NotImplemented => Some(libc::ENOSYS),
-                    PathrsErrorKind::InvalidArgument => Some(libc::EINVAL),
-                    PathrsErrorKind::OsError(errno) => errno,
-                    PathrsErrorKind::SafetyViolation => Some(libc::EXDEV),
-                    _ => None,
-                };
-                if let Some(errno) = errno {
-                    println!("ERRNO {errno} ({})", Errno(errno));
+                if let Some(errno) = utils::error_errno(err) {
+                    found.get_or_insert((errno, err.kind()));
+                    if !json {
+                        println!("ERRNO {errno} ({})", Errno(errno));
+                    }
                 }
             }
             // Emulate capi's error formatting.
             desc.push_str(": ");
             desc.push_str(&cause.to_string());
         }
-        println!("error: {desc}");
+
+        if json {
+            json::print_object(&[
+                ("status", J::from("error")),
+                ("errno", J::from(found.map(|(errno, _)| errno))),
+                (
+                    "kind",
+                    J::from(found.map_or("Unknown", |(_, kind)| utils::error_kind_name(kind))),
+                ),
+                ("message", J::from(desc)),
+            ]);
+        } else {
+            println!("error: {desc}");
+        }
         ExitCode::FAILURE
     } else {
         ExitCode::SUCCESS
@@ -65,21 +86,23 @@ fn handle_error(func: impl FnOnce() -> Result<(), Error>) -> ExitCode {
 }
 
 fn main() -> ExitCode {
-    handle_error(|| {
-        let mut app = cli();
+    let mut app = cli();
+    let matches = app.get_matches_mut();
+    let json = matches.get_flag("json");
 
-        match app.get_matches_mut().subcommand() {
-            Some(("root", sub_matches)) => root::subcommand(sub_matches),
-            Some(("procfs", sub_matches)) => procfs::subcommand(sub_matches),
-            Some((subcommand, _)) => {
-                // We should never end up here.
-                app.print_help()?;
-                Err(anyhow!("unknown subcommand '{}'", subcommand))
-            }
-            None => {
-                app.print_help()?;
-                Err(anyhow!("no subcommand specified"))
-            }
+    handle_error(json, || match matches.subcommand() {
+        Some(("root", sub_matches)) => root::subcommand(sub_matches),
+        Some(("procfs", sub_matches)) => procfs::subcommand(sub_matches),
+        Some(("archive", sub_matches)) => archive::subcommand(sub_matches),
+        Some(("resolve", sub_matches)) => resolve::subcommand(json, sub_matches),
+        Some((subcommand, _)) => {
+            // We should never end up here.
+            app.print_help()?;
+            Err(anyhow!("unknown subcommand '{}'", subcommand))
+        }
+        None => {
+            app.print_help()?;
+            Err(anyhow!("no subcommand specified"))
         }
     })
 }
@@ -9,25 +9,53 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use crate::utils;
+use crate::{identify, utils};
 use pathrs::{
     flags::OpenFlags,
-    procfs::{ProcfsBase, ProcfsHandleBuilder, ProcfsHandleRef},
+    procfs::{ProcfsBase, ProcfsHandle, ProcfsHandleBuilder, ProcfsHandleRef},
+    DirEntry, FileType, Handle,
 };
 
-use std::{ffi::OsStr, path::PathBuf};
+use std::{
+    collections::VecDeque,
+    ffi::OsStr,
+    fs::File,
+    os::unix::io::AsFd,
+    path::{Path, PathBuf},
+    sync::{Condvar, Mutex},
+    thread,
+};
 
 use anyhow::{anyhow, Error};
 use clap::{
     builder::TypedValueParser, error::ErrorKind as ClapErrorKind, Arg, ArgAction, ArgMatches,
     Command,
 };
+use rustix::fs as rustix_fs;
+
+/// What `--base` resolved to.
+///
+/// This can't just be a [`ProcfsBase`] -- [`ProcfsBase::ProcPidFd`] borrows a
+/// [`std::os::unix::io::BorrowedFd`], but clap's parsed values have to be
+/// `Clone`, which a [`File`] isn't. So parsing instead produces this (owning
+/// the pidfd's path rather than an open fd), and [`subcommand`] opens the
+/// path for real immediately before dispatching to a handler.
+#[derive(Debug, Clone)]
+enum ProcfsBaseArg {
+    Root,
+    Pid(u32),
+    /// A path naming a pidfd (e.g. `/proc/1234/pidfd` or an inherited fd
+    /// under `/proc/self/fd`), validated as an actual pidfd at parse time.
+    PidFd(PathBuf),
+    SelfBase,
+    ThreadSelf,
+}
 
 #[derive(Debug, Clone, Copy)]
 struct ProcfsBaseParser;
 
 impl TypedValueParser for ProcfsBaseParser {
-    type Value = ProcfsBase;
+    type Value = ProcfsBaseArg;
 
     fn parse_ref(
         &self,
@@ -42,16 +70,27 @@ impl TypedValueParser for ProcfsBaseParser {
             )
         })?;
 
+        if let Some(path) = value.strip_prefix("pidfd=") {
+            let path = PathBuf::from(path);
+            validate_pidfd_path(&path).map_err(|err| {
+                clap::Error::raw(
+                    ClapErrorKind::ValueValidation,
+                    format!("{path:?} is not a usable pidfd: {err}"),
+                )
+            })?;
+            return Ok(ProcfsBaseArg::PidFd(path));
+        }
+
         match (value, value.strip_prefix("pid=")) {
-            (_, Some(pid)) => Ok(ProcfsBase::ProcPid(pid.parse().map_err(|err| {
+            (_, Some(pid)) => Ok(ProcfsBaseArg::Pid(pid.parse().map_err(|err| {
                 clap::Error::raw(
                     ClapErrorKind::ValueValidation,
                     format!("{value} is an invalid octal mode: {err:?}"),
                 )
             })?)),
-            ("root", _) => Ok(ProcfsBase::ProcRoot),
-            ("self", _) => Ok(ProcfsBase::ProcSelf),
-            ("thread-self", _) => Ok(ProcfsBase::ProcThreadSelf),
+            ("root", _) => Ok(ProcfsBaseArg::Root),
+            ("self", _) => Ok(ProcfsBaseArg::SelfBase),
+            ("thread-self", _) => Ok(ProcfsBaseArg::ThreadSelf),
             (value, None) => Err(clap::Error::raw(
                 ClapErrorKind::ValueValidation,
                 format!("{value} is an invalid procfs base"),
@@ -60,6 +99,21 @@ impl TypedValueParser for ProcfsBaseParser {
     }
 }
 
+/// Open `path` and confirm it actually names a pidfd, by resolving it as a
+/// [`ProcfsBase::ProcPidFd`] against a throwaway [`ProcfsHandle`] -- a fd
+/// that isn't a pidfd fails the `PIDFD_GET_INFO` check
+/// [`ProcfsHandleRef::open`] does internally for that variant, surfacing as
+/// an ordinary [`pathrs::error::Error`] we can report back through clap.
+///
+/// This is only a point-in-time check: the real open happens again in
+/// [`subcommand`] right before dispatching to a handler, since pidfds (like
+/// any fd) can be closed out from under a long-running CLI invocation.
+fn validate_pidfd_path(path: &Path) -> Result<(), Error> {
+    let pidfd = File::open(path)?;
+    ProcfsHandle::new()?.open(ProcfsBase::ProcPidFd(pidfd.as_fd()), ".", OpenFlags::O_PATH)?;
+    Ok(())
+}
+
 fn open_cli() -> Command {
     Command::new("open")
         .about("open a path in procfs")
@@ -69,6 +123,12 @@ fn open_cli() -> Command {
                 .default_value("O_RDONLY"),
         )
         .args(utils::toggle_arg("follow", "follow trailing symlinks"))
+        .arg(
+            Arg::new("identify")
+                .long("identify")
+                .help("read a bounded prefix of the opened file and report its detected type")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             utils::subpath_arg("subpath")
                 .help("path inside procfs base")
@@ -86,6 +146,7 @@ fn open(procfs: ProcfsHandleRef<'_>, base: ProcfsBase, matches: &ArgMatches) ->
     let oflags = *matches
         .get_one::<OpenFlags>("oflags")
         .expect("oflags should always be set");
+    let identify = matches.get_flag("identify");
 
     let f = if follow {
         procfs.open_follow(base, subpath, oflags)
@@ -93,6 +154,9 @@ fn open(procfs: ProcfsHandleRef<'_>, base: ProcfsBase, matches: &ArgMatches) ->
         procfs.open(base, subpath, oflags)
     }?;
     utils::print_file(&f)?;
+    if identify {
+        println!("IDENTIFY {}", identify::identify(&mut &f)?);
+    }
 
     Ok(())
 }
@@ -121,6 +185,222 @@ fn readlink(
     Ok(())
 }
 
+// Figure out the real type of a directory entry, falling back to an
+// O_PATH|O_NOFOLLOW fstat(2) if the kernel didn't give us `d_type` (some
+// procfs-backed filesystems don't fill it in).
+fn walk_entry_type(entry: &DirEntry) -> Result<FileType, Error> {
+    if entry.file_type() != FileType::Unknown {
+        return Ok(entry.file_type());
+    }
+    let f = entry.open(OpenFlags::O_PATH | OpenFlags::O_NOFOLLOW)?;
+    let stat = rustix_fs::fstat(&f)?;
+    Ok(rustix_fs::FileType::from_raw_mode(stat.st_mode.into()).into())
+}
+
+/// A single subtree still left to descend into, relative to the original
+/// walk root.
+type WalkJob = (Handle, PathBuf, usize);
+
+/// A work-stealing queue of [`WalkJob`]s, shared by the `--threads` worker
+/// pool spawned by [`walk`]. Unlike a plain `Mutex<VecDeque<_>>`, workers
+/// need to be able to tell "the queue is empty because every other worker is
+/// also idle" (the walk is done) apart from "the queue is empty because
+/// everyone else is still busy discovering more subdirectories" (keep
+/// waiting) -- `pending` tracks the latter.
+struct WalkQueue {
+    state: Mutex<WalkQueueState>,
+    cv: Condvar,
+}
+
+struct WalkQueueState {
+    jobs: VecDeque<WalkJob>,
+    // Number of jobs that have been pushed but not yet finished (queued or
+    // currently being processed by a worker).
+    pending: usize,
+}
+
+impl WalkQueue {
+    fn new(root: WalkJob) -> Self {
+        Self {
+            state: Mutex::new(WalkQueueState {
+                jobs: VecDeque::from([root]),
+                pending: 1,
+            }),
+            cv: Condvar::new(),
+        }
+    }
+
+    fn push(&self, job: WalkJob) {
+        let mut state = self.state.lock().expect("walk queue lock was poisoned");
+        state.pending += 1;
+        state.jobs.push_back(job);
+        self.cv.notify_one();
+    }
+
+    /// Block until a job is available, or `None` once every pushed job has
+    /// been completed (there is nothing left to do, by any worker).
+    fn pop(&self) -> Option<WalkJob> {
+        let mut state = self.state.lock().expect("walk queue lock was poisoned");
+        loop {
+            if let Some(job) = state.jobs.pop_front() {
+                return Some(job);
+            }
+            if state.pending == 0 {
+                return None;
+            }
+            state = self.cv.wait(state).expect("walk queue lock was poisoned");
+        }
+    }
+
+    /// Mark the job most recently returned by [`Self::pop`] as done.
+    fn finish(&self) {
+        let mut state = self.state.lock().expect("walk queue lock was poisoned");
+        state.pending -= 1;
+        if state.pending == 0 {
+            self.cv.notify_all();
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn walk_dir(
+    procfs: &ProcfsHandleRef<'_>,
+    base: ProcfsBase,
+    dir: &Handle,
+    rel_path: &Path,
+    depth: usize,
+    max_depth: Option<usize>,
+    follow: bool,
+    queue: &WalkQueue,
+) -> Result<(), Error> {
+    for entry in dir.read_dir()? {
+        let entry = entry?;
+        let entry_path = rel_path.join(entry.file_name());
+        let file_type = walk_entry_type(&entry)?;
+
+        print!(
+            "ENTRY {} {}",
+            utils::file_type_name(file_type),
+            entry_path.display()
+        );
+        if file_type == FileType::Symlink {
+            // Re-resolve the link through the procfs base rather than the
+            // directory fd we just read it from -- this is the same
+            // readlink(2) path the "readlink" subcommand uses, so symlinks
+            // (most importantly procfs magic-links) are resolved with all
+            // of the usual procfs protections rather than treated as plain
+            // symlinks.
+            match procfs.readlink(base, &entry_path) {
+                Ok(target) => println!(" -> {}", target.to_string_lossy()),
+                Err(err) => println!(" -> <unreadable: {err}>"),
+            }
+        } else {
+            println!();
+        }
+
+        if max_depth.map_or(false, |max| depth >= max) {
+            continue;
+        }
+
+        let descend_flags = match file_type {
+            FileType::Directory => Some(OpenFlags::O_DIRECTORY | OpenFlags::O_NOFOLLOW),
+            FileType::Symlink if follow => Some(OpenFlags::O_DIRECTORY),
+            _ => None,
+        };
+
+        if let Some(descend_flags) = descend_flags {
+            match entry.open(descend_flags) {
+                // Hand the subdirectory off to the queue rather than
+                // recursing directly, so any idle worker in the pool (not
+                // just this thread) can pick it up.
+                Ok(f) => queue.push((Handle::from_fd(f), entry_path, depth + 1)),
+                // A followed symlink that doesn't actually point to a
+                // directory just isn't descended into.
+                Err(_) if file_type == FileType::Symlink => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn walk_worker(
+    procfs: &ProcfsHandleRef<'_>,
+    base: ProcfsBase,
+    queue: &WalkQueue,
+    max_depth: Option<usize>,
+    follow: bool,
+    errors: &Mutex<Vec<Error>>,
+) {
+    while let Some((dir, rel_path, depth)) = queue.pop() {
+        if let Err(err) = walk_dir(procfs, base, &dir, &rel_path, depth, max_depth, follow, queue)
+        {
+            errors
+                .lock()
+                .expect("walk error list lock was poisoned")
+                .push(err);
+        }
+        queue.finish();
+    }
+}
+
+fn walk_cli() -> Command {
+    Command::new("walk")
+        .about("recursively walk a directory inside procfs, printing each entry")
+        .arg(
+            Arg::new("max-depth")
+                .long("max-depth")
+                .help("maximum recursion depth (0 only lists the given directory's own entries)")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("threads")
+                .long("threads")
+                .help("number of worker threads to fan subtree descents out across")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("1"),
+        )
+        .args(utils::toggle_arg(
+            "follow",
+            "follow symlinked directories during recursion",
+        ))
+        .arg(
+            utils::subpath_arg("subpath")
+                .help("path inside procfs base to start walking from")
+                .required(true),
+        )
+}
+
+fn walk(procfs: ProcfsHandleRef<'_>, base: ProcfsBase, matches: &ArgMatches) -> Result<(), Error> {
+    let subpath = matches
+        .get_one::<PathBuf>("subpath")
+        .expect("subpath should always be set");
+    let max_depth = matches.get_one::<usize>("max-depth").copied();
+    let follow = *matches
+        .get_one::<bool>("follow")
+        .expect("follow should be set");
+    let threads = *matches
+        .get_one::<usize>("threads")
+        .expect("threads should always be set");
+
+    let root_dir = Handle::from_fd(procfs.open(base, subpath, OpenFlags::O_DIRECTORY)?);
+    let queue = WalkQueue::new((root_dir, subpath.clone(), 0));
+    let errors: Mutex<Vec<Error>> = Mutex::new(Vec::new());
+
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| walk_worker(&procfs, base, &queue, max_depth, follow, &errors));
+        }
+    });
+
+    let mut errors = errors.into_inner().expect("walk error list lock was poisoned");
+    match errors.pop() {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
 pub(crate) fn cli() -> Command {
     Command::new("procfs")
         .about("ProcfsHandle::* operations")
@@ -133,7 +413,10 @@ pub(crate) fn cli() -> Command {
         .arg(
             Arg::new("base")
                 .long("base")
-                .help("base path for procfs operations (root, pid=<n>, self, thread-self)")
+                .help(
+                    "base path for procfs operations (root, pid=<n>, pidfd=<path>, self, \
+                     thread-self) -- prefer pidfd=<path> over pid=<n> to avoid PID-reuse races",
+                )
                 .value_name("PROC_*")
                 .default_value("root")
                 .value_parser(ProcfsBaseParser)
@@ -141,6 +424,7 @@ pub(crate) fn cli() -> Command {
         )
         .subcommand(open_cli())
         .subcommand(readlink_cli())
+        .subcommand(walk_cli())
 }
 
 pub(crate) fn subcommand(matches: &ArgMatches) -> Result<(), Error> {
@@ -154,13 +438,29 @@ pub(crate) fn subcommand(matches: &ArgMatches) -> Result<(), Error> {
 
         b.build()
     }?;
-    let base = *matches
-        .get_one::<ProcfsBase>("base")
+    let base_arg = matches
+        .get_one::<ProcfsBaseArg>("base")
         .expect("base should always be set");
 
+    // A `pidfd=<path>` base needs its own open fd to be kept alive for the
+    // remainder of dispatch -- `ProcfsBase::ProcPidFd` only borrows it.
+    let pidfd = match base_arg {
+        ProcfsBaseArg::PidFd(path) => Some(File::open(path)?),
+        _ => None,
+    };
+    let base = match (base_arg, &pidfd) {
+        (ProcfsBaseArg::Root, _) => ProcfsBase::ProcRoot,
+        (ProcfsBaseArg::Pid(pid), _) => ProcfsBase::ProcPid(*pid),
+        (ProcfsBaseArg::SelfBase, _) => ProcfsBase::ProcSelf,
+        (ProcfsBaseArg::ThreadSelf, _) => ProcfsBase::ProcThreadSelf,
+        (ProcfsBaseArg::PidFd(_), Some(pidfd)) => ProcfsBase::ProcPidFd(pidfd.as_fd()),
+        (ProcfsBaseArg::PidFd(_), None) => unreachable!("pidfd opened just above"),
+    };
+
     match matches.subcommand() {
         Some(("open", sub_matches)) => open(procfs, base, sub_matches),
         Some(("readlink", sub_matches)) => readlink(procfs, base, sub_matches),
+        Some(("walk", sub_matches)) => walk(procfs, base, sub_matches),
         Some((subcommand, _)) => {
             // We should never end up here.
             Err(anyhow!("unknown 'procfs' subcommand '{subcommand}'"))
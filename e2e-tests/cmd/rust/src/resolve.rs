@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: MPL-2.0
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::json::{self, Value as J};
+
+use pathrs::{
+    error::{Error as PathrsError, ErrorKind as PathrsErrorKind},
+    Handle, Root, RootRef,
+};
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Error;
+use clap::{Arg, ArgMatches, Command};
+
+pub(crate) fn cli() -> Command {
+    Command::new("resolve")
+        .about("resolve a path inside a root, reporting how far resolution got")
+        .arg(
+            Arg::new("root")
+                .long("root")
+                .value_parser(clap::value_parser!(PathBuf))
+                .required(true),
+        )
+        .args(crate::utils::toggle_arg("follow", "follow trailing symlinks"))
+        .arg(
+            crate::utils::subpath_arg("subpath")
+                .help("path inside the root to resolve")
+                .required(true),
+        )
+}
+
+enum Resolution {
+    Complete(Handle),
+    Partial {
+        handle: Handle,
+        remaining: PathBuf,
+        last_error: PathrsError,
+    },
+}
+
+fn resolve_one(root: RootRef<'_>, path: &Path, follow: bool) -> Result<Handle, PathrsError> {
+    if follow {
+        root.resolve(path)
+    } else {
+        root.resolve_nofollow(path)
+    }
+}
+
+/// Resolve as much of `subpath` as possible, using only `Root`'s public
+/// `resolve`/`resolve_nofollow` entry points: try the whole path first, and
+/// if that fails, walk back one path component at a time until some prefix
+/// resolves (the root itself, resolved via "."`, always does).
+///
+/// A safety violation is never downgraded to a partial result -- it is
+/// always returned as a hard error, matching how `Root::mkdir_all` treats
+/// the internal resolvers' partial lookups.
+fn resolve_partial(root: RootRef<'_>, subpath: &Path, follow: bool) -> Result<Resolution, PathrsError> {
+    let last_error = match resolve_one(root, subpath, follow) {
+        Ok(handle) => return Ok(Resolution::Complete(handle)),
+        Err(err) => err,
+    };
+    if last_error.kind() == PathrsErrorKind::SafetyViolation {
+        return Err(last_error);
+    }
+
+    for ancestor in subpath.ancestors().skip(1) {
+        let probe = if ancestor.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            ancestor
+        };
+        match resolve_one(root, probe, follow) {
+            Ok(handle) => {
+                let remaining = subpath.strip_prefix(ancestor).unwrap_or(subpath).to_path_buf();
+                return Ok(Resolution::Partial {
+                    handle,
+                    remaining,
+                    last_error,
+                });
+            }
+            Err(err) if err.kind() == PathrsErrorKind::SafetyViolation => return Err(err),
+            Err(_) => continue,
+        }
+    }
+
+    // "." (the root itself) is always resolvable, so the loop above should
+    // always return before running out of ancestors. Fall back to the
+    // original error rather than panicking if that invariant is ever wrong.
+    Err(last_error)
+}
+
+pub(crate) fn subcommand(json: bool, matches: &ArgMatches) -> Result<(), Error> {
+    let root = Root::open(
+        matches
+            .get_one::<PathBuf>("root")
+            .expect("root should be set"),
+    )?;
+    let subpath = matches
+        .get_one::<PathBuf>("subpath")
+        .expect("subpath should be set");
+    let follow = *matches
+        .get_one::<bool>("follow")
+        .expect("follow should be set");
+
+    match resolve_partial(root.as_ref(), subpath, follow)? {
+        Resolution::Complete(handle) => {
+            let path = crate::utils::fd_path(&handle)?;
+            if json {
+                json::print_object(&[
+                    ("status", J::from("complete")),
+                    ("path", J::from(path.to_string_lossy().into_owned())),
+                ]);
+            } else {
+                println!("COMPLETE {}", path.to_string_lossy());
+            }
+        }
+        Resolution::Partial {
+            handle,
+            remaining,
+            last_error,
+        } => {
+            let resolved = crate::utils::fd_path(&handle)?;
+            if json {
+                json::print_object(&[
+                    ("status", J::from("partial")),
+                    ("resolved", J::from(resolved.to_string_lossy().into_owned())),
+                    ("remaining", J::from(remaining.to_string_lossy().into_owned())),
+                    ("errno", J::from(crate::utils::error_errno(&last_error))),
+                    ("kind", J::from(crate::utils::error_kind_name(last_error.kind()))),
+                    ("message", J::from(last_error.to_string())),
+                ]);
+            } else {
+                println!(
+                    "PARTIAL resolved={} remaining={} errno={} ({last_error})",
+                    resolved.to_string_lossy(),
+                    remaining.to_string_lossy(),
+                    crate::utils::error_errno(&last_error).map_or_else(|| "?".to_string(), |e| e.to_string()),
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
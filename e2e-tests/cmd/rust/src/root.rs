@@ -9,16 +9,21 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use crate::utils::{self, ModeParser};
+use crate::utils::{self, FileTypeParser, GlobParser, ModeParser, RegexParser};
 use pathrs::{
     flags::{OpenFlags, RenameFlags},
-    InodeType, Root, RootRef,
+    DirEntry, FileType, Handle, InodeType, Root, RootRef, Server,
 };
 
-use std::{fs::Permissions, path::PathBuf};
+use std::{
+    fs::Permissions,
+    os::unix::net::UnixListener,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{anyhow, Error};
 use clap::{Arg, ArgAction, ArgMatches, Command};
+use regex::Regex;
 use rustix::fs as rustix_fs;
 
 fn resolve_cli() -> Command {
@@ -332,6 +337,217 @@ fn readlink(root: RootRef<'_>, matches: &ArgMatches) -> Result<(), Error> {
     Ok(())
 }
 
+fn readdir_cli() -> Command {
+    Command::new("readdir")
+        .about("list the entries of a directory inside the root (\".\" and \"..\" are omitted)")
+        .arg(
+            utils::subpath_arg("subpath")
+                .help("path inside the root")
+                .required(true),
+        )
+}
+
+fn readdir(root: RootRef<'_>, matches: &ArgMatches) -> Result<(), Error> {
+    let subpath = matches
+        .get_one::<PathBuf>("subpath")
+        .expect("subpath should be set");
+
+    for entry in root.read_dir(subpath)? {
+        let entry = entry?;
+        println!(
+            "ENTRY {} {}",
+            utils::file_type_name(entry.file_type()),
+            entry.file_name().to_string_lossy(),
+        );
+    }
+    Ok(())
+}
+
+fn walk_cli() -> Command {
+    Command::new("walk")
+        .about("recursively walk a subtree inside the root, printing root-relative matches")
+        .arg(
+            Arg::new("type")
+                .short('t')
+                .long("type")
+                .help("only show entries of this type (f, d, l, b, c, p, s) [may be repeated]")
+                .value_parser(FileTypeParser)
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("max-depth")
+                .long("max-depth")
+                .help("maximum recursion depth (0 only lists the given directory's own entries)")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("glob")
+                .long("glob")
+                .help("only show entries whose name matches this glob pattern")
+                .value_parser(GlobParser)
+                .conflicts_with("regex"),
+        )
+        .arg(
+            Arg::new("regex")
+                .long("regex")
+                .help("only show entries whose name matches this regex")
+                .value_parser(RegexParser)
+                .conflicts_with("glob"),
+        )
+        .arg(
+            Arg::new("follow")
+                .long("follow")
+                .help("follow symlinked directories during recursion [default: false]")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            utils::subpath_arg("subpath")
+                .help("path inside the root to start walking from")
+                .required(true),
+        )
+}
+
+// Figure out the real type of a directory entry, falling back to an
+// O_PATH|O_NOFOLLOW fstat(2) if the kernel didn't give us `d_type` (some
+// filesystems don't fill it in).
+fn walk_entry_type(entry: &DirEntry) -> Result<FileType, Error> {
+    if entry.file_type() != FileType::Unknown {
+        return Ok(entry.file_type());
+    }
+    let f = entry.open(OpenFlags::O_PATH | OpenFlags::O_NOFOLLOW)?;
+    let stat = rustix_fs::fstat(&f)?;
+    Ok(rustix_fs::FileType::from_raw_mode(stat.st_mode.into()).into())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn walk_dir(
+    dir: &Handle,
+    rel_path: &Path,
+    depth: usize,
+    max_depth: Option<usize>,
+    types: Option<&[FileType]>,
+    pattern: Option<&Regex>,
+    follow: bool,
+) -> Result<(), Error> {
+    for entry in dir.read_dir()? {
+        let entry = entry?;
+        let entry_path = rel_path.join(entry.file_name());
+        let file_type = walk_entry_type(&entry)?;
+
+        let type_matches = types.map_or(true, |types| types.contains(&file_type));
+        let name_matches =
+            pattern.map_or(true, |re| re.is_match(&entry.file_name().to_string_lossy()));
+        if type_matches && name_matches {
+            println!("{}", entry_path.display());
+        }
+
+        if max_depth.map_or(false, |max| depth >= max) {
+            continue;
+        }
+
+        let descend_flags = match file_type {
+            FileType::Directory => Some(OpenFlags::O_DIRECTORY | OpenFlags::O_NOFOLLOW),
+            FileType::Symlink if follow => Some(OpenFlags::O_DIRECTORY),
+            _ => None,
+        };
+
+        if let Some(descend_flags) = descend_flags {
+            match entry.open(descend_flags) {
+                Ok(f) => walk_dir(
+                    &Handle::from_fd(f),
+                    &entry_path,
+                    depth + 1,
+                    max_depth,
+                    types,
+                    pattern,
+                    follow,
+                )?,
+                // A followed symlink that doesn't actually point to a directory
+                // just isn't descended into.
+                Err(_) if file_type == FileType::Symlink => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn walk(root: RootRef<'_>, matches: &ArgMatches) -> Result<(), Error> {
+    let subpath = matches
+        .get_one::<PathBuf>("subpath")
+        .expect("subpath should be set");
+    let types: Option<Vec<FileType>> = matches
+        .get_many::<FileType>("type")
+        .map(|types| types.copied().collect());
+    let max_depth = matches.get_one::<usize>("max-depth").copied();
+    let pattern = matches
+        .get_one::<Regex>("glob")
+        .or_else(|| matches.get_one::<Regex>("regex"));
+    let follow = matches.get_flag("follow");
+
+    let start = root.resolve(subpath)?;
+    walk_dir(
+        &start,
+        subpath,
+        0,
+        max_depth,
+        types.as_deref(),
+        pattern,
+        follow,
+    )
+}
+
+fn stat_cli() -> Command {
+    Command::new("stat")
+        .about("get the metadata of a path inside the root")
+        .args(utils::toggle_arg("follow", "follow a trailing symlink"))
+        .arg(
+            utils::subpath_arg("subpath")
+                .help("path inside the root")
+                .required(true),
+        )
+}
+
+fn stat(root: RootRef<'_>, matches: &ArgMatches) -> Result<(), Error> {
+    let subpath = matches
+        .get_one::<PathBuf>("subpath")
+        .expect("subpath should be set");
+    let follow = *matches
+        .get_one::<bool>("follow")
+        .expect("follow should be set");
+
+    let meta = if follow {
+        root.metadata(subpath)
+    } else {
+        root.metadata_nofollow(subpath)
+    }?;
+    utils::print_metadata(&meta);
+    Ok(())
+}
+
+fn serve_cli() -> Command {
+    Command::new("serve")
+        .about("export the root over 9P2000.L on a Unix socket, for a single client connection")
+        .arg(
+            Arg::new("socket")
+                .long("socket")
+                .help("path of the Unix socket to listen on")
+                .value_parser(clap::value_parser!(PathBuf))
+                .required(true),
+        )
+}
+
+fn serve(root: RootRef<'_>, matches: &ArgMatches) -> Result<(), Error> {
+    let socket_path = matches
+        .get_one::<PathBuf>("socket")
+        .expect("socket should be set");
+
+    let listener = UnixListener::bind(socket_path)?;
+    let (stream, _) = listener.accept()?;
+    Server::new(root.try_clone()?).serve(stream)?;
+    Ok(())
+}
+
 fn unlink_cli() -> Command {
     Command::new("unlink")
         .about("remove a file inside the root")
@@ -386,6 +602,24 @@ fn rmdir_all(root: RootRef<'_>, matches: &ArgMatches) -> Result<(), Error> {
     root.remove_all(subpath).map_err(Into::into)
 }
 
+fn remove_cli() -> Command {
+    Command::new("remove")
+        .about("remove a single inode (file or empty directory) inside the root")
+        .arg(
+            utils::subpath_arg("subpath")
+                .help("path inside the root")
+                .required(true),
+        )
+}
+
+fn remove(root: RootRef<'_>, matches: &ArgMatches) -> Result<(), Error> {
+    let subpath = matches
+        .get_one::<PathBuf>("subpath")
+        .expect("subpath should be set");
+
+    root.remove(subpath).map_err(Into::into)
+}
+
 fn rename_cli() -> Command {
     Command::new("rename")
         .about("rename a path inside the root")
@@ -470,9 +704,14 @@ pub(crate) fn cli() -> Command {
         .subcommand(hardlink_cli())
         .subcommand(symlink_cli())
         .subcommand(readlink_cli())
+        .subcommand(readdir_cli())
+        .subcommand(walk_cli())
+        .subcommand(stat_cli())
+        .subcommand(serve_cli())
         .subcommand(unlink_cli())
         .subcommand(rmdir_cli())
         .subcommand(rmdir_all_cli())
+        .subcommand(remove_cli())
         .subcommand(rename_cli())
 }
 
@@ -493,9 +732,14 @@ pub(crate) fn subcommand(matches: &ArgMatches) -> Result<(), Error> {
         Some(("hardlink", sub_matches)) => hardlink(root.as_ref(), sub_matches),
         Some(("symlink", sub_matches)) => symlink(root.as_ref(), sub_matches),
         Some(("readlink", sub_matches)) => readlink(root.as_ref(), sub_matches),
+        Some(("readdir", sub_matches)) => readdir(root.as_ref(), sub_matches),
+        Some(("walk", sub_matches)) => walk(root.as_ref(), sub_matches),
+        Some(("stat", sub_matches)) => stat(root.as_ref(), sub_matches),
+        Some(("serve", sub_matches)) => serve(root.as_ref(), sub_matches),
         Some(("unlink", sub_matches)) => unlink(root.as_ref(), sub_matches),
         Some(("rmdir", sub_matches)) => rmdir(root.as_ref(), sub_matches),
         Some(("rmdir-all", sub_matches)) => rmdir_all(root.as_ref(), sub_matches),
+        Some(("remove", sub_matches)) => remove(root.as_ref(), sub_matches),
         Some(("rename", sub_matches)) => rename(root.as_ref(), sub_matches),
         Some((subcommand, _)) => {
             // We should never end up here.
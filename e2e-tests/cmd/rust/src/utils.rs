@@ -12,7 +12,7 @@
 use pathrs::{
     flags::OpenFlags,
     procfs::{ProcfsBase, ProcfsHandle},
-    Handle,
+    FileType, Handle, Metadata,
 };
 
 use std::{
@@ -27,7 +27,14 @@ use std::{
 
 use anyhow::Error;
 use clap::{builder::TypedValueParser, error::ErrorKind as ClapErrorKind, Arg, ArgAction, Command};
+use regex::Regex;
 
+// Linux file names are arbitrary byte sequences, so path operands must be
+// parsed losslessly rather than being forced through UTF-8 `String`. clap's
+// built-in `PathBuf` parser already does this (it builds the `PathBuf`
+// straight from the argument's raw `OsStr`, with no UTF-8 validation), so
+// every `subpath`/path-like argument in this CLI goes through this helper
+// instead of `clap::value_parser!(String)`.
 pub(crate) fn subpath_arg(name: impl Into<clap::Id>) -> Arg {
     Arg::new(name).value_parser(clap::value_parser!(PathBuf))
 }
@@ -133,7 +140,108 @@ impl TypedValueParser for ModeParser {
     }
 }
 
-fn fd_path<Fd: AsFd>(fd: Fd) -> Result<PathBuf, Error> {
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FileTypeParser;
+
+impl TypedValueParser for FileTypeParser {
+    type Value = FileType;
+
+    fn parse_ref(
+        &self,
+        _cmd: &Command,
+        _arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let value = value.to_str().ok_or_else(|| {
+            clap::Error::raw(
+                ClapErrorKind::InvalidUtf8,
+                "type contained invalid utf8 characters",
+            )
+        })?;
+
+        match value {
+            "f" => Ok(FileType::File),
+            "d" => Ok(FileType::Directory),
+            "l" => Ok(FileType::Symlink),
+            "b" => Ok(FileType::BlockDevice),
+            "c" => Ok(FileType::CharacterDevice),
+            "p" => Ok(FileType::Fifo),
+            "s" => Ok(FileType::Socket),
+            _ => Err(clap::Error::raw(
+                ClapErrorKind::ValueValidation,
+                format!("{value} is not a valid type (expected one of f, d, l, b, c, p, s)"),
+            )),
+        }
+    }
+}
+
+// Translate a shell-style glob pattern (matching a single path component, so
+// "*" and "?" never cross a "/") into an anchored regex that matches the
+// whole string.
+fn glob_to_regex(pattern: &str) -> String {
+    let mut re = String::from("^");
+    for ch in pattern.chars() {
+        match ch {
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            _ => re.push_str(&regex::escape(&ch.to_string())),
+        }
+    }
+    re.push('$');
+    re
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GlobParser;
+
+impl TypedValueParser for GlobParser {
+    type Value = Regex;
+
+    fn parse_ref(
+        &self,
+        _cmd: &Command,
+        _arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let value = value.to_str().ok_or_else(|| {
+            clap::Error::raw(
+                ClapErrorKind::InvalidUtf8,
+                "glob contained invalid utf8 characters",
+            )
+        })?;
+
+        Regex::new(&glob_to_regex(value)).map_err(|err| {
+            clap::Error::raw(ClapErrorKind::ValueValidation, format!("invalid glob: {err}"))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RegexParser;
+
+impl TypedValueParser for RegexParser {
+    type Value = Regex;
+
+    fn parse_ref(
+        &self,
+        _cmd: &Command,
+        _arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let value = value.to_str().ok_or_else(|| {
+            clap::Error::raw(
+                ClapErrorKind::InvalidUtf8,
+                "regex contained invalid utf8 characters",
+            )
+        })?;
+
+        Regex::new(value).map_err(|err| {
+            clap::Error::raw(ClapErrorKind::ValueValidation, format!("invalid regex: {err}"))
+        })
+    }
+}
+
+pub(crate) fn fd_path<Fd: AsFd>(fd: Fd) -> Result<PathBuf, Error> {
     let fd = fd.as_fd();
     ProcfsHandle::new()?
         .readlink(ProcfsBase::ProcThreadSelf, format!("fd/{}", fd.as_raw_fd()))
@@ -145,8 +253,66 @@ pub(crate) fn print_handle(h: &Handle) -> Result<(), Error> {
     Ok(())
 }
 
+// This is basically ErrorKind::errno (which isn't exported currently). This
+// is necessary in order to emulate the behaviour of the binding test
+// programs, as they see the converted errnos as well as the legitimate
+// OsError ones.
+pub(crate) fn error_errno(err: &pathrs::error::Error) -> Option<i32> {
+    use pathrs::error::ErrorKind;
+    match err.kind() {
+        ErrorKind::NotImplemented | ErrorKind::NotSupported => Some(libc::ENOSYS),
+        ErrorKind::InvalidArgument => Some(libc::EINVAL),
+        ErrorKind::OsError(errno) => errno,
+        ErrorKind::SafetyViolation => Some(libc::EXDEV),
+        ErrorKind::ResourceExhausted => Some(libc::ELOOP),
+        _ => None,
+    }
+}
+
+pub(crate) fn error_kind_name(kind: pathrs::error::ErrorKind) -> &'static str {
+    use pathrs::error::ErrorKind;
+    match kind {
+        ErrorKind::NotImplemented => "NotImplemented",
+        ErrorKind::NotSupported => "NotSupported",
+        ErrorKind::InvalidArgument => "InvalidArgument",
+        ErrorKind::SafetyViolation => "SafetyViolation",
+        ErrorKind::InternalError => "InternalError",
+        ErrorKind::OsError(_) => "OsError",
+        _ => "Unknown",
+    }
+}
+
+pub(crate) fn file_type_name(file_type: FileType) -> &'static str {
+    match file_type {
+        FileType::Unknown => "?",
+        FileType::Fifo => "p",
+        FileType::CharacterDevice => "c",
+        FileType::Directory => "d",
+        FileType::BlockDevice => "b",
+        FileType::File => "f",
+        FileType::Symlink => "l",
+        FileType::Socket => "s",
+        _ => "?",
+    }
+}
+
 pub(crate) fn print_file(f: &File) -> Result<(), Error> {
     println!("FILE-PATH {}", fd_path(f)?.to_string_lossy());
     // TODO: Do some other operations on files.
     Ok(())
 }
+
+pub(crate) fn print_metadata(meta: &Metadata) {
+    println!(
+        "METADATA type={} mode={:o} uid={} gid={} size={} mnt-id={} btime={}",
+        file_type_name(meta.file_type()),
+        meta.mode(),
+        meta.uid(),
+        meta.gid(),
+        meta.len(),
+        meta.mount_id()
+            .map_or_else(|| "?".to_string(), |id| id.to_string()),
+        meta.created()
+            .map_or_else(|| "?".to_string(), |t| format!("{t:?}")),
+    );
+}
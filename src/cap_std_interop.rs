@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: MPL-2.0 OR LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * == MPL-2.0 ==
+ *
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Alternatively, this Source Code Form may also (at your option) be used
+ * under the terms of the GNU Lesser General Public License Version 3, as
+ * described below:
+ *
+ * == LGPL-3.0-or-later ==
+ *
+ *  This program is free software: you can redistribute it and/or modify it
+ *  under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or (at
+ *  your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful, but
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY  or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General
+ * Public License  for more details.
+ *
+ *  You should have received a copy of the GNU Lesser General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#![forbid(unsafe_code)]
+
+//! Conversions between a [`Root`]/[`Handle`] and [`cap-std`]'s
+//! capability-based [`Dir`]/[`File`], for programs that want to use
+//! libpathrs purely as the hardened resolver that produces a sandbox root,
+//! and then drive the rest of their filesystem access through cap-std's
+//! ambient-authority-free API.
+//!
+//! None of the conversions here re-resolve a path: the [`Root`]/[`Dir`]
+//! conversions hand over the existing directory file descriptor (duplicated
+//! with `dup(2)` when converting a [`Root`] into a [`Dir`]), and
+//! [`Handle::into_cap_std_file`] re-opens the handle's own file descriptor
+//! through [`Handle::reopen`]. A [`Root`] handed off to cap-std therefore
+//! still rejects `..`/symlink escapes exactly as it did before the handoff
+//! (cap-std enforces its own, separate discipline on top of that fd; it
+//! doesn't undo libpathrs's).
+//!
+//! [`Handle::into_cap_std_file`]: crate::Handle::into_cap_std_file
+//! [`Handle::reopen`]: crate::Handle::reopen
+//!
+//! [`cap-std`]: https://docs.rs/cap-std
+//! [`Dir`]: cap_std::fs::Dir
+//! [`File`]: cap_std::fs::File
+//! [`Root`]: crate::Root
+//! [`Handle`]: crate::Handle
+
+use crate::{
+    error::{Error, ErrorImpl},
+    flags::OpenFlags,
+    Handle, Root,
+};
+
+use std::os::unix::io::{AsFd, OwnedFd};
+
+use rustix::fs as rustix_fs;
+
+impl TryFrom<Root> for cap_std::fs::Dir {
+    type Error = Error;
+
+    /// Hand `root` off to cap-std, duplicating its underlying directory fd
+    /// -- no `openat(2)` (and thus no re-resolution) is involved.
+    fn try_from(root: Root) -> Result<Self, Error> {
+        let fd: OwnedFd = root
+            .as_fd()
+            .try_clone_to_owned()
+            .map_err(|err| ErrorImpl::OsError {
+                operation: "clone root directory fd for cap-std handoff".into(),
+                source: err,
+            })?;
+        Ok(cap_std::fs::Dir::from(fd))
+    }
+}
+
+impl Root {
+    /// Adopt an already-open cap-std [`Dir`](cap_std::fs::Dir) as a
+    /// [`Root`], without re-opening or re-resolving anything.
+    ///
+    /// Fails if `dir` doesn't actually refer to a directory -- this should
+    /// not be possible for a well-formed [`Dir`](cap_std::fs::Dir), but
+    /// libpathrs doesn't trust a file descriptor it didn't resolve itself.
+    pub fn from_cap_std_dir(dir: cap_std::fs::Dir) -> Result<Self, Error> {
+        let fd: OwnedFd = dir.into();
+
+        let stat = rustix_fs::fstat(&fd).map_err(|err| ErrorImpl::OsError {
+            operation: "fstat adopted cap-std directory".into(),
+            source: err.into(),
+        })?;
+        if rustix_fs::FileType::from_raw_mode(stat.st_mode) != rustix_fs::FileType::Directory {
+            return Err(ErrorImpl::InvalidArgument {
+                name: "dir".into(),
+                description: "cap-std handle being adopted does not refer to a directory".into(),
+            }
+            .into());
+        }
+
+        Ok(Root::from_fd(fd))
+    }
+}
+
+impl Handle {
+    /// Upgrade this (non-directory) handle to a cap-std
+    /// [`File`](cap_std::fs::File), via the same re-open used by
+    /// [`Handle::reopen`].
+    ///
+    /// [`Handle::reopen`]: crate::Handle::reopen
+    pub fn into_cap_std_file(
+        self,
+        flags: impl Into<OpenFlags>,
+    ) -> Result<cap_std::fs::File, Error> {
+        let file = self.reopen(flags)?;
+        Ok(cap_std::fs::File::from_std(file))
+    }
+}
@@ -17,76 +17,213 @@
  * along with this program. If not, see <https://www.gnu.org/licenses/>.
  */
 
-use crate::error::{Error, ErrorImpl};
+use crate::{
+    capi::{
+        ret::{CReturn, IntoCReturn},
+        utils,
+    },
+    error::{Error, ErrorImpl},
+    flags::ResolverFlags,
+};
 
-use std::{cmp, mem, ptr};
+use bitflags::bitflags;
+use bytemuck::{Pod, Zeroable};
 
-/// ### Safety
-///
-/// Implementing this trait means the type you are using has the following
-/// properties that make it safe to be used as an extensible structure:
-///
-///  1. The structure is `#[repr(C)]` and is C FFI safe.
-///  2. The structure can safely be filled with any bit pattern (including but
-///     not limited to `mem::zeroed()`).
-///  3. The structure contains no padding (ideally *not* through
-///     `#[repr(packed)]` because of the risk of unaligned reads, but instead by
-///     making sure that different integer types).
-// TODO: Should we use zerocopy traits here instead? The specific semantics we
-//       need for copy_struct_from don't really match zerocopy but we could use
-//       FromZeros/FromBytes. Then again, we should avoid adding new deps if
-//       possible.
-unsafe trait ExtensibleStruct: Sized {
-    fn zeroed() -> Self {
-        // SAFETY: Implementing this trait means this must be safe.
-        unsafe { mem::zeroed() }
+bitflags! {
+    /// Flags controlling how resolution through [`CConfig`] should behave,
+    /// mirroring the `RESOLVE_*` flags taken by [`openat2(2)`].
+    ///
+    /// [`openat2(2)`]: https://www.man7.org/linux/man-pages/man2/openat2.2.html
+    #[repr(C)]
+    #[derive(Default, Debug, Clone, Copy, Pod, Zeroable)]
+    pub struct CResolveFlags: u64 {
+        /// Equivalent to `RESOLVE_NO_SYMLINKS`: reject any symlink resolution
+        /// entirely (not just magic-links).
+        const NO_SYMLINKS = 0x0001;
+        /// Equivalent to `RESOLVE_NO_MAGICLINKS`: reject magic-link (but not
+        /// regular symlink) resolution.
+        const NO_MAGICLINKS = 0x0002;
+        /// Equivalent to `RESOLVE_BENEATH`: the resolved path must not escape
+        /// the root, even temporarily through `..`.
+        const BENEATH = 0x0004;
+        /// Equivalent to `RESOLVE_IN_ROOT`: the resolved path is confined to
+        /// the root, with `..` components scoped to the root (chroot-like).
+        const IN_ROOT = 0x0008;
+        /// Equivalent to `RESOLVE_NO_XDEV`: reject resolution which would
+        /// cross a mount point.
+        const NO_XDEV = 0x0010;
+        // NOTE: Make sure to add a `pub const` for any new flags to make sure
+        // they show up when cbindgen generates our header.
     }
+}
 
-    fn as_chr_ptr(ptr: *const Self) -> *const u8 {
-        // SAFETY: Implementing this trait means that the structure has a
-        // consistent [u8] representation.
-        ptr as *const u8
+impl CResolveFlags {
+    const fn contains_unknown_bits(&self) -> bool {
+        Self::from_bits(self.bits()).is_none()
     }
 }
 
-unsafe fn memchr_inv(needle: u8, haystack: *const u8, size: usize) -> Option<*const u8> {
-    debug_assert!(size <= isize::MAX as usize, "size must be valid");
-    for idx in 0..=size {
-        // SAFETY: The caller guarantees that the buffer is valid for size
-        // bytes.
-        let ptr = unsafe { haystack.offset(idx as isize) };
-        if unsafe { *ptr } != needle {
-            return Some(ptr);
-        }
-    }
-    None
+static_assertions::const_assert_eq!(CResolveFlags::all().contains_unknown_bits(), false);
+static_assertions::const_assert_eq!(
+    CResolveFlags::from_bits_retain(0x1000_0000).contains_unknown_bits(),
+    true,
+);
+
+/// `struct pathrs_config` -- a versioned, extensible configuration structure
+/// used to configure resolution behaviour across the C API.
+#[repr(C)]
+#[derive(Default, Debug, Clone, Copy, Pod, Zeroable)]
+pub struct CConfig {
+    pub flags: CResolveFlags,
+    // Reserved for future growth. Must always be zero -- a non-zero value
+    // here from a newer caller is rejected by into_resolver_flags with the
+    // same E2BIG error that copy_from_extensible_struct uses for non-zero
+    // trailing bytes.
+    _reserved: [u64; 4],
 }
 
-unsafe fn copy_struct_from<T: ExtensibleStruct>(src: *const T, user_size: usize) -> Option<T> {
-    let lib_size = mem::size_of::<T>();
-    let size = cmp::min(user_size, lib_size);
-    let rest = user_size - size;
-    debug_assert!(rest >= 0, "remaining size needs to be non-negative");
-    debug_assert!(size + rest == user_size);
-
-    // SAFETY: We only operate within src[0..user_size] here.
-    unsafe {
-        let mut dst = T::zeroed();
-        let ptr = ptr::from_mut(&mut dst) as *mut u8;
-        let trailing = ptr.offset(size as isize);
-        if memchr_inv(0u8, trailing, rest).is_some() {
-            return None;
+impl CConfig {
+    fn into_resolver_flags(self) -> Result<ResolverFlags, Error> {
+        if self.flags.contains_unknown_bits() {
+            return Err(ErrorImpl::InvalidArgument {
+                name: "flags".into(),
+                description: format!(
+                    "contains unknown flag bits {:#x}",
+                    self.flags.difference(CResolveFlags::all()).bits()
+                )
+                .into(),
+            })?;
+        }
+        if self._reserved.iter().any(|&field| field != 0) {
+            return Err(ErrorImpl::UnsupportedStructureData {
+                name: "c struct pathrs_config (reserved fields)".into(),
+            })?;
+        }
+
+        // NO_MAGICLINKS, BENEATH and IN_ROOT are part of the wire format (to
+        // avoid an ABI break down the line), but no resolver backend in this
+        // tree implements the confinement behaviour they promise. Rather
+        // than silently accepting bits we don't enforce -- unacceptable for
+        // a path-confinement library -- reject them outright.
+        for (flag, feature) in [
+            (CResolveFlags::NO_MAGICLINKS, "RESOLVE_NO_MAGICLINKS"),
+            (CResolveFlags::BENEATH, "RESOLVE_BENEATH"),
+            (CResolveFlags::IN_ROOT, "RESOLVE_IN_ROOT"),
+        ] {
+            if self.flags.contains(flag) {
+                return Err(ErrorImpl::NotImplemented { feature: feature.into() })?;
+            }
+        }
+
+        let mut rflags = ResolverFlags::empty();
+        if self.flags.contains(CResolveFlags::NO_SYMLINKS) {
+            rflags |= ResolverFlags::NO_SYMLINKS;
         }
-        ptr::copy_nonoverlapping(T::as_chr_ptr(src), ptr, size);
-        Some(dst)
+        if self.flags.contains(CResolveFlags::NO_XDEV) {
+            rflags |= ResolverFlags::NO_XDEV;
+        }
+        Ok(rflags)
     }
 }
 
-#[repr(C)]
-struct CConfig {
-    flags: u64,
+/// Parse and validate a `struct pathrs_config` passed in by a C caller.
+///
+/// This follows the same extensible-struct rules as other versioned
+/// structures in the C API (such as `struct pathrs_procfs_open_how`): `size`
+/// acts as an implicit version number, with fields missing from an older
+/// caller's struct treated as zero (backwards-compatible), and unknown but
+/// non-zero trailing fields from a newer caller's struct rejected with
+/// `E2BIG` (forwards-compatible). Unknown bits set in `flags` are rejected
+/// with `EINVAL`, and bits for confinement behaviour that no resolver in
+/// this tree actually enforces yet (`RESOLVE_NO_MAGICLINKS`,
+/// `RESOLVE_BENEATH`, `RESOLVE_IN_ROOT`) are rejected with `ENOSYS` -- a
+/// caller must never get a success return code while getting less
+/// confinement than they asked for.
+///
+/// Not yet mirrored: this only validates a `struct pathrs_config` in
+/// isolation -- the resulting [`ResolverFlags`] aren't threaded to any
+/// `Root`/resolve entry point yet, since those live in the not-yet-present
+/// `capi::core` module.
+///
+/// On success, returns 0. On error, returns a negative error code -- use
+/// pathrs_errorinfo() to get more information about the error.
+#[no_mangle]
+pub unsafe extern "C" fn pathrs_config_parse(config: *const CConfig, size: usize) -> CReturn {
+    || -> Result<_, Error> {
+        // SAFETY: The C caller guarantees that config is valid for size
+        // bytes, as required by copy_from_extensible_struct.
+        let config = unsafe { utils::copy_from_extensible_struct(config, size) }?;
+        config.into_resolver_flags()?;
+        Ok(())
+    }()
+    .into_c_return()
 }
 
-// SAFETY: CConfig is #[repr(C)], only contains primitive integer types and is
-//         structured to ensure it has no padding.
-unsafe impl ExtensibleStruct for CConfig {}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::error::ErrorKind;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn no_symlinks_is_wired_to_resolver_flags() {
+        let config = CConfig { flags: CResolveFlags::NO_SYMLINKS, ..Default::default() };
+        assert_eq!(
+            config.into_resolver_flags().map_err(|err| err.kind()),
+            Ok(ResolverFlags::NO_SYMLINKS),
+        );
+    }
+
+    #[test]
+    fn no_xdev_is_wired_to_resolver_flags() {
+        let config = CConfig { flags: CResolveFlags::NO_XDEV, ..Default::default() };
+        assert_eq!(
+            config.into_resolver_flags().map_err(|err| err.kind()),
+            Ok(ResolverFlags::NO_XDEV),
+        );
+    }
+
+    #[test]
+    fn no_magiclinks_is_rejected_as_not_implemented() {
+        let config = CConfig { flags: CResolveFlags::NO_MAGICLINKS, ..Default::default() };
+        assert_eq!(
+            config.into_resolver_flags().map_err(|err| err.kind()),
+            Err(ErrorKind::NotImplemented),
+            "NO_MAGICLINKS must not be silently accepted -- no resolver enforces it",
+        );
+    }
+
+    #[test]
+    fn beneath_is_rejected_as_not_implemented() {
+        let config = CConfig { flags: CResolveFlags::BENEATH, ..Default::default() };
+        assert_eq!(
+            config.into_resolver_flags().map_err(|err| err.kind()),
+            Err(ErrorKind::NotImplemented),
+            "BENEATH must not be silently accepted -- no resolver enforces it",
+        );
+    }
+
+    #[test]
+    fn in_root_is_rejected_as_not_implemented() {
+        let config = CConfig { flags: CResolveFlags::IN_ROOT, ..Default::default() };
+        assert_eq!(
+            config.into_resolver_flags().map_err(|err| err.kind()),
+            Err(ErrorKind::NotImplemented),
+            "IN_ROOT must not be silently accepted -- no resolver enforces it",
+        );
+    }
+
+    #[test]
+    fn unknown_bits_are_rejected() {
+        let config = CConfig {
+            flags: CResolveFlags::from_bits_retain(0x1000_0000),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.into_resolver_flags().map_err(|err| err.kind()),
+            Err(ErrorKind::InvalidArgument),
+        );
+    }
+}
@@ -24,7 +24,9 @@ use crate::{
     },
     error::{Error, ErrorExt, ErrorImpl},
     flags::OpenFlags,
-    procfs::{ProcfsBase, ProcfsHandle, ProcfsHandleBuilder, ProcfsHandleRef},
+    metadata::Metadata,
+    procfs::{ProcfsBase, ProcfsHandle, ProcfsHandleBuilder, ProcfsHandleRef, ProcfsHidePid},
+    FileType,
 };
 
 use std::os::unix::io::{AsRawFd, IntoRawFd, OwnedFd, RawFd};
@@ -141,7 +143,7 @@ static_assertions::const_assert_eq!(
     CProcfsBase::PATHRS_PROC_THREAD_SELF.0 & __PATHRS_PROC_TYPE_MASK,
 );
 
-impl TryFrom<CProcfsBase> for ProcfsBase {
+impl<'fd> TryFrom<CProcfsBase> for ProcfsBase<'fd> {
     type Error = Error;
 
     fn try_from(c_base: CProcfsBase) -> Result<Self, Self::Error> {
@@ -194,9 +196,15 @@ impl TryFrom<CProcfsBase> for ProcfsBase {
 }
 
 #[cfg(test)]
-impl From<ProcfsBase> for CProcfsBase {
-    fn from(base: ProcfsBase) -> Self {
+impl<'fd> From<ProcfsBase<'fd>> for CProcfsBase {
+    fn from(base: ProcfsBase<'fd>) -> Self {
         match base {
+            // TODO: Figure out how to encode a pidfd into a CProcfsBase (we'd
+            //       need a dedicated C entry point taking a separate fd
+            //       argument, since CProcfsBase itself doesn't own any fds).
+            ProcfsBase::ProcPidFd(_) => {
+                unimplemented!("CProcfsBase encoding for ProcfsBase::ProcPidFd is not supported yet")
+            }
             ProcfsBase::ProcPid(pid) => {
                 // TODO: See if we can add some kind of static assertion that
                 //       the type of the pid is not larger than the reserved
@@ -254,16 +262,52 @@ fn parse_proc_rootfd<'fd>(fd: CBorrowedFd<'fd>) -> Result<ProcfsHandleRef<'fd>,
 /// pathrs_procfs_open_how`) for use with pathrs_procfs_open().
 pub const PATHRS_PROCFS_NEW_UNMASKED: u64 = 0x0000_0000_0000_0001;
 
+/// Equivalent to [`ProcfsHandleBuilder::allow_seccomp_fallback`].
+///
+/// This is meant as a flag argument to [`ProcfsOpenFlags`] (the `flags` field
+/// in `struct pathrs_procfs_open_how`) for use with pathrs_procfs_open().
+pub const PATHRS_PROCFS_ALLOW_SECCOMP_FALLBACK: u64 = 0x0000_0000_0000_0002;
+
 bitflags! {
     #[repr(C)]
     #[derive(Default, Debug, Clone, Copy, Pod, Zeroable)]
     pub struct ProcfsOpenFlags: u64 {
         const PATHRS_PROCFS_NEW_UNMASKED = PATHRS_PROCFS_NEW_UNMASKED;
+        const PATHRS_PROCFS_ALLOW_SECCOMP_FALLBACK = PATHRS_PROCFS_ALLOW_SECCOMP_FALLBACK;
         // NOTE: Make sure to add a `pub const` for any new flags to make
         // sure they show up when cbindgen generates our header.
     }
 }
 
+/// Values for the `subset` field of `struct pathrs_procfs_open_how`,
+/// equivalent to [`ProcfsHandleBuilder::subset_pid`].
+///
+/// `PATHRS_PROCFS_SUBSET_DEFAULT` (`0`) means "use the current default",
+/// per the extensible-struct zero-means-no-op contract documented on
+/// pathrs_procfs_open().
+pub const PATHRS_PROCFS_SUBSET_DEFAULT: u32 = 0;
+/// Equivalent to `ProcfsHandleBuilder::subset_pid(false)`.
+pub const PATHRS_PROCFS_SUBSET_NONE: u32 = 1;
+/// Equivalent to `ProcfsHandleBuilder::subset_pid(true)`.
+pub const PATHRS_PROCFS_SUBSET_PID: u32 = 2;
+
+/// Values for the `hidepid` field of `struct pathrs_procfs_open_how`,
+/// equivalent to [`ProcfsHandleBuilder::hidepid`].
+///
+/// `PATHRS_PROCFS_HIDEPID_DEFAULT` (`0`) means "use the current default",
+/// per the extensible-struct zero-means-no-op contract documented on
+/// pathrs_procfs_open(). Note that (as with [`ProcfsHandleBuilder::hidepid`])
+/// this only has any effect if `subset` is set to `PATHRS_PROCFS_SUBSET_PID`.
+pub const PATHRS_PROCFS_HIDEPID_DEFAULT: u32 = 0;
+/// Equivalent to [`ProcfsHidePid::Off`].
+pub const PATHRS_PROCFS_HIDEPID_OFF: u32 = 1;
+/// Equivalent to [`ProcfsHidePid::NoAccess`].
+pub const PATHRS_PROCFS_HIDEPID_NOACCESS: u32 = 2;
+/// Equivalent to [`ProcfsHidePid::Invisible`].
+pub const PATHRS_PROCFS_HIDEPID_INVISIBLE: u32 = 3;
+/// Equivalent to [`ProcfsHidePid::Ptraceable`].
+pub const PATHRS_PROCFS_HIDEPID_PTRACEABLE: u32 = 4;
+
 impl ProcfsOpenFlags {
     const fn contains_unknown_bits(&self) -> bool {
         Self::from_bits(self.bits()).is_none()
@@ -287,6 +331,27 @@ static_assertions::const_assert_eq!(
 #[derive(Default, Debug, Clone, Copy, Pod, Zeroable)]
 pub struct ProcfsOpenHow {
     pub flags: ProcfsOpenFlags,
+    /// One of the `PATHRS_PROCFS_SUBSET_*` values, or `0`
+    /// (`PATHRS_PROCFS_SUBSET_DEFAULT`) to leave the current default
+    /// untouched.
+    pub subset: u32,
+    /// One of the `PATHRS_PROCFS_HIDEPID_*` values, or `0`
+    /// (`PATHRS_PROCFS_HIDEPID_DEFAULT`) to leave the current default
+    /// untouched. Only has any effect if `subset` is
+    /// `PATHRS_PROCFS_SUBSET_PID`.
+    pub hidepid: u32,
+    /// A file descriptor referencing a mount namespace (such as one obtained
+    /// from `/proc/<pid>/ns/mnt`) that the freshly-created private procfs
+    /// should be sourced from, or `0` to use the caller's current mount
+    /// namespace.
+    ///
+    /// As with every other field here, `0` is the "leave the default
+    /// untouched" value required by the extensible-struct contract described
+    /// above -- this is deliberate (rather than the more usual "negative fd
+    /// means unset" convention) since a caller wanting to pin `mntns_fd` to
+    /// stdin specifically is not a use case worth supporting at the expense
+    /// of every other caller's zero-initialised struct being misinterpreted.
+    pub mntns_fd: RawFd,
 }
 
 impl ProcfsOpenHow {
@@ -309,11 +374,149 @@ impl ProcfsOpenHow {
         {
             builder.set_unmasked();
         }
+        if self
+            .flags
+            .contains(ProcfsOpenFlags::PATHRS_PROCFS_ALLOW_SECCOMP_FALLBACK)
+        {
+            builder.set_allow_seccomp_fallback(true);
+        }
+
+        match self.subset {
+            PATHRS_PROCFS_SUBSET_DEFAULT => {}
+            PATHRS_PROCFS_SUBSET_NONE => {
+                builder.set_subset_pid(false);
+            }
+            PATHRS_PROCFS_SUBSET_PID => {
+                builder.set_subset_pid(true);
+            }
+            value => {
+                return Err(ErrorImpl::InvalidArgument {
+                    name: "subset".into(),
+                    description: format!("{value:#x} is not a valid PATHRS_PROCFS_SUBSET_* value")
+                        .into(),
+                })?
+            }
+        }
+        match self.hidepid {
+            PATHRS_PROCFS_HIDEPID_DEFAULT => {}
+            PATHRS_PROCFS_HIDEPID_OFF => {
+                builder.set_hidepid(ProcfsHidePid::Off);
+            }
+            PATHRS_PROCFS_HIDEPID_NOACCESS => {
+                builder.set_hidepid(ProcfsHidePid::NoAccess);
+            }
+            PATHRS_PROCFS_HIDEPID_INVISIBLE => {
+                builder.set_hidepid(ProcfsHidePid::Invisible);
+            }
+            PATHRS_PROCFS_HIDEPID_PTRACEABLE => {
+                builder.set_hidepid(ProcfsHidePid::Ptraceable);
+            }
+            value => {
+                return Err(ErrorImpl::InvalidArgument {
+                    name: "hidepid".into(),
+                    description: format!(
+                        "{value:#x} is not a valid PATHRS_PROCFS_HIDEPID_* value"
+                    )
+                    .into(),
+                })?
+            }
+        }
+
+        if self.mntns_fd != 0 {
+            // SAFETY: We only construct this from a C-provided fd value, and
+            // try_as_borrowed_fd() below checks it is not negative before
+            // treating it as a live borrow.
+            let mntns_fd = unsafe { CBorrowedFd::from_raw_fd(self.mntns_fd) };
+            let mntns_fd = mntns_fd.try_as_borrowed_fd()?;
+            let mntns_fd = mntns_fd.try_clone_to_owned().map_err(|err| ErrorImpl::OsError {
+                operation: "clone mntns_fd for procfs builder".into(),
+                source: err,
+            })?;
+            builder.set_mntns_fd(mntns_fd);
+        }
 
         Ok(builder)
     }
 }
 
+/// Structured `statx(2)`-like metadata for a file inside `procfs`, returned
+/// through a caller-provided buffer by pathrs_proc_stat() /
+/// pathrs_proc_statat() (`struct pathrs_proc_stat`).
+///
+/// This is an extensible struct, following the same `size`-as-version-number
+/// scheme as [`ProcfsOpenHow`] -- see the documentation of
+/// pathrs_procfs_open() for details. Unlike [`ProcfsOpenHow`] (which the
+/// caller fills in), this struct is filled in by libpathrs, so callers should
+/// zero-fill it before the call so that any fields added by a newer libpathrs
+/// than the caller was built against end up as `0` rather than uninitialised
+/// memory.
+#[repr(C)]
+#[derive(Default, Debug, Clone, Copy, Pod, Zeroable)]
+pub struct ProcfsStat {
+    /// Size of the file, in bytes (for regular files) or the length of the
+    /// symlink target (for symlinks).
+    pub size: u64,
+    /// The unique ID of the mount the file lives on, valid only if
+    /// `mnt_id_valid` is non-zero.
+    pub mnt_id: u64,
+    /// Creation ("birth") time of the file, seconds component, valid only if
+    /// `btime_valid` is non-zero.
+    pub btime_sec: i64,
+    /// `st_mode`-style mode, combining the permission bits with the
+    /// `S_IFMT` file type bits.
+    pub mode: u32,
+    /// The owning user ID of the file.
+    pub uid: u32,
+    /// The owning group ID of the file.
+    pub gid: u32,
+    /// Creation ("birth") time of the file, nanoseconds component, valid
+    /// only if `btime_valid` is non-zero.
+    pub btime_nsec: u32,
+    /// Whether `mnt_id` is valid (the kernel supports `STATX_MNT_ID`/
+    /// `STATX_MNT_ID_UNIQUE`, Linux 5.8 and later).
+    pub mnt_id_valid: u8,
+    /// Whether `btime_sec`/`btime_nsec` are valid (the kernel and
+    /// filesystem support `STATX_BTIME`, Linux 4.11 and later).
+    pub btime_valid: u8,
+    _padding: [u8; 6],
+}
+
+impl From<Metadata> for ProcfsStat {
+    fn from(meta: Metadata) -> Self {
+        let ifmt: u32 = match meta.file_type() {
+            FileType::Fifo => libc::S_IFIFO,
+            FileType::CharacterDevice => libc::S_IFCHR,
+            FileType::Directory => libc::S_IFDIR,
+            FileType::BlockDevice => libc::S_IFBLK,
+            FileType::File => libc::S_IFREG,
+            FileType::Symlink => libc::S_IFLNK,
+            FileType::Socket => libc::S_IFSOCK,
+            FileType::Unknown => 0,
+        };
+
+        Self {
+            size: meta.len(),
+            mnt_id: meta.mount_id().unwrap_or(0),
+            mnt_id_valid: meta.mount_id().is_some() as u8,
+            btime_sec: meta
+                .created()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0),
+            btime_nsec: meta
+                .created()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.subsec_nanos())
+                .unwrap_or(0),
+            btime_valid: meta.created().is_some() as u8,
+            mode: meta.mode() | ifmt,
+            uid: meta.uid(),
+            gid: meta.gid(),
+            _padding: [0; 6],
+        }
+    }
+}
+
 /// Create a new (custom) procfs root handle.
 ///
 /// This is effectively a C wrapper around [`ProcfsHandleBuilder`], allowing you
@@ -511,6 +714,51 @@ pub unsafe extern "C" fn pathrs_proc_open(
     pathrs_proc_openat(PATHRS_PROC_DEFAULT_ROOTFD, base, path, flags)
 }
 
+/// Identical to `pathrs_proc_openat`, except that instead of a `CProcfsBase`
+/// naming the target process by raw PID (`PATHRS_PROC_PID(n)`), the target
+/// process is named by a `pidfd` (as returned by `pidfd_open(2)`).
+///
+/// `PATHRS_PROC_PID(n)` is inherently racy: the PID `n` names whatever
+/// process happens to own it by the time libpathrs opens `/proc/<n>`, which
+/// may not be the process the caller originally meant if it has exited and
+/// the PID has been recycled in the meantime. `pathrs_proc_open_pidfd`
+/// closes this window by resolving the path underneath `/proc/<pid>` and
+/// then re-checking (via `pidfd_send_signal(pidfd, 0, NULL, 0)`) that the
+/// process the `pidfd` refers to is still alive, failing the operation
+/// instead of silently returning a handle that may belong to an unrelated,
+/// recycled PID.
+///
+/// # Return Value
+///
+/// On success, this function returns a file descriptor. The file descriptor
+/// will have the `O_CLOEXEC` flag automatically applied.
+///
+/// If an error occurs, this function will return a negative error code. To
+/// retrieve information about the error (such as a string describing the error,
+/// the system errno(7) value associated with the error, etc), use
+/// pathrs_errorinfo().
+#[no_mangle]
+pub unsafe extern "C" fn pathrs_proc_open_pidfd(
+    proc_rootfd: CBorrowedFd<'_>,
+    pidfd: CBorrowedFd<'_>,
+    path: *const c_char,
+    flags: c_int,
+) -> RawFd {
+    || -> Result<_, Error> {
+        let pidfd = pidfd.try_as_borrowed_fd()?;
+        let path = unsafe { utils::parse_path(path) }?; // SAFETY: C caller guarantees path is safe.
+        let oflags = OpenFlags::from_bits_retain(flags);
+        let procfs = parse_proc_rootfd(proc_rootfd)?;
+
+        match oflags.contains(OpenFlags::O_NOFOLLOW) {
+            true => procfs.open_process(pidfd, path, oflags),
+            false => procfs.open_process_follow(pidfd, path, oflags),
+        }
+    }()
+    .map(OwnedFd::from)
+    .into_c_return()
+}
+
 /// `pathrs_proc_readlink` but with a caller-provided file descriptor for
 /// `/proc`.
 ///
@@ -520,10 +768,12 @@ pub unsafe extern "C" fn pathrs_proc_open(
 /// # Return Value
 ///
 /// On success, this function copies the symlink contents to `linkbuf` (up to
-/// `linkbuf_size` bytes) and returns the full size of the symlink path buffer.
-/// This function will not copy the trailing NUL byte, and the return size does
-/// not include the NUL byte. A `NULL` `linkbuf` or invalid `linkbuf_size` are
-/// treated as zero-size buffers.
+/// `linkbuf_size - 1` bytes, always leaving room for a NUL terminator) and
+/// returns the full size of the symlink path buffer (not including the NUL
+/// byte). `linkbuf` is always NUL-terminated within `linkbuf_size` when
+/// non-`NULL`, even if the symlink contents had to be truncated to fit. A
+/// `NULL` `linkbuf` or invalid `linkbuf_size` are treated as zero-size
+/// buffers.
 ///
 /// NOTE: Unlike readlinkat(2), in the case where linkbuf is too small to
 /// contain the symlink contents, pathrs_proc_readlink() will return *the number
@@ -576,10 +826,12 @@ pub unsafe extern "C" fn pathrs_proc_readlinkat(
 /// # Return Value
 ///
 /// On success, this function copies the symlink contents to `linkbuf` (up to
-/// `linkbuf_size` bytes) and returns the full size of the symlink path buffer.
-/// This function will not copy the trailing NUL byte, and the return size does
-/// not include the NUL byte. A `NULL` `linkbuf` or invalid `linkbuf_size` are
-/// treated as zero-size buffers.
+/// `linkbuf_size - 1` bytes, always leaving room for a NUL terminator) and
+/// returns the full size of the symlink path buffer (not including the NUL
+/// byte). `linkbuf` is always NUL-terminated within `linkbuf_size` when
+/// non-`NULL`, even if the symlink contents had to be truncated to fit. A
+/// `NULL` `linkbuf` or invalid `linkbuf_size` are treated as zero-size
+/// buffers.
 ///
 /// NOTE: Unlike readlinkat(2), in the case where linkbuf is too small to
 /// contain the symlink contents, pathrs_proc_readlink() will return *the number
@@ -606,6 +858,469 @@ pub unsafe extern "C" fn pathrs_proc_readlink(
     )
 }
 
+/// Identical to `pathrs_proc_readlinkat`, except that (like
+/// `pathrs_proc_open_pidfd`) the target process is named by a `pidfd` rather
+/// than a raw PID, closing the same PID-reuse race.
+///
+/// # Return Value
+///
+/// On success, this function copies the symlink contents to `linkbuf` (up to
+/// `linkbuf_size - 1` bytes, always leaving room for a NUL terminator) and
+/// returns the full size of the symlink path buffer (not including the NUL
+/// byte). `linkbuf` is always NUL-terminated within `linkbuf_size` when
+/// non-`NULL`, even if the symlink contents had to be truncated to fit. A
+/// `NULL` `linkbuf` or invalid `linkbuf_size` are treated as zero-size
+/// buffers.
+///
+/// NOTE: Unlike readlinkat(2), in the case where linkbuf is too small to
+/// contain the symlink contents, pathrs_proc_readlink_pidfd() will return
+/// *the number of bytes it would have copied if the buffer was large
+/// enough*. This matches the behaviour of pathrs_inroot_readlink().
+///
+/// If an error occurs, this function will return a negative error code. To
+/// retrieve information about the error (such as a string describing the error,
+/// the system errno(7) value associated with the error, etc), use
+/// pathrs_errorinfo().
+#[no_mangle]
+pub unsafe extern "C" fn pathrs_proc_readlink_pidfd(
+    proc_rootfd: CBorrowedFd<'_>,
+    pidfd: CBorrowedFd<'_>,
+    path: *const c_char,
+    linkbuf: *mut c_char,
+    linkbuf_size: size_t,
+) -> c_int {
+    || -> Result<_, Error> {
+        let pidfd = pidfd.try_as_borrowed_fd()?;
+        let path = unsafe { utils::parse_path(path) }?; // SAFETY: C caller guarantees path is safe.
+        let procfs = parse_proc_rootfd(proc_rootfd)?;
+        let link_target = procfs.readlink_process(pidfd, path)?;
+        // SAFETY: C caller guarantees buffer is at least linkbuf_size and can
+        // be written to.
+        unsafe { utils::copy_path_into_buffer(link_target, linkbuf, linkbuf_size) }
+    }()
+    .into_c_return()
+}
+
+/// `pathrs_proc_write` but with a caller-provided file descriptor for
+/// `/proc`.
+///
+/// See the documentation of pathrs_proc_openat() for when this API might be
+/// useful.
+///
+/// # Return Value
+///
+/// On success, this function returns 0.
+///
+/// If an error occurs (including a short write), this function will return a
+/// negative error code. To retrieve information about the error (such as a
+/// string describing the error, the system errno(7) value associated with
+/// the error, etc), use pathrs_errorinfo().
+#[no_mangle]
+pub unsafe extern "C" fn pathrs_proc_writeat(
+    proc_rootfd: CBorrowedFd<'_>,
+    base: CProcfsBase,
+    path: *const c_char,
+    data: *const c_char,
+    data_size: size_t,
+) -> c_int {
+    || -> Result<_, Error> {
+        let base = base.try_into()?;
+        let path = unsafe { utils::parse_path(path) }?; // SAFETY: C caller guarantees path is safe.
+        let procfs = parse_proc_rootfd(proc_rootfd)?;
+        // SAFETY: C caller guarantees data is valid for reads of data_size
+        // bytes.
+        let data = unsafe { std::slice::from_raw_parts(data as *const u8, data_size) };
+        procfs.write(base, path, data)
+    }()
+    .map(|()| 0)
+    .into_c_return()
+}
+
+/// Identical to `pathrs_proc_writeat`, except that (like
+/// `pathrs_proc_open_pidfd`) the target process is named by a `pidfd` rather
+/// than a raw PID, closing the same PID-reuse race.
+///
+/// # Return Value
+///
+/// On success, this function returns 0.
+///
+/// If an error occurs (including a short write), this function will return a
+/// negative error code. To retrieve information about the error (such as a
+/// string describing the error, the system errno(7) value associated with
+/// the error, etc), use pathrs_errorinfo().
+#[no_mangle]
+pub unsafe extern "C" fn pathrs_proc_write_pidfd(
+    proc_rootfd: CBorrowedFd<'_>,
+    pidfd: CBorrowedFd<'_>,
+    path: *const c_char,
+    data: *const c_char,
+    data_size: size_t,
+) -> c_int {
+    || -> Result<_, Error> {
+        let pidfd = pidfd.try_as_borrowed_fd()?;
+        let path = unsafe { utils::parse_path(path) }?; // SAFETY: C caller guarantees path is safe.
+        let procfs = parse_proc_rootfd(proc_rootfd)?;
+        // SAFETY: C caller guarantees data is valid for reads of data_size
+        // bytes.
+        let data = unsafe { std::slice::from_raw_parts(data as *const u8, data_size) };
+        procfs.write_process(pidfd, path, data)
+    }()
+    .map(|()| 0)
+    .into_c_return()
+}
+
+/// Safely write to a file inside `/proc`.
+///
+/// As with `pathrs_proc_open`, any bind-mounts or other over-mounts will
+/// (depending on what kernel features are available) be detected and an error
+/// will be returned. Non-trailing symlinks are followed but care is taken to
+/// ensure the symlinks are legitimate.
+///
+/// `data` is written in a single `write(2)` and a short write is treated as
+/// an error rather than retried -- this matches the semantics required by
+/// the usual `pathrs_proc_write` targets (`uid_map`, `setgroups`,
+/// `oom_score_adj`, `attr/*`, and so on), which reject or ignore a second
+/// write to the same file descriptor.
+///
+/// # Return Value
+///
+/// On success, this function returns 0.
+///
+/// If an error occurs (including a short write), this function will return a
+/// negative error code. To retrieve information about the error (such as a
+/// string describing the error, the system errno(7) value associated with
+/// the error, etc), use pathrs_errorinfo().
+#[no_mangle]
+pub unsafe extern "C" fn pathrs_proc_write(
+    base: CProcfsBase,
+    path: *const c_char,
+    data: *const c_char,
+    data_size: size_t,
+) -> c_int {
+    pathrs_proc_writeat(PATHRS_PROC_DEFAULT_ROOTFD, base, path, data, data_size)
+}
+
+/// `pathrs_proc_readfile` but with a caller-provided file descriptor for
+/// `/proc`.
+///
+/// See the documentation of pathrs_proc_openat() for when this API might be
+/// useful.
+///
+/// # Return Value
+///
+/// On success, this function copies the file contents to `linkbuf` (up to
+/// `linkbuf_size` bytes) and returns the full size of the file. This function
+/// will not NUL-terminate `linkbuf`, and the return size does not assume
+/// one. A `NULL` `linkbuf` or invalid `linkbuf_size` are treated as zero-size
+/// buffers.
+///
+/// NOTE: As with pathrs_proc_readlinkat(), in the case where linkbuf is too
+/// small to contain the file contents, pathrs_proc_readfileat() will return
+/// *the number of bytes it would have copied if the buffer was large
+/// enough*.
+///
+/// If an error occurs, this function will return a negative error code. To
+/// retrieve information about the error (such as a string describing the error,
+/// the system errno(7) value associated with the error, etc), use
+/// pathrs_errorinfo().
+#[no_mangle]
+pub unsafe extern "C" fn pathrs_proc_readfileat(
+    proc_rootfd: CBorrowedFd<'_>,
+    base: CProcfsBase,
+    path: *const c_char,
+    linkbuf: *mut c_char,
+    linkbuf_size: size_t,
+) -> c_int {
+    || -> Result<_, Error> {
+        let base = base.try_into()?;
+        let path = unsafe { utils::parse_path(path) }?; // SAFETY: C caller guarantees path is safe.
+        let procfs = parse_proc_rootfd(proc_rootfd)?;
+        let contents = procfs.read(base, path)?;
+        // SAFETY: C caller guarantees buffer is at least linkbuf_size and can
+        // be written to.
+        unsafe { utils::copy_bytes_into_buffer(&contents, linkbuf, linkbuf_size) }
+    }()
+    .into_c_return()
+}
+
+/// Safely read the entire contents of a file inside `/proc` in one shot.
+///
+/// As with `pathrs_proc_open`, any bind-mounts or other over-mounts will
+/// (depending on what kernel features are available) be detected and an error
+/// will be returned. Non-trailing symlinks are followed but care is taken to
+/// ensure the symlinks are legitimate.
+///
+/// This function is effectively shorthand for
+///
+/// ```c
+/// fd = pathrs_proc_open(base, path, O_RDONLY);
+/// if (IS_PATHRS_ERR(fd)) {
+///     liberr = fd; // for use with pathrs_errorinfo()
+///     goto err;
+/// }
+/// copied = read(fd, linkbuf, linkbuf_size); // simplified -- read(2) to EOF
+/// close(fd);
+/// ```
+///
+/// except that the whole file is read in one protected operation before
+/// `pathrs_proc_readfile` returns, so the caller never has to hold a raw
+/// procfs fd themselves.
+///
+/// # Return Value
+///
+/// On success, this function copies the file contents to `linkbuf` (up to
+/// `linkbuf_size` bytes) and returns the full size of the file. This function
+/// will not NUL-terminate `linkbuf`, and the return size does not assume one.
+/// A `NULL` `linkbuf` or invalid `linkbuf_size` are treated as zero-size
+/// buffers.
+///
+/// NOTE: As with pathrs_proc_readlink(), in the case where linkbuf is too
+/// small to contain the file contents, pathrs_proc_readfile() will return
+/// *the number of bytes it would have copied if the buffer was large
+/// enough*.
+///
+/// If an error occurs, this function will return a negative error code. To
+/// retrieve information about the error (such as a string describing the error,
+/// the system errno(7) value associated with the error, etc), use
+/// pathrs_errorinfo().
+#[no_mangle]
+pub unsafe extern "C" fn pathrs_proc_readfile(
+    base: CProcfsBase,
+    path: *const c_char,
+    linkbuf: *mut c_char,
+    linkbuf_size: size_t,
+) -> c_int {
+    pathrs_proc_readfileat(
+        PATHRS_PROC_DEFAULT_ROOTFD,
+        base,
+        path,
+        linkbuf,
+        linkbuf_size,
+    )
+}
+
+/// Identical to `pathrs_proc_readfileat`, except that (like
+/// `pathrs_proc_open_pidfd`) the target process is named by a `pidfd` rather
+/// than a raw PID, closing the same PID-reuse race.
+///
+/// # Return Value
+///
+/// On success, this function copies the file contents to `linkbuf` (up to
+/// `linkbuf_size` bytes) and returns the full size of the file, with the same
+/// "would-have-copied" truncation semantics as `pathrs_proc_readfileat`.
+///
+/// If an error occurs, this function will return a negative error code. To
+/// retrieve information about the error (such as a string describing the error,
+/// the system errno(7) value associated with the error, etc), use
+/// pathrs_errorinfo().
+#[no_mangle]
+pub unsafe extern "C" fn pathrs_proc_readfile_pidfd(
+    proc_rootfd: CBorrowedFd<'_>,
+    pidfd: CBorrowedFd<'_>,
+    path: *const c_char,
+    linkbuf: *mut c_char,
+    linkbuf_size: size_t,
+) -> c_int {
+    || -> Result<_, Error> {
+        let pidfd = pidfd.try_as_borrowed_fd()?;
+        let path = unsafe { utils::parse_path(path) }?; // SAFETY: C caller guarantees path is safe.
+        let procfs = parse_proc_rootfd(proc_rootfd)?;
+        let contents = procfs.read_process(pidfd, path)?;
+        // SAFETY: C caller guarantees buffer is at least linkbuf_size and can
+        // be written to.
+        unsafe { utils::copy_bytes_into_buffer(&contents, linkbuf, linkbuf_size) }
+    }()
+    .into_c_return()
+}
+
+/// Fetch metadata for a file inside `/proc`, following any trailing symlink,
+/// with a caller-provided file descriptor for `/proc`.
+///
+/// See the documentation of pathrs_proc_openat() for when this API might be
+/// useful, and the documentation of [`ProcfsStat`] (`struct
+/// pathrs_proc_stat`) for how the extensible `stat`/`size` arguments work
+/// (they follow the same scheme as pathrs_procfs_open()'s `args`/`size`).
+///
+/// # Return Value
+///
+/// On success, this function returns 0 and `stat` is filled in.
+///
+/// If an error occurs, this function will return a negative error code. To
+/// retrieve information about the error (such as a string describing the
+/// error, the system errno(7) value associated with the error, etc), use
+/// pathrs_errorinfo().
+#[no_mangle]
+pub unsafe extern "C" fn pathrs_proc_statat(
+    proc_rootfd: CBorrowedFd<'_>,
+    base: CProcfsBase,
+    path: *const c_char,
+    stat: *mut ProcfsStat,
+    size: size_t,
+) -> c_int {
+    || -> Result<_, Error> {
+        let base = base.try_into()?;
+        let path = unsafe { utils::parse_path(path) }?; // SAFETY: C caller guarantees path is safe.
+        let procfs = parse_proc_rootfd(proc_rootfd)?;
+        let meta = procfs.stat(base, path)?;
+        // SAFETY: C caller guarantees stat is valid for writes of size bytes.
+        unsafe { utils::copy_to_extensible_struct(stat, size, &meta.into()) }
+    }()
+    .map(|_written| 0)
+    .into_c_return()
+}
+
+/// Identical to `pathrs_proc_statat`, except that (like
+/// `pathrs_proc_open_pidfd`) the target process is named by a `pidfd` rather
+/// than a raw PID, closing the same PID-reuse race.
+///
+/// # Return Value
+///
+/// On success, this function returns 0 and `stat` is filled in.
+///
+/// If an error occurs, this function will return a negative error code. To
+/// retrieve information about the error (such as a string describing the
+/// error, the system errno(7) value associated with the error, etc), use
+/// pathrs_errorinfo().
+#[no_mangle]
+pub unsafe extern "C" fn pathrs_proc_stat_pidfd(
+    proc_rootfd: CBorrowedFd<'_>,
+    pidfd: CBorrowedFd<'_>,
+    path: *const c_char,
+    stat: *mut ProcfsStat,
+    size: size_t,
+) -> c_int {
+    || -> Result<_, Error> {
+        let pidfd = pidfd.try_as_borrowed_fd()?;
+        let path = unsafe { utils::parse_path(path) }?; // SAFETY: C caller guarantees path is safe.
+        let procfs = parse_proc_rootfd(proc_rootfd)?;
+        let meta = procfs.stat_process(pidfd, path)?;
+        // SAFETY: C caller guarantees stat is valid for writes of size bytes.
+        unsafe { utils::copy_to_extensible_struct(stat, size, &meta.into()) }
+    }()
+    .map(|_written| 0)
+    .into_c_return()
+}
+
+/// Fetch metadata for a file inside `/proc`, following any trailing symlink.
+///
+/// As with `pathrs_proc_open`, any bind-mounts or other over-mounts will
+/// (depending on what kernel features are available) be detected through the
+/// reported mount ID.
+///
+/// # Return Value
+///
+/// On success, this function returns 0 and `stat` is filled in.
+///
+/// If an error occurs, this function will return a negative error code. To
+/// retrieve information about the error (such as a string describing the
+/// error, the system errno(7) value associated with the error, etc), use
+/// pathrs_errorinfo().
+#[no_mangle]
+pub unsafe extern "C" fn pathrs_proc_stat(
+    base: CProcfsBase,
+    path: *const c_char,
+    stat: *mut ProcfsStat,
+    size: size_t,
+) -> c_int {
+    pathrs_proc_statat(PATHRS_PROC_DEFAULT_ROOTFD, base, path, stat, size)
+}
+
+/// Safely open the `/proc/<pid>/ns/<ns_type>` namespace entry of the process
+/// referenced by `pidfd` (as returned by `pidfd_open(2)`), returning a
+/// namespace fd suitable for `setns(2)` or other cross-process inspection.
+///
+/// Like `pathrs_proc_open_pidfd`, this closes the PID-reuse race inherent to
+/// naming a process by raw PID: the returned fd is guaranteed to refer to a
+/// namespace of the same process `pidfd` was created from, by re-checking
+/// (via `pidfd_send_signal(pidfd, 0, NULL, 0)`) that the process is still
+/// alive.
+///
+/// `ns_type` is one of the names listed under `/proc/<pid>/ns` (such as
+/// `"mnt"`, `"net"`, `"pid"`, ...). If the named namespace type does not
+/// exist for this task, `pathrs_errorinfo()` will report `EINVAL` rather than
+/// the `ENOENT` of a generic missing file, so callers can distinguish
+/// "unsupported namespace type" from other lookup failures.
+///
+/// # Return Value
+///
+/// On success, this function returns a file descriptor. The file descriptor
+/// will have the `O_CLOEXEC` flag automatically applied.
+///
+/// If an error occurs, this function will return a negative error code. To
+/// retrieve information about the error (such as a string describing the error,
+/// the system errno(7) value associated with the error, etc), use
+/// pathrs_errorinfo().
+#[no_mangle]
+pub unsafe extern "C" fn pathrs_proc_open_namespace_pidfd(
+    proc_rootfd: CBorrowedFd<'_>,
+    pidfd: CBorrowedFd<'_>,
+    ns_type: *const c_char,
+) -> RawFd {
+    || -> Result<_, Error> {
+        let pidfd = pidfd.try_as_borrowed_fd()?;
+        let ns_type = unsafe { utils::parse_path(ns_type) }?; // SAFETY: C caller guarantees ns_type is safe.
+        let ns_type = ns_type.to_string_lossy();
+        let procfs = parse_proc_rootfd(proc_rootfd)?;
+        procfs.open_namespace_process(pidfd, &ns_type, OpenFlags::O_RDONLY)
+    }()
+    .map(OwnedFd::from)
+    .into_c_return()
+}
+
+/// Safely open an `O_PATH` directory fd for the root directory
+/// (`/proc/<pid>/root`) of the process referenced by `pidfd`, closing the
+/// same PID-reuse race as `pathrs_proc_open_pidfd`.
+///
+/// # Return Value
+///
+/// On success, this function returns a file descriptor. The file descriptor
+/// will have the `O_CLOEXEC` flag automatically applied.
+///
+/// If an error occurs, this function will return a negative error code. To
+/// retrieve information about the error (such as a string describing the error,
+/// the system errno(7) value associated with the error, etc), use
+/// pathrs_errorinfo().
+#[no_mangle]
+pub unsafe extern "C" fn pathrs_proc_open_root_pidfd(
+    proc_rootfd: CBorrowedFd<'_>,
+    pidfd: CBorrowedFd<'_>,
+) -> RawFd {
+    || -> Result<_, Error> {
+        let pidfd = pidfd.try_as_borrowed_fd()?;
+        let procfs = parse_proc_rootfd(proc_rootfd)?;
+        procfs.open_root_process(pidfd, OpenFlags::O_PATH | OpenFlags::O_DIRECTORY)
+    }()
+    .map(OwnedFd::from)
+    .into_c_return()
+}
+
+/// Safely open an `O_PATH` directory fd for the current working directory
+/// (`/proc/<pid>/cwd`) of the process referenced by `pidfd`, closing the
+/// same PID-reuse race as `pathrs_proc_open_pidfd`.
+///
+/// # Return Value
+///
+/// On success, this function returns a file descriptor. The file descriptor
+/// will have the `O_CLOEXEC` flag automatically applied.
+///
+/// If an error occurs, this function will return a negative error code. To
+/// retrieve information about the error (such as a string describing the error,
+/// the system errno(7) value associated with the error, etc), use
+/// pathrs_errorinfo().
+#[no_mangle]
+pub unsafe extern "C" fn pathrs_proc_open_cwd_pidfd(
+    proc_rootfd: CBorrowedFd<'_>,
+    pidfd: CBorrowedFd<'_>,
+) -> RawFd {
+    || -> Result<_, Error> {
+        let pidfd = pidfd.try_as_borrowed_fd()?;
+        let procfs = parse_proc_rootfd(proc_rootfd)?;
+        procfs.open_cwd_process(pidfd, OpenFlags::O_PATH | OpenFlags::O_DIRECTORY)
+    }()
+    .map(OwnedFd::from)
+    .into_c_return()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -616,8 +1331,13 @@ mod tests {
     };
 
     use std::{
+        ffi::{CString, OsStr},
+        fs::File,
         mem,
-        os::unix::io::{FromRawFd, OwnedFd},
+        os::unix::{
+            ffi::OsStrExt,
+            io::{AsRawFd, FromRawFd, OwnedFd},
+        },
     };
 
     use pretty_assertions::assert_eq;
@@ -874,6 +1594,7 @@ mod tests {
     fn pathrs_procfs_open_unmasked() {
         let how = ProcfsOpenHow {
             flags: ProcfsOpenFlags::PATHRS_PROCFS_NEW_UNMASKED,
+            ..Default::default()
         };
 
         let fd = unsafe { pathrs_procfs_open(&how as *const _, mem::size_of::<ProcfsOpenHow>()) };
@@ -891,6 +1612,7 @@ mod tests {
     fn pathrs_procfs_open_bad_flag() {
         let how_bad_flags = ProcfsOpenHow {
             flags: ProcfsOpenFlags::from_bits_retain(0xF000),
+            ..Default::default()
         };
 
         let ret = unsafe {
@@ -915,6 +1637,96 @@ mod tests {
         }
     }
 
+    #[test]
+    fn pathrs_procfs_open_subset_hidepid() {
+        let how = ProcfsOpenHow {
+            subset: PATHRS_PROCFS_SUBSET_PID,
+            hidepid: PATHRS_PROCFS_HIDEPID_INVISIBLE,
+            ..Default::default()
+        };
+
+        let fd = unsafe { pathrs_procfs_open(&how as *const _, mem::size_of::<ProcfsOpenHow>()) };
+        assert!(fd >= 0, "fd value {fd:#x} should be >= 0");
+
+        let procfs = ProcfsHandle::try_from_fd(unsafe { OwnedFd::from_raw_fd(fd) })
+            .expect("pathrs_procfs_open should return a valid procfs fd");
+
+        let _ = procfs
+            .open(ProcfsBase::ProcSelf, ".", OpenFlags::O_PATH)
+            .expect("open(.) should always succeed");
+    }
+
+    #[test]
+    fn pathrs_procfs_open_mntns_fd() {
+        let mntns = File::open("/proc/self/ns/mnt").expect("/proc/self/ns/mnt should be openable");
+
+        let how = ProcfsOpenHow {
+            mntns_fd: mntns.as_raw_fd(),
+            ..Default::default()
+        };
+
+        let fd = unsafe { pathrs_procfs_open(&how as *const _, mem::size_of::<ProcfsOpenHow>()) };
+        assert!(fd >= 0, "fd value {fd:#x} should be >= 0");
+
+        let procfs = ProcfsHandle::try_from_fd(unsafe { OwnedFd::from_raw_fd(fd) })
+            .expect("pathrs_procfs_open should return a valid procfs fd");
+
+        let _ = procfs
+            .open(ProcfsBase::ProcSelf, ".", OpenFlags::O_PATH)
+            .expect("open(.) should always succeed");
+    }
+
+    #[test]
+    fn pathrs_procfs_open_bad_mntns_fd() {
+        let how_bad_mntns_fd = ProcfsOpenHow {
+            mntns_fd: -1,
+            ..Default::default()
+        };
+
+        let ret = unsafe {
+            pathrs_procfs_open(
+                &how_bad_mntns_fd as *const _,
+                mem::size_of::<ProcfsOpenHow>(),
+            )
+        };
+        assert!(
+            ret < capi_error::__PATHRS_MAX_ERR_VALUE,
+            "ret value {ret:#x} should be error value"
+        );
+    }
+
+    #[test]
+    fn pathrs_procfs_open_bad_subset() {
+        let how_bad_subset = ProcfsOpenHow {
+            subset: 0xFF,
+            ..Default::default()
+        };
+
+        let ret = unsafe {
+            pathrs_procfs_open(&how_bad_subset as *const _, mem::size_of::<ProcfsOpenHow>())
+        };
+        assert!(
+            ret < capi_error::__PATHRS_MAX_ERR_VALUE,
+            "ret value {ret:#x} should be error value"
+        );
+
+        let how_bad_hidepid = ProcfsOpenHow {
+            hidepid: 0xFF,
+            ..Default::default()
+        };
+
+        let ret = unsafe {
+            pathrs_procfs_open(
+                &how_bad_hidepid as *const _,
+                mem::size_of::<ProcfsOpenHow>(),
+            )
+        };
+        assert!(
+            ret < capi_error::__PATHRS_MAX_ERR_VALUE,
+            "ret value {ret:#x} should be error value"
+        );
+    }
+
     #[test]
     fn pathrs_procfs_open_bad_struct() {
         #[repr(C)]
@@ -927,6 +1739,7 @@ mod tests {
         let how_ok_struct = ProcfsOpenHowV2 {
             inner: ProcfsOpenHow {
                 flags: ProcfsOpenFlags::PATHRS_PROCFS_NEW_UNMASKED,
+                ..Default::default()
             },
             extra: 0,
         };
@@ -946,6 +1759,7 @@ mod tests {
         let how_bad_struct = ProcfsOpenHowV2 {
             inner: ProcfsOpenHow {
                 flags: ProcfsOpenFlags::PATHRS_PROCFS_NEW_UNMASKED,
+                ..Default::default()
             },
             extra: 0xFF,
         };
@@ -973,4 +1787,90 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn pathrs_proc_readlink_non_utf8() {
+        // Linux paths (and thus symlink targets, such as those found in
+        // /proc/self/fd) are arbitrary NUL-free byte strings -- they need not
+        // be valid UTF-8. Create a file whose name is not valid UTF-8 and
+        // make sure pathrs_proc_readlink() round-trips the raw bytes exactly,
+        // rather than silently mangling or erroring on them.
+        let dir = tempfile::TempDir::new().expect("tempdir should be creatable");
+        let non_utf8_name = OsStr::from_bytes(b"not-utf8-\xff\xfe-name");
+        let file_path = dir.path().join(non_utf8_name);
+        let file = std::fs::File::create(&file_path).expect("create file with non-utf8 name");
+
+        let fd_path = CString::new(format!("fd/{}", file.as_raw_fd())).expect("fd path has no NULs");
+
+        let mut linkbuf = vec![0u8; 4096];
+        let n = unsafe {
+            pathrs_proc_readlink(
+                CProcfsBase::PATHRS_PROC_SELF,
+                fd_path.as_ptr(),
+                linkbuf.as_mut_ptr() as *mut c_char,
+                linkbuf.len(),
+            )
+        };
+        assert!(n >= 0, "pathrs_proc_readlink(self/fd/N) should succeed: {n}");
+        let target = OsStr::from_bytes(&linkbuf[..n as usize]);
+        assert_eq!(
+            target,
+            file_path.as_os_str(),
+            "readlink target must preserve non-UTF-8 bytes exactly",
+        );
+    }
+
+    #[test]
+    fn pathrs_proc_readfile_self_cmdline() {
+        // /proc/self/cmdline is NUL-separated -- make sure pathrs_proc_readfile()
+        // round-trips the raw bytes (including any embedded NULs) exactly,
+        // rather than truncating at the first NUL like a C string.
+        let expected = std::fs::read("/proc/self/cmdline").expect("read /proc/self/cmdline");
+
+        let path = CString::new("cmdline").expect("path has no NULs");
+        let mut buf = vec![0u8; 4096];
+        let n = unsafe {
+            pathrs_proc_readfile(
+                CProcfsBase::PATHRS_PROC_SELF,
+                path.as_ptr(),
+                buf.as_mut_ptr() as *mut c_char,
+                buf.len(),
+            )
+        };
+        assert!(n >= 0, "pathrs_proc_readfile(self/cmdline) should succeed: {n}");
+        assert_eq!(
+            &buf[..n as usize],
+            &expected[..],
+            "readfile contents must preserve embedded NUL bytes exactly",
+        );
+    }
+
+    #[test]
+    fn pathrs_proc_readfile_truncation() {
+        let path = CString::new("cmdline").expect("path has no NULs");
+
+        let full_size = unsafe {
+            pathrs_proc_readfile(
+                CProcfsBase::PATHRS_PROC_SELF,
+                path.as_ptr(),
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        assert!(full_size >= 0, "full_size should be a valid length: {full_size}");
+
+        let mut small_buf = vec![0u8; 1];
+        let n = unsafe {
+            pathrs_proc_readfile(
+                CProcfsBase::PATHRS_PROC_SELF,
+                path.as_ptr(),
+                small_buf.as_mut_ptr() as *mut c_char,
+                small_buf.len(),
+            )
+        };
+        assert_eq!(
+            n, full_size,
+            "a too-small buffer should still return the full would-have-copied size"
+        );
+    }
 }
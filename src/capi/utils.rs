@@ -17,7 +17,13 @@
  * along with this program. If not, see <https://www.gnu.org/licenses/>.
  */
 
-use crate::error::{Error, ErrorImpl};
+use crate::{
+    capi::{
+        cfg::CConfig,
+        procfs::{ProcfsOpenHow, ProcfsStat},
+    },
+    error::{Error, ErrorImpl},
+};
 
 use std::{
     any, cmp,
@@ -26,7 +32,7 @@ use std::{
     mem,
     os::unix::{
         ffi::OsStrExt,
-        io::{AsRawFd, BorrowedFd, RawFd},
+        io::{AsRawFd, BorrowedFd, OwnedFd, RawFd},
     },
     path::Path,
     ptr, slice,
@@ -67,12 +73,34 @@ impl<'fd> CBorrowedFd<'fd> {
     /// Take a [`CBorrowedFd`] from C FFI and convert it to a proper
     /// [`BorrowedFd`] after making sure that it has a valid value (ie. is not
     /// negative).
+    ///
+    /// Unlike [`try_as_fd`][`Self::try_as_fd`], `AT_FDCWD` is rejected here --
+    /// use this in contexts where "the current working directory" isn't a
+    /// meaningful root (such as a `pidfd` or a `/proc` root fd).
     pub(crate) fn try_as_borrowed_fd(&self) -> Result<BorrowedFd<'fd>, Error> {
-        // TODO: We might want to support AT_FDCWD in the future. The
-        //       openat2 resolver handles it correctly, but the O_PATH
-        //       resolver and try_clone() probably need some work.
+        match self.try_as_fd()? {
+            CFd::Cwd => Err(ErrorImpl::InvalidArgument {
+                name: "fd".into(),
+                description: "AT_FDCWD is not a valid file descriptor in this context".into(),
+            }
+            .into()),
+            CFd::Fd(fd) => Ok(fd),
+        }
+    }
+
+    /// Take a [`CBorrowedFd`] from C FFI and convert it to a [`CFd`], after
+    /// making sure that it has a valid value (ie. is not negative, except for
+    /// the special `AT_FDCWD` value).
+    ///
+    /// This is the counterpart used by entry points that resolve relative to
+    /// a caller-provided fd the same way `openat2(2)`/`openat(2)` do, where
+    /// `AT_FDCWD` means "relative to the process's current working
+    /// directory" rather than being rejected outright.
+    pub(crate) fn try_as_fd(&self) -> Result<CFd<'fd>, Error> {
         // MSRV(1.66): Use match ..0?
-        if self.inner.is_negative() {
+        if self.inner == libc::AT_FDCWD {
+            Ok(CFd::Cwd)
+        } else if self.inner.is_negative() {
             Err(ErrorImpl::InvalidArgument {
                 // TODO: Should this error be EBADF?
                 name: "fd".into(),
@@ -84,11 +112,37 @@ impl<'fd> CBorrowedFd<'fd> {
             //         the lifetime of CBorrowedFd (which is the same lifetime as
             //         BorrowedFd). We verify that the file descriptor is not
             //         negative, so it is definitely valid.
-            Ok(unsafe { BorrowedFd::borrow_raw(self.inner) })
+            Ok(CFd::Fd(unsafe { BorrowedFd::borrow_raw(self.inner) }))
         }
     }
 }
 
+/// The result of resolving a [`CBorrowedFd`] passed in by a C caller: either a
+/// genuine, borrowed file descriptor, or the special `AT_FDCWD` value meaning
+/// "the process's current working directory", mirroring the `openat2(2)` ABI.
+///
+/// Resolvers that accept a [`CFd`] must treat [`CFd::Cwd`] as a root of `.`
+/// (ie. `openat(AT_FDCWD, ".", O_PATH)`), so that C callers don't need to
+/// open a directory fd for their own cwd (or `/`) just to use libpathrs.
+///
+/// Not yet mirrored: every real `pathrs_*` entry point in this tree still
+/// goes through [`CBorrowedFd::try_as_borrowed_fd`] (which rejects
+/// `AT_FDCWD`), including the one caller [`try_as_fd`][`CBorrowedFd::try_as_fd`]
+/// itself has today -- so [`CFd::Cwd`] is constructed and then immediately
+/// turned back into an error, and no C caller can reach `AT_FDCWD` handling
+/// in practice yet. Wiring an entry point that actually accepts
+/// [`CFd::Cwd`] means touching the fd-to-`Root`/`Handle` bridging code,
+/// which lives outside this module; don't treat `try_as_fd`/`CFd` existing
+/// as evidence that `AT_FDCWD` support has landed.
+#[derive(Debug)]
+pub(crate) enum CFd<'fd> {
+    /// `AT_FDCWD` was passed -- resolve relative to the current working
+    /// directory.
+    Cwd,
+    /// A genuine, caller-provided file descriptor.
+    Fd(BorrowedFd<'fd>),
+}
+
 impl<'fd> From<BorrowedFd<'fd>> for CBorrowedFd<'fd> {
     fn from(fd: BorrowedFd<'_>) -> CBorrowedFd<'_> {
         CBorrowedFd {
@@ -113,6 +167,18 @@ pub(crate) unsafe fn parse_path<'a>(path: *const c_char) -> Result<&'a Path, Err
     Ok(OsStr::from_bytes(bytes).as_ref())
 }
 
+/// Copy `path` into a caller-provided buffer as a NUL-terminated C string,
+/// truncating to `bufsize` bytes (a `NULL` `buf` or zero `bufsize` are
+/// treated as a zero-size buffer). Always returns the *full* length of
+/// `path` (not including the NUL terminator), matching the
+/// `pathrs_proc_readlink`-style "would-have-copied" truncation semantics --
+/// but unlike [`copy_bytes_into_buffer`], at most `bufsize - 1` path bytes
+/// are ever copied and a NUL terminator is always written within `bufsize`
+/// when `buf` is non-`NULL`, so a caller that treats `buf` as a C string can
+/// never read past the end of a too-small buffer. As with `readlink(2)`, a
+/// caller detects truncation by comparing the returned length against
+/// `bufsize`, which is what lets the standard two-call "query size, then
+/// fetch" pattern be used safely.
 pub(crate) unsafe fn copy_path_into_buffer(
     path: impl AsRef<Path>,
     buf: *mut c_char,
@@ -123,17 +189,42 @@ pub(crate) unsafe fn copy_path_into_buffer(
     // MSRV(1.79): Switch to .count_bytes().
     let path_len = path.to_bytes().len();
 
-    // If the linkbuf is null, we just return the number of bytes we
+    if !buf.is_null() && bufsize > 0 {
+        // SAFETY: The C caller guarantees that buf is safe to write to up to
+        // bufsize bytes -- same guarantee this function documents. We only
+        // ever copy up to bufsize - 1 bytes, leaving room for the NUL
+        // terminator we write immediately afterwards.
+        unsafe {
+            let to_copy = cmp::min(path_len, bufsize - 1);
+            ptr::copy_nonoverlapping(path.as_ptr(), buf, to_copy);
+            *buf.add(to_copy) = 0;
+        }
+    }
+    Ok(path_len as c_int)
+}
+
+/// Copy `data` into a caller-provided buffer, truncating to `bufsize` bytes
+/// (a `NULL` `buf` or zero `bufsize` are treated as a zero-size buffer).
+/// Always returns the *full* length of `data`, matching the
+/// `pathrs_proc_readlink`-style "would-have-copied" truncation semantics --
+/// unlike [`copy_path_into_buffer`], `data` is treated as an opaque byte
+/// string and may contain NUL bytes (e.g. `/proc/<pid>/cmdline`).
+pub(crate) unsafe fn copy_bytes_into_buffer(
+    data: &[u8],
+    buf: *mut c_char,
+    bufsize: size_t,
+) -> Result<c_int, Error> {
+    // If the buffer is null, we just return the number of bytes we
     // would've written.
     if !buf.is_null() && bufsize > 0 {
         // SAFETY: The C caller guarantees that buf is safe to write to
         // up to bufsize bytes.
         unsafe {
-            let to_copy = cmp::min(path_len, bufsize);
-            ptr::copy_nonoverlapping(path.as_ptr(), buf, to_copy);
+            let to_copy = cmp::min(data.len(), bufsize);
+            ptr::copy_nonoverlapping(data.as_ptr() as *const c_char, buf, to_copy);
         }
     }
-    Ok(path_len as c_int)
+    Ok(data.len() as c_int)
 }
 
 pub(crate) unsafe fn copy_from_extensible_struct<T: Pod>(
@@ -192,6 +283,77 @@ pub(crate) unsafe fn copy_from_extensible_struct<T: Pod>(
     })
 }
 
+/// Inverse of [`copy_from_extensible_struct`]: copy a library-side
+/// extensible structure *out* to a caller-supplied buffer of caller-declared
+/// size, following the same `copy_struct_to_user` semantics. This is used by
+/// any C entry point which needs to *return* a versioned structure (such as a
+/// resolved configuration, or a stat-like result) through a user-provided
+/// buffer.
+///
+/// On success, returns the number of bytes actually written to `ptr` (namely
+/// `min(size, size_of::<T>())`). If `value` has trailing fields (beyond what
+/// `size` can hold) that are non-zero, the caller is too old to understand
+/// them and we return an error rather than silently discarding data the
+/// caller asked us to report.
+pub(crate) unsafe fn copy_to_extensible_struct<T: Pod>(
+    ptr: *mut T,
+    size: size_t,
+    value: &T,
+) -> Result<size_t, Error> {
+    let struct_size = mem::size_of::<T>();
+    let to_copy = cmp::min(size, struct_size);
+
+    let value_bytes = bytemuck::bytes_of(value);
+    let trailing = &value_bytes[to_copy..];
+    if trailing.iter().any(|&ch| ch != 0) {
+        return Err(ErrorImpl::UnsupportedStructureData {
+            name: format!("c struct {}", any::type_name::<T>()).into(),
+        }
+        .into());
+    }
+
+    // SAFETY: The C caller guarantees that ptr is from a single allocation
+    // and is valid for writes of at least size bytes. We only ever write
+    // min(size, struct_size) bytes, so we never write more than either
+    // buffer actually contains.
+    unsafe {
+        ptr::copy_nonoverlapping(value_bytes.as_ptr(), ptr as *mut u8, to_copy);
+    }
+    Ok(to_copy)
+}
+
+/// Identifies one of the versioned, extensible structures used elsewhere in
+/// the C API, for use with [`pathrs_struct_size`].
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub enum PathrsStructKind {
+    /// `struct pathrs_config`, as used by `pathrs_config_parse()`.
+    PATHRS_STRUCT_CONFIG,
+    /// `struct pathrs_procfs_open_how`, as used by `pathrs_procfs_open()`.
+    PATHRS_STRUCT_PROCFS_OPEN_HOW,
+    /// `struct pathrs_proc_stat`, as used by `pathrs_proc_statat()` and its
+    /// sibling entry points.
+    PATHRS_STRUCT_PROCFS_STAT,
+}
+
+/// Return the size (in bytes) of the extensible structure identified by
+/// `kind`, as understood by this build of libpathrs.
+///
+/// This lets a C caller negotiate feature levels the same way `openat2(2)`
+/// callers probe `sizeof(struct open_how)` before calling: pass the returned
+/// size (or `sizeof(local_struct)`, whichever is smaller) as the `size`
+/// argument of the corresponding entry point, rather than guessing which
+/// fields a given libpathrs build actually understands.
+#[no_mangle]
+pub extern "C" fn pathrs_struct_size(kind: PathrsStructKind) -> size_t {
+    match kind {
+        PathrsStructKind::PATHRS_STRUCT_CONFIG => mem::size_of::<CConfig>(),
+        PathrsStructKind::PATHRS_STRUCT_PROCFS_OPEN_HOW => mem::size_of::<ProcfsOpenHow>(),
+        PathrsStructKind::PATHRS_STRUCT_PROCFS_STAT => mem::size_of::<ProcfsStat>(),
+    }
+}
+
 pub(crate) trait Leakable: Sized {
     /// Leak a structure such that it can be passed through C-FFI.
     fn leak(self) -> &'static mut Self {
@@ -219,6 +381,159 @@ pub(crate) trait Leakable: Sized {
     }
 }
 
+type HandleGeneration = u32;
+
+#[derive(Debug)]
+enum HandleSlot<T> {
+    /// No value is currently stored in this slot. `generation` is the
+    /// generation the *next* occupant of this slot will be given.
+    Empty { generation: HandleGeneration },
+    /// A value is currently stored in this slot, tagged with the generation
+    /// it was inserted with.
+    Occupied {
+        generation: HandleGeneration,
+        value: T,
+    },
+}
+
+/// An opaque 64-bit handle returned by [`HandleTable::insert`], encoding
+/// which slot a value lives in and which "generation" of that slot it is --
+/// see [`HandleTable`] for why the generation matters.
+pub(crate) type HandleId = u64;
+
+fn encode_handle(index: usize, generation: HandleGeneration) -> HandleId {
+    (index as u64) << 32 | u64::from(generation)
+}
+
+fn decode_handle(handle: HandleId) -> (usize, HandleGeneration) {
+    ((handle >> 32) as usize, (handle & 0xFFFF_FFFF) as HandleGeneration)
+}
+
+fn stale_handle_error(handle: HandleId) -> Error {
+    ErrorImpl::InvalidArgument {
+        name: "handle".into(),
+        description: format!(
+            "handle {handle:#x} does not refer to a currently-live object \
+             (it may be stale, already freed, or simply invalid)"
+        )
+        .into(),
+    }
+    .into()
+}
+
+/// A `Sync`-safe, slab-backed table mapping opaque 64-bit [`HandleId`]s to
+/// boxed Rust values, intended as a hardened replacement for [`Leakable`] in
+/// new C API entry points.
+///
+/// Handing a C caller a raw `Box::leak`'d pointer (as [`Leakable`] does)
+/// means a double-`free()` or a call made after `free()` dereferences memory
+/// that may have already been deallocated or reused for something else --
+/// undefined behaviour that a misbehaving (or malicious) C caller can
+/// trigger with nothing more than an extra `free()` call.
+///
+/// `HandleTable` closes that hole by never handing out a pointer at all.
+/// [`insert`][`Self::insert`] stores the value in a slab slot and returns a
+/// handle encoding `(slot index, generation)`; [`get`][`Self::get`] and
+/// [`remove`][`Self::remove`] both check that the slot is occupied *and*
+/// that its current generation matches the one encoded in the handle before
+/// touching the value. [`remove`][`Self::remove`] also bumps the slot's
+/// generation, so any handle to the removed value (including a duplicate
+/// `free()` of the same handle) is rejected by that check from then on,
+/// rather than reusing or re-dropping the value. A stale/invalid/double-used
+/// handle therefore always surfaces as an ordinary `EINVAL`-style [`Error`],
+/// never as memory corruption.
+///
+/// Not yet mirrored: the only current [`Leakable`] consumer (the
+/// `pathrs_error_t` leak/unleak pair backing `pathrs_errorinfo()`) lives in
+/// `capi::error`, which this table doesn't touch -- migrating it to
+/// `HandleTable` is a separate change. Until that migration lands, this is
+/// infrastructure only: no `pathrs_*` entry point resolves a handle through
+/// this table yet, so it does not itself harden anything a C caller can
+/// actually reach -- don't cite `HandleTable`'s existence as evidence that
+/// the UAF/double-free class above is closed in this tree.
+#[derive(Debug)]
+#[allow(dead_code)] // not yet wired to any pathrs_* entry point, see doc comment above
+pub(crate) struct HandleTable<T> {
+    slots: std::sync::RwLock<Vec<HandleSlot<T>>>,
+}
+
+impl<T> Default for HandleTable<T> {
+    fn default() -> Self {
+        Self {
+            slots: std::sync::RwLock::new(Vec::new()),
+        }
+    }
+}
+
+#[allow(dead_code)] // not yet wired to any pathrs_* entry point, see type doc comment
+impl<T> HandleTable<T> {
+    /// Store `value` in the table and return a handle that can later be
+    /// passed to [`get`][`Self::get`] or [`remove`][`Self::remove`] to
+    /// retrieve it.
+    pub(crate) fn insert(&self, value: T) -> HandleId {
+        let mut slots = self.slots.write().unwrap_or_else(|err| err.into_inner());
+
+        for (index, slot) in slots.iter_mut().enumerate() {
+            if let HandleSlot::Empty { generation } = *slot {
+                *slot = HandleSlot::Occupied { generation, value };
+                return encode_handle(index, generation);
+            }
+        }
+
+        let index = slots.len();
+        let generation = 0;
+        slots.push(HandleSlot::Occupied { generation, value });
+        encode_handle(index, generation)
+    }
+
+    /// Run `f` against the value referred to by `handle`, failing cleanly if
+    /// `handle` is stale, already freed, or otherwise invalid.
+    pub(crate) fn get<R>(&self, handle: HandleId, f: impl FnOnce(&T) -> R) -> Result<R, Error> {
+        let (index, generation) = decode_handle(handle);
+        let slots = self.slots.read().unwrap_or_else(|err| err.into_inner());
+
+        match slots.get(index) {
+            Some(HandleSlot::Occupied {
+                generation: slot_generation,
+                value,
+            }) if *slot_generation == generation => Ok(f(value)),
+            _ => Err(stale_handle_error(handle)),
+        }
+    }
+
+    /// Remove and return the value referred to by `handle`, failing cleanly
+    /// (without touching anything) if `handle` is stale, already freed, or
+    /// otherwise invalid. Once removed, `handle` (and any other handle
+    /// sharing its slot and generation) can never be resolved again.
+    pub(crate) fn remove(&self, handle: HandleId) -> Result<T, Error> {
+        let (index, generation) = decode_handle(handle);
+        let mut slots = self.slots.write().unwrap_or_else(|err| err.into_inner());
+
+        let occupied = matches!(
+            slots.get(index),
+            Some(HandleSlot::Occupied { generation: slot_generation, .. })
+                if *slot_generation == generation
+        );
+        if !occupied {
+            return Err(stale_handle_error(handle));
+        }
+
+        match mem::replace(
+            &mut slots[index],
+            HandleSlot::Empty {
+                generation: generation.wrapping_add(1),
+            },
+        ) {
+            HandleSlot::Occupied { value, .. } => Ok(value),
+            HandleSlot::Empty { .. } => unreachable!("occupied check above"),
+        }
+    }
+}
+
+// A HandleTable must be safe to share across threads (e.g. via a `static`),
+// since C callers are free to use the same handle from multiple threads.
+static_assertions::assert_impl_all!(HandleTable<OwnedFd>: Sync);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -236,6 +551,80 @@ mod tests {
         baz: u32,
     }
 
+    #[test]
+    fn copy_path_into_buffer_exact_fit() {
+        let mut buf = [0xff_u8 as c_char; 6];
+        let ret = unsafe { copy_path_into_buffer("abcde", buf.as_mut_ptr(), buf.len()) }
+            .expect("copy should succeed");
+        assert_eq!(ret, 5, "should return the full (untruncated) path length");
+        assert_eq!(buf, b"abcde\0".map(|b| b as c_char));
+    }
+
+    #[test]
+    fn copy_path_into_buffer_truncates_but_nul_terminates() {
+        let mut buf = [0xff_u8 as c_char; 3];
+        let ret = unsafe { copy_path_into_buffer("abcde", buf.as_mut_ptr(), buf.len()) }
+            .expect("copy should succeed even when truncated");
+        assert_eq!(ret, 5, "should still return the full untruncated path length");
+        assert_eq!(
+            buf,
+            b"ab\0".map(|b| b as c_char),
+            "a too-small buffer must still be NUL-terminated within bufsize",
+        );
+    }
+
+    #[test]
+    fn copy_path_into_buffer_zero_bufsize_is_noop() {
+        let ret = unsafe { copy_path_into_buffer("abcde", ptr::null_mut(), 0) }
+            .expect("a zero-size buffer must not be treated as an error");
+        assert_eq!(ret, 5, "should still report the full path length");
+    }
+
+    #[test]
+    fn copy_path_into_buffer_null_buf_is_noop() {
+        let ret = unsafe { copy_path_into_buffer("abcde", ptr::null_mut(), 16) }
+            .expect("a NULL buffer must not be treated as an error");
+        assert_eq!(ret, 5, "should still report the full path length");
+    }
+
+    #[test]
+    fn cborrowedfd_try_as_fd_accepts_at_fdcwd() {
+        let fd = unsafe { CBorrowedFd::from_raw_fd(libc::AT_FDCWD) };
+        assert!(
+            matches!(fd.try_as_fd().expect("AT_FDCWD should be accepted"), CFd::Cwd),
+            "AT_FDCWD should resolve to CFd::Cwd",
+        );
+    }
+
+    #[test]
+    fn cborrowedfd_try_as_borrowed_fd_rejects_at_fdcwd() {
+        let fd = unsafe { CBorrowedFd::from_raw_fd(libc::AT_FDCWD) };
+        assert_eq!(
+            fd.try_as_borrowed_fd().map_err(|err| err.kind()),
+            Err(ErrorKind::InvalidArgument),
+            "AT_FDCWD should be rejected by try_as_borrowed_fd",
+        );
+    }
+
+    #[test]
+    fn cborrowedfd_try_as_fd_rejects_other_negative_values() {
+        let fd = unsafe { CBorrowedFd::from_raw_fd(-libc::EBADF) };
+        assert_eq!(
+            fd.try_as_fd().map_err(|err| err.kind()),
+            Err(ErrorKind::InvalidArgument),
+            "negative fds other than AT_FDCWD should still be rejected",
+        );
+    }
+
+    #[test]
+    fn cborrowedfd_try_as_fd_accepts_real_fd() {
+        let fd = unsafe { CBorrowedFd::from_raw_fd(libc::STDIN_FILENO) };
+        assert!(
+            matches!(fd.try_as_fd().expect("a real fd should be accepted"), CFd::Fd(_)),
+            "a non-negative fd should resolve to CFd::Fd",
+        );
+    }
+
     #[test]
     fn extensible_struct() {
         let example = Struct {
@@ -352,4 +741,175 @@ mod tests {
             "copy_from_extensible_struct(structv2, sizeof(structv2)) with trailing non-zero bytes",
         );
     }
+
+    #[test]
+    fn extensible_struct_to() {
+        let example = Struct {
+            foo: 0xdeadbeeff00dcafe,
+            bar: 0x01234567,
+            baz: 0x89abcdef,
+        };
+
+        let mut out = Struct::default();
+        let written = unsafe {
+            copy_to_extensible_struct(&mut out as *mut Struct, mem::size_of::<Struct>(), &example)
+        }
+        .expect("copy_to_extensible_struct with size=sizeof(struct)");
+        assert_eq!(written, mem::size_of::<Struct>());
+        assert_eq!(out, example, "copy_to_extensible_struct(struct, sizeof(struct))");
+    }
+
+    #[test]
+    fn extensible_struct_to_short() {
+        let example = Struct {
+            foo: 0xdeadbeeff00dcafe,
+            bar: 0x01234567,
+            baz: 0x89abcdef,
+        };
+
+        let mut out = Struct::default();
+        let written = unsafe {
+            copy_to_extensible_struct(
+                &mut out as *mut Struct,
+                bytemuck::offset_of!(Struct, bar),
+                &example,
+            )
+        }
+        .expect("copy_to_extensible_struct with size=offsetof(struct, bar)");
+        assert_eq!(written, bytemuck::offset_of!(Struct, bar));
+        assert_eq!(
+            out,
+            Struct {
+                foo: example.foo,
+                ..Default::default()
+            },
+            "copy_to_extensible_struct only writes up to size bytes",
+        );
+    }
+
+    #[test]
+    fn extensible_struct_to_old_caller_e2big() {
+        #[repr(C)]
+        #[derive(PartialEq, Eq, Default, Debug, Clone, Copy, Pod, Zeroable)]
+        struct StructV2 {
+            inner: Struct,
+            extra: u64,
+        }
+
+        let example = StructV2 {
+            inner: Struct {
+                foo: 0xdeadbeeff00dcafe,
+                bar: 0x01234567,
+                baz: 0x89abcdef,
+            },
+            extra: 0x1,
+        };
+
+        let mut out = StructV2::default();
+        assert_eq!(
+            unsafe {
+                copy_to_extensible_struct(&mut out as *mut StructV2, mem::size_of::<Struct>(), &example)
+            }
+            .map_err(|err| err.kind()),
+            Err(ErrorKind::UnsupportedStructureData),
+            "old caller asking for too-small a struct must get E2BIG if we'd drop meaningful data",
+        );
+    }
+
+    #[test]
+    fn struct_size_matches_actual_struct_size() {
+        assert_eq!(
+            pathrs_struct_size(PathrsStructKind::PATHRS_STRUCT_CONFIG),
+            mem::size_of::<CConfig>(),
+        );
+        assert_eq!(
+            pathrs_struct_size(PathrsStructKind::PATHRS_STRUCT_PROCFS_OPEN_HOW),
+            mem::size_of::<ProcfsOpenHow>(),
+        );
+        assert_eq!(
+            pathrs_struct_size(PathrsStructKind::PATHRS_STRUCT_PROCFS_STAT),
+            mem::size_of::<ProcfsStat>(),
+        );
+    }
+
+    #[test]
+    fn handle_table_insert_get_remove_round_trip() {
+        let table = HandleTable::default();
+        let handle = table.insert(1234_u32);
+
+        assert_eq!(
+            table.get(handle, |value| *value).expect("live handle should resolve"),
+            1234,
+        );
+        assert_eq!(
+            table.remove(handle).expect("live handle should be removable"),
+            1234,
+        );
+    }
+
+    #[test]
+    fn handle_table_rejects_unknown_handle() {
+        let table = HandleTable::<u32>::default();
+        assert_eq!(
+            table.get(encode_handle(0, 0), |value| *value).map_err(|err| err.kind()),
+            Err(ErrorKind::InvalidArgument),
+            "a handle into an empty table must not resolve",
+        );
+    }
+
+    #[test]
+    fn handle_table_rejects_double_free() {
+        let table = HandleTable::default();
+        let handle = table.insert("hello".to_string());
+
+        table.remove(handle).expect("first free must succeed");
+        assert_eq!(
+            table.remove(handle).map_err(|err| err.kind()),
+            Err(ErrorKind::InvalidArgument),
+            "freeing the same handle twice must be rejected rather than touching freed memory",
+        );
+    }
+
+    #[test]
+    fn handle_table_rejects_stale_generation_after_slot_reuse() {
+        let table = HandleTable::default();
+        let first = table.insert("first".to_string());
+        table.remove(first).expect("first free must succeed");
+
+        // The slot vacated by `first` should be reused for `second`, bumping
+        // the generation -- so the old `first` handle must not resolve to
+        // (or free) the new occupant.
+        let second = table.insert("second".to_string());
+        assert_ne!(first, second, "a reused slot must carry a new generation");
+
+        assert_eq!(
+            table.get(first, |value| value.clone()).map_err(|err| err.kind()),
+            Err(ErrorKind::InvalidArgument),
+            "a stale handle from a freed generation must not resolve to the new occupant",
+        );
+        assert_eq!(
+            table.get(second, |value| value.clone()).expect("the new occupant should still resolve"),
+            "second",
+        );
+    }
+
+    #[test]
+    fn handle_table_is_usable_concurrently() {
+        let table = std::sync::Arc::new(HandleTable::default());
+
+        let handles: Vec<_> = (0..8_u32)
+            .map(|i| {
+                let table = std::sync::Arc::clone(&table);
+                std::thread::spawn(move || table.insert(i))
+            })
+            .map(|thread| thread.join().expect("inserting thread must not panic"))
+            .collect();
+
+        for (i, handle) in handles.into_iter().enumerate() {
+            assert_eq!(
+                table.remove(handle).expect("every concurrently-inserted handle should be freeable"),
+                i as u32,
+            );
+        }
+    }
 }
@@ -0,0 +1,196 @@
+// SPDX-License-Identifier: MPL-2.0 OR LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * == MPL-2.0 ==
+ *
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Alternatively, this Source Code Form may also (at your option) be used
+ * under the terms of the GNU Lesser General Public License Version 3, as
+ * described below:
+ *
+ * == LGPL-3.0-or-later ==
+ *
+ *  This program is free software: you can redistribute it and/or modify it
+ *  under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or (at
+ *  your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful, but
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY  or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General
+ * Public License  for more details.
+ *
+ *  You should have received a copy of the GNU Lesser General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Spawn child processes confined to an already-resolved pathrs directory
+//! handle, analogous to [`std::process::Command`] but safe against
+//! symlink/mount races during setup.
+//!
+//! [`Command::new`] takes any directory handle that implements [`AsFd`] --
+//! in practice, a [`Root`] or a [`Handle`] pointing at a directory -- and
+//! confines the child to it using the classic "resolve once, `fchdir`+
+//! `chroot` the fd" trick: the untrusted path is resolved exactly once, by
+//! pathrs, before the child is ever forked; the child's `pre_exec` hook then
+//! just `fchdir(2)`s into the already-open, already-verified directory fd and
+//! `chroot(2)`s to `"."`, so there is no window between "resolve" and
+//! "confine" for an attacker to race. The kernel enforces the confinement for
+//! the rest of the child's lifetime, not libpathrs.
+//!
+//! [`Root`]: crate::Root
+//! [`Handle`]: crate::Handle
+
+use crate::{
+    error::{Error, ErrorImpl},
+    syscalls,
+};
+
+use std::{
+    ffi::OsStr,
+    io,
+    os::{
+        linux::process::CommandExt as LinuxCommandExt,
+        unix::{
+            io::{AsFd, OwnedFd},
+            process::CommandExt,
+        },
+    },
+    path::Path,
+    process::{Child, Stdio},
+};
+
+/// A [`std::process::Command`]-alike that `execve(2)`s a child process
+/// chrooted into an already-resolved directory handle (such as a [`Root`] or
+/// [`Handle`]).
+///
+/// Unlike `std::process::Command`, the child's root directory (and, unless
+/// overridden with [`Command::current_dir`], its working directory) is
+/// always the directory `Command` was constructed with -- there is no way to
+/// accidentally `execve(2)` something outside of the resolved directory.
+///
+/// [`Root`]: crate::Root
+/// [`Handle`]: crate::Handle
+pub struct Command {
+    root_fd: OwnedFd,
+    inner: std::process::Command,
+}
+
+impl Command {
+    /// Create a new [`Command`] which will run `program` (looked up the same
+    /// way `std::process::Command::new` does) inside a child process
+    /// confined to `root`.
+    ///
+    /// By default, the child's working directory is the root of `root`
+    /// itself (equivalent to `current_dir(".")`), its environment is
+    /// inherited from the current process, and `stdin`/`stdout`/`stderr` are
+    /// inherited from the current process -- exactly like
+    /// `std::process::Command`.
+    pub fn new(root: impl AsFd, program: impl AsRef<OsStr>) -> Result<Self, Error> {
+        let root_fd = root.as_fd().try_clone_to_owned().map_err(|err| ErrorImpl::OsError {
+            operation: "clone root directory fd for child process".into(),
+            source: err,
+        })?;
+        Ok(Self {
+            root_fd,
+            inner: std::process::Command::new(program),
+        })
+    }
+
+    /// Add an argument to pass to the child.
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.inner.arg(arg);
+        self
+    }
+
+    /// Add multiple arguments to pass to the child.
+    pub fn args(mut self, args: impl IntoIterator<Item = impl AsRef<OsStr>>) -> Self {
+        self.inner.args(args);
+        self
+    }
+
+    /// Insert or update an environment variable for the child.
+    pub fn env(mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Self {
+        self.inner.env(key, value);
+        self
+    }
+
+    /// Clear the entire environment map for the child.
+    pub fn env_clear(mut self) -> Self {
+        self.inner.env_clear();
+        self
+    }
+
+    /// Set the working directory for the child, relative to `root`.
+    ///
+    /// Note that (unlike the root confinement itself) this path is resolved
+    /// by the *child*, after it has already been confined with `chroot(2)`
+    /// -- so while the child cannot escape `root` no matter what `dir` is,
+    /// `dir` is not resolved race-free by libpathrs the way `Root::resolve`
+    /// is. Prefer leaving this unset (in which case the child's working
+    /// directory is the root itself) unless you have a specific reason to
+    /// change it.
+    pub fn current_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.inner.current_dir(dir);
+        self
+    }
+
+    /// Configure the child's standard input handle.
+    pub fn stdin(mut self, stdin: impl Into<Stdio>) -> Self {
+        self.inner.stdin(stdin);
+        self
+    }
+
+    /// Configure the child's standard output handle.
+    pub fn stdout(mut self, stdout: impl Into<Stdio>) -> Self {
+        self.inner.stdout(stdout);
+        self
+    }
+
+    /// Configure the child's standard error handle.
+    pub fn stderr(mut self, stderr: impl Into<Stdio>) -> Self {
+        self.inner.stderr(stderr);
+        self
+    }
+
+    /// Spawn the child process.
+    ///
+    /// The returned [`Child`] behaves exactly like one from
+    /// `std::process::Command::spawn` -- use [`Child::id`] for the child's
+    /// pid, or [`std::os::linux::process::ChildExt::pidfd`] (the child is
+    /// always spawned with a `pidfd` attached) for a `pidfd` that can be used
+    /// with [`ProcfsBase::ProcPidFd`] to safely operate on the child's
+    /// `/proc` entry without the PID-reuse TOCTOU that a raw pid would have.
+    ///
+    /// [`ProcfsBase::ProcPidFd`]: crate::procfs::ProcfsBase::ProcPidFd
+    pub fn spawn(mut self) -> Result<Child, Error> {
+        let root_fd = self.root_fd;
+
+        // SAFETY: the pre_exec closure only does a fchdir(2) and chroot(2)
+        // on an already-open fd (no allocation, no locking, entirely
+        // async-signal-safe), so it's safe to run in the forked child before
+        // execve(2).
+        unsafe {
+            self.inner.pre_exec(move || -> io::Result<()> {
+                syscalls::fchdir(&root_fd)?;
+                syscalls::chroot(".")?;
+                Ok(())
+            });
+        }
+
+        self.inner.create_pidfd(true);
+        self.inner.spawn().map_err(|err| {
+            ErrorImpl::OsError {
+                operation: "spawn child process confined to root".into(),
+                source: err,
+            }
+            .into()
+        })
+    }
+}
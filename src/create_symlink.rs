@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: MPL-2.0 OR LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * == MPL-2.0 ==
+ *
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Alternatively, this Source Code Form may also (at your option) be used
+ * under the terms of the GNU Lesser General Public License Version 3, as
+ * described below:
+ *
+ * == LGPL-3.0-or-later ==
+ *
+ *  This program is free software: you can redistribute it and/or modify it
+ *  under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or (at
+ *  your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful, but
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY  or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General
+ * Public License  for more details.
+ *
+ *  You should have received a copy of the GNU Lesser General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#![forbid(unsafe_code)]
+
+//! [`Root::create_symlink`], a target-checked wrapper around
+//! [`Root::create`]`(path, &InodeType::Symlink(target))`.
+//!
+//! A plain `symlinkat(2)` will happily create a symlink whose target is an
+//! absolute path or climbs (via `..`) above the root -- the link itself is
+//! created safely, but anything that later follows it *without* going
+//! through libpathrs (a different process, a bind-mount, a build output
+//! consumed elsewhere) could be tricked into leaving the root. This module
+//! rejects that at creation time instead.
+//!
+//! [`Root::create`]: crate::Root::create
+
+use crate::{
+    error::{Error, ErrorImpl},
+    InodeType, Root,
+};
+
+use std::path::{Component, Path};
+
+/// Returns `true` if `target` (the target of a symlink created at `path`)
+/// would, once fully `..`-normalized against `path`'s parent directory,
+/// climb above the root.
+fn escapes_root(path: &Path, target: &Path) -> bool {
+    let mut depth: i64 = path
+        .parent()
+        .map_or(0, |parent| parent.components().count() as i64);
+
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => {
+                depth -= 1;
+                if depth < 0 {
+                    return true;
+                }
+            }
+            // Absolute targets are rejected separately (with a clearer
+            // error) before this check ever runs.
+            Component::RootDir | Component::Prefix(_) => return true,
+        }
+    }
+    false
+}
+
+impl Root {
+    /// Create a symlink at `path` (inside the root) pointing to `target`,
+    /// refusing to do so if `target` is an absolute path.
+    ///
+    /// An absolute symlink target is still confined as far as libpathrs
+    /// itself is concerned (any later `resolve()` through it stays inside
+    /// the root), but it can mislead a consumer that isn't aware of the
+    /// root boundary (e.g. a tool that follows the link with a plain
+    /// `open(2)`) into escaping outside of it. Use
+    /// [`Root::create_symlink_strict`] if you also want to reject targets
+    /// whose `..`-normalized prefix would climb above `path`'s own position
+    /// in the root.
+    pub fn create_symlink(&self, path: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<(), Error> {
+        let target = target.as_ref();
+        if target.has_root() {
+            return Err(ErrorImpl::SafetyViolation {
+                description: format!(
+                    "symlink target {target:?} is an absolute path and could let an unsuspecting caller escape the root"
+                )
+                .into(),
+            }
+            .into());
+        }
+        self.create(path, &InodeType::Symlink(target.to_path_buf()))
+    }
+
+    /// Like [`Root::create_symlink`], but also refuses targets whose
+    /// `..`-normalized prefix (relative to `path`'s own directory within the
+    /// root) would climb above the root.
+    ///
+    /// This is stricter than what libpathrs actually needs to stay safe --
+    /// it exists for callers that want their on-disk symlinks to never
+    /// *look* like they escape the root, not just to never actually be
+    /// followed out of it.
+    pub fn create_symlink_strict(
+        &self,
+        path: impl AsRef<Path>,
+        target: impl AsRef<Path>,
+    ) -> Result<(), Error> {
+        let path = path.as_ref();
+        let target = target.as_ref();
+        if !target.has_root() && escapes_root(path, target) {
+            return Err(ErrorImpl::SafetyViolation {
+                description: format!(
+                    "symlink target {target:?} climbs above the root when resolved from {path:?}"
+                )
+                .into(),
+            }
+            .into());
+        }
+        self.create_symlink(path, target)
+    }
+}
@@ -0,0 +1,262 @@
+// SPDX-License-Identifier: MPL-2.0 OR LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * == MPL-2.0 ==
+ *
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Alternatively, this Source Code Form may also (at your option) be used
+ * under the terms of the GNU Lesser General Public License Version 3, as
+ * described below:
+ *
+ * == LGPL-3.0-or-later ==
+ *
+ *  This program is free software: you can redistribute it and/or modify it
+ *  under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or (at
+ *  your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful, but
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY  or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General
+ * Public License  for more details.
+ *
+ *  You should have received a copy of the GNU Lesser General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#![forbid(unsafe_code)]
+
+//! Race-free directory iteration, returned by [`Root::read_dir`] and
+//! [`Handle::read_dir`].
+//!
+//! Unlike opening a directory yourself and calling `readdir(3)`/[`getdents64`]
+//! on it, every [`DirEntry`] yielded by [`Directory`] carries the directory fd
+//! it was read from, so re-opening an entry (via [`DirEntry::open`] or
+//! [`DirEntry::resolve`]) is always done relative to that fd rather than by
+//! reconstructing a path -- a concurrent rename of an ancestor component
+//! cannot redirect the re-open outside of the directory that was listed.
+//!
+//! [`Root::read_dir`]: crate::Root::read_dir
+//! [`Handle::read_dir`]: crate::Handle::read_dir
+//! [`getdents64`]: https://www.man7.org/linux/man-pages/man2/getdents64.2.html
+//!
+//! Not yet mirrored: `CapiRoot`'s `RootImpl` test-trait impl doesn't
+//! implement `read_dir` (or several other `RootImpl` methods, such as
+//! `resolve_partial`/`metadata`/`remove`), and there is no
+//! `pathrs_inroot_readdir` C API entry point -- the same pre-existing gap
+//! that already affects `CapiHandle`. Closing it means designing how a
+//! directory-iteration object gets leaked across the FFI boundary, which
+//! is a bigger change than adding one more delegating method.
+
+use crate::{
+    error::{Error, ErrorImpl},
+    flags::OpenFlags,
+    syscalls, Handle,
+};
+
+use std::{
+    ffi::{OsStr, OsString},
+    fs::File,
+    os::unix::{ffi::OsStrExt, io::AsFd},
+    rc::Rc,
+};
+
+use rustix::fs::{self as rustix_fs, AtFlags};
+
+/// The type of a directory entry, as reported by the kernel's `d_type` field.
+///
+/// Unlike [`std::fs::FileType`], this can be constructed without doing an
+/// extra `fstatat(2)` -- but for the same reason, [`FileType::Unknown`] must
+/// be handled by callers that need a definitive answer (some filesystems
+/// don't fill in `d_type`, in which case the caller needs to stat the entry
+/// themselves).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FileType {
+    /// The kernel did not tell us the type of this entry (`DT_UNKNOWN`). The
+    /// caller needs to `fstatat(2)` the entry to find out its real type.
+    Unknown,
+    /// A named pipe (`DT_FIFO`).
+    Fifo,
+    /// A character device (`DT_CHR`).
+    CharacterDevice,
+    /// A directory (`DT_DIR`).
+    Directory,
+    /// A block device (`DT_BLK`).
+    BlockDevice,
+    /// A regular file (`DT_REG`).
+    File,
+    /// A symbolic link (`DT_LNK`).
+    Symlink,
+    /// A Unix domain socket (`DT_SOCK`).
+    Socket,
+}
+
+impl From<rustix_fs::FileType> for FileType {
+    fn from(file_type: rustix_fs::FileType) -> Self {
+        match file_type {
+            rustix_fs::FileType::Fifo => Self::Fifo,
+            rustix_fs::FileType::CharacterDevice => Self::CharacterDevice,
+            rustix_fs::FileType::Directory => Self::Directory,
+            rustix_fs::FileType::BlockDevice => Self::BlockDevice,
+            rustix_fs::FileType::RegularFile => Self::File,
+            rustix_fs::FileType::Symlink => Self::Symlink,
+            rustix_fs::FileType::Socket => Self::Socket,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// A single entry of a [`Directory`] being iterated.
+///
+/// `DirEntry` carries a reference to the directory fd it was read from, so
+/// [`DirEntry::open`] and [`DirEntry::resolve`] re-open the entry relative to
+/// that fd rather than by path -- this is what makes iterating a [`Directory`]
+/// race-free.
+#[derive(Debug)]
+pub struct DirEntry {
+    dirfd: Rc<File>,
+    file_name: OsString,
+    file_type: FileType,
+}
+
+impl DirEntry {
+    /// The name of this entry, relative to the directory it was read from.
+    pub fn file_name(&self) -> &OsStr {
+        &self.file_name
+    }
+
+    /// The type of this entry, if the kernel provided it in `d_type`.
+    ///
+    /// Some filesystems don't fill in `d_type`, in which case this will be
+    /// [`FileType::Unknown`] and you will need to `fstatat(2)` the entry (for
+    /// instance through [`DirEntry::open`]) to find out its real type.
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    /// The type of this entry, resolving [`FileType::Unknown`] with an
+    /// `fstatat(2)` if necessary.
+    ///
+    /// Most filesystems fill in `d_type` and this never does any syscall at
+    /// all, but some (certain network filesystems, `xfs` in some
+    /// configurations, ...) always report [`FileType::Unknown`] -- this falls
+    /// back to an `AT_SYMLINK_NOFOLLOW` `fstatat(2)` of the entry (relative to
+    /// the directory fd it was read from, so no path is involved) to get a
+    /// definitive answer in that case.
+    pub fn resolved_file_type(&self) -> Result<FileType, Error> {
+        if self.file_type != FileType::Unknown {
+            return Ok(self.file_type);
+        }
+
+        let stat = rustix_fs::statat(
+            self.dirfd.as_fd(),
+            self.file_name.as_os_str(),
+            AtFlags::SYMLINK_NOFOLLOW,
+        )
+        .map_err(|err| ErrorImpl::OsError {
+            operation: "stat directory entry with unknown d_type".into(),
+            source: err.into(),
+        })?;
+
+        Ok(rustix_fs::FileType::from_raw_mode(stat.st_mode).into())
+    }
+
+    /// Open this entry relative to the directory it was read from.
+    ///
+    /// Like the main resolver, trailing symlinks are never silently followed
+    /// unless `flags` does not contain `O_NOFOLLOW` -- set it explicitly if
+    /// you want to open a symlink itself rather than its target.
+    pub fn open(&self, flags: impl Into<OpenFlags>) -> Result<File, Error> {
+        syscalls::openat(self.dirfd.as_fd(), &self.file_name, flags.into(), 0)
+            .map(File::from)
+            .map_err(|err| {
+                ErrorImpl::RawOsError {
+                    operation: "open directory entry".into(),
+                    source: err,
+                }
+                .into()
+            })
+    }
+
+    /// Resolve this entry to a [`Handle`], relative to the directory it was
+    /// read from.
+    pub fn resolve(&self) -> Result<Handle, Error> {
+        syscalls::openat(
+            self.dirfd.as_fd(),
+            &self.file_name,
+            OpenFlags::O_PATH | OpenFlags::O_NOFOLLOW,
+            0,
+        )
+        .map(Handle::from_fd)
+        .map_err(|err| {
+            ErrorImpl::RawOsError {
+                operation: "open directory entry as O_PATH handle".into(),
+                source: err,
+            }
+            .into()
+        })
+    }
+}
+
+/// A race-free iterator over the entries of a directory, returned by
+/// [`Root::read_dir`] and [`Handle::read_dir`].
+///
+/// `.` and `..` are filtered out automatically.
+///
+/// [`Root::read_dir`]: crate::Root::read_dir
+/// [`Handle::read_dir`]: crate::Handle::read_dir
+#[derive(Debug)]
+pub struct Directory {
+    dirfd: Rc<File>,
+    stream: rustix_fs::Dir,
+}
+
+impl Directory {
+    pub(crate) fn from_file(dirfd: File) -> Result<Self, Error> {
+        let stream = rustix_fs::Dir::read_from(&dirfd).map_err(|err| ErrorImpl::OsError {
+            operation: "create directory iteration stream".into(),
+            source: err.into(),
+        })?;
+        Ok(Self {
+            dirfd: Rc::new(dirfd),
+            stream,
+        })
+    }
+}
+
+impl Iterator for Directory {
+    type Item = Result<DirEntry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.stream.next()? {
+                Ok(entry) => entry,
+                Err(err) => {
+                    return Some(Err(ErrorImpl::OsError {
+                        operation: "read next directory entry".into(),
+                        source: err.into(),
+                    }
+                    .into()))
+                }
+            };
+
+            let file_name = entry.file_name().to_bytes();
+            if matches!(file_name, b"." | b"..") {
+                continue;
+            }
+
+            return Some(Ok(DirEntry {
+                dirfd: Rc::clone(&self.dirfd),
+                file_name: OsStr::from_bytes(file_name).to_os_string(),
+                file_type: entry.file_type().into(),
+            }));
+        }
+    }
+}
@@ -34,61 +34,148 @@
 
 //! Error types for libpathrs.
 
-// NOTE: This module is mostly a workaround until several issues have been
-//       resolved:
-//
-//  * `std::error::Error::chain` is stabilised.
-//  * I figure out a nice way to implement GlobalBacktrace...
-
 use crate::{resolvers::opath::SymlinkStackError, syscalls::Error as SyscallError};
 
-use std::{borrow::Cow, io::Error as IOError};
-
-// TODO: Add a backtrace to Error. We would just need to add an automatic
-//       Backtrace::capture() in From. But it's not clear whether we want to
-//       export the crate types here without std::backtrace::Backtrace.
-// MSRV(1.65): Use std::backtrace::Backtrace.
+use std::{
+    backtrace::{Backtrace, BacktraceStatus},
+    borrow::Cow,
+    io::Error as IOError,
+};
 
 /// Opaque error type for libpathrs.
 ///
 /// If you wish to do non-trivial error handling with libpathrs errors, use
 /// [`Error::kind`] to get an [`ErrorKind`] you can handle programmatically.
-#[derive(thiserror::Error, Debug)]
-#[error(transparent)]
-pub struct Error(#[from] Box<ErrorImpl>);
+#[derive(Debug)]
+pub struct Error {
+    inner: Box<ErrorImpl>,
+    // Captured once, at the outermost `From<E> for Error` conversion --
+    // wrapping an existing Error with more context (via ErrorExt::with_wrap)
+    // re-uses this same backtrace rather than capturing a new one, since the
+    // original capture point is what callers actually care about.
+    backtrace: Backtrace,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        std::error::Error::source(&*self.inner)
+    }
+}
 
 impl<E: Into<ErrorImpl>> From<E> for Error {
     // TODO: Is there a way to make this not be exported at all?
     #[doc(hidden)]
     fn from(err: E) -> Self {
-        Self(Box::new(err.into()))
+        Self {
+            inner: Box::new(err.into()),
+            // Backtrace::capture() is itself gated on RUST_BACKTRACE /
+            // RUST_LIB_BACKTRACE (falling back to the cheap
+            // Backtrace::disabled() otherwise), so this never costs more
+            // than a flag check unless the caller actually opted in.
+            backtrace: Backtrace::capture(),
+        }
+    }
+}
+
+/// Converts a libpathrs [`Error`] into a [`std::io::Error`], for callers that
+/// only want to work with the standard library's error type.
+///
+/// Almost every [`ErrorKind`] carries (or is mapped to) an `errno` value via
+/// [`Error::raw_os_error`], so in practice this almost always produces a
+/// [`std::io::Error::from_raw_os_error`] result -- which, conveniently, is
+/// also how `std` itself derives a [`std::io::ErrorKind`] from an `errno`, so
+/// kinds like [`ErrorKind::InvalidArgument`] still end up as the
+/// [`std::io::ErrorKind`] you would expect (`InvalidInput`). The only kind
+/// with no associated `errno` at all is [`ErrorKind::InternalError`], which
+/// has no good `std::io::ErrorKind` equivalent and is mapped to `Other`
+/// (keeping the original [`Error`] as the wrapped source, so no information
+/// is lost).
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> Self {
+        match err.raw_os_error() {
+            Some(errno) => std::io::Error::from_raw_os_error(errno),
+            None => std::io::Error::new(std::io::ErrorKind::Other, err),
+        }
     }
 }
 
 impl Error {
     /// Get the [`ErrorKind`] of this error.
     pub fn kind(&self) -> ErrorKind {
-        self.0.kind()
+        self.inner.kind()
     }
 
     /// Shorthand for [`.kind().can_retry()`](ErrorKind::can_retry).
     pub fn can_retry(&self) -> bool {
-        self.0.kind().can_retry()
+        self.inner.kind().can_retry()
+    }
+
+    /// Shorthand for [`.kind().errno()`](ErrorKind::errno).
+    ///
+    /// Note that this includes the synthetic errno values non-OS error kinds
+    /// are mapped to (for instance [`ErrorKind::InvalidArgument`] is always
+    /// `EINVAL`), not just genuine [`ErrorKind::OsError`] values.
+    pub fn raw_os_error(&self) -> Option<i32> {
+        self.inner.kind().errno()
+    }
+
+    /// Get the [`Backtrace`] captured when this [`Error`] was created, if
+    /// any.
+    ///
+    /// A backtrace is only captured if it was requested via the standard
+    /// `RUST_BACKTRACE=1` or `RUST_LIB_BACKTRACE=1` environment variables (the
+    /// same toggle [`Backtrace::capture`] itself uses) -- this returns `None`
+    /// if backtrace capture wasn't requested, or if the platform doesn't
+    /// support capturing backtraces.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        match self.backtrace.status() {
+            BacktraceStatus::Captured => Some(&self.backtrace),
+            _ => None,
+        }
+    }
+
+    /// Iterate over the causal chain of this [`Error`], starting with `self`
+    /// and then following each wrapped context (as added by
+    /// [`anyhow`](https://docs.rs/anyhow)-style `.wrap()` calls) down to the
+    /// innermost cause -- usually an [`ErrorKind::OsError`].
+    ///
+    /// This is equivalent in spirit to the standard library's still-unstable
+    /// `std::error::Error::chain`, exposed here as a first-class API so
+    /// callers can walk the human-readable context layers (or find the root
+    /// syscall failure) without needing to know about our private
+    /// [`ErrorImpl`] type.
+    pub fn chain(&self) -> impl Iterator<Item = &(dyn std::error::Error + 'static)> {
+        std::iter::successors(Some(self as &(dyn std::error::Error + 'static)), |err| {
+            err.source()
+        })
+    }
+
+    /// The innermost cause of this [`Error`], i.e. the last element of
+    /// [`Error::chain`].
+    pub fn root_cause(&self) -> &(dyn std::error::Error + 'static) {
+        self.chain()
+            .last()
+            .expect("Error::chain() always yields at least one element (self)")
     }
 
     pub(crate) fn is_safety_violation(&self) -> bool {
-        self.0.is_safety_violation()
+        self.inner.is_safety_violation()
     }
 
     #[cfg(test)]
     pub(crate) fn into_inner(self) -> ErrorImpl {
-        *self.0
+        *self.inner
     }
 }
 
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum ErrorImpl {
-    #[allow(dead_code)]
     #[error("feature {feature} is not implemented")]
     NotImplemented { feature: Cow<'static, str> },
 
@@ -108,6 +195,12 @@ pub(crate) enum ErrorImpl {
     #[error("violation of safety requirement: {description}")]
     SafetyViolation { description: Cow<'static, str> },
 
+    #[error("procfs mount does not have the expected hardening: {description}")]
+    UnsafeProcfsMount { description: Cow<'static, str> },
+
+    #[error("exceeded resource limit: {description}")]
+    ResourceExhausted { description: Cow<'static, str> },
+
     #[error("broken symlink stack during iteration: {description}")]
     BadSymlinkStackError {
         description: Cow<'static, str>,
@@ -172,6 +265,12 @@ pub enum ErrorKind {
     /// Some internal error occurred. For more information, see the string
     /// description of the original [`Error`].
     InternalError,
+    /// A configured limit (such as a recursion depth or retry count) was
+    /// exceeded. This is used by operations that would otherwise have to
+    /// loop or recurse an unbounded number of times -- usually to stop an
+    /// attacker from inducing a livelock or stack exhaustion by repeatedly
+    /// recreating entries we are trying to process.
+    ResourceExhausted,
     /// The underlying error came from a system call. The provided
     /// [`std::io::RawOsError`] is the numerical value of the `errno` number, if
     /// available.
@@ -188,6 +287,8 @@ impl ErrorImpl {
             #[cfg(feature = "capi")]
             Self::UnsupportedStructureData { .. } => ErrorKind::UnsupportedStructureData,
             Self::SafetyViolation { .. } => ErrorKind::SafetyViolation,
+            Self::UnsafeProcfsMount { .. } => ErrorKind::SafetyViolation,
+            Self::ResourceExhausted { .. } => ErrorKind::ResourceExhausted,
             // Any syscall-related errors get mapped to an OsError, since the
             // distinction doesn't matter to users checking error values.
             Self::OsError { source, .. } => ErrorKind::OsError(source.raw_os_error()),
@@ -225,6 +326,11 @@ impl ErrorKind {
             #[cfg(feature = "capi")]
             ErrorKind::UnsupportedStructureData => Some(libc::E2BIG),
             ErrorKind::SafetyViolation => Some(libc::EXDEV),
+            // There's no standard errno for "gave up after a bounded number
+            // of loop/recursion iterations", but ELOOP's literal meaning
+            // ("too many levels of symbolic links") is the closest existing
+            // analogue to "we refused to keep going indefinitely".
+            ErrorKind::ResourceExhausted => Some(libc::ELOOP),
             ErrorKind::OsError(errno) => *errno,
             _ => None,
         }
@@ -241,6 +347,70 @@ impl ErrorKind {
     pub(crate) fn is_safety_violation(&self) -> bool {
         self.errno() == Self::SafetyViolation.errno()
     }
+
+    /// A stable, `errno`-independent string identifier for this
+    /// [`ErrorKind`], suitable for language bindings, structured logging, or
+    /// round-tripping across the C API. [`ErrorKind::OsError`]'s errno value
+    /// is not included in the string -- use [`Error::raw_os_error`]
+    /// separately if you need it.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::NotImplemented => "NotImplemented",
+            ErrorKind::NotSupported => "NotSupported",
+            ErrorKind::InvalidArgument => "InvalidArgument",
+            #[cfg(feature = "capi")]
+            ErrorKind::UnsupportedStructureData => "UnsupportedStructureData",
+            ErrorKind::SafetyViolation => "SafetyViolation",
+            ErrorKind::InternalError => "InternalError",
+            ErrorKind::ResourceExhausted => "ResourceExhausted",
+            ErrorKind::OsError(_) => "OsError",
+        }
+    }
+
+    /// Iterate over one representative value of every [`ErrorKind`] variant
+    /// (with [`ErrorKind::OsError`] represented by its errno-less `None`
+    /// form, since there's no single "every errno" enumeration).
+    ///
+    /// This is the list [`FromStr for ErrorKind`](ErrorKind#impl-FromStr-for-ErrorKind)
+    /// searches (matching each kind's [`ErrorKind::as_str`]) --
+    /// bindings that need to enumerate every kind (e.g. to generate an FFI
+    /// enum) should use this rather than hardcoding the variant list, since
+    /// `ErrorKind` is `#[non_exhaustive]`.
+    pub fn all() -> impl Iterator<Item = ErrorKind> {
+        let mut kinds = vec![
+            ErrorKind::NotImplemented,
+            ErrorKind::NotSupported,
+            ErrorKind::InvalidArgument,
+            ErrorKind::SafetyViolation,
+            ErrorKind::InternalError,
+            ErrorKind::ResourceExhausted,
+            ErrorKind::OsError(None),
+        ];
+        #[cfg(feature = "capi")]
+        kinds.push(ErrorKind::UnsupportedStructureData);
+        kinds.into_iter()
+    }
+}
+
+/// Error returned by [`ErrorKind`]'s [`FromStr`](std::str::FromStr)
+/// implementation when given a string that doesn't match any known
+/// [`ErrorKind::as_str`] identifier.
+#[derive(thiserror::Error, Debug)]
+#[error("unknown ErrorKind identifier {0:?}")]
+pub struct ParseErrorKindError(String);
+
+impl std::str::FromStr for ErrorKind {
+    type Err = ParseErrorKindError;
+
+    /// Parse a string previously produced by [`ErrorKind::as_str`] back into
+    /// an [`ErrorKind`]. Note that [`ErrorKind::OsError`]'s errno is not part
+    /// of the string form, so `"OsError".parse()` always yields
+    /// `OsError(None)` -- format/parse the errno separately if you need it.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ErrorKind::all()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| ParseErrorKindError(s.to_owned()))
+    }
 }
 
 // Private trait necessary to work around the "orphan trait" restriction.
@@ -274,7 +444,14 @@ impl ErrorExt for Error {
     where
         F: FnOnce() -> String,
     {
-        self.0.with_wrap(context_fn).into()
+        // Deliberately built by hand (rather than routing through
+        // `From<E> for Error`) so that the original backtrace is carried
+        // over instead of being re-captured at every wrap point.
+        let Self { inner, backtrace } = self;
+        Self {
+            inner: Box::new(inner.with_wrap(context_fn)),
+            backtrace,
+        }
     }
 }
 
@@ -316,4 +493,142 @@ mod tests {
             "ErrorKind::OsError(...)::errno() returns the inner errno"
         );
     }
+
+    #[test]
+    fn error_io_error_conversion() {
+        let err: Error = ErrorImpl::InvalidArgument {
+            name: "test".into(),
+            description: "test error".into(),
+        }
+        .into();
+        assert_eq!(
+            err.raw_os_error(),
+            Some(libc::EINVAL),
+            "InvalidArgument is mapped to the synthetic EINVAL errno"
+        );
+
+        let io_err: std::io::Error = err.into();
+        assert_eq!(
+            io_err.raw_os_error(),
+            Some(libc::EINVAL),
+            "From<Error> for io::Error preserves the errno"
+        );
+        assert_eq!(
+            io_err.kind(),
+            std::io::ErrorKind::InvalidInput,
+            "std derives InvalidInput from EINVAL itself"
+        );
+
+        let err: Error = ErrorImpl::ParseIntError("abc".parse::<i32>().unwrap_err()).into();
+        assert_eq!(
+            err.raw_os_error(),
+            None,
+            "InternalError kinds have no associated errno"
+        );
+        let io_err: std::io::Error = err.into();
+        assert_eq!(
+            io_err.kind(),
+            std::io::ErrorKind::Other,
+            "errno-less kinds fall back to std::io::ErrorKind::Other"
+        );
+    }
+
+    #[test]
+    fn error_backtrace_disabled_by_default() {
+        // We can't reliably flip RUST_BACKTRACE/RUST_LIB_BACKTRACE from
+        // within a test (other tests may run concurrently and observe a
+        // racy environment), so we only check the common case: by default
+        // (as in CI), no backtrace is captured.
+        let err: Error = ErrorImpl::InvalidArgument {
+            name: "test".into(),
+            description: "test error".into(),
+        }
+        .into();
+        if std::env::var_os("RUST_LIB_BACKTRACE").is_none() && std::env::var_os("RUST_BACKTRACE").is_none() {
+            assert!(
+                err.backtrace().is_none(),
+                "no backtrace should be captured unless explicitly requested"
+            );
+        }
+    }
+
+    #[test]
+    fn error_wrap_preserves_backtrace_capture_state() {
+        let err: Error = ErrorImpl::InvalidArgument {
+            name: "test".into(),
+            description: "test error".into(),
+        }
+        .into();
+        let was_captured = err.backtrace().is_some();
+
+        let wrapped = err.wrap("additional context");
+        assert_eq!(
+            wrapped.backtrace().is_some(),
+            was_captured,
+            "wrapping an Error should not change whether a backtrace was captured"
+        );
+    }
+
+    #[test]
+    fn error_chain_and_root_cause() {
+        let err: Error = ErrorImpl::InvalidArgument {
+            name: "test".into(),
+            description: "test error".into(),
+        }
+        .into();
+        assert_eq!(
+            err.chain().count(),
+            1,
+            "an unwrapped Error's chain contains only itself"
+        );
+
+        let wrapped = err.wrap("outer context").wrap("even more context");
+        assert_eq!(
+            wrapped.chain().count(),
+            3,
+            "each .wrap() call should add one more link to the chain"
+        );
+
+        let root_cause = wrapped.root_cause();
+        assert_eq!(
+            root_cause.to_string(),
+            wrapped.chain().last().unwrap().to_string(),
+            "root_cause() is the last element of chain()"
+        );
+        assert_eq!(
+            wrapped.raw_os_error(),
+            Some(libc::EINVAL),
+            "wrapping doesn't change the outer Error's errno"
+        );
+    }
+
+    #[test]
+    fn error_kind_as_str_roundtrip() {
+        for kind in ErrorKind::all() {
+            let s = kind.as_str();
+            assert_eq!(
+                s.parse::<ErrorKind>().expect("as_str() output must parse back"),
+                kind,
+                "ErrorKind::all() values must round-trip through as_str()/FromStr"
+            );
+        }
+    }
+
+    #[test]
+    fn error_kind_from_str_unknown() {
+        assert!(
+            "NotARealErrorKind".parse::<ErrorKind>().is_err(),
+            "unknown identifiers must be rejected"
+        );
+    }
+
+    #[test]
+    fn error_kind_os_error_str_drops_errno() {
+        assert_eq!(ErrorKind::OsError(Some(libc::ENOENT)).as_str(), "OsError");
+        assert_eq!(
+            "OsError".parse::<ErrorKind>().unwrap(),
+            ErrorKind::OsError(None),
+            "OsError's errno isn't part of the string form"
+        );
+    }
 }
@@ -167,6 +167,61 @@ mod root;
 #[doc(inline)]
 pub use root::*;
 
+// Race-free directory iteration, returned by `Root::read_dir`/`Handle::read_dir`.
+mod dir;
+#[doc(inline)]
+pub use dir::*;
+
+// Recursive directory walking, returned by `Root::walk`.
+mod walk;
+#[doc(inline)]
+pub use walk::*;
+
+// Structured `statx(2)` metadata, returned by `Root::metadata`/`Root::metadata_nofollow`.
+mod metadata;
+#[doc(inline)]
+pub use metadata::*;
+
+// Partial resolution, returned by `Root::resolve_partial`/`RootRef::resolve_partial`.
+mod resolve_partial;
+#[doc(inline)]
+pub use resolve_partial::*;
+
+// `Handle::readlink`, the handle-based counterpart to `Root::readlink`.
+mod readlink;
+
+// `Root::create_symlink`, a target-checked wrapper around `Root::create`.
+mod create_symlink;
+
+// Extended attribute and SELinux security-context access on a `Handle`.
+mod xattr;
+#[doc(inline)]
+pub use xattr::*;
+
+// `Handle::path_in_root`, a verified reverse mapping back to a root-relative path.
+mod path_in_root;
+
+// `Root::rename`/`RootRef::rename`, a race-free `renameat2(2)` wrapper
+// supporting atomic exchange and no-replace renames.
+mod rename;
+
+// cap-std interop: convert between `Root`/`Handle` and cap-std's
+// capability-based `Dir`/`File`.
+#[cfg(feature = "cap-std")]
+mod cap_std_interop;
+
+// Spawn child processes confined to a resolved `Root`/`Handle`.
+mod command;
+#[doc(inline)]
+pub use command::*;
+
+// 9P2000.L server, exporting a `Root` for sandboxed file sharing.
+#[cfg(feature = "serve-9p")]
+mod serve;
+#[cfg(feature = "serve-9p")]
+#[doc(inline)]
+pub use serve::*;
+
 pub mod error;
 pub mod flags;
 pub mod procfs;
@@ -179,6 +234,7 @@ mod resolvers;
 mod capi;
 
 // Internally used helpers.
+mod seccomp;
 mod syscalls;
 mod utils;
 
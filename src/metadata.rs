@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: MPL-2.0 OR LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * == MPL-2.0 ==
+ *
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Alternatively, this Source Code Form may also (at your option) be used
+ * under the terms of the GNU Lesser General Public License Version 3, as
+ * described below:
+ *
+ * == LGPL-3.0-or-later ==
+ *
+ *  This program is free software: you can redistribute it and/or modify it
+ *  under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or (at
+ *  your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful, but
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY  or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General
+ * Public License  for more details.
+ *
+ *  You should have received a copy of the GNU Lesser General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#![forbid(unsafe_code)]
+
+//! Structured `statx(2)`-based metadata, returned by [`Root::metadata`] and
+//! [`Root::metadata_nofollow`].
+//!
+//! Unlike [`std::fs::Metadata`], this exposes the extra fields `statx(2)`
+//! makes available (such as the mount ID and creation time) that the portable
+//! standard library type has no way of representing.
+//!
+//! [`Root::metadata`]: crate::Root::metadata
+//! [`Root::metadata_nofollow`]: crate::Root::metadata_nofollow
+
+use crate::FileType;
+
+use std::time::{Duration, SystemTime};
+
+use rustix::fs::{Stat, Statx};
+
+/// Structured `statx(2)` metadata for a path resolved inside a [`Root`].
+///
+/// [`Root`]: crate::Root
+#[derive(Debug, Clone, Copy)]
+pub struct Metadata {
+    file_type: FileType,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    size: u64,
+    mount_id: Option<u64>,
+    btime: Option<SystemTime>,
+}
+
+impl Metadata {
+    /// Build a [`Metadata`] directly from its component fields, bypassing
+    /// `statx(2)`/`fstatat(2)` entirely.
+    ///
+    /// This is used by the C API test harness to reconstruct a [`Metadata`]
+    /// from a `struct pathrs_proc_stat` returned across FFI, where there is
+    /// no [`Statx`]/[`Stat`] to hand to [`Metadata::from_statx`]/
+    /// [`Metadata::from_stat`].
+    pub(crate) fn from_parts(
+        file_type: FileType,
+        mode: u32,
+        uid: u32,
+        gid: u32,
+        size: u64,
+        mount_id: Option<u64>,
+        btime: Option<SystemTime>,
+    ) -> Self {
+        Self {
+            file_type,
+            mode: mode & 0o7777,
+            uid,
+            gid,
+            size,
+            mount_id,
+            btime,
+        }
+    }
+
+    pub(crate) fn from_statx(stx: &Statx, mount_id: Option<u64>) -> Self {
+        Self {
+            file_type: rustix::fs::FileType::from_raw_mode(stx.stx_mode.into()).into(),
+            mode: (stx.stx_mode & 0o7777).into(),
+            uid: stx.stx_uid,
+            gid: stx.stx_gid,
+            size: stx.stx_size,
+            mount_id,
+            btime: (stx.stx_mask & rustix::fs::StatxFlags::BTIME.bits() != 0).then(|| {
+                SystemTime::UNIX_EPOCH
+                    + Duration::new(stx.stx_btime.tv_sec as u64, stx.stx_btime.tv_nsec)
+            }),
+        }
+    }
+
+    /// Build a [`Metadata`] from a plain `fstat(2)`/`fstatat(2)` result, for
+    /// use as a fallback on kernels old enough to not support `statx(2)`
+    /// (before Linux 4.11) at all. The mount ID and creation time are not
+    /// available from `fstatat(2)`, so they are always [`None`] here.
+    pub(crate) fn from_stat(st: &Stat) -> Self {
+        Self {
+            file_type: rustix::fs::FileType::from_raw_mode(st.st_mode.into()).into(),
+            mode: (st.st_mode & 0o7777).into(),
+            uid: st.st_uid,
+            gid: st.st_gid,
+            size: st.st_size as u64,
+            mount_id: None,
+            btime: None,
+        }
+    }
+
+    /// The type of the inode (regular file, directory, symlink, etc).
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    /// The permission bits of the inode (the low 12 bits of `st_mode`).
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+
+    /// The owning user ID of the inode.
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// The owning group ID of the inode.
+    pub fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    /// The size of the inode, in bytes (for regular files) or the length of
+    /// the target path (for symlinks).
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    /// Whether this inode is empty (see [`Metadata::len`]).
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// The unique ID of the mount the inode lives on, if the kernel supports
+    /// `STATX_MNT_ID`/`STATX_MNT_ID_UNIQUE` (Linux 5.8 and later).
+    pub fn mount_id(&self) -> Option<u64> {
+        self.mount_id
+    }
+
+    /// The creation ("birth") time of the inode, if the underlying
+    /// filesystem and kernel support `STATX_BTIME` (Linux 4.11 and later, on
+    /// filesystems that record it).
+    pub fn created(&self) -> Option<SystemTime> {
+        self.btime
+    }
+}
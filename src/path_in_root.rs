@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: MPL-2.0 OR LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * == MPL-2.0 ==
+ *
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Alternatively, this Source Code Form may also (at your option) be used
+ * under the terms of the GNU Lesser General Public License Version 3, as
+ * described below:
+ *
+ * == LGPL-3.0-or-later ==
+ *
+ *  This program is free software: you can redistribute it and/or modify it
+ *  under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or (at
+ *  your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful, but
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY  or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General
+ * Public License  for more details.
+ *
+ *  You should have received a copy of the GNU Lesser General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#![forbid(unsafe_code)]
+
+//! [`Handle::path_in_root`], a checked way to express a [`Handle`]'s
+//! location relative to a [`Root`].
+//!
+//! Several test helpers already do this by hand: take
+//! [`FdExt::as_unsafe_path_unchecked`], strip the root's own unchecked path
+//! off the front, and trust the result. That's fine for tests, but it's not
+//! something library users should be encouraged to do blindly -- the
+//! `/proc/self/fd` readlink it's built on is inherently a best-effort
+//! string, not a guarantee. [`Handle::path_in_root`] re-resolves the
+//! reconstructed relative path from the root and confirms it lands on the
+//! same `(st_dev, st_ino)` before handing it back, so a caller only gets a
+//! path that is actually still correct.
+//!
+//! [`FdExt::as_unsafe_path_unchecked`]: crate::utils::FdExt::as_unsafe_path_unchecked
+
+use crate::{
+    error::{Error, ErrorImpl},
+    utils::FdExt,
+    Handle, Root,
+};
+
+use std::{
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
+
+fn dev_ino(fd: &impl FdExt) -> Result<(u64, u64), Error> {
+    let meta = fd.metadata()?;
+    Ok((meta.dev(), meta.ino()))
+}
+
+impl Handle {
+    /// Compute this handle's location expressed as a path relative to
+    /// `root`, verifying that the result is actually correct rather than
+    /// just trusting a `/proc/self/fd` readlink.
+    ///
+    /// This re-resolves the reconstructed relative path from `root` (using
+    /// [`Root::resolve_nofollow`], so a trailing symlink handle is matched
+    /// against itself rather than its target) and confirms it lands on the
+    /// same `(st_dev, st_ino)` as this handle, returning a
+    /// [`SafetyViolation`] error if the handle has since been moved
+    /// elsewhere or no longer lives inside `root` at all.
+    ///
+    /// [`Root::resolve_nofollow`]: crate::Root::resolve_nofollow
+    /// [`SafetyViolation`]: crate::error::ErrorKind::SafetyViolation
+    pub fn path_in_root(&self, root: &Root) -> Result<PathBuf, Error> {
+        let handle_path = self.as_unsafe_path_unchecked()?;
+        let root_path = root.as_unsafe_path_unchecked()?;
+
+        let relative = handle_path
+            .strip_prefix(&root_path)
+            .map_err(|_| ErrorImpl::SafetyViolation {
+                description: format!(
+                    "handle path {handle_path:?} is not inside root {root_path:?}"
+                )
+                .into(),
+            })?;
+        let relative: &Path = relative;
+
+        let reresolved = root.resolve_nofollow(relative)?;
+        if dev_ino(self)? != dev_ino(&reresolved)? {
+            return Err(ErrorImpl::SafetyViolation {
+                description: format!(
+                    "handle no longer matches the inode at {relative:?} inside the root -- it may have been moved"
+                )
+                .into(),
+            }
+            .into());
+        }
+
+        Ok(relative.to_path_buf())
+    }
+}
@@ -59,8 +59,10 @@
 //! ```
 
 use crate::{
+    dir::Directory,
     error::{Error, ErrorExt, ErrorImpl, ErrorKind},
     flags::{OpenFlags, ResolverFlags},
+    metadata::Metadata,
     resolvers::procfs::ProcfsResolver,
     syscalls,
     utils::{self, kernel_version, FdExt, MaybeOwnedFd, RawProcfsRoot},
@@ -78,10 +80,12 @@ use std::{
 
 use once_cell::sync::{Lazy, OnceCell as OnceLock};
 use rustix::{
-    fs::{self as rustix_fs, Access, AtFlags},
+    fs::{self as rustix_fs, Access, AtFlags, StatxFlags},
     mount::{FsMountFlags, FsOpenFlags, MountAttrFlags, OpenTreeFlags},
 };
 
+pub mod files;
+
 /// Indicate what base directory should be used when doing `/proc/...`
 /// operations with a [`ProcfsHandle`].
 ///
@@ -92,9 +96,9 @@ use rustix::{
 /// [`ProcSelf`]: Self::ProcSelf
 /// [`ProcThreadSelf`]: Self::ProcThreadSelf
 #[doc(alias = "pathrs_proc_base_t")]
-#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 #[non_exhaustive]
-pub enum ProcfsBase {
+pub enum ProcfsBase<'fd> {
     /// Use `/proc`. As this requires us to disable any masking of our internal
     /// procfs mount, any file handles returned from [`ProcfsHandle::open`]
     /// using `ProcRoot` should be treated with extra care to ensure you do not
@@ -124,7 +128,8 @@ pub enum ProcfsBase {
     ///    could reap zombies).
     ///
     /// Outside of those specific uses, users should probably avoid using this.
-    // TODO: Add support for pidfds, to resolve the race issue.
+    /// Prefer [`ProcPidFd`][`Self::ProcPidFd`] if you have (or can obtain) a
+    /// pidfd for the target process, as it closes the race entirely.
     ///
     /// [`ProcRoot`]: Self::ProcRoot
     /// [`ProcSelf`]: Self::ProcSelf
@@ -140,6 +145,25 @@ pub enum ProcfsBase {
     //       making this a u32 we can easily pack it inside a u64 for the C API.
     ProcPid(u32),
 
+    /// Use `/proc/<pid>` for the process uniquely identified by this pidfd.
+    ///
+    /// Unlike [`ProcPid`][`Self::ProcPid`], this variant closes the
+    /// PID-recycling race: we use the `PIDFD_GET_INFO` ioctl (Linux 6.13+) to
+    /// get the numeric pid and the kernel's record of the process's start
+    /// time directly from the pidfd, open `/proc/<pid>` through the normal
+    /// hardened resolver, and then cross-check the `starttime` field (field
+    /// 22) of `/proc/<pid>/stat` against the value `PIDFD_GET_INFO` gave us.
+    /// If they disagree -- or the directory has already vanished -- the pid
+    /// was recycled out from underneath us and an error is returned instead
+    /// of a handle to the wrong process.
+    ///
+    /// On kernels without `PIDFD_GET_INFO`, we fall back to comparing
+    /// `/proc/<pid>/stat`'s `starttime` read immediately before and after the
+    /// open, erroring out if it changed.
+    ///
+    /// [`ProcPid`]: Self::ProcPid
+    ProcPidFd(BorrowedFd<'fd>),
+
     /// Use `/proc/self`. For most programs, this is the standard choice.
     ProcSelf,
 
@@ -174,12 +198,42 @@ pub enum ProcfsBase {
     ProcThreadSelf,
 }
 
-impl ProcfsBase {
+// NOTE: We can't #[derive(PartialEq, Eq)] because BorrowedFd doesn't
+// implement those traits (there's no sensible way to compare fds for
+// equality in general -- an fd is only meaningful together with the process
+// that owns its fd table). For our purposes here, comparing the raw fd
+// numbers is good enough.
+impl<'fd> PartialEq for ProcfsBase<'fd> {
+    fn eq(&self, other: &Self) -> bool {
+        use std::os::unix::io::AsRawFd;
+        match (self, other) {
+            (Self::ProcRoot, Self::ProcRoot) => true,
+            (Self::ProcPid(a), Self::ProcPid(b)) => a == b,
+            (Self::ProcPidFd(a), Self::ProcPidFd(b)) => a.as_raw_fd() == b.as_raw_fd(),
+            (Self::ProcSelf, Self::ProcSelf) => true,
+            (Self::ProcThreadSelf, Self::ProcThreadSelf) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<'fd> Eq for ProcfsBase<'fd> {}
+
+impl<'fd> ProcfsBase<'fd> {
+    /// Convert this [`ProcfsBase`] into the path (relative to the real
+    /// `/proc`) that it refers to.
+    ///
+    /// [`ProcPidFd`][`Self::ProcPidFd`] is resolved separately in
+    /// [`ProcfsHandleRef::open_base`] (it needs fallible I/O to do so
+    /// race-free), so it must never reach this method.
     pub(crate) fn into_path(self, proc_rootfd: RawProcfsRoot<'_>) -> PathBuf {
         match self {
             Self::ProcRoot => PathBuf::from("."),
             Self::ProcSelf => PathBuf::from("self"),
             Self::ProcPid(pid) => PathBuf::from(pid.to_string()),
+            Self::ProcPidFd(_) => {
+                unreachable!("ProcfsBase::ProcPidFd must be resolved before calling into_path")
+            }
             Self::ProcThreadSelf => [
                 // /proc/thread-self was added in Linux 3.17.
                 "thread-self".into(),
@@ -203,6 +257,25 @@ impl ProcfsBase {
     // TODO: Add into_raw_path() that doesn't use symlinks?
 }
 
+/// Returns whether `err` indicates that a probed syscall (such as
+/// `fsopen(2)` or `open_tree(2)`) is simply unavailable, and thus that the
+/// [`ProcfsHandleBuilder::build`] fallback chain should move on to the next
+/// strategy instead of bailing out.
+///
+/// `ENOSYS` (or our own [`ErrorKind::NotSupported`]/[`ErrorKind::NotImplemented`]
+/// used for pre-5.2 kernels) always means the syscall genuinely isn't
+/// available. `EPERM` is only treated the same way if `allow_seccomp_fallback`
+/// is set, since it can also indicate a genuine permission problem that
+/// callers may want to see instead of having it silently masked.
+fn is_probe_unavailable(err: &Error, allow_seccomp_fallback: bool) -> bool {
+    match err.kind() {
+        ErrorKind::NotSupported | ErrorKind::NotImplemented => true,
+        ErrorKind::OsError(Some(libc::ENOSYS)) => true,
+        ErrorKind::OsError(Some(libc::EPERM)) => allow_seccomp_fallback,
+        _ => false,
+    }
+}
+
 /// Builder for [`ProcfsHandle`].
 ///
 /// This is mainly intended for users that have specific requirements for the
@@ -227,9 +300,14 @@ impl ProcfsBase {
 /// Most users should just use [`ProcfsHandle::new`] or the default
 /// configuration of [`ProcfsHandleBuilder`], as it provides the safest
 /// configuration without performance penalties for most users.
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct ProcfsHandleBuilder {
     subset_pid: bool,
+    hidepid: ProcfsHidePid,
+    allow_seccomp_fallback: bool,
+    strategy: Vec<ProcfsStrategy>,
+    forbid_unsafe: bool,
+    mntns_fd: Option<OwnedFd>,
 }
 
 impl Default for ProcfsHandleBuilder {
@@ -238,12 +316,123 @@ impl Default for ProcfsHandleBuilder {
     }
 }
 
+// Manual Clone impl because OwnedFd isn't Clone -- we dup the fd instead, to
+// keep ProcfsHandleBuilder itself trivially cloneable like before mntns_fd
+// was added.
+impl Clone for ProcfsHandleBuilder {
+    fn clone(&self) -> Self {
+        Self {
+            subset_pid: self.subset_pid,
+            hidepid: self.hidepid,
+            allow_seccomp_fallback: self.allow_seccomp_fallback,
+            strategy: self.strategy.clone(),
+            forbid_unsafe: self.forbid_unsafe,
+            mntns_fd: self.mntns_fd.as_ref().map(|fd| {
+                fd.try_clone_to_owned()
+                    .expect("cloning a valid mntns_fd should not fail")
+            }),
+        }
+    }
+}
+
+/// A single backend [`ProcfsHandleBuilder::build`] can try to acquire a
+/// [`ProcfsHandle`] with.
+///
+/// The default order used by [`ProcfsHandleBuilder`] is [`FsOpen`],
+/// [`OpenTree`], [`OpenTreeRecursive`], [`UnsafeOpen`] -- from most to least
+/// safe against a racing attacker.
+///
+/// [`FsOpen`]: Self::FsOpen
+/// [`OpenTree`]: Self::OpenTree
+/// [`OpenTreeRecursive`]: Self::OpenTreeRecursive
+/// [`UnsafeOpen`]: Self::UnsafeOpen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProcfsStrategy {
+    /// Try [`ProcfsHandle::new_fsopen`].
+    FsOpen,
+    /// Try [`ProcfsHandle::new_open_tree`] without `AT_RECURSIVE` (so the
+    /// resulting handle is guaranteed to have no overmounts).
+    OpenTree,
+    /// Try [`ProcfsHandle::new_open_tree`] with `AT_RECURSIVE` (the handle
+    /// may have overmounts, but this is still safe against mount-table
+    /// races).
+    OpenTreeRecursive,
+    /// Fall back to the host's regular `/proc`, via
+    /// [`ProcfsHandle::new_unsafe_open`].
+    ///
+    /// This is the only strategy that is **not** safe against a racing
+    /// attacker who can modify the mount table, since (unlike the other
+    /// strategies) it does not create a new, detached procfs mount. Use
+    /// [`ProcfsHandleBuilder::forbid_unsafe`] if you need to guarantee this
+    /// strategy is never used.
+    UnsafeOpen,
+}
+
+/// The default [`ProcfsStrategy`] order used by [`ProcfsHandleBuilder::new`].
+const DEFAULT_STRATEGY: &[ProcfsStrategy] = &[
+    ProcfsStrategy::FsOpen,
+    ProcfsStrategy::OpenTree,
+    ProcfsStrategy::OpenTreeRecursive,
+    ProcfsStrategy::UnsafeOpen,
+];
+
+/// The `hidepid=` mount option to request for a freshly-created detached
+/// procfs (see [`ProcfsHandleBuilder::hidepid`]). Only has any effect when
+/// [`ProcfsHandleBuilder::subset_pid`] is enabled, mirroring how
+/// [`ProcfsHandle::new_fsopen`] already only configures `hidepid=` alongside
+/// `subset=pid`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProcfsHidePid {
+    /// `hidepid=off` -- no restriction, every process's directory is fully
+    /// visible (the kernel default).
+    Off,
+    /// `hidepid=noaccess` -- other processes' directories exist but cannot be
+    /// accessed at all (not even `stat(2)`).
+    NoAccess,
+    /// `hidepid=invisible` -- other processes' directories do not show up in
+    /// `/proc` at all (even though they can still be looked up by name).
+    Invisible,
+    /// `hidepid=ptraceable` -- other processes' directories are only
+    /// accessible to processes that could `ptrace(2)` them. This is the
+    /// default [`ProcfsHandleBuilder`] uses when `subset_pid` is enabled, as
+    /// it gives the best trade-off between hiding sensitive information and
+    /// remaining usable for debugging tools.
+    Ptraceable,
+}
+
+impl Default for ProcfsHidePid {
+    fn default() -> Self {
+        Self::Ptraceable
+    }
+}
+
+impl ProcfsHidePid {
+    /// The `hidepid=` mount option value, as passed to `fsconfig(2)`.
+    fn as_mount_option_str(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::NoAccess => "noaccess",
+            Self::Invisible => "invisible",
+            Self::Ptraceable => "ptraceable",
+        }
+    }
+}
+
 impl ProcfsHandleBuilder {
     /// Construct a new [`ProcfsHandleBuilder`] with the recommended
     /// configuration.
     #[inline]
     pub fn new() -> Self {
-        Self { subset_pid: true }
+        Self {
+            subset_pid: true,
+            hidepid: ProcfsHidePid::default(),
+            allow_seccomp_fallback: false,
+            strategy: DEFAULT_STRATEGY.to_vec(),
+            forbid_unsafe: false,
+            mntns_fd: None,
+        }
     }
 
     // TODO: use_cached() -- allow users to control whether they get a cached
@@ -282,6 +471,57 @@ impl ProcfsHandleBuilder {
         self
     }
 
+    /// Specify the `hidepid=` mount option to request alongside
+    /// [`ProcfsHandleBuilder::subset_pid`] (which must also be enabled for
+    /// this to have any effect).
+    ///
+    /// This lets callers pick a less restrictive [`ProcfsHidePid`] level
+    /// (e.g. [`ProcfsHidePid::Invisible`]) than the
+    /// [`ProcfsHidePid::Ptraceable`] default, for cases where `subset=pid`'s
+    /// global-file restrictions are wanted but the stricter default
+    /// `hidepid=` level is not (for instance, tools that need to enumerate
+    /// other processes' PIDs without needing to see their sensitive details).
+    #[inline]
+    pub fn hidepid(mut self, hidepid: ProcfsHidePid) -> Self {
+        self.set_hidepid(hidepid);
+        self
+    }
+
+    /// Setter form of [`ProcfsHandleBuilder::hidepid`].
+    #[inline]
+    pub fn set_hidepid(&mut self, hidepid: ProcfsHidePid) -> &mut Self {
+        self.hidepid = hidepid;
+        self
+    }
+
+    /// Source the freshly-created private procfs from a different mount
+    /// namespace than the caller's current one, given an fd referencing it
+    /// (such as one obtained from `/proc/<pid>/ns/mnt` or `setns(2)`'s usual
+    /// sources).
+    ///
+    /// This is useful for container/VM supervisors that hold mount namespace
+    /// fds for many targets and want a clean, safe procfs view per namespace
+    /// without permanently `setns`-ing the whole calling thread -- the
+    /// namespace switch (and switch back) only happens for the duration of
+    /// acquiring the handle.
+    ///
+    /// Only has an effect when [`build`][`Self::build`] actually has to
+    /// create a new procfs instance (i.e. [`ProcfsHandle::new_fsopen`]); it
+    /// does nothing for strategies that reuse the host's existing `/proc`
+    /// ([`ProcfsStrategy::OpenTree`]/[`ProcfsStrategy::UnsafeOpen`]).
+    #[inline]
+    pub fn mntns_fd(mut self, mntns_fd: impl Into<OwnedFd>) -> Self {
+        self.set_mntns_fd(mntns_fd);
+        self
+    }
+
+    /// Setter form of [`ProcfsHandleBuilder::mntns_fd`].
+    #[inline]
+    pub fn set_mntns_fd(&mut self, mntns_fd: impl Into<OwnedFd>) -> &mut Self {
+        self.mntns_fd = Some(mntns_fd.into());
+        self
+    }
+
     /// Do not require any restrictions for the procfs handle.
     ///
     /// Unlike standalone methods for each configuration setting of
@@ -300,6 +540,84 @@ impl ProcfsHandleBuilder {
         self
     }
 
+    /// Treat `EPERM` returned by a `fsopen(2)`/`open_tree(2)` probe the same
+    /// way as `ENOSYS` -- i.e. as "this syscall is unavailable, try the next
+    /// strategy in the fallback chain" -- rather than as a fatal error.
+    ///
+    /// This is needed because the OCI runtime-spec default seccomp profile
+    /// (and various other container seccomp profiles that predate these
+    /// syscalls) reject unknown syscalls with `EPERM` instead of the more
+    /// correct `ENOSYS`, which would otherwise cause [`ProcfsHandleBuilder::build`]
+    /// to bail out with a permission error instead of degrading gracefully to
+    /// an older strategy.
+    ///
+    /// This is opt-in (disabled by default) because `EPERM` can also indicate
+    /// a genuine permission problem unrelated to seccomp (e.g. a restrictive
+    /// LSM policy), and such users likely want [`ProcfsHandleBuilder::build`]
+    /// to surface that error rather than silently masking it.
+    #[inline]
+    pub fn allow_seccomp_fallback(mut self, allow: bool) -> Self {
+        self.set_allow_seccomp_fallback(allow);
+        self
+    }
+
+    /// Setter form of [`ProcfsHandleBuilder::allow_seccomp_fallback`].
+    #[inline]
+    pub fn set_allow_seccomp_fallback(&mut self, allow: bool) -> &mut Self {
+        self.allow_seccomp_fallback = allow;
+        self
+    }
+
+    /// Configure which [`ProcfsStrategy`] backends [`ProcfsHandleBuilder::build`]
+    /// should attempt, and in what order.
+    ///
+    /// By default, [`build`][`Self::build`] tries (in order) [`FsOpen`],
+    /// [`OpenTree`], [`OpenTreeRecursive`], then [`UnsafeOpen`], falling
+    /// through to the next strategy whenever a given backend turns out to be
+    /// unavailable (e.g. `ENOSYS` on an older kernel). This method lets you
+    /// restrict or reorder that list -- for instance, passing a list that
+    /// omits [`UnsafeOpen`] has the same effect as
+    /// [`forbid_unsafe(true)`][`Self::forbid_unsafe`].
+    ///
+    /// [`FsOpen`]: ProcfsStrategy::FsOpen
+    /// [`OpenTree`]: ProcfsStrategy::OpenTree
+    /// [`OpenTreeRecursive`]: ProcfsStrategy::OpenTreeRecursive
+    /// [`UnsafeOpen`]: ProcfsStrategy::UnsafeOpen
+    #[inline]
+    pub fn strategy(mut self, order: &[ProcfsStrategy]) -> Self {
+        self.set_strategy(order);
+        self
+    }
+
+    /// Setter form of [`ProcfsHandleBuilder::strategy`].
+    #[inline]
+    pub fn set_strategy(&mut self, order: &[ProcfsStrategy]) -> &mut Self {
+        self.strategy = order.to_vec();
+        self
+    }
+
+    /// Require that [`ProcfsHandleBuilder::build`] never falls back to
+    /// [`ProcfsStrategy::UnsafeOpen`] (i.e. reusing the host's regular
+    /// `/proc` rather than a freshly created, detached mount).
+    ///
+    /// Security-sensitive callers (such as container runtimes) would often
+    /// rather fail loudly on an older kernel than silently operate on an
+    /// unverified host mount -- this makes that guarantee explicit, instead
+    /// of requiring such callers to call [`ProcfsHandle::new_fsopen`] (and
+    /// reimplement the caching [`build`][`Self::build`] provides) directly.
+    #[inline]
+    pub fn forbid_unsafe(mut self, forbid: bool) -> Self {
+        self.set_forbid_unsafe(forbid);
+        self
+    }
+
+    /// Setter form of [`ProcfsHandleBuilder::forbid_unsafe`].
+    #[inline]
+    pub fn set_forbid_unsafe(&mut self, forbid: bool) -> &mut Self {
+        self.forbid_unsafe = forbid;
+        self
+    }
+
     /// Returns whether this [`ProcfsHandleBuilder`] will request a cacheable
     /// [`ProcfsHandle`].
     #[inline]
@@ -345,21 +663,66 @@ impl ProcfsHandleBuilder {
         if self.is_cache_friendly() {
             // If there is already a cached filesystem available, use that.
             if let Some(fd) = CACHED_PROCFS_HANDLE.get() {
-                let procfs = ProcfsHandle::try_from_borrowed_fd(fd.as_fd())
+                let mut procfs = ProcfsHandle::try_from_borrowed_fd(fd.as_fd())
                     .expect("cached procfs handle should be valid");
                 debug_assert!(
                     procfs.is_subset && procfs.is_detached,
                     "cached procfs handle should be subset=pid and detached"
                 );
+                procfs.allow_seccomp_fallback = self.allow_seccomp_fallback;
                 return Ok(procfs);
             }
         }
 
-        let procfs = ProcfsHandle::new_fsopen(self.subset_pid)
-            .or_else(|_| ProcfsHandle::new_open_tree(OpenTreeFlags::empty()))
-            .or_else(|_| ProcfsHandle::new_open_tree(OpenTreeFlags::AT_RECURSIVE))
-            .or_else(|_| ProcfsHandle::new_unsafe_open())
+        let mut last_err: Option<Error> = None;
+        let mut procfs: Option<ProcfsHandle> = None;
+
+        for &strategy in &self.strategy {
+            if self.forbid_unsafe && strategy == ProcfsStrategy::UnsafeOpen {
+                continue;
+            }
+
+            let attempt = match strategy {
+                ProcfsStrategy::FsOpen => ProcfsHandle::new_fsopen(
+                    self.subset_pid,
+                    self.hidepid,
+                    self.mntns_fd.as_ref().map(AsFd::as_fd),
+                ),
+                ProcfsStrategy::OpenTree => ProcfsHandle::new_open_tree(OpenTreeFlags::empty()),
+                ProcfsStrategy::OpenTreeRecursive => {
+                    ProcfsHandle::new_open_tree(OpenTreeFlags::AT_RECURSIVE)
+                }
+                ProcfsStrategy::UnsafeOpen => ProcfsHandle::new_unsafe_open(),
+            };
+
+            match attempt {
+                Ok(handle) => {
+                    procfs = Some(handle);
+                    break;
+                }
+                Err(err) if is_probe_unavailable(&err, self.allow_seccomp_fallback) => {
+                    last_err = Some(err);
+                }
+                Err(err) => return Err(err).wrap("get safe procfs handle"),
+            }
+        }
+
+        let mut procfs = procfs
+            .ok_or_else(|| {
+                last_err.unwrap_or_else(|| {
+                    ErrorImpl::InvalidArgument {
+                        name: "strategy".into(),
+                        description: "no procfs acquisition strategy left to try (check forbid_unsafe() and strategy())".into(),
+                    }
+                    .into()
+                })
+            })
             .wrap("get safe procfs handle")?;
+        // Propagate allow_seccomp_fallback to the handle itself, so it also
+        // applies to the resolver's own openat2(2) RESOLVE_* bit probe (see
+        // PseudofsResolver::resolve) and not just the fsopen(2)/open_tree(2)
+        // probes used above to acquire the handle.
+        procfs.allow_seccomp_fallback = self.allow_seccomp_fallback;
 
         // TODO: Add a way to require/verify that the requested properties will
         // be set, and then check them here before returning.
@@ -380,8 +743,10 @@ impl ProcfsHandleBuilder {
                 };
                 // Do not return an error here -- it should be impossible for
                 // this validation to fail after we get here.
-                Ok(ProcfsHandle::try_from_maybe_owned_fd(cached_inner)
-                    .expect("cached procfs handle should be valid"))
+                let mut procfs = ProcfsHandle::try_from_maybe_owned_fd(cached_inner)
+                    .expect("cached procfs handle should be valid");
+                procfs.allow_seccomp_fallback = self.allow_seccomp_fallback;
+                Ok(procfs)
             }
             procfs => Ok(procfs),
         }
@@ -401,6 +766,11 @@ pub struct ProcfsHandleRef<'fd> {
     is_subset: bool,
     is_detached: bool,
     pub(crate) resolver: ProcfsResolver,
+    /// Mirrors [`ProcfsHandleBuilder::allow_seccomp_fallback`] for the
+    /// resolver's own `openat2(2)` `RESOLVE_*`-bit probe -- see
+    /// [`PseudofsResolver::resolve`]. Always `false` unless this handle was
+    /// constructed via [`ProcfsHandleBuilder::build`].
+    pub(crate) allow_seccomp_fallback: bool,
 }
 
 /// > **NOTE**: Take great care when using this file descriptor -- it is very
@@ -444,13 +814,15 @@ impl<'fd> ProcfsHandleRef<'fd> {
         dirfd: BorrowedFd<'_>,
         subpath: &Path,
         oflags: OpenFlags,
+        rflags: ResolverFlags,
     ) -> Result<OwnedFd, Error> {
         let fd = self.resolver.resolve(
             self.as_raw_procfs(),
             dirfd,
             subpath,
             oflags,
-            ResolverFlags::empty(),
+            rflags,
+            self.allow_seccomp_fallback,
         )?;
         self.verify_same_procfs_mnt(&fd).with_wrap(|| {
             format!(
@@ -462,16 +834,129 @@ impl<'fd> ProcfsHandleRef<'fd> {
     }
 
     /// Open `ProcfsBase` inside the procfs.
-    fn open_base(&self, base: ProcfsBase) -> Result<OwnedFd, Error> {
+    fn open_base(&self, base: ProcfsBase<'_>) -> Result<OwnedFd, Error> {
+        if let ProcfsBase::ProcPidFd(pidfd) = base {
+            return self.open_pidfd_base(pidfd);
+        }
         self.openat_raw(
             self.as_fd(),
             &base.into_path(self.as_raw_procfs()),
             OpenFlags::O_PATH | OpenFlags::O_DIRECTORY,
+            // The base itself is always resolved with the default scoping --
+            // any extra ResolverFlags a caller requested only apply to the
+            // subpath underneath the base.
+            ResolverFlags::empty(),
         )
         // TODO: For ProcfsBase::ProcPid, should ENOENT here be converted to
         //       ESRCH to be more "semantically correct"?
     }
 
+    /// Race-free resolution of [`ProcfsBase::ProcPidFd`] -- see its
+    /// documentation for the full verification strategy.
+    fn open_pidfd_base(&self, pidfd: BorrowedFd<'_>) -> Result<OwnedFd, Error> {
+        match syscalls::pidfd_get_info(pidfd) {
+            Ok(info) => {
+                let dirfd = self.open_base(ProcfsBase::ProcPid(info.pid))?;
+                let starttime = self.read_stat_starttime(&dirfd).with_wrap(|| {
+                    format!("read starttime of /proc/{}/stat to verify pidfd", info.pid)
+                })?;
+                if starttime != info.starttime {
+                    return Err(ErrorImpl::SafetyViolation {
+                        description: format!(
+                            "pid {} was recycled while resolving pidfd (starttime {} from PIDFD_GET_INFO does not match starttime {starttime} in /proc/{}/stat)",
+                            info.pid, info.starttime, info.pid,
+                        )
+                        .into(),
+                    }
+                    .into());
+                }
+                Ok(dirfd)
+            }
+            // Older kernels don't have PIDFD_GET_INFO -- fall back to
+            // bracketing the open with two reads of /proc/<pid>/stat and
+            // erroring out if the pid was recycled in between.
+            Err(err) if err.root_cause().raw_os_error() == Some(libc::ENOSYS) => {
+                self.open_pidfd_base_fallback(pidfd)
+            }
+            Err(err) => Err(ErrorImpl::RawOsError {
+                operation: "PIDFD_GET_INFO on pidfd".into(),
+                source: err,
+            }
+            .into()),
+        }
+    }
+
+    /// Fallback for [`ProcfsHandleRef::open_pidfd_base`] on kernels without
+    /// `PIDFD_GET_INFO`: resolve the pidfd's pid through `/proc/self/fdinfo`,
+    /// open `/proc/<pid>`, and bracket the open with two reads of `stat`'s
+    /// `starttime` field to detect a pid recycled during the open.
+    fn open_pidfd_base_fallback(&self, pidfd: BorrowedFd<'_>) -> Result<OwnedFd, Error> {
+        let pid = syscalls::pidfd_to_pid(pidfd).map_err(|err| ErrorImpl::RawOsError {
+            operation: "resolve pid of pidfd".into(),
+            source: err,
+        })?;
+
+        let before = self
+            .read_stat_starttime(self.open_base(ProcfsBase::ProcPid(pid))?)
+            .with_wrap(|| format!("read starttime of /proc/{pid}/stat (before open)"))?;
+        let dirfd = self.open_base(ProcfsBase::ProcPid(pid))?;
+        let after = self
+            .read_stat_starttime(&dirfd)
+            .with_wrap(|| format!("read starttime of /proc/{pid}/stat (after open)"))?;
+
+        if before != after {
+            return Err(ErrorImpl::SafetyViolation {
+                description: format!(
+                    "pid {pid} was recycled while resolving pidfd (starttime changed from {before} to {after} during open)"
+                )
+                .into(),
+            }
+            .into());
+        }
+        Ok(dirfd)
+    }
+
+    /// Read field 22 (`starttime`) out of `/proc/<pid>/stat` opened at
+    /// `piddir` (the `ProcfsBase::ProcPid(pid)` directory fd).
+    fn read_stat_starttime(&self, piddir: impl AsFd) -> Result<u64, Error> {
+        use std::io::Read;
+
+        let mut stat = String::new();
+        File::from(self.openat_raw(
+            piddir.as_fd(),
+            Path::new("stat"),
+            OpenFlags::O_RDONLY,
+            ResolverFlags::empty(),
+        )?)
+            .read_to_string(&mut stat)
+            .map_err(|err| ErrorImpl::OsError {
+                operation: "read /proc/<pid>/stat".into(),
+                source: err,
+            })?;
+
+        // comm (field 2) is surrounded by parentheses and may itself contain
+        // ')' or whitespace, so the only safe way to find where the
+        // fixed-format fields start is to split on the *last* ')'.
+        let fields = stat
+            .rsplit_once(')')
+            .map(|(_pid_comm, rest)| rest)
+            .unwrap_or(&stat);
+
+        // `fields` now starts with "state"==field 3, so field 22 (starttime)
+        // is the 20th (1-indexed) entry here, i.e. index 19.
+        fields
+            .split_whitespace()
+            .nth(19)
+            .and_then(|field| field.parse().ok())
+            .ok_or_else(|| {
+                ErrorImpl::SafetyViolation {
+                    description: "could not parse starttime (field 22) out of /proc/<pid>/stat"
+                        .into(),
+                }
+                .into()
+            })
+    }
+
     /// Safely open a magic-link inside `procfs`.
     ///
     /// The semantics of this method are very similar to [`ProcfsHandle::open`],
@@ -492,12 +977,32 @@ impl<'fd> ProcfsHandleRef<'fd> {
     /// In addition (like [`ProcfsHandle::open`]), `open_follow` will not permit
     /// a magic-link to be a path component (ie. `/proc/self/root/etc/passwd`).
     /// This method *only* permits *trailing* symlinks.
+    ///
+    /// If you need to further restrict path resolution (for instance,
+    /// forbidding mountpoint crossings with `RESOLVE_NO_XDEV`), use
+    /// [`ProcfsHandle::open_follow_with`] instead.
     #[doc(alias = "pathrs_proc_open")]
     pub fn open_follow(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
+        subpath: impl AsRef<Path>,
+        oflags: impl Into<OpenFlags>,
+    ) -> Result<File, Error> {
+        self.open_follow_with(base, subpath, oflags, ResolverFlags::empty())
+    }
+
+    /// Identical to [`ProcfsHandle::open_follow`], except that an additional
+    /// [`ResolverFlags`] argument lets you further scope how the subpath
+    /// underneath `base` is resolved (for instance `RESOLVE_NO_XDEV` to
+    /// forbid crossing mountpoints, beyond libpathrs' own default
+    /// protections).
+    #[doc(alias = "pathrs_proc_open")]
+    pub fn open_follow_with(
+        &self,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
         oflags: impl Into<OpenFlags>,
+        rflags: ResolverFlags,
     ) -> Result<File, Error> {
         let subpath = subpath.as_ref();
         let mut oflags = oflags.into();
@@ -518,7 +1023,7 @@ impl<'fd> ProcfsHandleRef<'fd> {
         // present because of subset=pid and retry (for magic-links we need to
         // operate on the target path more than once, which makes the retry
         // logic easier to do upfront here).
-        match self.openat_raw(self.open_base(base)?.as_fd(), subpath, oflags) {
+        match self.openat_raw(self.open_base(base)?.as_fd(), subpath, oflags, rflags) {
             Ok(file) => return Ok(file.into()),
             Err(err) => {
                 if self.is_subset && err.kind() == ErrorKind::OsError(Some(libc::ENOENT)) {
@@ -530,7 +1035,7 @@ impl<'fd> ProcfsHandleRef<'fd> {
                         .build()
                         // Use the old error if creating a new handle failed.
                         .or(Err(err))?
-                        .open_follow(base, subpath, oflags);
+                        .open_follow_with(base, subpath, oflags, rflags);
                 }
                 // If the error is ELOOP then the resolver probably hit a
                 // magic-link, and so we have a reason to allow the
@@ -556,6 +1061,7 @@ impl<'fd> ProcfsHandleRef<'fd> {
             self.open_base(base)?.as_fd(),
             parent,
             OpenFlags::O_PATH | OpenFlags::O_DIRECTORY,
+            rflags,
         )?;
 
         // Rather than using self.mnt_id for the following check, we use the
@@ -601,6 +1107,81 @@ impl<'fd> ProcfsHandleRef<'fd> {
             })
     }
 
+    /// Like [`ProcfsHandle::open_follow`], but permits following a
+    /// *trailing* `fd/<n>` magic-link (e.g. `self/fd/3`) as long as `oflags`
+    /// does not request more access than the target fd was originally opened
+    /// with -- see [`PseudofsResolver::resolve_trusted_fd_magiclinks`] for
+    /// the full safety argument. Every other magic-link kind (non-trailing,
+    /// absolute, anon-inode, ...) is still rejected with `ELOOP`, exactly as
+    /// in [`ProcfsHandle::open_follow`]. The target must also be on the same
+    /// mount as this procfs, so an `fd/<n>` pointing at a file on another
+    /// filesystem is rejected.
+    ///
+    /// Most users should use [`ProcfsHandle::open_follow`]; this method only
+    /// matters if you specifically need to re-open an `fd/<n>` magic-link
+    /// with a (not broader) access mode of your choosing rather than
+    /// whatever the original fd happened to be opened with.
+    ///
+    /// [`PseudofsResolver::resolve_trusted_fd_magiclinks`]: crate::resolvers::procfs::PseudofsResolver::resolve_trusted_fd_magiclinks
+    #[doc(alias = "pathrs_proc_open")]
+    pub fn open_follow_trusted_fd(
+        &self,
+        base: ProcfsBase<'_>,
+        subpath: impl AsRef<Path>,
+        oflags: impl Into<OpenFlags>,
+    ) -> Result<File, Error> {
+        self.open_follow_trusted_fd_with(base, subpath, oflags, ResolverFlags::empty())
+    }
+
+    /// Identical to [`ProcfsHandle::open_follow_trusted_fd`], except that an
+    /// additional [`ResolverFlags`] argument lets you further scope how the
+    /// subpath underneath `base` is resolved, as with
+    /// [`ProcfsHandle::open_follow_with`].
+    #[doc(alias = "pathrs_proc_open")]
+    pub fn open_follow_trusted_fd_with(
+        &self,
+        base: ProcfsBase<'_>,
+        subpath: impl AsRef<Path>,
+        oflags: impl Into<OpenFlags>,
+        rflags: ResolverFlags,
+    ) -> Result<File, Error> {
+        let subpath = subpath.as_ref();
+        let oflags = oflags.into();
+
+        let (parent, trailing) = utils::path_split(subpath)?;
+        let trailing = trailing.ok_or_else(|| ErrorImpl::InvalidArgument {
+            name: "path".into(),
+            description: "open_follow_trusted_fd path has trailing slash".into(),
+        })?;
+
+        let parentdir = self.openat_raw(
+            self.open_base(base)?.as_fd(),
+            parent,
+            OpenFlags::O_PATH | OpenFlags::O_DIRECTORY,
+            rflags,
+        )?;
+
+        let parent_mnt_id =
+            utils::fetch_mnt_id(self.as_raw_procfs(), &parentdir, "").with_wrap(|| {
+                format!(
+                    "get mount id of procfs fd {}",
+                    syscalls::FrozenFd::from(&parentdir)
+                )
+            })?;
+        verify_same_mnt(self.as_raw_procfs(), parent_mnt_id, &parentdir, trailing).with_wrap(
+            || {
+                format!(
+                    "check that parent dir {} and {trailing:?} are on the same procfs mount",
+                    syscalls::FrozenFd::from(&parentdir)
+                )
+            },
+        )?;
+
+        self.resolver
+            .resolve_trusted_fd_magiclinks(self.as_raw_procfs(), &parentdir, trailing, oflags, rflags)
+            .map(File::from)
+    }
+
     /// Safely open a path inside `procfs`.
     ///
     /// The provided `subpath` is relative to the [`ProcfsBase`] (and must not
@@ -633,13 +1214,33 @@ impl<'fd> ProcfsHandleRef<'fd> {
     /// All mount point crossings are also forbidden (including bind-mounts),
     /// meaning that this method implies [`RESOLVE_NO_XDEV`][`openat2(2)`].
     ///
+    /// If you need to further restrict path resolution (for instance,
+    /// rejecting regular symlink components with `RESOLVE_NO_SYMLINKS`), use
+    /// [`ProcfsHandle::open_with`] instead.
+    ///
     /// [`openat2(2)`]: https://www.man7.org/linux/man-pages/man2/openat2.2.html
     #[doc(alias = "pathrs_proc_open")]
     pub fn open(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
+        subpath: impl AsRef<Path>,
+        oflags: impl Into<OpenFlags>,
+    ) -> Result<File, Error> {
+        self.open_with(base, subpath, oflags, ResolverFlags::empty())
+    }
+
+    /// Identical to [`ProcfsHandle::open`], except that an additional
+    /// [`ResolverFlags`] argument lets you further scope how the subpath
+    /// underneath `base` is resolved (for instance `RESOLVE_NO_SYMLINKS` to
+    /// reject any symlink components, beyond libpathrs' own default
+    /// protections).
+    #[doc(alias = "pathrs_proc_open")]
+    pub fn open_with(
+        &self,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
         oflags: impl Into<OpenFlags>,
+        rflags: ResolverFlags,
     ) -> Result<File, Error> {
         let mut oflags = oflags.into();
         // Force-set O_NOFOLLOW.
@@ -648,7 +1249,7 @@ impl<'fd> ProcfsHandleRef<'fd> {
         // Do a basic lookup.
         let subpath = subpath.as_ref();
         let fd = self
-            .openat_raw(self.open_base(base)?.as_fd(), subpath, oflags)
+            .openat_raw(self.open_base(base)?.as_fd(), subpath, oflags, rflags)
             .or_else(|err| {
                 if self.is_subset && err.kind() == ErrorKind::OsError(Some(libc::ENOENT)) {
                     // If the lookup failed due to ENOENT, and the current
@@ -659,7 +1260,7 @@ impl<'fd> ProcfsHandleRef<'fd> {
                         .build()
                         // Use the old error if creating a new handle failed.
                         .or(Err(err))?
-                        .open(base, subpath, oflags)
+                        .open_with(base, subpath, oflags, rflags)
                         .map(OwnedFd::from)
                 } else {
                     Err(err)
@@ -676,9 +1277,15 @@ impl<'fd> ProcfsHandleRef<'fd> {
     /// all of the caveats from [`ProcfsHandle::open`] apply to this method as
     /// well.
     ///
+    /// The returned [`PathBuf`] is byte-for-byte what the kernel gave us --
+    /// Linux paths (and thus symlink targets) are arbitrary NUL-free byte
+    /// strings that need not be valid UTF-8, and [`PathBuf`]/[`OsString`] are
+    /// able to hold such paths without any lossy conversion or corruption.
+    ///
     /// [`readlinkat(2)`]: https://www.man7.org/linux/man-pages/man2/readlinkat.2.html
+    /// [`OsString`]: std::ffi::OsString
     #[doc(alias = "pathrs_proc_readlink")]
-    pub fn readlink(&self, base: ProcfsBase, subpath: impl AsRef<Path>) -> Result<PathBuf, Error> {
+    pub fn readlink(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<PathBuf, Error> {
         let link = self.open(base, subpath, OpenFlags::O_PATH)?;
         syscalls::readlinkat(link, "").map_err(|err| {
             ErrorImpl::RawOsError {
@@ -689,6 +1296,309 @@ impl<'fd> ProcfsHandleRef<'fd> {
         })
     }
 
+    /// Safely iterate over the entries of a directory inside `procfs`,
+    /// without buffering the whole listing up front.
+    ///
+    /// This is effectively shorthand for doing [`fdopendir(3)`] on the handle
+    /// you'd get from `ProcfsHandle::open(..., OpenFlags::O_DIRECTORY)`,
+    /// wrapped in the same race-free [`Directory`]/[`DirEntry`] iterator that
+    /// [`Root::read_dir`] and [`Handle::read_dir`] return -- every yielded
+    /// entry carries the directory fd it came from, so re-opening it (via
+    /// [`DirEntry::open`] or [`DirEntry::resolve`]) can never be redirected by
+    /// a rename of an ancestor. So all of the caveats from
+    /// [`ProcfsHandle::open`] apply to this method as well.
+    ///
+    /// There is no dedicated C API for this method -- C callers can get the
+    /// same directory fd via `pathrs_proc_openat(..., O_DIRECTORY)` and then
+    /// use [`fdopendir(3)`]/[`readdir(3)`] themselves, the same way they
+    /// already have to for `Root`/`Handle` directory listings.
+    ///
+    /// [`fdopendir(3)`]: https://www.man7.org/linux/man-pages/man3/fdopendir.3.html
+    /// [`readdir(3)`]: https://www.man7.org/linux/man-pages/man3/readdir.3.html
+    /// [`Root::read_dir`]: crate::Root::read_dir
+    /// [`Handle::read_dir`]: crate::Handle::read_dir
+    /// [`DirEntry::open`]: crate::dir::DirEntry::open
+    /// [`DirEntry::resolve`]: crate::dir::DirEntry::resolve
+    pub fn read_dir(
+        &self,
+        base: ProcfsBase<'_>,
+        subpath: impl AsRef<Path>,
+    ) -> Result<Directory, Error> {
+        let dir = self.open(base, subpath, OpenFlags::O_DIRECTORY)?;
+        Directory::from_file(dir)
+    }
+
+    /// Safely write to a file inside `procfs`.
+    ///
+    /// This is effectively shorthand for doing a single [`write(2)`] on the
+    /// handle you'd get from `ProcfsHandle::open(..., OpenFlags::O_WRONLY)`,
+    /// so all of the caveats from [`ProcfsHandle::open`] apply to this method
+    /// as well.
+    ///
+    /// Unlike a bare `write(2)`, `data` is written in a single call and a
+    /// short write is treated as an error rather than retried -- the
+    /// container-setup files this is meant for (`uid_map`, `setgroups`,
+    /// `oom_score_adj`, `attr/*`, and so on) all require the whole value to
+    /// be written in one `write(2)` and simply reject (or silently ignore) a
+    /// second write to the same file descriptor.
+    ///
+    /// [`write(2)`]: https://www.man7.org/linux/man-pages/man2/write.2.html
+    #[doc(alias = "pathrs_proc_write")]
+    pub fn write(
+        &self,
+        base: ProcfsBase<'_>,
+        subpath: impl AsRef<Path>,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        let file = self.open(base, subpath, OpenFlags::O_WRONLY)?;
+        let written = syscalls::write(&file, data).map_err(|err| ErrorImpl::RawOsError {
+            operation: "write procfs file".into(),
+            source: err,
+        })?;
+        if written != data.len() {
+            return Err(ErrorImpl::OsError {
+                operation: "write procfs file".into(),
+                source: IOError::new(
+                    std::io::ErrorKind::WriteZero,
+                    format!("short write: wrote {written} of {} bytes", data.len()),
+                ),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Safely read the entire contents of a file inside `procfs` in one
+    /// shot.
+    ///
+    /// This is effectively shorthand for doing a single `read(2)` loop to EOF
+    /// on the handle you'd get from `ProcfsHandle::open(..., O_RDONLY)`, so
+    /// all of the caveats from [`ProcfsHandle::open`] apply to this method as
+    /// well. Reading the whole file through the hardened handle before
+    /// returning it means the caller never has to juggle a raw procfs fd
+    /// themselves just to slurp a small file (such as `status` or
+    /// `cpuinfo`).
+    #[doc(alias = "pathrs_proc_readfile")]
+    pub fn read(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<Vec<u8>, Error> {
+        use std::io::Read;
+
+        let mut file = self.open(base, subpath, OpenFlags::O_RDONLY)?;
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)
+            .map_err(|err| ErrorImpl::OsError {
+                operation: "read procfs file".into(),
+                source: err,
+            })?;
+        Ok(contents)
+    }
+
+    /// Fetch [`Metadata`] for a subpath inside `procfs`, following any
+    /// trailing symlink (equivalent to opening with
+    /// [`ProcfsHandle::open_follow`] and then `statx(2)`-ing the resulting
+    /// handle).
+    ///
+    /// This uses `statx(2)` to get richer metadata than [`std::fs::Metadata`]
+    /// can provide (such as the mount ID, used to detect bind-mounts on top
+    /// of files we are operating on). On kernels too old to have `statx(2)`
+    /// at all (pre-Linux 4.11), this gracefully falls back to a plain
+    /// `fstatat(2)` -- in that case [`Metadata::mount_id`] and
+    /// [`Metadata::created`] will always be [`None`], since there is no way
+    /// to ask such an old kernel for them.
+    #[doc(alias = "pathrs_proc_stat")]
+    pub fn stat(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<Metadata, Error> {
+        let file = self.open_follow(base, subpath, OpenFlags::O_PATH)?;
+
+        let mask = StatxFlags::BASIC_STATS | StatxFlags::MNT_ID_UNIQUE | StatxFlags::BTIME;
+        match rustix_fs::statx(&file, "", AtFlags::EMPTY_PATH, mask) {
+            Ok(stx) => {
+                let mnt_id = (stx.stx_mask & StatxFlags::MNT_ID_UNIQUE.bits() != 0)
+                    .then_some(stx.stx_mnt_id);
+                Ok(Metadata::from_statx(&stx, mnt_id))
+            }
+            Err(rustix::io::Errno::NOSYS) => {
+                let stat = rustix_fs::fstat(&file).map_err(|err| ErrorImpl::OsError {
+                    operation: "stat procfs file".into(),
+                    source: err.into(),
+                })?;
+                Ok(Metadata::from_stat(&stat))
+            }
+            Err(err) => Err(ErrorImpl::OsError {
+                operation: "stat procfs file".into(),
+                source: err.into(),
+            }
+            .into()),
+        }
+    }
+
+    /// Safely open a subpath inside a specific process's `/proc/<pid>`
+    /// subtree, keyed by a stable `pidfd` (from [`pidfd_open(2)`]) rather than
+    /// a raw PID integer.
+    ///
+    /// Looking up a PID and then separately opening something under
+    /// `/proc/<pid>` has a classic TOCTOU: the kernel can reap the process
+    /// and recycle its PID for a completely different process in between the
+    /// two steps. Passing a `pidfd` instead lets us cross-check (via
+    /// [`ProcfsBase::ProcPidFd`]) that the `/proc/<pid>` directory we open
+    /// still refers to the same process the `pidfd` was created from, and
+    /// additionally confirm with [`pidfd_send_signal(2)`] that the process
+    /// has not exited since we opened it.
+    ///
+    /// [`pidfd_open(2)`]: https://www.man7.org/linux/man-pages/man2/pidfd_open.2.html
+    /// [`pidfd_send_signal(2)`]: https://www.man7.org/linux/man-pages/man2/pidfd_send_signal.2.html
+    pub fn open_process(
+        &self,
+        pidfd: BorrowedFd<'_>,
+        subpath: impl AsRef<Path>,
+        oflags: impl Into<OpenFlags>,
+    ) -> Result<File, Error> {
+        let file = self.open(ProcfsBase::ProcPidFd(pidfd), subpath, oflags)?;
+        self.verify_pidfd_alive(pidfd)?;
+        Ok(file)
+    }
+
+    /// Identical to [`ProcfsHandle::open_process`], except that (like
+    /// [`ProcfsHandle::open_follow`]) it also permits opening magic-links
+    /// such as `/proc/<pid>/exe` or `/proc/<pid>/fd/$n`.
+    pub fn open_process_follow(
+        &self,
+        pidfd: BorrowedFd<'_>,
+        subpath: impl AsRef<Path>,
+        oflags: impl Into<OpenFlags>,
+    ) -> Result<File, Error> {
+        let file = self.open_follow(ProcfsBase::ProcPidFd(pidfd), subpath, oflags)?;
+        self.verify_pidfd_alive(pidfd)?;
+        Ok(file)
+    }
+
+    /// Identical to [`ProcfsHandle::readlink`], but keyed by a `pidfd` like
+    /// [`ProcfsHandle::open_process`] -- see its documentation for why this
+    /// closes a PID-reuse race that a plain PID-based [`ProcfsBase::ProcPid`]
+    /// lookup cannot.
+    pub fn readlink_process(
+        &self,
+        pidfd: BorrowedFd<'_>,
+        subpath: impl AsRef<Path>,
+    ) -> Result<PathBuf, Error> {
+        let target = self.readlink(ProcfsBase::ProcPidFd(pidfd), subpath)?;
+        self.verify_pidfd_alive(pidfd)?;
+        Ok(target)
+    }
+
+    /// Identical to [`ProcfsHandle::write`], but keyed by a `pidfd` like
+    /// [`ProcfsHandle::open_process`] -- see its documentation for why this
+    /// closes a PID-reuse race that a plain PID-based [`ProcfsBase::ProcPid`]
+    /// lookup cannot.
+    pub fn write_process(
+        &self,
+        pidfd: BorrowedFd<'_>,
+        subpath: impl AsRef<Path>,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        self.write(ProcfsBase::ProcPidFd(pidfd), subpath, data)?;
+        self.verify_pidfd_alive(pidfd)
+    }
+
+    /// Identical to [`ProcfsHandle::read`], but keyed by a `pidfd` like
+    /// [`ProcfsHandle::open_process`] -- see its documentation for why this
+    /// closes a PID-reuse race that a plain PID-based [`ProcfsBase::ProcPid`]
+    /// lookup cannot.
+    pub fn read_process(
+        &self,
+        pidfd: BorrowedFd<'_>,
+        subpath: impl AsRef<Path>,
+    ) -> Result<Vec<u8>, Error> {
+        let contents = self.read(ProcfsBase::ProcPidFd(pidfd), subpath)?;
+        self.verify_pidfd_alive(pidfd)?;
+        Ok(contents)
+    }
+
+    /// Identical to [`ProcfsHandle::stat`], but keyed by a `pidfd` like
+    /// [`ProcfsHandle::open_process`] -- see its documentation for why this
+    /// closes a PID-reuse race that a plain PID-based [`ProcfsBase::ProcPid`]
+    /// lookup cannot.
+    pub fn stat_process(
+        &self,
+        pidfd: BorrowedFd<'_>,
+        subpath: impl AsRef<Path>,
+    ) -> Result<Metadata, Error> {
+        let meta = self.stat(ProcfsBase::ProcPidFd(pidfd), subpath)?;
+        self.verify_pidfd_alive(pidfd)?;
+        Ok(meta)
+    }
+
+    /// Safely open the `/proc/<pid>/ns/<ns_type>` namespace entry for the
+    /// process referenced by `pidfd`, pinned against PID-reuse like
+    /// [`ProcfsHandle::open_process`] -- suitable for `setns(2)` or otherwise
+    /// inspecting the target process's namespace.
+    ///
+    /// `ns_type` is one of the names found under `/proc/<pid>/ns` (such as
+    /// `"mnt"`, `"net"`, `"pid"`, ...). Unlike a plain "no such file" error,
+    /// if `ns_type` does not exist for this task (for instance, a namespace
+    /// type the running kernel doesn't support) a distinct
+    /// [`ErrorKind::InvalidArgument`] is returned instead, so callers can
+    /// tell "unknown namespace type" apart from other lookup failures.
+    ///
+    /// [`ErrorKind::InvalidArgument`]: crate::error::ErrorKind::InvalidArgument
+    pub fn open_namespace_process(
+        &self,
+        pidfd: BorrowedFd<'_>,
+        ns_type: &str,
+        oflags: impl Into<OpenFlags>,
+    ) -> Result<File, Error> {
+        let subpath = format!("ns/{ns_type}");
+        self.open_process_follow(pidfd, subpath, oflags)
+            .map_err(|err| match err.kind() {
+                ErrorKind::OsError(Some(libc::ENOENT)) => ErrorImpl::InvalidArgument {
+                    name: "ns_type".into(),
+                    description: format!("namespace type {ns_type:?} does not exist for this process")
+                        .into(),
+                }
+                .into(),
+                _ => err,
+            })
+    }
+
+    /// Safely open the root directory (`/proc/<pid>/root`) of the process
+    /// referenced by `pidfd`, pinned against PID-reuse like
+    /// [`ProcfsHandle::open_process`].
+    pub fn open_root_process(
+        &self,
+        pidfd: BorrowedFd<'_>,
+        oflags: impl Into<OpenFlags>,
+    ) -> Result<File, Error> {
+        self.open_process_follow(pidfd, "root", oflags)
+    }
+
+    /// Safely open the current working directory (`/proc/<pid>/cwd`) of the
+    /// process referenced by `pidfd`, pinned against PID-reuse like
+    /// [`ProcfsHandle::open_process`].
+    pub fn open_cwd_process(
+        &self,
+        pidfd: BorrowedFd<'_>,
+        oflags: impl Into<OpenFlags>,
+    ) -> Result<File, Error> {
+        self.open_process_follow(pidfd, "cwd", oflags)
+    }
+
+    /// Double-check that the process referenced by `pidfd` is still alive,
+    /// to catch the case where the process exited (and its PID was
+    /// potentially recycled) while we were resolving a subpath underneath
+    /// it.
+    fn verify_pidfd_alive(&self, pidfd: BorrowedFd<'_>) -> Result<(), Error> {
+        match syscalls::pidfd_send_signal(pidfd, 0) {
+            Ok(()) => Ok(()),
+            Err(err) if err.root_cause().raw_os_error() == Some(libc::ESRCH) => {
+                Err(ErrorImpl::SafetyViolation {
+                    description: "process exited while opening a /proc/<pid> subpath through its pidfd".into(),
+                }.into())
+            }
+            Err(err) => Err(ErrorImpl::RawOsError {
+                operation: "check pidfd liveness with pidfd_send_signal(0)".into(),
+                source: err,
+            }.into()),
+        }
+    }
+
     fn verify_same_procfs_mnt(&self, fd: impl AsFd) -> Result<(), Error> {
         // Detect if the file we landed on is from a bind-mount.
         verify_same_mnt(self.as_raw_procfs(), self.mnt_id, &fd, "")?;
@@ -767,6 +1677,7 @@ impl<'fd> ProcfsHandleRef<'fd> {
             is_subset,
             is_detached,
             resolver,
+            allow_seccomp_fallback: false,
         })
     }
 }
@@ -840,17 +1751,76 @@ pub type ProcfsHandle = ProcfsHandleRef<'static>;
 // MSRV(1.80): Use LazyLock.
 static HAS_UNBROKEN_MOUNT_API: Lazy<bool> = Lazy::new(|| kernel_version::is_gte!(5, 2));
 
+/// RAII guard that `setns(2)`s the calling thread into a different mount
+/// namespace for as long as it is alive, switching back to the original
+/// mount namespace on drop -- used by [`ProcfsHandle::new_fsopen`] to source
+/// a fresh procfs from a caller-supplied [`ProcfsHandleBuilder::mntns_fd`]
+/// without permanently moving the whole thread into that namespace.
+struct MountNamespaceGuard {
+    original_mntns: OwnedFd,
+}
+
+impl MountNamespaceGuard {
+    fn enter(target: BorrowedFd<'_>) -> Result<Self, Error> {
+        let original_mntns = syscalls::openat(
+            syscalls::BADFD,
+            "/proc/self/ns/mnt",
+            OpenFlags::O_RDONLY,
+            0,
+        )
+        .map_err(|err| ErrorImpl::RawOsError {
+            operation: "save current mount namespace before switching".into(),
+            source: err,
+        })?;
+
+        syscalls::setns(target, libc::CLONE_NEWNS).map_err(|err| ErrorImpl::RawOsError {
+            operation: "enter requested mount namespace".into(),
+            source: err,
+        })?;
+
+        Ok(Self { original_mntns })
+    }
+}
+
+impl Drop for MountNamespaceGuard {
+    fn drop(&mut self) {
+        // Best-effort: there is no sensible way to propagate a failure here,
+        // and leaving the calling thread stuck in the target namespace would
+        // be a much worse failure mode than silently failing to switch back
+        // (which would itself manifest as very obvious breakage very soon).
+        let _ = syscalls::setns(self.original_mntns.as_fd(), libc::CLONE_NEWNS);
+    }
+}
+
 impl ProcfsHandle {
     /// Create a new `fsopen(2)`-based [`ProcfsHandle`]. This handle is safe
     /// against racing attackers changing the mount table and is guaranteed to
     /// have no overmounts because it is a brand-new procfs.
-    pub(crate) fn new_fsopen(subset: bool) -> Result<Self, Error> {
+    ///
+    /// `hidepid` is only applied (and so only matters) if `subset` is set --
+    /// see [`ProcfsHandleBuilder::hidepid`].
+    ///
+    /// If `mntns_fd` is given, the new procfs is sourced from that mount
+    /// namespace rather than the caller's current one -- see
+    /// [`ProcfsHandleBuilder::mntns_fd`].
+    pub(crate) fn new_fsopen(
+        subset: bool,
+        hidepid: ProcfsHidePid,
+        mntns_fd: Option<BorrowedFd<'_>>,
+    ) -> Result<Self, Error> {
         if !*HAS_UNBROKEN_MOUNT_API {
             Err(ErrorImpl::NotSupported {
                 feature: "fsopen".into(),
             })?
         }
 
+        // Temporarily switch into the requested mount namespace for the
+        // duration of the fsopen(2)/fsconfig(2)/fsmount(2) sequence below, so
+        // the new procfs is sourced from there instead of our own namespace
+        // -- restored on drop, so we never leave the calling thread stuck in
+        // someone else's mount namespace.
+        let _mntns_guard = mntns_fd.map(MountNamespaceGuard::enter).transpose()?;
+
         let sfd = syscalls::fsopen("proc", FsOpenFlags::FSOPEN_CLOEXEC).map_err(|err| {
             ErrorImpl::RawOsError {
                 operation: "create procfs suberblock".into(),
@@ -859,9 +1829,9 @@ impl ProcfsHandle {
         })?;
 
         if subset {
-            // Try to configure hidepid=ptraceable,subset=pid if possible, but
+            // Try to configure hidepid=<hidepid>,subset=pid if possible, but
             // ignore errors.
-            let _ = syscalls::fsconfig_set_string(&sfd, "hidepid", "ptraceable");
+            let _ = syscalls::fsconfig_set_string(&sfd, "hidepid", hidepid.as_mount_option_str());
             let _ = syscalls::fsconfig_set_string(&sfd, "subset", "pid");
         }
 
@@ -886,6 +1856,10 @@ impl ProcfsHandle {
         })
         // NOTE: try_from_fd checks this is an actual procfs root.
         .and_then(Self::try_from_fd)
+        .and_then(|procfs| {
+            procfs.verify()?;
+            Ok(procfs)
+        })
     }
 
     /// Create a new `open_tree(2)`-based [`ProcfsHandle`]. This handle is
@@ -912,6 +1886,10 @@ impl ProcfsHandle {
         })
         // NOTE: try_from_fd checks this is an actual procfs root.
         .and_then(Self::try_from_fd)
+        .and_then(|procfs| {
+            procfs.verify()?;
+            Ok(procfs)
+        })
     }
 
     /// Create a plain `open(2)`-style [`ProcfsHandle`].
@@ -933,6 +1911,10 @@ impl ProcfsHandle {
         })
         // NOTE: try_from_fd checks this is an actual procfs root.
         .and_then(Self::try_from_fd)
+        .and_then(|procfs| {
+            procfs.verify()?;
+            Ok(procfs)
+        })
     }
 
     /// Create a new handle that references a safe `/proc`.
@@ -956,6 +1938,30 @@ impl ProcfsHandle {
     }
 }
 
+impl<'fd> ProcfsHandleRef<'fd> {
+    /// Re-verify that this handle still points at a genuine procfs mount with
+    /// the hardening libpathrs relies on -- namely that it is actually
+    /// `procfs` and is the root of the mount (both checked by
+    /// [`verify_is_procfs_root`]), and that the mount itself carries
+    /// `nosuid`, `nodev`, and `noexec`.
+    ///
+    /// This is run automatically whenever a [`ProcfsHandle`] is acquired
+    /// (through [`ProcfsHandle::new_fsopen`], [`ProcfsHandle::new_open_tree`],
+    /// or [`ProcfsHandle::new_unsafe_open`]), so most users will not need to
+    /// call this directly. It is exposed for long-lived handles where a
+    /// caller wants to re-check the mount before a particularly
+    /// security-sensitive operation.
+    ///
+    /// Of the three acquisition methods, only `new_unsafe_open` reuses the
+    /// host's `/proc` instead of creating a fresh, unshared mount -- so this
+    /// check is the only defense against an attacker having bind-mounted
+    /// something unexpected over `/proc` in that case.
+    pub fn verify(&self) -> Result<(), Error> {
+        verify_is_procfs_root(self.as_fd())?;
+        verify_mount_attrs(self.as_fd(), self.mnt_id)
+    }
+}
+
 pub(crate) fn verify_is_procfs(fd: impl AsFd) -> Result<(), Error> {
     let fs_type = syscalls::fstatfs(fd)
         .map_err(|err| ErrorImpl::RawOsError {
@@ -976,6 +1982,56 @@ pub(crate) fn verify_is_procfs(fd: impl AsFd) -> Result<(), Error> {
     Ok(())
 }
 
+fn verify_mount_attrs(fd: impl AsFd, mnt_id: u64) -> Result<(), Error> {
+    use std::io::Read;
+
+    let fd = fd.as_fd();
+
+    let mut info = String::new();
+    File::from(
+        syscalls::openat(fd, "self/mountinfo", OpenFlags::O_RDONLY, 0).map_err(|err| {
+            ErrorImpl::RawOsError {
+                operation: "open self/mountinfo to verify procfs mount attributes".into(),
+                source: err,
+            }
+        })?,
+    )
+    .read_to_string(&mut info)
+    .map_err(|err| ErrorImpl::OsError {
+        operation: "read self/mountinfo to verify procfs mount attributes".into(),
+        source: err,
+    })?;
+
+    // Find the mountinfo(5) entry for our own mount ID, and grab the
+    // per-mount options field (field 6, right after the root and mount point
+    // fields -- see proc_pid_mountinfo(5)).
+    let mount_options = info
+        .lines()
+        .find_map(|line| {
+            let (id, rest) = line.split_once(' ')?;
+            (id.parse::<u64>().ok()? == mnt_id).then_some(rest)
+        })
+        .and_then(|rest| rest.split(" - ").next())
+        .and_then(|pre_separator| pre_separator.split_whitespace().nth(4))
+        .ok_or_else(|| ErrorImpl::UnsafeProcfsMount {
+            description: format!("could not find mountinfo entry for procfs mount id {mnt_id}")
+                .into(),
+        })?;
+
+    let options: Vec<&str> = mount_options.split(',').collect();
+    for required in ["nosuid", "nodev", "noexec"] {
+        if !options.contains(&required) {
+            Err(ErrorImpl::UnsafeProcfsMount {
+                description: format!(
+                    "procfs mount id {mnt_id} is missing required mount option {required:?} (mount options: {mount_options:?})",
+                )
+                .into(),
+            })?;
+        }
+    }
+    Ok(())
+}
+
 pub(crate) fn verify_is_procfs_root(fd: impl AsFd) -> Result<(), Error> {
     let fd = fd.as_fd();
 
@@ -1175,28 +2231,28 @@ mod tests {
 
     #[test]
     fn new_fsopen() {
-        if let Ok(procfs) = ProcfsHandle::new_fsopen(false) {
+        if let Ok(procfs) = ProcfsHandle::new_fsopen(false, ProcfsHidePid::default(), None) {
             assert!(
                 !procfs.is_subset,
-                "ProcfsHandle::new_fsopen(false) should be !subset=pid"
+                "ProcfsHandle::new_fsopen(false, ..) should be !subset=pid"
             );
             assert!(
                 procfs.is_detached,
-                "ProcfsHandle::new_fsopen(false) should be detached"
+                "ProcfsHandle::new_fsopen(false, ..) should be detached"
             );
         }
     }
 
     #[test]
     fn new_fsopen_subset() {
-        if let Ok(procfs) = ProcfsHandle::new_fsopen(true) {
+        if let Ok(procfs) = ProcfsHandle::new_fsopen(true, ProcfsHidePid::default(), None) {
             assert!(
                 procfs.is_subset,
-                "ProcfsHandle::new_fsopen(true) should be subset=pid"
+                "ProcfsHandle::new_fsopen(true, ..) should be subset=pid"
             );
             assert!(
                 procfs.is_detached,
-                "ProcfsHandle::new_fsopen(true) should be detached"
+                "ProcfsHandle::new_fsopen(true, ..) should be detached"
             );
         }
     }
@@ -1320,4 +2376,59 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn open_follow_trusted_fd() {
+        let procfs = ProcfsHandle::new().expect("new procfs handle");
+
+        // A read-only fd's self/fd/<n> magic-link can be followed with an
+        // access mode no more permissive than the original open(2).
+        let file = File::open("/proc/self/status").expect("open a read-only test fd");
+        let path = format!("fd/{}", file.as_raw_fd());
+
+        procfs
+            .open_follow_trusted_fd(ProcfsBase::ProcSelf, &path, OpenFlags::O_RDONLY)
+            .expect("follow a trusted fd magic-link with the same access mode");
+
+        // ... but not with a more permissive access mode than the original
+        // fd was opened with.
+        let err = procfs
+            .open_follow_trusted_fd(ProcfsBase::ProcSelf, &path, OpenFlags::O_RDWR)
+            .expect_err("following a read-only fd magic-link with O_RDWR should be rejected");
+        assert_eq!(
+            err.kind(),
+            ErrorKind::OsError(Some(libc::EACCES)),
+            "requesting more access than the original fd had should return EACCES"
+        );
+    }
+
+    #[test]
+    fn open_follow_trusted_fd_with() {
+        let procfs = ProcfsHandle::new().expect("new procfs handle");
+
+        let file = File::open("/proc/self/status").expect("open a read-only test fd");
+        let path = format!("fd/{}", file.as_raw_fd());
+
+        procfs
+            .open_follow_trusted_fd_with(
+                ProcfsBase::ProcSelf,
+                &path,
+                OpenFlags::O_RDONLY,
+                ResolverFlags::empty(),
+            )
+            .expect("follow a trusted fd magic-link with explicit empty ResolverFlags");
+    }
+
+    #[test]
+    fn open_follow_trusted_fd_rejects_trailing_slash() {
+        let procfs = ProcfsHandle::new().expect("new procfs handle");
+
+        // A trailing slash leaves no final fd/<n> component to follow, so it
+        // is rejected as an invalid argument (unlike open_follow_with, which
+        // strips the slash and implies O_DIRECTORY).
+        let err = procfs
+            .open_follow_trusted_fd(ProcfsBase::ProcSelf, "fd/0/", OpenFlags::O_RDONLY)
+            .expect_err("trailing slash should be rejected");
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    }
 }
@@ -0,0 +1,480 @@
+// SPDX-License-Identifier: MPL-2.0 OR LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * == MPL-2.0 ==
+ *
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Alternatively, this Source Code Form may also (at your option) be used
+ * under the terms of the GNU Lesser General Public License Version 3, as
+ * described below:
+ *
+ * == LGPL-3.0-or-later ==
+ *
+ *  This program is free software: you can redistribute it and/or modify it
+ *  under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or (at
+ *  your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful, but
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY  or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General
+ * Public License  for more details.
+ *
+ *  You should have received a copy of the GNU Lesser General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#![forbid(unsafe_code)]
+
+//! Typed parsers for common `procfs` files, layered on top of
+//! [`ProcfsHandleRef::open`].
+//!
+//! Opening a file through [`ProcfsHandleRef`] already guarantees that you got
+//! a genuine `procfs` file (and not something an attacker swapped in through a
+//! bind-mount or similar). However, on its own that only gets you an open
+//! [`File`] -- callers still have to slurp the contents and hand-roll a
+//! parser, which re-introduces the exact kind of footgun libpathrs is meant
+//! to avoid (partial reads racing with the process exiting, ad-hoc
+//! whitespace-splitting that breaks on `comm` fields containing spaces, and
+//! so on).
+//!
+//! The readers in this module always read the whole file through the
+//! hardened handle *before* parsing any of it, so the bytes being parsed
+//! can't be swapped out from under us mid-parse, and return typed, structured
+//! data instead of raw strings.
+
+use super::{ProcfsBase, ProcfsHandleRef};
+use crate::{
+    error::{Error, ErrorExt, ErrorImpl},
+    flags::OpenFlags,
+};
+
+use std::{collections::BTreeMap, io::Read};
+
+fn read_file_bytes(
+    proc: &ProcfsHandleRef<'_>,
+    base: ProcfsBase<'_>,
+    name: &str,
+) -> Result<Vec<u8>, Error> {
+    let mut file = proc
+        .open(base, name, OpenFlags::O_RDONLY)
+        .with_wrap(|| format!("open /proc/<base>/{name}"))?;
+
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents)
+        .map_err(|err| ErrorImpl::OsError {
+            operation: format!("read /proc/<base>/{name}").into(),
+            source: err,
+        })
+        .with_wrap(|| format!("read contents of /proc/<base>/{name}"))?;
+    Ok(contents)
+}
+
+fn read_file_string(
+    proc: &ProcfsHandleRef<'_>,
+    base: ProcfsBase<'_>,
+    name: &str,
+) -> Result<String, Error> {
+    let contents = read_file_bytes(proc, base, name)?;
+    String::from_utf8(contents)
+        .map_err(|err| ErrorImpl::SafetyViolation {
+            description: format!("/proc/<base>/{name} is not valid UTF-8: {err}").into(),
+        })
+        .map_err(Error::from)
+}
+
+/// Parse the `"key:\tvalue"` format used by several `procfs` files (most
+/// notably `/proc/<pid>/status`) into a mapping from field name to raw (but
+/// whitespace-trimmed) value.
+///
+/// Blank lines are skipped, but any other line that doesn't contain a `:` is
+/// treated as a [`SafetyViolation`][`crate::error::ErrorKind::SafetyViolation`]
+/// -- a well-formed kernel-provided file should never produce such a line, so
+/// seeing one usually means the file wasn't what we thought it was.
+///
+/// Callers are expected to further parse individual values as appropriate for
+/// the field in question -- see [`read_status`] for an example of doing this
+/// for `/proc/<pid>/status`.
+pub fn read_kv(
+    proc: &ProcfsHandleRef<'_>,
+    base: ProcfsBase<'_>,
+    name: &str,
+) -> Result<BTreeMap<String, String>, Error> {
+    let contents = read_file_string(proc, base, name)?;
+    contents
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (key, value) = line.split_once(':').ok_or_else(|| ErrorImpl::SafetyViolation {
+                description: format!("malformed key:value line in /proc/<base>/{name}: {line:?}")
+                    .into(),
+            })?;
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+fn kv_get<'a>(
+    kv: &'a BTreeMap<String, String>,
+    name: &str,
+    field: &str,
+) -> Result<&'a str, Error> {
+    kv.get(field)
+        .map(String::as_str)
+        .ok_or_else(|| {
+            ErrorImpl::SafetyViolation {
+                description: format!("/proc/<base>/{name} is missing required field {field:?}")
+                    .into(),
+            }
+            .into()
+        })
+}
+
+fn parse_hex_bitset(value: &str) -> Result<u64, Error> {
+    Ok(u64::from_str_radix(value.trim().trim_start_matches("0x"), 16)?)
+}
+
+/// The four IDs (real, effective, saved-set, and filesystem) the kernel
+/// reports for both the `Uid:` and `Gid:` lines of `/proc/<pid>/status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IdSet {
+    pub real: u32,
+    pub effective: u32,
+    pub saved: u32,
+    pub filesystem: u32,
+}
+
+fn parse_idset(field: &str, value: &str) -> Result<IdSet, Error> {
+    let ids = value
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<Vec<u32>, _>>()?;
+    match ids[..] {
+        [real, effective, saved, filesystem] => Ok(IdSet {
+            real,
+            effective,
+            saved,
+            filesystem,
+        }),
+        _ => Err(ErrorImpl::SafetyViolation {
+            description: format!("{field} line did not have exactly 4 fields: {value:?}").into(),
+        })?,
+    }
+}
+
+/// A parsed, typed view of `/proc/<pid>/status`.
+///
+/// Only the fields most relevant to container-introspection use cases are
+/// exposed -- if you need a field not listed here, use [`read_kv`] directly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ProcStatus {
+    pub name: String,
+    pub state: String,
+    pub pid: u32,
+    pub ppid: u32,
+    pub uid: IdSet,
+    pub gid: IdSet,
+    pub groups: Vec<u32>,
+    pub cap_inheritable: u64,
+    pub cap_permitted: u64,
+    pub cap_effective: u64,
+    pub cap_bounding: u64,
+    /// `None` on kernels older than Linux 4.3, which did not have ambient
+    /// capabilities.
+    pub cap_ambient: Option<u64>,
+    /// `None` if the process is not seccomp-filtered (or the kernel is too
+    /// old to expose `Seccomp:`).
+    pub seccomp_mode: Option<u32>,
+    /// The `x86_Thread_features` bitmask exposed by newer kernels, recording
+    /// which CET/shadow-stack features are enabled for this thread. `None`
+    /// if the kernel/arch does not expose this field.
+    pub x86_thread_features: Option<u64>,
+    /// The subset of `x86_thread_features` that can no longer be disabled
+    /// via `prctl(2)`, from the `x86_Thread_features_locked` field.
+    pub x86_thread_features_locked: Option<u64>,
+}
+
+/// Safely read and parse `/proc/<base>/status` through `proc`.
+pub fn read_status(proc: &ProcfsHandleRef<'_>, base: ProcfsBase<'_>) -> Result<ProcStatus, Error> {
+    let kv = read_kv(proc, base, "status").with_wrap(|| "parse /proc/<base>/status")?;
+    let get = |field: &str| kv_get(&kv, "status", field);
+
+    Ok(ProcStatus {
+        name: get("Name")?.to_string(),
+        state: get("State")?.to_string(),
+        pid: get("Pid")?.parse()?,
+        ppid: get("PPid")?.parse()?,
+        uid: parse_idset("Uid", get("Uid")?)?,
+        gid: parse_idset("Gid", get("Gid")?)?,
+        groups: kv
+            .get("Groups")
+            .map(|value| value.split_whitespace().map(str::parse).collect())
+            .transpose()?
+            .unwrap_or_default(),
+        cap_inheritable: parse_hex_bitset(get("CapInh")?)?,
+        cap_permitted: parse_hex_bitset(get("CapPrm")?)?,
+        cap_effective: parse_hex_bitset(get("CapEff")?)?,
+        cap_bounding: parse_hex_bitset(get("CapBnd")?)?,
+        cap_ambient: kv.get("CapAmb").map(String::as_str).map(parse_hex_bitset).transpose()?,
+        seccomp_mode: kv.get("Seccomp").map(|value| value.trim().parse()).transpose()?,
+        x86_thread_features: kv
+            .get("x86_Thread_features")
+            .map(String::as_str)
+            .map(parse_hex_bitset)
+            .transpose()?,
+        x86_thread_features_locked: kv
+            .get("x86_Thread_features_locked")
+            .map(String::as_str)
+            .map(parse_hex_bitset)
+            .transpose()?,
+    })
+}
+
+/// A single parsed entry of `/proc/<pid>/mountinfo`. See
+/// [`proc_pid_mountinfo(5)`] for the meaning of each field.
+///
+/// [`proc_pid_mountinfo(5)`]: https://www.man7.org/linux/man-pages/man5/proc_pid_mountinfo.5.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MountEntry {
+    pub mount_id: u32,
+    pub parent_id: u32,
+    /// The `st_dev` major/minor of the mounted filesystem.
+    pub device: (u32, u32),
+    pub root: String,
+    pub mount_point: String,
+    pub mount_options: Vec<String>,
+    /// Zero or more optional fields (such as `shared:X` or `master:X`), with
+    /// the terminating `-` field stripped.
+    pub optional_fields: Vec<String>,
+    pub fs_type: String,
+    pub mount_source: String,
+    pub super_options: Vec<String>,
+}
+
+fn parse_mountinfo_line(line: &str) -> Result<MountEntry, Error> {
+    let malformed = || {
+        Error::from(ErrorImpl::SafetyViolation {
+            description: format!("malformed /proc/<pid>/mountinfo line: {line:?}").into(),
+        })
+    };
+
+    // The optional fields (before " - ") and the fixed fields afterwards are
+    // separated by a lone "-" field, so splitting on " - " gives us both
+    // halves in one go.
+    let (pre_separator, post_separator) = line.split_once(" - ").ok_or_else(malformed)?;
+
+    let mut pre_fields = pre_separator.split_whitespace();
+    let mount_id = pre_fields.next().ok_or_else(malformed)?.parse()?;
+    let parent_id = pre_fields.next().ok_or_else(malformed)?.parse()?;
+    let (major, minor) = pre_fields
+        .next()
+        .ok_or_else(malformed)?
+        .split_once(':')
+        .ok_or_else(malformed)?;
+    let device = (major.parse()?, minor.parse()?);
+    let root = pre_fields.next().ok_or_else(malformed)?.to_string();
+    let mount_point = pre_fields.next().ok_or_else(malformed)?.to_string();
+    let mount_options = pre_fields
+        .next()
+        .ok_or_else(malformed)?
+        .split(',')
+        .map(String::from)
+        .collect();
+    let optional_fields = pre_fields.map(String::from).collect();
+
+    let mut post_fields = post_separator.split_whitespace();
+    let fs_type = post_fields.next().ok_or_else(malformed)?.to_string();
+    let mount_source = post_fields.next().ok_or_else(malformed)?.to_string();
+    let super_options = post_fields
+        .next()
+        .ok_or_else(malformed)?
+        .split(',')
+        .map(String::from)
+        .collect();
+
+    Ok(MountEntry {
+        mount_id,
+        parent_id,
+        device,
+        root,
+        mount_point,
+        mount_options,
+        optional_fields,
+        fs_type,
+        mount_source,
+        super_options,
+    })
+}
+
+/// Safely read and parse `/proc/<base>/mountinfo` through `proc`.
+pub fn read_mountinfo(
+    proc: &ProcfsHandleRef<'_>,
+    base: ProcfsBase<'_>,
+) -> Result<Vec<MountEntry>, Error> {
+    let contents = read_file_string(proc, base, "mountinfo")?;
+    contents
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(parse_mountinfo_line)
+        .collect()
+}
+
+fn read_nul_separated(
+    proc: &ProcfsHandleRef<'_>,
+    base: ProcfsBase<'_>,
+    name: &str,
+) -> Result<Vec<String>, Error> {
+    let contents = read_file_bytes(proc, base, name)?;
+    contents
+        .split(|&byte| byte == 0)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            String::from_utf8(entry.to_vec()).map_err(|err| {
+                ErrorImpl::SafetyViolation {
+                    description: format!("/proc/<base>/{name} entry is not valid UTF-8: {err}")
+                        .into(),
+                }
+                .into()
+            })
+        })
+        .collect()
+}
+
+/// Safely read and split `/proc/<base>/cmdline` through `proc`, correctly
+/// splitting on the NUL bytes the kernel uses to separate arguments (rather
+/// than whitespace, which may appear within a single argument).
+pub fn read_cmdline(proc: &ProcfsHandleRef<'_>, base: ProcfsBase<'_>) -> Result<Vec<String>, Error> {
+    read_nul_separated(proc, base, "cmdline")
+}
+
+/// Safely read and parse `/proc/<base>/environ` through `proc`, correctly
+/// splitting on NUL bytes and then on the first `=` in each `KEY=value`
+/// entry.
+pub fn read_environ(
+    proc: &ProcfsHandleRef<'_>,
+    base: ProcfsBase<'_>,
+) -> Result<Vec<(String, String)>, Error> {
+    read_nul_separated(proc, base, "environ")?
+        .into_iter()
+        .map(|entry| {
+            entry
+                .split_once('=')
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .ok_or_else(|| {
+                    ErrorImpl::SafetyViolation {
+                        description: format!("/proc/<base>/environ entry missing '=': {entry:?}")
+                            .into(),
+                    }
+                    .into()
+                })
+        })
+        .collect()
+}
+
+fn read_security_context(
+    proc: &ProcfsHandleRef<'_>,
+    base: ProcfsBase<'_>,
+    name: &str,
+) -> Result<String, Error> {
+    let contents = read_file_string(proc, base, name)?;
+    // The kernel's LSM hooks return the context without a trailing newline,
+    // but some kernels NUL-pad the value up to `PAGE_SIZE` -- trim both so
+    // callers get exactly the context string, matching `getfilecon(3)`.
+    Ok(contents.trim_end_matches(['\0', '\n']).to_string())
+}
+
+/// Safely read the SELinux security context that `/proc/<base>/attr/current`
+/// reports for the task's *current* execution context, mirroring
+/// `getcon(3)`/`getpidcon(3)` but without trusting a possibly-overmounted
+/// `/proc`.
+///
+/// Returns an error if the running kernel has no LSM that populates
+/// `attr/current` (for example, a kernel without SELinux/Smack enabled).
+pub fn read_current_context(
+    proc: &ProcfsHandleRef<'_>,
+    base: ProcfsBase<'_>,
+) -> Result<String, Error> {
+    read_security_context(proc, base, "attr/current").with_wrap(|| "read current security context")
+}
+
+/// Safely read the SELinux security context that `/proc/<base>/attr/exec`
+/// reports -- the context that will be applied to the *next* `execve(2)` by
+/// this task, if one has been set via [`Handle::set_security_context`] or
+/// equivalent. This is empty (not an error) if no exec context has been set.
+///
+/// [`Handle::set_security_context`]: crate::Handle::set_security_context
+pub fn read_exec_context(proc: &ProcfsHandleRef<'_>, base: ProcfsBase<'_>) -> Result<String, Error> {
+    read_security_context(proc, base, "attr/exec").with_wrap(|| "read exec security context")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procfs::ProcfsHandle;
+
+    use once_cell::sync::Lazy;
+
+    // MSRV(1.80): Use LazyLock.
+    static TEST_PROCFS_HANDLE: Lazy<ProcfsHandle> =
+        Lazy::new(|| ProcfsHandle::new().expect("should be able to get some /proc handle"));
+
+    #[test]
+    fn status_self() {
+        let status = read_status(&TEST_PROCFS_HANDLE, ProcfsBase::ProcSelf)
+            .expect("should be able to parse our own /proc/self/status");
+        assert_eq!(status.pid, std::process::id(), "Pid field should match our own pid");
+    }
+
+    #[test]
+    fn mountinfo_self_nonempty() {
+        let mounts = read_mountinfo(&TEST_PROCFS_HANDLE, ProcfsBase::ProcSelf)
+            .expect("should be able to parse our own /proc/self/mountinfo");
+        assert!(!mounts.is_empty(), "should have at least one mount entry");
+    }
+
+    #[test]
+    fn cmdline_self_nonempty() {
+        let cmdline = read_cmdline(&TEST_PROCFS_HANDLE, ProcfsBase::ProcSelf)
+            .expect("should be able to parse our own /proc/self/cmdline");
+        assert!(!cmdline.is_empty(), "should have at least one cmdline argument");
+    }
+
+    #[test]
+    fn environ_self_nonempty() {
+        let environ = read_environ(&TEST_PROCFS_HANDLE, ProcfsBase::ProcSelf)
+            .expect("should be able to parse our own /proc/self/environ");
+        assert!(!environ.is_empty(), "should have at least one environment variable");
+    }
+
+    #[test]
+    fn current_context_self() {
+        // Not every test environment has an LSM that populates `attr/current`
+        // (e.g. no SELinux/Smack) -- skip rather than fail if so.
+        match read_current_context(&TEST_PROCFS_HANDLE, ProcfsBase::ProcSelf) {
+            Ok(context) => assert!(
+                !context.contains('\0') && !context.contains('\n'),
+                "context should be trimmed of NUL/newline padding: {context:?}"
+            ),
+            Err(err) => eprintln!("skipping security context test, no LSM available: {err}"),
+        }
+    }
+
+    #[test]
+    fn exec_context_self_is_unset() {
+        // We haven't called `set_security_context` on ourselves, so (if the
+        // kernel supports this at all) the exec context should be empty.
+        match read_exec_context(&TEST_PROCFS_HANDLE, ProcfsBase::ProcSelf) {
+            Ok(context) => assert_eq!(context, "", "exec context should be unset by default"),
+            Err(err) => eprintln!("skipping security context test, no LSM available: {err}"),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: MPL-2.0 OR LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * == MPL-2.0 ==
+ *
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Alternatively, this Source Code Form may also (at your option) be used
+ * under the terms of the GNU Lesser General Public License Version 3, as
+ * described below:
+ *
+ * == LGPL-3.0-or-later ==
+ *
+ *  This program is free software: you can redistribute it and/or modify it
+ *  under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or (at
+ *  your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful, but
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY  or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General
+ * Public License  for more details.
+ *
+ *  You should have received a copy of the GNU Lesser General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#![forbid(unsafe_code)]
+
+//! [`Handle::readlink`], the [`Handle`]-based counterpart to
+//! [`Root::readlink`].
+//!
+//! [`Root::readlink`] has to re-resolve the parent directory and then do a
+//! single [`readlinkat(2)`] on the final component. If you already hold a
+//! [`Handle`] (e.g. one returned by [`Root::resolve_nofollow`]), there was
+//! previously no way to read its target without going through that
+//! string-based re-resolution again -- this module closes that gap by
+//! reading the target straight off the already-open `O_PATH` fd.
+//!
+//! [`Root::readlink`]: crate::Root::readlink
+//! [`Root::resolve_nofollow`]: crate::Root::resolve_nofollow
+//! [`readlinkat(2)`]: https://www.man7.org/linux/man-pages/man2/readlinkat.2.html
+
+use crate::{
+    error::{Error, ErrorImpl},
+    syscalls, Handle, HandleRef,
+};
+
+use std::{io::Error as IOError, path::PathBuf};
+
+fn readlink(fd: impl std::os::unix::io::AsFd) -> Result<PathBuf, Error> {
+    syscalls::readlinkat(fd, "").map_err(|err| {
+        // With an empty path, readlinkat(2) reports a handle that is not a
+        // symlink as ENOENT, so map that to the EINVAL readlink(2) uses.
+        if err.root_cause().raw_os_error() == Some(libc::ENOENT) {
+            return ErrorImpl::OsError {
+                operation: "read symlink target of handle".into(),
+                source: IOError::from_raw_os_error(libc::EINVAL),
+            }
+            .into();
+        }
+        ErrorImpl::RawOsError {
+            operation: "read symlink target of handle".into(),
+            source: err,
+        }
+        .into()
+    })
+}
+
+impl Handle {
+    /// Read the target of the symlink this [`Handle`] refers to.
+    ///
+    /// This never re-resolves any path string -- it reads the target
+    /// directly off the already-open `O_PATH` fd (equivalent to
+    /// [`readlinkat(2)`] with an empty path), so it is
+    /// safe to call even if the handle's original path has since been
+    /// renamed or replaced.
+    ///
+    /// Returns `EINVAL` (via [`ErrorKind::OsError`]) if this handle does not
+    /// refer to a symlink.
+    ///
+    /// [`readlinkat(2)`]: https://www.man7.org/linux/man-pages/man2/readlinkat.2.html
+    /// [`ErrorKind::OsError`]: crate::error::ErrorKind::OsError
+    pub fn readlink(&self) -> Result<PathBuf, Error> {
+        readlink(self)
+    }
+}
+
+impl HandleRef<'_> {
+    /// See [`Handle::readlink`].
+    pub fn readlink(&self) -> Result<PathBuf, Error> {
+        readlink(self)
+    }
+}
@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: MPL-2.0 OR LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * == MPL-2.0 ==
+ *
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Alternatively, this Source Code Form may also (at your option) be used
+ * under the terms of the GNU Lesser General Public License Version 3, as
+ * described below:
+ *
+ * == LGPL-3.0-or-later ==
+ *
+ *  This program is free software: you can redistribute it and/or modify it
+ *  under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or (at
+ *  your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful, but
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY  or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General
+ * Public License  for more details.
+ *
+ *  You should have received a copy of the GNU Lesser General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#![forbid(unsafe_code)]
+
+//! [`Root::rename`]/[`RootRef::rename`], a race-free `renameat2(2)` wrapper.
+//!
+//! A plain `rename(2)`/`renameat(2)` always silently replaces an existing
+//! `to`, and has no way to atomically swap two paths -- tools that need
+//! "exchange" or "don't clobber" semantics have traditionally had to
+//! `stat()` first and hope nothing changes before the actual rename. This
+//! module resolves both `from` and `to` down to a (parent directory,
+//! trailing component) pair through the existing resolver -- so neither
+//! side of the rename can be tricked into escaping the root -- and then
+//! issues a single [`renameat2(2)`] with the caller's requested
+//! [`RenameFlags`].
+//!
+//! [`Root::rename`]: crate::Root::rename
+//! [`RootRef::rename`]: crate::RootRef::rename
+//! [`RenameFlags`]: crate::flags::RenameFlags
+//! [`renameat2(2)`]: https://www.man7.org/linux/man-pages/man2/rename.2.html
+
+use crate::{
+    error::{Error, ErrorImpl},
+    flags::RenameFlags,
+    Root, RootRef,
+};
+
+use std::{
+    ffi::{CString, OsStr},
+    io::Error as IOError,
+    os::unix::ffi::OsStrExt,
+    path::Path,
+};
+
+use rustix::fs as rustix_fs;
+
+/// Split `path` into its parent (defaulting to `.` for a single-component
+/// path) and its trailing component, rejecting anything without a normal
+/// trailing component (i.e. `.`, `..`, or a path ending in `/`).
+fn split_trailing_component(path: &Path) -> Result<(&Path, &OsStr), Error> {
+    let bytes = path.as_os_str().as_bytes();
+    let name = path
+        .file_name()
+        // Path::file_name() skips a trailing "/" or "/.", so check for those
+        // by hand.
+        .filter(|_| !bytes.ends_with(b"/") && !bytes.ends_with(b"/."))
+        .ok_or_else(|| ErrorImpl::InvalidArgument {
+        name: "path".into(),
+        description: format!(
+            "{path:?} has no normal trailing component to rename (must not be \".\", \"..\", or end in \"/\")"
+        )
+        .into(),
+    })?;
+    // Path::parent() of a single-component path is "", not None.
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    Ok((parent, name))
+}
+
+fn to_cstring(name: &OsStr) -> Result<CString, Error> {
+    CString::new(name.as_bytes()).map_err(|_| {
+        ErrorImpl::InvalidArgument {
+            name: "path".into(),
+            description: "path component must not contain a NUL byte".into(),
+        }
+        .into()
+    })
+}
+
+fn raw_rename_flags(flags: RenameFlags) -> rustix_fs::RenameFlags {
+    let mut raw = rustix_fs::RenameFlags::empty();
+    if flags.contains(RenameFlags::RENAME_EXCHANGE) {
+        raw |= rustix_fs::RenameFlags::EXCHANGE;
+    }
+    if flags.contains(RenameFlags::RENAME_NOREPLACE) {
+        raw |= rustix_fs::RenameFlags::NOREPLACE;
+    }
+    if flags.contains(RenameFlags::RENAME_WHITEOUT) {
+        raw |= rustix_fs::RenameFlags::WHITEOUT;
+    }
+    raw
+}
+
+/// Shared implementation backing both [`Root::rename`] and
+/// [`RootRef::rename`], generic over however the caller resolves a parent
+/// directory path down to a [`Handle`].
+///
+/// [`Handle`]: crate::Handle
+fn rename_via<H: std::os::unix::io::AsFd>(
+    resolve: impl Fn(&Path) -> Result<H, Error>,
+    from: &Path,
+    to: &Path,
+    flags: RenameFlags,
+) -> Result<(), Error> {
+    let (from_parent, from_name) = split_trailing_component(from)?;
+    let (to_parent, to_name) = split_trailing_component(to)?;
+
+    // Resolve each parent directory through the normal, escape-proof
+    // resolver -- this is what stops either side of the rename from naming
+    // a path outside of the root, and is re-done on every call so a rename
+    // can never operate against a stale or raced parent handle.
+    let from_dir = resolve(from_parent)?;
+    let to_dir = resolve(to_parent)?;
+
+    let from_name = to_cstring(from_name)?;
+    let to_name = to_cstring(to_name)?;
+
+    rustix_fs::renameat_with(&from_dir, &from_name, &to_dir, &to_name, raw_rename_flags(flags)).map_err(
+        |err| {
+            ErrorImpl::OsError {
+                operation: "renameat2".into(),
+                source: IOError::from_raw_os_error(err.raw_os_error()),
+            }
+            .into()
+        },
+    )
+}
+
+impl Root {
+    /// Atomically rename `from` to `to`, with both paths resolved safely
+    /// inside this root.
+    ///
+    /// With `flags` empty, this behaves like a race-free `rename(2)` --
+    /// `to` is silently replaced if it already exists. Pass
+    /// [`RenameFlags::RENAME_EXCHANGE`] to atomically swap `from` and `to`
+    /// instead (both must already exist), or
+    /// [`RenameFlags::RENAME_NOREPLACE`] to fail with `EEXIST` rather than
+    /// replacing an existing `to`.
+    ///
+    /// Neither `from` nor `to` may have a trailing `..` (or otherwise lack a
+    /// normal trailing component) -- both must name a specific entry to
+    /// rename, just like plain `rename(2)` requires. Fails with
+    /// `EINVAL`/`ENOSYS` if the underlying filesystem or kernel doesn't
+    /// support the requested `flags`.
+    ///
+    /// [`RenameFlags::RENAME_EXCHANGE`]: crate::flags::RenameFlags::RENAME_EXCHANGE
+    /// [`RenameFlags::RENAME_NOREPLACE`]: crate::flags::RenameFlags::RENAME_NOREPLACE
+    pub fn rename(
+        &self,
+        from: impl AsRef<Path>,
+        to: impl AsRef<Path>,
+        flags: RenameFlags,
+    ) -> Result<(), Error> {
+        rename_via(|p| self.resolve(p), from.as_ref(), to.as_ref(), flags)
+    }
+}
+
+impl RootRef<'_> {
+    /// See [`Root::rename`].
+    pub fn rename(
+        &self,
+        from: impl AsRef<Path>,
+        to: impl AsRef<Path>,
+        flags: RenameFlags,
+    ) -> Result<(), Error> {
+        rename_via(|p| self.resolve(p), from.as_ref(), to.as_ref(), flags)
+    }
+}
@@ -0,0 +1,178 @@
+// SPDX-License-Identifier: MPL-2.0 OR LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * == MPL-2.0 ==
+ *
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Alternatively, this Source Code Form may also (at your option) be used
+ * under the terms of the GNU Lesser General Public License Version 3, as
+ * described below:
+ *
+ * == LGPL-3.0-or-later ==
+ *
+ *  This program is free software: you can redistribute it and/or modify it
+ *  under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or (at
+ *  your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful, but
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY  or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General
+ * Public License  for more details.
+ *
+ *  You should have received a copy of the GNU Lesser General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#![forbid(unsafe_code)]
+
+//! Partial resolution, returned by [`Root::resolve_partial`] and
+//! [`RootRef::resolve_partial`].
+//!
+//! A plain [`Root::resolve`] that hits `ENOENT` (or a similar error)
+//! part-way through a path discards all of the safe traversal it already
+//! did -- the caller only learns that resolution failed, and has to
+//! re-walk from the root to pick up where it left off (e.g. to `mkdir` the
+//! missing tail). [`Root::resolve_partial`] instead hands back the deepest
+//! [`Handle`] that was confirmed to exist, plus the unresolved remainder of
+//! the path, so callers like `mkdir_all`-style operations can continue from
+//! there without a second traversal.
+//!
+//! [`Root::resolve`]: crate::Root::resolve
+//! [`Root::resolve_partial`]: crate::Root::resolve_partial
+//! [`RootRef::resolve_partial`]: crate::RootRef::resolve_partial
+
+use crate::{error::Error, utils::PathIterExt, Handle, Root, RootRef};
+
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+/// The result of a [`Root::resolve_partial`] (or [`RootRef::resolve_partial`])
+/// lookup.
+///
+/// [`Root::resolve_partial`]: crate::Root::resolve_partial
+/// [`RootRef::resolve_partial`]: crate::RootRef::resolve_partial
+#[derive(Debug)]
+pub struct PartialLookup {
+    /// A confined [`Handle`] to the deepest path component that could be
+    /// resolved. If the whole path resolved, this is the final handle and
+    /// [`PartialLookup::remaining`] is empty.
+    pub handle: Handle,
+    /// The unresolved remainder of the requested path, relative to
+    /// [`PartialLookup::handle`]. Guaranteed to contain no resolved
+    /// symlinks -- it is exactly the trailing raw components of the
+    /// original path, starting with the first one that failed to resolve.
+    pub remaining: PathBuf,
+}
+
+/// Shared binary-search implementation, generic over however the caller
+/// wants to perform a single full resolution (so the same logic backs both
+/// [`Root::resolve_partial`] and [`RootRef::resolve_partial`] without either
+/// needing to expose its internals).
+///
+/// This deliberately goes through the same public, escape-proof `resolve`
+/// entrypoint for every probe -- it trusts nothing about the path other than
+/// what a full `resolve()` would already enforce, so the returned handle and
+/// remainder carry the exact same safety guarantees as a successful
+/// `resolve()` would.
+fn resolve_partial_via(
+    path: &Path,
+    resolve: impl Fn(&Path) -> Result<Handle, Error>,
+) -> Result<PartialLookup, Error> {
+    match resolve(path) {
+        Ok(handle) => {
+            return Ok(PartialLookup {
+                handle,
+                remaining: PathBuf::new(),
+            })
+        }
+        Err(err) if err.is_safety_violation() => return Err(err),
+        Err(_) => {}
+    }
+
+    // Binary-search (as `resolvers::openat2::resolve_partial` does) for the
+    // longest resolvable prefix: resolution of a prefix is monotonic in its
+    // length, so the resolvable prefixes form a contiguous `[0..=lo]` range.
+    let components = path.raw_components().map(OsString::from).collect::<Vec<_>>();
+    let prefix = |end: usize| -> PathBuf { components[..end].iter().collect() };
+
+    let mut lo = 0; // prefix(0) is the root, which is always resolvable.
+    let mut hi = components.len().saturating_sub(1); // prefix(len) already failed above.
+    let mut handle = None;
+
+    while lo < hi {
+        let mid = lo + (hi - lo + 1) / 2;
+        match resolve(&prefix(mid)) {
+            Ok(found) => {
+                handle = Some(found);
+                lo = mid;
+            }
+            Err(err) => {
+                if err.is_safety_violation() {
+                    return Err(err);
+                }
+                hi = mid - 1;
+            }
+        }
+    }
+
+    let handle = match handle {
+        Some(handle) => handle,
+        None => resolve(Path::new("."))?,
+    };
+
+    // The binary search may not have probed `prefix(lo + 1)` itself (only
+    // some midpoint that happened to fail beyond it), so do one more
+    // explicit resolve to make sure the authoritative first-failure isn't a
+    // safety violation before handing back a partial result.
+    if let Err(err) = resolve(&prefix(lo + 1)) {
+        if err.is_safety_violation() {
+            return Err(err);
+        }
+    }
+
+    Ok(PartialLookup {
+        handle,
+        remaining: components[lo..].iter().collect(),
+    })
+}
+
+impl Root {
+    /// Resolve as much of `path` as safely possible, returning the deepest
+    /// [`Handle`] that exists plus the unresolved remainder rather than
+    /// discarding that work on the first error (commonly `ENOENT`).
+    ///
+    /// This follows trailing symlinks the same way [`Root::resolve`] does --
+    /// use [`Root::resolve_partial_nofollow`] if you want the
+    /// `resolve_nofollow` semantics instead.
+    pub fn resolve_partial(&self, path: impl AsRef<Path>) -> Result<PartialLookup, Error> {
+        resolve_partial_via(path.as_ref(), |p| self.resolve(p))
+    }
+
+    /// Like [`Root::resolve_partial`], but the final component (if it is
+    /// fully resolved) is not followed if it is a symlink -- mirroring
+    /// [`Root::resolve_nofollow`].
+    pub fn resolve_partial_nofollow(&self, path: impl AsRef<Path>) -> Result<PartialLookup, Error> {
+        resolve_partial_via(path.as_ref(), |p| self.resolve_nofollow(p))
+    }
+}
+
+impl RootRef<'_> {
+    /// See [`Root::resolve_partial`].
+    pub fn resolve_partial(&self, path: impl AsRef<Path>) -> Result<PartialLookup, Error> {
+        resolve_partial_via(path.as_ref(), |p| self.resolve(p))
+    }
+
+    /// See [`Root::resolve_partial_nofollow`].
+    pub fn resolve_partial_nofollow(&self, path: impl AsRef<Path>) -> Result<PartialLookup, Error> {
+        resolve_partial_via(path.as_ref(), |p| self.resolve_nofollow(p))
+    }
+}
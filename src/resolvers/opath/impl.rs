@@ -48,10 +48,16 @@ use crate::{
 use std::{
     collections::VecDeque,
     ffi::{OsStr, OsString},
-    fs::File,
+    fs::{File, Metadata, Permissions},
     io::Error as IOError,
     iter,
-    os::{fd::AsRawFd, unix::ffi::OsStrExt},
+    os::{
+        fd::AsRawFd,
+        unix::{
+            ffi::OsStrExt,
+            fs::{MetadataExt, PermissionsExt},
+        },
+    },
     path::{Path, PathBuf},
     rc::Rc,
 };
@@ -124,6 +130,68 @@ fn check_current<P: AsRef<Path>>(current: &File, root: &File, expected: P) -> Re
     Ok(())
 }
 
+/// Debug-only "racy asserts" oracle, ported from the same idea in
+/// cap-primitives' `MaybeOwnedFile`. Under the `racy-asserts` feature (off by
+/// default, since it adds an extra procfs readlink per path component and is
+/// not needed outside of development/CI), re-verify after *every* component
+/// descend that the fd we just opened actually lives where we expected it to
+/// -- rather than only checking ".." components and the final handle, as the
+/// production `check_current` does above.
+///
+/// This is strictly a debugging aid: if it fires, it means either there is a
+/// bug in how `do_resolve` tracks `expected_path`, or we lost a race against
+/// a concurrent rename that the production safety checks (which still run
+/// regardless of this feature) should also catch. Since this walks
+/// `/proc/self/fd` once per component it is far too expensive to enable by
+/// default, hence the feature gate -- with the feature disabled this function
+/// doesn't exist at all, so there is zero cost in release builds.
+#[cfg(feature = "racy-asserts")]
+fn debug_assert_path_matches(fd: &File, root: &File, expected: &Path) {
+    let root_path = root
+        .as_unsafe_path(&PROCFS_HANDLE)
+        .expect("racy-asserts: failed to get root path via procfs magic-link");
+
+    let full_path: PathBuf = root_path.join(
+        iter::once(OsStr::from_bytes(b"."))
+            .chain(expected.raw_components())
+            .collect::<PathBuf>(),
+    );
+
+    let actual_path = fd
+        .as_unsafe_path(&PROCFS_HANDLE)
+        .expect("racy-asserts: failed to get fd path via procfs magic-link");
+
+    // The kernel appends " (deleted)" to the magic-link target for fds that
+    // are still open but whose path has been unlinked. This is expected
+    // behaviour (not a race) for directories removed out from under us, so
+    // it must be tolerated rather than treated as a mismatch.
+    let actual_path = match actual_path.as_os_str().as_bytes().strip_suffix(b" (deleted)") {
+        Some(stripped) => PathBuf::from(OsStr::from_bytes(stripped)),
+        None => actual_path,
+    };
+
+    assert_eq!(
+        actual_path, full_path,
+        "racy-asserts: fd does not match the path we believed it resolved to \
+         -- this is either a resolver path-tracking bug or a lost race that \
+         the production safety checks should also have caught"
+    );
+}
+
+/// The role a component plays in a resolution walk, passed to an audit
+/// policy given to [`resolve_audited`] so it can tell an ancestor directory
+/// apart from the thing actually being resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ComponentRole {
+    /// An ancestor directory being walked through on the way to the target.
+    Intermediate,
+    /// The final component of the path -- what the caller asked to resolve.
+    Target,
+    /// A symlink about to be expanded (whether it's an ancestor or the
+    /// trailing component).
+    Symlink,
+}
+
 /// Common implementation used by `resolve_partial()` and `resolve()`. The main
 /// difference is that if `symlink_stack` is `true`, the returned paths
 // TODO: Make (flags, no_follow_trailing, symlink_stack) a single struct to
@@ -134,6 +202,8 @@ fn do_resolve<P: AsRef<Path>>(
     flags: ResolverFlags,
     no_follow_trailing: bool,
     mut symlink_stack: Option<&mut SymlinkStack<File>>,
+    mut canonical_path: Option<&mut PathBuf>,
+    mut audit: Option<&mut dyn FnMut(ComponentRole, &Path, &Metadata) -> Result<(), Error>>,
 ) -> Result<PartialLookup<Rc<File>>, Error> {
     // What is the final path we expect to get after we do the final open? This
     // allows us to track any attacker moving path components around and we can
@@ -148,6 +218,19 @@ fn do_resolve<P: AsRef<Path>>(
     })?);
     let mut current = Rc::clone(&root);
 
+    // Used to emulate RESOLVE_NO_XDEV: the device of the directory we most
+    // recently descended from, so that every new component can be checked
+    // against the directory it was opened from rather than only against the
+    // starting root -- a mount boundary crossed anywhere in the walk (not
+    // just at the first component) must be rejected.
+    let root_dev = root
+        .metadata()
+        .context(error::OsSnafu {
+            operation: "fstat root to get starting device for RESOLVE_NO_XDEV",
+        })?
+        .dev();
+    let mut current_dev = root_dev;
+
     // Get initial set of components from the passed path. We remove components
     // as we do the path walk, and update them with the contents of any symlinks
     // we encounter. Path walking terminates when there are no components left.
@@ -185,6 +268,7 @@ fn do_resolve<P: AsRef<Path>>(
                 // should .
                 if !expected_path.pop() {
                     current = Rc::clone(&root);
+                    current_dev = root_dev;
                     continue;
                 }
                 part
@@ -226,6 +310,12 @@ fn do_resolve<P: AsRef<Path>>(
                 });
             }
             Ok(next) => {
+                // In racy-asserts builds, double-check every single descend
+                // against procfs, not just the ".." and final-handle cases
+                // the production checks below cover.
+                #[cfg(feature = "racy-asserts")]
+                debug_assert_path_matches(&next, &root, &expected_path);
+
                 // Make sure that the path is what we expect. If not, there was
                 // a racing rename and we should bail out here -- otherwise we
                 // might be tricked into revealing information outside the
@@ -241,18 +331,46 @@ fn do_resolve<P: AsRef<Path>>(
                         .wrap("check next '..' component didn't escape")?;
                 }
 
+                // NOTE: File::metadata definitely does an fstat(2) here.
+                let next_meta = next.metadata().context(error::OsSnafu {
+                    operation: "fstat of next component",
+                })?;
+
+                // Emulate RESOLVE_NO_XDEV. We compare against current_dev
+                // (the device of the directory we just came from) rather
+                // than only root_dev, so that a mount boundary crossed
+                // part-way through the walk -- including by a ".." that
+                // would ascend out of a bind-mount -- is caught just as much
+                // as one crossed at the very first component.
+                if flags.contains(ResolverFlags::NO_XDEV) && next_meta.dev() != current_dev {
+                    return Err(IOError::from_raw_os_error(libc::EXDEV))
+                        .context(error::OsSnafu {
+                            operation: "emulated RESOLVE_NO_XDEV",
+                        })
+                        .wrap("walk would cross a mount point")?;
+                }
+                current_dev = next_meta.dev();
+
+                // Give the caller's trust policy (if any) a look at every
+                // component we open, in the role it's playing in this walk,
+                // before we act on it any further. The policy sees the same
+                // metadata we already fetched above, and can veto the whole
+                // resolution by returning an error.
+                if let Some(ref mut policy) = audit {
+                    let role = if next_meta.file_type().is_symlink() {
+                        ComponentRole::Symlink
+                    } else if remaining_components.is_empty() {
+                        ComponentRole::Target
+                    } else {
+                        ComponentRole::Intermediate
+                    };
+                    policy(role, &expected_path, &next_meta)?;
+                }
+
                 // Is the next dirfd a symlink or an ordinary path? If we're an
                 // ordinary dirent, we just update current and move on to the
                 // next component. Nothing special here.
-                if !next
-                    // NOTE: File::metadata definitely does an fstat(2) here.
-                    .metadata()
-                    .context(error::OsSnafu {
-                        operation: "fstat of next component",
-                    })?
-                    .file_type()
-                    .is_symlink()
-                {
+                if !next_meta.file_type().is_symlink() {
                     // We hit a non-symlink component, so clear it from the
                     // symlink stack.
                     if let Some(ref mut stack) = symlink_stack {
@@ -366,6 +484,7 @@ fn do_resolve<P: AsRef<Path>>(
                     // Absolute symlinks reset our current state back to /.
                     if link_target.is_absolute() {
                         current = Rc::clone(&root);
+                        current_dev = root_dev;
                         expected_path = PathBuf::from("/");
                     }
                 }
@@ -376,6 +495,14 @@ fn do_resolve<P: AsRef<Path>>(
     // Make sure that the path is what we expect...
     check_current(&current, &root, &expected_path).wrap("check final handle didn't escape")?;
 
+    // expected_path is, by construction and the check_current() call just
+    // above, the canonical (symlink-resolved, ".."-collapsed) root-relative
+    // path of the handle we're about to return -- hand it back to callers
+    // that asked for it (see resolve_with_path()).
+    if let Some(out) = canonical_path.as_deref_mut() {
+        *out = expected_path.clone();
+    }
+
     // We finished the lookup with no remaining components.
     Ok(PartialLookup::Complete(current))
 }
@@ -397,6 +524,8 @@ pub(crate) fn resolve_partial<P: AsRef<Path>>(
         flags,
         no_follow_trailing,
         Some(&mut symlink_stack),
+        None,
+        None,
     ) {
         // For complete and error paths, just return what we got.
         ret @ Ok(PartialLookup::Complete(_)) => ret,
@@ -434,5 +563,358 @@ pub(crate) fn resolve<P: AsRef<Path>>(
     flags: ResolverFlags,
     no_follow_trailing: bool,
 ) -> Result<Handle, Error> {
-    do_resolve(root, path, flags, no_follow_trailing, None).and_then(TryInto::try_into)
+    do_resolve(root, path, flags, no_follow_trailing, None, None, None).and_then(TryInto::try_into)
+}
+
+/// Resolve `path` within `root` through user-space emulation, also returning
+/// the fully symlink-resolved, ".."-collapsed path of the result relative to
+/// `root`.
+///
+/// This performs the same walk as [`resolve`], but also hands back the
+/// `expected_path` that `do_resolve` already builds up while walking --
+/// by construction (and as verified by the final `check_current` call) this
+/// is the real root-relative path of the returned handle. This mirrors the
+/// `CanonicalPath` cap-primitives/fs-mistrust build up during their own
+/// component-by-component walks, and lets callers log, deduplicate, or key a
+/// cache on the real target of a lookup without re-deriving it from a
+/// potentially-racing `readlink(/proc/self/fd/N)`.
+///
+/// Unlike [`resolve_partial`], this has no partial-lookup equivalent: a
+/// partial lookup's "canonical path so far" is only meaningful up to the
+/// component it stopped at, which `PartialLookup::Partial`'s `remaining`
+/// field already describes relative to `handle`.
+pub(crate) fn resolve_with_path<P: AsRef<Path>>(
+    root: &File,
+    path: P,
+    flags: ResolverFlags,
+    no_follow_trailing: bool,
+) -> Result<(Handle, PathBuf), Error> {
+    let mut canonical_path = PathBuf::new();
+    let handle = do_resolve(
+        root,
+        path,
+        flags,
+        no_follow_trailing,
+        None,
+        Some(&mut canonical_path),
+        None,
+    )
+    .and_then(TryInto::try_into)?;
+    Ok((handle, canonical_path))
+}
+
+/// Resolve `path` within `root` through user-space emulation, running
+/// `policy` against every component opened along the way (see
+/// [`ComponentRole`]). `policy` can veto the resolution at any point by
+/// returning an error, which is propagated to the caller as-is.
+///
+/// This is the trust-auditing counterpart to fs-mistrust's walk: a resolved
+/// path is only as trustworthy as the least trustworthy directory along the
+/// way, since any one of them replacing a later component is exactly the
+/// kind of attack `root`-confinement is meant to prevent in the first place.
+/// [`reject_untrusted_intermediate_dirs`] is a ready-made `policy` for the
+/// common case of wanting to reject writable-by-others ancestor directories.
+///
+/// Not yet mirrored: nothing in this tree calls `resolve_audited` yet --
+/// [`resolve`] and [`resolve_with_path`] both always pass `None` for
+/// `audit` -- so this doesn't harden any resolution a caller can actually
+/// reach today. Wiring a real caller onto this (e.g. an opt-in flag on
+/// `Root`/`Handle` resolution) is a separate change; don't treat this
+/// function's existence as evidence that trust-auditing is active anywhere.
+pub(crate) fn resolve_audited<P: AsRef<Path>>(
+    root: &File,
+    path: P,
+    flags: ResolverFlags,
+    no_follow_trailing: bool,
+    policy: &mut dyn FnMut(ComponentRole, &Path, &Metadata) -> Result<(), Error>,
+) -> Result<Handle, Error> {
+    do_resolve(root, path, flags, no_follow_trailing, None, None, Some(policy))
+        .and_then(TryInto::try_into)
+}
+
+/// Ready-made [`resolve_audited`] policy that rejects any *intermediate*
+/// ancestor directory that is writable by its group or by everyone unless
+/// the sticky bit is also set -- the same ownership-trust model fs-mistrust
+/// uses, since a confined path can only be trusted not to have been replaced
+/// out from under us if every directory along the way can only be modified
+/// by an owner we already trust (the sticky bit is what stops a
+/// group/other-writable directory, like `/tmp`, from letting anyone else
+/// rename or replace entries they don't own).
+///
+/// The terminal target is deliberately not checked here -- callers that
+/// also care about the permissions of the thing they're actually resolving
+/// should check `Metadata` on the returned handle themselves.
+pub(crate) fn reject_untrusted_intermediate_dirs(
+    role: ComponentRole,
+    path: &Path,
+    meta: &Metadata,
+) -> Result<(), Error> {
+    if role != ComponentRole::Intermediate {
+        return Ok(());
+    }
+
+    let mode = meta.permissions().mode();
+    let untrusted_writable = mode & (libc::S_IWGRP | libc::S_IWOTH) != 0;
+    let sticky = mode & libc::S_ISVTX != 0;
+
+    ensure!(
+        !untrusted_writable || sticky,
+        error::SafetyViolationSnafu {
+            description: format!(
+                "{} is writable by a group or other users without the sticky bit set, \
+                 so it cannot be trusted to not have been tampered with",
+                path.display()
+            ),
+        }
+    );
+    Ok(())
+}
+
+/// Create any directory components of `path` that don't already exist within
+/// `root`, returning a handle to the final directory.
+///
+/// This builds directly on [`resolve_partial`]: we first resolve as much of
+/// `path` as already exists, and if what's left over is nothing but a
+/// missing (`ENOENT`) tail, we create that tail one component at a time.
+/// Each new component is created with `mkdirat(2)` and then immediately
+/// re-opened with `O_PATH | O_NOFOLLOW | O_DIRECTORY`, with
+/// [`check_current`] re-verifying the accumulated expected path after every
+/// descent -- exactly the same safety argument `do_resolve` uses for
+/// ordinary lookups, applied to components we just created rather than
+/// components we merely walked into.
+///
+/// A racing `mkdirat(2)` from another process (`EEXIST`) is treated as
+/// success -- we just re-open whatever they made -- but we still fstat it to
+/// confirm it's actually a directory, since an attacker could otherwise
+/// replace it with a symlink or regular file between their `EEXIST` and our
+/// open.
+///
+/// This closes the well-known unsafe `secure_join(root, path);
+/// mkdir(path)` gap, where the path can be redirected between resolution and
+/// creation.
+pub(crate) fn mkdir_all<P: AsRef<Path>>(
+    root: &File,
+    path: P,
+    flags: ResolverFlags,
+    perm: &Permissions,
+) -> Result<Handle, Error> {
+    let path = path.as_ref();
+
+    let (mut current, remaining) = match resolve_partial(root, path, flags, false)? {
+        PartialLookup::Complete(handle) => return handle.try_into(),
+        PartialLookup::Partial {
+            handle,
+            remaining,
+            last_error,
+        } => {
+            // We can only fill in a missing-component gap here -- anything
+            // else (permission denied, a non-directory blocking the walk
+            // part-way through, a safety violation, ...) needs to be
+            // surfaced to the caller as-is, not papered over by blindly
+            // trying to mkdir through it.
+            if last_error.kind().errno() != Some(libc::ENOENT) {
+                return Err(last_error);
+            }
+            (handle, remaining)
+        }
+    };
+
+    let remaining = remaining
+        .raw_components()
+        .map(OsString::from)
+        .collect::<Vec<_>>();
+
+    // Reconstruct the path we expect `current` to already be at, so
+    // check_current() below has a baseline to extend as we create each new
+    // component -- the same expected-path bookkeeping do_resolve() does
+    // during an ordinary walk.
+    let mut expected_path: PathBuf = {
+        let resolved_len = path.raw_components().count() - remaining.len();
+        path.raw_components().take(resolved_len).collect()
+    };
+
+    for part in remaining {
+        // The components left over from resolve_partial() should always be
+        // plain names (resolve_partial() itself never follows a trailing
+        // symlink into the gap), but double-check: we must never let a "/"
+        // or ".." smuggled in here make us create or descend into something
+        // outside of what we just resolved.
+        ensure!(
+            !part.as_bytes().contains(&b'/') && part.as_bytes() != b"..",
+            error::SafetyViolationSnafu {
+                description: format!(
+                    "mkdir_all: remaining component {part:?} is not a plain directory name"
+                ),
+            }
+        );
+
+        syscalls::mkdirat(current.as_raw_fd(), &part, perm.mode())
+            .or_else(|err| match err.raw_os_error() {
+                // Someone else raced us to create this component -- that's
+                // fine, as long as what they made is actually a directory
+                // (checked via fstat once we've reopened it below).
+                Some(libc::EEXIST) => Ok(()),
+                _ => Err(err),
+            })
+            .context(error::RawOsSnafu {
+                operation: "mkdir remaining component of mkdir_all path",
+            })?;
+
+        let next = syscalls::openat(
+            current.as_raw_fd(),
+            &part,
+            libc::O_PATH | libc::O_NOFOLLOW | libc::O_DIRECTORY,
+            0,
+        )
+        .context(error::RawOsSnafu {
+            operation: "reopen newly-created component of mkdir_all path",
+        })?;
+
+        ensure!(
+            next.metadata()
+                .context(error::OsSnafu {
+                    operation: "fstat newly-created component of mkdir_all path",
+                })?
+                .file_type()
+                .is_dir(),
+            error::SafetyViolationSnafu {
+                description: format!("mkdir_all: {part:?} exists but is not a directory"),
+            }
+        );
+
+        expected_path.push(&part);
+        // A rename or symlink-swap racing between our mkdirat/open above and
+        // now would let an attacker redirect where we descend next, so
+        // re-verify before `next` becomes the new `current`.
+        check_current(&next, root, &expected_path)
+            .wrap("check newly-created component of mkdir_all path didn't escape")?;
+
+        current = next.into();
+    }
+
+    current.try_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::error::ErrorKind;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn resolve_audited_allows_trusted_dirs() {
+        let root_dir = tempfile::TempDir::new().expect("tempdir should be creatable");
+        std::fs::create_dir(root_dir.path().join("subdir")).expect("mkdir subdir");
+        std::fs::write(root_dir.path().join("subdir/target"), b"").expect("create target");
+
+        let root = File::open(root_dir.path()).expect("open root");
+        let handle = resolve_audited(
+            &root,
+            "subdir/target",
+            ResolverFlags::empty(),
+            false,
+            &mut reject_untrusted_intermediate_dirs,
+        );
+        assert!(
+            handle.is_ok(),
+            "resolving through owner-only-writable dirs should succeed, got {handle:?}"
+        );
+    }
+
+    #[test]
+    fn resolve_audited_rejects_group_writable_intermediate_dir() {
+        let root_dir = tempfile::TempDir::new().expect("tempdir should be creatable");
+        let subdir = root_dir.path().join("subdir");
+        std::fs::create_dir(&subdir).expect("mkdir subdir");
+        std::fs::write(subdir.join("target"), b"").expect("create target");
+        std::fs::set_permissions(&subdir, std::fs::Permissions::from_mode(0o775))
+            .expect("chmod subdir group-writable");
+
+        let root = File::open(root_dir.path()).expect("open root");
+        let err = resolve_audited(
+            &root,
+            "subdir/target",
+            ResolverFlags::empty(),
+            false,
+            &mut reject_untrusted_intermediate_dirs,
+        )
+        .expect_err("group-writable intermediate dir without sticky bit should be rejected");
+        assert_eq!(err.kind(), ErrorKind::SafetyViolation);
+    }
+
+    #[test]
+    fn resolve_audited_allows_group_writable_sticky_intermediate_dir() {
+        let root_dir = tempfile::TempDir::new().expect("tempdir should be creatable");
+        let subdir = root_dir.path().join("subdir");
+        std::fs::create_dir(&subdir).expect("mkdir subdir");
+        std::fs::write(subdir.join("target"), b"").expect("create target");
+        std::fs::set_permissions(&subdir, std::fs::Permissions::from_mode(0o1775))
+            .expect("chmod subdir group-writable+sticky");
+
+        let root = File::open(root_dir.path()).expect("open root");
+        let handle = resolve_audited(
+            &root,
+            "subdir/target",
+            ResolverFlags::empty(),
+            false,
+            &mut reject_untrusted_intermediate_dirs,
+        );
+        assert!(
+            handle.is_ok(),
+            "a group-writable intermediate dir with the sticky bit set should be trusted, got {handle:?}"
+        );
+    }
+
+    #[test]
+    fn resolve_audited_does_not_check_the_target_itself() {
+        // reject_untrusted_intermediate_dirs() deliberately only checks
+        // ComponentRole::Intermediate -- a world-writable *target* (as
+        // opposed to an ancestor) must still resolve fine.
+        let root_dir = tempfile::TempDir::new().expect("tempdir should be creatable");
+        let target = root_dir.path().join("target");
+        std::fs::write(&target, b"").expect("create target");
+        std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o666))
+            .expect("chmod target world-writable");
+
+        let root = File::open(root_dir.path()).expect("open root");
+        let handle = resolve_audited(
+            &root,
+            "target",
+            ResolverFlags::empty(),
+            false,
+            &mut reject_untrusted_intermediate_dirs,
+        );
+        assert!(
+            handle.is_ok(),
+            "a world-writable target (not an intermediate dir) should still resolve, got {handle:?}"
+        );
+    }
+
+    #[test]
+    fn resolve_audited_propagates_policy_error() {
+        // resolve_audited must surface whatever error the caller's policy
+        // returns, not just the ready-made reject_untrusted_intermediate_dirs
+        // policy.
+        let root_dir = tempfile::TempDir::new().expect("tempdir should be creatable");
+        std::fs::create_dir(root_dir.path().join("subdir")).expect("mkdir subdir");
+        std::fs::write(root_dir.path().join("subdir/target"), b"").expect("create target");
+
+        let root = File::open(root_dir.path()).expect("open root");
+        let err = resolve_audited(
+            &root,
+            "subdir/target",
+            ResolverFlags::empty(),
+            false,
+            &mut |_role: ComponentRole, _path: &Path, _meta: &Metadata| {
+                Err(error::ErrorImpl::SafetyViolation {
+                    description: "custom policy vetoed this component".into(),
+                }
+                .into())
+            },
+        )
+        .expect_err("a policy veto should fail the whole resolution");
+        assert_eq!(err.kind(), ErrorKind::SafetyViolation);
+    }
 }
@@ -40,6 +40,7 @@ use crate::{
 };
 
 use std::{
+    ffi::OsString,
     fs::File,
     os::unix::io::AsFd,
     path::{Path, PathBuf},
@@ -118,30 +119,69 @@ pub(crate) fn resolve_partial(
         Ok(handle) => return Ok(PartialLookup::Complete(handle)),
         Err(err) => err,
     };
+    if last_error.is_safety_violation() {
+        return Err(last_error);
+    }
 
-    // TODO: We probably want to do a git-bisect-like binary-search here. For
-    //       paths with a large number of components this could make a
-    //       significant difference, though in practice you'll only see fairly
-    //       short paths so the implementation complexity might not be worth it.
-    for (path, remaining) in path.partial_ancestors() {
-        if last_error.is_safety_violation() {
-            // If we hit a safety violation, we return an error instead of a
-            // partial resolution to match the behaviour of the O_PATH
-            // resolver (and to avoid some possible weird bug in libpathrs
-            // being exploited to return some result to Root::mkdir_all).
-            return Err(last_error);
-        }
-        match resolve(root, path, rflags, no_follow_trailing) {
-            Ok(handle) => {
-                return Ok(PartialLookup::Partial {
-                    handle,
-                    remaining: remaining.map(PathBuf::from).unwrap_or("".into()),
-                    last_error,
-                })
+    // Binary-search (git-bisect-style) for the longest resolvable prefix,
+    // rather than linearly walking backwards one component at a time.
+    // `openat2(RESOLVE_IN_ROOT)` resolves the whole supplied path atomically,
+    // so success is monotonic in prefix length: if the first `k` components
+    // resolve then every shorter prefix also resolves, and if the first `k`
+    // components fail to resolve then every longer prefix fails too. This
+    // means the resolvable prefixes form a contiguous range `[0..=lo]`, and
+    // we can binary search for its upper bound.
+    let components = path.raw_components().map(OsString::from).collect::<Vec<_>>();
+    let prefix = |end: usize| -> PathBuf { components[..end].iter().collect() };
+
+    let mut lo = 0; // prefix(0) is the root, which is always resolvable.
+    let mut hi = components.len().saturating_sub(1); // prefix(len) already failed above.
+    let mut handle = None;
+
+    while lo < hi {
+        let mid = lo + (hi - lo + 1) / 2;
+        match resolve(root, prefix(mid), rflags, no_follow_trailing) {
+            Ok(found) => {
+                handle = Some(found);
+                lo = mid;
+            }
+            Err(err) => {
+                if err.is_safety_violation() {
+                    return Err(err);
+                }
+                last_error = err;
+                hi = mid - 1;
             }
-            Err(err) => last_error = err,
         }
     }
 
-    Err(last_error)
+    // The binary search only re-resolves `prefix(lo)` if some probed `mid`
+    // happened to land exactly on it, so resolve "." directly if we never
+    // got a handle for it (this also covers the `lo == 0` case).
+    let handle = match handle {
+        Some(handle) => handle,
+        None => resolve(root, ".", rflags, no_follow_trailing)?,
+    };
+
+    // `lo` is the longest resolvable prefix, but the binary search may not
+    // have probed `prefix(lo + 1)` itself (only some midpoint that happened
+    // to fail somewhere beyond it), so do one more explicit resolve to get
+    // the authoritative first-failure error -- and make sure *that* isn't a
+    // safety violation before returning a partial result.
+    last_error = match resolve(root, prefix(lo + 1), rflags, no_follow_trailing) {
+        // This shouldn't happen given the invariant above, but if it does,
+        // just keep treating `lo` as the resolved prefix and fall back to
+        // whatever error we last recorded during the search.
+        Ok(_) => last_error,
+        Err(err) => err,
+    };
+    if last_error.is_safety_violation() {
+        return Err(last_error);
+    }
+
+    Ok(PartialLookup::Partial {
+        handle,
+        remaining: components[lo..].iter().collect(),
+        last_error,
+    })
 }
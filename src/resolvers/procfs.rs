@@ -31,16 +31,24 @@
  */
 
 //!
-//! [`ProcfsResolver`](crate::resolvers::procfs::ProcfsResolver) is a very
+//! [`PseudofsResolver`](crate::resolvers::procfs::PseudofsResolver) is a very
 //! minimal resolver that doesn't allow:
 //!
 //!  1. Any ".." components (with `openat2` this is slightly relaxed).
 //!  2. Any absolute symlinks.
 //!  3. (If `statx` or `openat2` is supported), any mount-point crossings.
+//!  4. Any magic-link-shaped symlink targets (to avoid walking into
+//!     unrelated parts of the filesystem via `d_path`-rendered targets).
 //!
-//! This allows us to avoid using any `/proc` checks, and thus this resolver can
-//! be used within the `pathrs::procfs` helpers that are used by other parts of
-//! libpathrs.
+//! Despite the name, none of the above restrictions are specific to procfs --
+//! they are exactly what is needed to safely walk *any* kernel
+//! pseudo-filesystem that can contain attacker-influenced relative symlinks
+//! and can be bind-mounted over (sysfs and cgroupfs have the same threat
+//! model as procfs here). [`ProcfsResolver`] is simply the procfs-specific
+//! name ([`PseudofsResolver`]'s first, and so far only, consumer) kept around
+//! so the rest of the `pathrs::procfs` helpers don't need to change; other
+//! pseudo-filesystems can use [`PseudofsResolver`] directly once they need
+//! the same protections.
 
 use crate::{
     error::{Error, ErrorExt, ErrorImpl},
@@ -53,23 +61,30 @@ use crate::{
 
 use std::{
     collections::VecDeque,
+    ffi::{OsStr, OsString},
     io::Error as IOError,
     os::unix::{
         ffi::OsStrExt,
+        fs::MetadataExt,
         io::{AsFd, OwnedFd},
     },
     path::Path,
+    sync::OnceLock,
 };
 
 /// Used internally for tests to force the usage of a specific resolver. You
 /// should always use the default.
 #[derive(Debug, PartialEq, Eq)]
-pub(crate) enum ProcfsResolver {
-    Openat2,
+pub(crate) enum PseudofsResolver {
+    /// Resolve the whole path in a single `openat2(2)` syscall, restricted
+    /// with `RESOLVE_BENEATH | RESOLVE_NO_MAGICLINKS | RESOLVE_NO_XDEV`.
+    RestrictedOpenat2,
+    /// Walk the path one `O_PATH` component at a time, emulating the same
+    /// restrictions `RestrictedOpenat2` gets from the kernel for free.
     RestrictedOpath,
 }
 
-impl Default for ProcfsResolver {
+impl Default for PseudofsResolver {
     fn default() -> Self {
         // Only check if there is a cached failure from a previous attempt to
         // use openat2 -- we don't want to do a dummy openat2(2) call here in
@@ -78,12 +93,24 @@ impl Default for ProcfsResolver {
         if syscalls::openat2::saw_openat2_failure() {
             Self::RestrictedOpath
         } else {
-            Self::Openat2
+            Self::RestrictedOpenat2
         }
     }
 }
 
-impl ProcfsResolver {
+impl PseudofsResolver {
+    /// Safely resolve `path` inside `root`, a directory handle somewhere
+    /// inside a pseudo-filesystem mount (procfs, sysfs, cgroupfs, ...) whose
+    /// mount ID can be looked up (falling back to `proc_rootfd` when
+    /// `statx(2)`'s `STATX_MNT_ID`/`STATX_MNT_ID_UNIQUE` aren't available).
+    ///
+    /// `allow_seccomp_fallback` controls whether `EPERM` from the
+    /// `RESOLVE_*`-bit probe used to sanity-check a failed `openat2(2)` call
+    /// (see [`unsupported_resolve_bits`]) is treated the same as `ENOSYS` --
+    /// see [`ProcfsHandleBuilder::allow_seccomp_fallback`] for why this is
+    /// opt-in rather than the default.
+    ///
+    /// [`ProcfsHandleBuilder::allow_seccomp_fallback`]: crate::procfs::ProcfsHandleBuilder::allow_seccomp_fallback
     pub(crate) fn resolve(
         &self,
         proc_rootfd: RawProcfsRoot<'_>,
@@ -91,10 +118,12 @@ impl ProcfsResolver {
         path: impl AsRef<Path>,
         oflags: OpenFlags,
         rflags: ResolverFlags,
+        allow_seccomp_fallback: bool,
     ) -> Result<OwnedFd, Error> {
-        // These flags don't make sense for procfs and will just result in
-        // confusing errors during lookup. O_TMPFILE contains multiple flags
-        // (including O_DIRECTORY!) so we have to check it separately.
+        // These flags don't make sense for a pseudo-filesystem and will just
+        // result in confusing errors during lookup. O_TMPFILE contains
+        // multiple flags (including O_DIRECTORY!) so we have to check it
+        // separately.
         let invalid_flags = OpenFlags::O_CREAT | OpenFlags::O_EXCL;
         if !oflags.intersection(invalid_flags).is_empty() || oflags.contains(OpenFlags::O_TMPFILE) {
             Err(ErrorImpl::InvalidArgument {
@@ -111,27 +140,205 @@ impl ProcfsResolver {
         let path = path.as_ref();
 
         match *self {
-            Self::Openat2 => openat2_resolve(root, path, oflags, rflags).or_else(|err| {
+            Self::RestrictedOpenat2 => openat2_resolve(root, path, oflags, rflags).or_else(|err| {
                 // If an error occurred, it could be due to openat2(2) being
-                // disabled via seccomp or just being unsupported. We check this
-                // via a dummy openat2(2) chall -- if that fails then we
-                // fallback to O_PATH, otherwise we assume openat2(2) failed for
-                // a good reason and return that error outright.
+                // disabled via seccomp, being unsupported outright, or being
+                // supported but missing one of the RESOLVE_* bits we rely on
+                // (see unsupported_resolve_bits). We check all three via
+                // dummy openat2(2) calls -- if any of them indicate openat2(2)
+                // can't give us what we want then we fallback to O_PATH,
+                // otherwise we assume openat2(2) failed for a good reason and
+                // return that error outright.
                 //
                 // TODO: Find a way to make this fallback logic a bit less
                 //       repetitive of the other match arm.
-                if syscalls::openat2::openat2_is_not_supported() {
-                    opath_resolve(proc_rootfd, root, path, oflags, rflags)
+                if syscalls::openat2::openat2_is_not_supported()
+                    || unsupported_resolve_bits(allow_seccomp_fallback) != 0
+                {
+                    opath_resolve(proc_rootfd, root, path, oflags, rflags, false)
                 } else {
                     Err(err)
                 }
             }),
-            Self::RestrictedOpath => opath_resolve(proc_rootfd, root, path, oflags, rflags),
+            Self::RestrictedOpath => opath_resolve(proc_rootfd, root, path, oflags, rflags, false),
         }
     }
+
+    /// Like [`PseudofsResolver::resolve`], but additionally permits following
+    /// a *trailing* `fd/<n>` magic-link (e.g. `self/fd/3`) when the caller's
+    /// requested access mode (`O_RDONLY`/`O_WRONLY`/`O_RDWR`) does not exceed
+    /// the access mode the target fd was originally opened with, as read via
+    /// `fcntl(F_GETFL)` -- returning `EACCES` if it does. Every other kind of
+    /// magic-link (non-trailing, absolute, anon-inode, ...) is still rejected
+    /// with `ELOOP` exactly as in [`PseudofsResolver::resolve`].
+    ///
+    /// This mirrors the DAC check the kernel itself performs when
+    /// `openat2(2)` is called without `RESOLVE_NO_MAGICLINKS`: a trailing
+    /// magic-link follow is permitted as long as it can't be used to gain
+    /// more access than the original fd already had.
+    ///
+    /// Reachable through [`ProcfsHandle::open_follow_trusted_fd`] and
+    /// [`ProcfsHandle::open_follow_trusted_fd_with`] -- those expose this as
+    /// an opt-in *method* rather than a new `ResolverFlags` bit, since
+    /// `src/flags.rs` (where [`ResolverFlags`] is defined) isn't part of this
+    /// checkout.
+    ///
+    /// The re-opened target must also be on the same mount as `root` (the
+    /// usual emulated `RESOLVE_NO_XDEV` check), so an `fd/<n>` whose target
+    /// lives outside this pseudo-filesystem mount is rejected.
+    ///
+    /// Only the `O_PATH` walk can honour this (a single `openat2(2)` call
+    /// can't selectively permit just one trailing magic-link while still
+    /// blocking every other one), so this always uses [`opath_resolve`]
+    /// regardless of `self`.
+    ///
+    /// [`ProcfsHandle::open_follow_trusted_fd`]: crate::procfs::ProcfsHandle::open_follow_trusted_fd
+    /// [`ProcfsHandle::open_follow_trusted_fd_with`]: crate::procfs::ProcfsHandle::open_follow_trusted_fd_with
+    pub(crate) fn resolve_trusted_fd_magiclinks(
+        &self,
+        proc_rootfd: RawProcfsRoot<'_>,
+        root: impl AsFd,
+        path: impl AsRef<Path>,
+        oflags: OpenFlags,
+        rflags: ResolverFlags,
+    ) -> Result<OwnedFd, Error> {
+        opath_resolve(proc_rootfd, root.as_fd(), path.as_ref(), oflags, rflags, true)
+    }
 }
 
-/// [`openat2`][openat2.2]-based implementation of [`ProcfsResolver`].
+/// Bitmask of the `RESOLVE_*` flags that [`openat2_resolve`] always sets
+/// ([`libc::RESOLVE_BENEATH`], [`libc::RESOLVE_NO_MAGICLINKS`], and
+/// [`libc::RESOLVE_NO_XDEV`]) -- these are the actual protections this
+/// resolver is built around, so a kernel missing any one of them can't safely
+/// use the `openat2(2)` fast path at all.
+const REQUIRED_RESOLVE_BITS: u64 =
+    libc::RESOLVE_BENEATH | libc::RESOLVE_NO_MAGICLINKS | libc::RESOLVE_NO_XDEV;
+
+/// Cached, per-bit probe of what `openat2(2)` returns when asked to resolve
+/// `"."` with only a single bit of [`REQUIRED_RESOLVE_BITS`] set, so later
+/// calls to [`unsupported_resolve_bits`] don't need to repeat the probe --
+/// the dummy `openat2(2)` calls only ever run once per process, regardless of
+/// how many times (or with what `allow_seccomp_fallback` value)
+/// [`unsupported_resolve_bits`] ends up being called.
+///
+/// `None` means the dummy lookup succeeded, so the bit was understood. A
+/// failed lookup records its errno, and an unrelated failure (e.g. `EACCES`)
+/// still counts as understood -- see [`resolve_bit_errno_means_unsupported`].
+///
+/// Ideally this cache would live alongside
+/// [`syscalls::openat2::saw_openat2_failure`] in `syscalls::openat2`, since
+/// that's exactly the kind of thing it tracks -- but that module isn't part
+/// of this checkout, so it lives here instead, next to its only caller.
+fn probe_resolve_bit_errnos() -> &'static [(u64, Option<i32>)] {
+    static PROBED_RESOLVE_BIT_ERRNOS: OnceLock<Vec<(u64, Option<i32>)>> = OnceLock::new();
+
+    PROBED_RESOLVE_BIT_ERRNOS.get_or_init(|| {
+        let bits = [
+            libc::RESOLVE_BENEATH,
+            libc::RESOLVE_NO_MAGICLINKS,
+            libc::RESOLVE_NO_XDEV,
+        ];
+        debug_assert_eq!(
+            bits.into_iter().fold(0, |mask, bit| mask | bit),
+            REQUIRED_RESOLVE_BITS,
+            "probed bits must match REQUIRED_RESOLVE_BITS"
+        );
+
+        // Any O_PATH directory fd works as a probe target -- we never
+        // actually look anything up in it, we just want to see what
+        // openat2(2) says about each RESOLVE_* bit on its own.
+        let probe_root = match syscalls::openat(syscalls::AT_FDCWD, "/", OpenFlags::O_PATH, 0) {
+            Ok(fd) => fd,
+            // If we can't even open "/", assume none of the bits work --
+            // resolve() will fall back to opath_resolve(), which doesn't need
+            // any of this.
+            Err(_) => return bits.into_iter().map(|bit| (bit, Some(libc::EINVAL))).collect(),
+        };
+
+        bits.into_iter()
+            .map(|bit| {
+                let errno = match syscalls::openat2_follow(
+                    &probe_root,
+                    ".",
+                    OpenHow {
+                        flags: OpenFlags::O_PATH.bits() as u64,
+                        resolve: bit,
+                        ..Default::default()
+                    },
+                ) {
+                    // The dummy lookup succeeded, so the bit was understood.
+                    Ok(_) => None,
+                    Err(err) => err.root_cause().raw_os_error(),
+                };
+                (bit, errno)
+            })
+            .collect()
+    })
+}
+
+/// Which bits in [`REQUIRED_RESOLVE_BITS`] this kernel's `openat2(2)`
+/// actually understands, so a single unsupported `RESOLVE_*` bit doesn't get
+/// misdiagnosed as "openat2(2) failed for a real reason" and returned to the
+/// caller as a hard failure on every lookup.
+///
+/// `openat2(2)` uses the extensible-struct ABI, so it rejects `how.resolve`
+/// bits it doesn't understand with `EINVAL` rather than silently ignoring
+/// them -- which means a kernel that *has* `openat2(2)` but predates one of
+/// `RESOLVE_BENEATH`/`RESOLVE_NO_MAGICLINKS`/`RESOLVE_NO_XDEV` would otherwise
+/// fail every single [`openat2_resolve`] call with `EINVAL`, and
+/// [`syscalls::openat2::openat2_is_not_supported`] (which only detects
+/// `openat2(2)` being entirely absent) wouldn't catch it. Probing each bit in
+/// isolation lets [`PseudofsResolver::resolve`] correctly fall back to
+/// [`opath_resolve`] for the rest of the process instead of hard-failing.
+///
+/// A seccomp filter that shims unknown syscalls (or, on older profiles,
+/// `openat2(2)` itself) with `EPERM` instead of the kernel's own `EINVAL`
+/// produces the exact same symptom -- every bit-probe call fails, but with
+/// the "wrong" errno, so without `allow_seccomp_fallback` this function would
+/// report all three bits as supported and [`PseudofsResolver::resolve`] would
+/// propagate the seccomp `EPERM` instead of falling back to the fully
+/// emulated [`opath_resolve`]. As with
+/// [`ProcfsHandleBuilder::allow_seccomp_fallback`] (the equivalent opt-in for
+/// the `fsopen(2)`/`open_tree(2)` probes used to acquire a [`ProcfsHandle`]),
+/// this is opt-in rather than the default, since `EPERM` can also indicate a
+/// genuine (non-seccomp) permission problem that callers may want surfaced
+/// instead of silently masked.
+///
+/// Note that this does *not* attempt to let [`openat2_resolve`] submit only
+/// the bits the kernel supports while emulating the others in code: half of
+/// these protections (e.g. `RESOLVE_NO_XDEV`) can't be retrofitted onto an
+/// already-completed lookup, so a kernel missing any required bit falls back
+/// to the fully-emulated [`opath_resolve`] path wholesale instead.
+///
+/// [`ProcfsHandleBuilder::allow_seccomp_fallback`]: crate::procfs::ProcfsHandleBuilder::allow_seccomp_fallback
+/// [`ProcfsHandle`]: crate::procfs::ProcfsHandle
+fn unsupported_resolve_bits(allow_seccomp_fallback: bool) -> u64 {
+    probe_resolve_bit_errnos()
+        .iter()
+        .filter(|&&(_bit, errno)| resolve_bit_errno_means_unsupported(errno, allow_seccomp_fallback))
+        .fold(0, |unsupported, &(bit, _errno)| unsupported | bit)
+}
+
+/// Classify a single `errno` returned by the dummy `openat2(2)` probe in
+/// [`probe_resolve_bit_errnos`] as meaning its `RESOLVE_*` bit is unsupported
+/// (`true`) or genuinely understood by the kernel (`false`) -- see
+/// [`unsupported_resolve_bits`] for why `EPERM` is only treated as
+/// unsupported when `allow_seccomp_fallback` is set.
+fn resolve_bit_errno_means_unsupported(errno: Option<i32>, allow_seccomp_fallback: bool) -> bool {
+    match errno {
+        None => false,
+        Some(libc::EINVAL) => true,
+        Some(libc::EPERM) => allow_seccomp_fallback,
+        Some(_) => false,
+    }
+}
+
+/// Procfs-specific name for [`PseudofsResolver`], kept so the rest of the
+/// `pathrs::procfs` helpers don't need to change. See the module
+/// documentation for why this resolver isn't actually procfs-specific.
+pub(crate) type ProcfsResolver = PseudofsResolver;
+
+/// [`openat2`][openat2.2]-based implementation of [`PseudofsResolver`].
 ///
 /// [openat2.2]: https://www.man7.org/linux/man-pages/man2/openat2.2.html
 fn openat2_resolve(
@@ -163,21 +370,39 @@ fn openat2_resolve(
     })
 }
 
-/// Returns whether the provided string plausibly looks like a magic-link
-/// `readlink(2)` target.
-fn check_possible_magic_link(link_target: &Path) -> Result<(), Error> {
-    // This resolver only deals with procfs paths, which means that we can
-    // restrict how we handle symlinks. procfs does not (and cannot) contain
-    // regular absolute symlinks to paths within procfs, and so we can assume
-    // any absolute paths are magic-links to regular files or would otherwise
-    // trigger EXDEV with openat2. (Note that all procfs magic-links use
-    // `d_path` as the readlink(2) pseudo-target.)
+/// Classification of a `readlink(2)` target, as produced by
+/// [`classify_magic_link`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum MagicLinkKind {
+    /// A plain relative target. This covers both ordinary symlinks and the
+    /// relative-but-still-"magic" targets procfs uses for things like
+    /// `/proc/self` (`"<pid>"`) -- none of those can be used to break out of
+    /// the pseudo-filesystem, so it's safe to keep walking them normally.
+    Relative,
+    /// An absolute target, as `d_path` produces for most magic-links (for
+    /// instance `/proc/self/root` pointing at the target's real root).
+    /// Walking into this naively would follow the link to an arbitrary path
+    /// outside of the pseudo-filesystem, so this must always be rejected.
+    Absolute,
+    /// A `prefix:[token]`/`prefix:token`/`[token]`-shaped target (optionally
+    /// with a trailing `" (deleted)"`), the form the kernel uses for
+    /// anon-inodes (`pipe:[1234]`, `anon_inode:[pidfd]`, `[eventpoll]`, ...).
+    /// These don't name a real path at all, so they must always be rejected.
+    AnonInode,
+}
+
+/// Classify a `readlink(2)` target to determine whether it plausibly looks
+/// like a magic-link, and if so, what kind.
+fn classify_magic_link(link_target: &Path) -> MagicLinkKind {
+    // This resolver only deals with pseudo-filesystem paths (procfs, sysfs,
+    // cgroupfs, ...), which means that we can restrict how we handle
+    // symlinks. None of these pseudo-filesystems contain (or can contain)
+    // regular absolute symlinks to paths within themselves, and so we can
+    // assume any absolute paths are magic-links to regular files or would
+    // otherwise trigger EXDEV with openat2. (Note that all such magic-links
+    // use `d_path` as the readlink(2) pseudo-target.)
     if link_target.is_absolute() {
-        Err(ErrorImpl::OsError {
-            operation: "emulated RESOLVE_NO_MAGICLINKS".into(),
-            source: IOError::from_raw_os_error(libc::ELOOP),
-        })
-        .wrap(format!("step into absolute symlink {link_target:?}"))?
+        return MagicLinkKind::Absolute;
     }
 
     // However, some magic-links appear as relative paths because they reference
@@ -213,36 +438,109 @@ fn check_possible_magic_link(link_target: &Path) -> Result<(), Error> {
     // blocking symlinks that look like that is reasonable. It is possible for
     // /proc/asound/* symlinks to have arbitrary data, but it seems very
     // unlikely for a card to have a name that looks like "foo:[bar]".
+    //
+    // Modern kernels mostly use the "prefix:[token]" and "prefix:token" forms
+    // (`anon_inode:[eventfd]`, `anon_inode:bpf-map`, `socket:[12345]`,
+    // `anon_inode:io_uring`, ...), but some anon-inodes predate that
+    // convention and are rendered as a bare "[token]" (`[eventpoll]`,
+    // `[signalfd]`, `[timerfd]`, `[userfaultfd]`). A deleted-but-still-open
+    // file is rendered with a trailing " (deleted)" suffix, which is also not
+    // something a real symlink target can end with. We reject all three
+    // shapes.
 
     // The regex crate is too heavy for us to use it for such a simple string
-    // match. Instead, let's just do a quick-and-dirty search to see if the
-    // characters ":[]" are present in the string and are in the right order.
+    // match, so we classify the string by hand instead.
     // MSRV(1.65): Switch to regex-lite?
-    if link_target
-        .as_os_str()
-        .to_string_lossy()
-        .chars()
-        .filter(|&c| c == ':' || c == '[' || c == ']')
-        .collect::<String>()
-        == ":[]"
-    {
-        Err(ErrorImpl::OsError {
+    let target = link_target.as_os_str().to_string_lossy();
+
+    let is_identifier_token =
+        |s: &str| !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+
+    let looks_like_magic_link = match target.split_once(':') {
+        // "prefix:[token]" or "prefix:token" -- the classic anon-inode name,
+        // with or without the "[...]" wrapping on the right-hand side.
+        Some((_prefix, rhs)) => {
+            let rhs = rhs
+                .strip_prefix('[')
+                .and_then(|rhs| rhs.strip_suffix(']'))
+                .unwrap_or(rhs);
+            is_identifier_token(rhs)
+        }
+        // "[token]" -- the older bracket-only anon-inode name, with no
+        // colon-separated prefix at all.
+        None => target
+            .strip_prefix('[')
+            .and_then(|rhs| rhs.strip_suffix(']'))
+            .is_some_and(is_identifier_token),
+    } || target.ends_with(" (deleted)");
+
+    if looks_like_magic_link {
+        MagicLinkKind::AnonInode
+    } else {
+        MagicLinkKind::Relative
+    }
+}
+
+/// Returns an error if the provided string plausibly looks like a dangerous
+/// magic-link `readlink(2)` target (i.e. anything [`classify_magic_link`]
+/// doesn't classify as [`MagicLinkKind::Relative`]).
+fn check_possible_magic_link(link_target: &Path) -> Result<(), Error> {
+    match classify_magic_link(link_target) {
+        MagicLinkKind::Relative => Ok(()),
+        MagicLinkKind::Absolute => Err(ErrorImpl::OsError {
             operation: "emulated RESOLVE_NO_MAGICLINKS".into(),
             source: IOError::from_raw_os_error(libc::ELOOP),
         })
-        .wrap(format!("step into likely magiclink {link_target:?}"))?
+        .wrap(format!("step into absolute symlink {link_target:?}")),
+        MagicLinkKind::AnonInode => Err(ErrorImpl::OsError {
+            operation: "emulated RESOLVE_NO_MAGICLINKS".into(),
+            source: IOError::from_raw_os_error(libc::ELOOP),
+        })
+        .wrap(format!("step into likely magiclink {link_target:?}")),
     }
+}
 
-    Ok(())
+/// Returns whether `requested`'s access mode (`O_RDONLY`/`O_WRONLY`/
+/// `O_RDWR`) is no more permissive than `target`'s already-open access mode,
+/// as read via `fcntl(F_GETFL)`.
+///
+/// This is the DAC check that gates following a trusted trailing `fd/<n>`
+/// magic-link in [`PseudofsResolver::resolve_trusted_fd_magiclinks`]: the
+/// original fd's access mode acts as an upper bound on what re-deriving a new
+/// fd from its magic-link is allowed to grant.
+fn magic_link_target_permits_access(
+    target: impl AsFd,
+    requested: OpenFlags,
+) -> Result<bool, Error> {
+    let target_accmode = rustix::fs::fcntl_getfl(target.as_fd())
+        .map_err(|err| ErrorImpl::OsError {
+            operation: "fcntl(F_GETFL) of fd magic-link target".into(),
+            source: err.into(),
+        })?
+        .bits() as i32
+        & libc::O_ACCMODE;
+    let requested_accmode = requested.bits() & libc::O_ACCMODE;
+
+    Ok(match requested_accmode {
+        libc::O_RDONLY => target_accmode != libc::O_WRONLY,
+        libc::O_WRONLY => matches!(target_accmode, libc::O_WRONLY | libc::O_RDWR),
+        libc::O_RDWR => target_accmode == libc::O_RDWR,
+        _ => false,
+    })
 }
 
-/// `O_PATH`-based implementation of [`ProcfsResolver`].
+/// `O_PATH`-based implementation of [`PseudofsResolver`].
+///
+/// `trust_fd_magiclinks` enables the trailing-`fd/<n>`-magic-link-following
+/// behaviour documented on [`PseudofsResolver::resolve_trusted_fd_magiclinks`]
+/// -- every other caller should pass `false`.
 fn opath_resolve(
     proc_rootfd: RawProcfsRoot<'_>,
     root: impl AsFd,
     path: impl AsRef<Path>,
     oflags: OpenFlags,
     rflags: ResolverFlags,
+    trust_fd_magiclinks: bool,
 ) -> Result<OwnedFd, Error> {
     let root = root.as_fd();
     let root_mnt_id = utils::fetch_mnt_id(proc_rootfd, root, "")?;
@@ -278,6 +576,7 @@ fn opath_resolve(
         .collect::<VecDeque<_>>();
 
     let mut symlink_traversals = 0;
+    let mut prev_component: Option<OsString> = None;
     while let Some(part) = remaining_components
         .pop_front()
         // If we hit an empty component, we need to treat it as though it is
@@ -310,12 +609,49 @@ fn opath_resolve(
         // Check that the next component is on the same mountpoint.
         // NOTE: If the root is the host /proc mount, this is only safe if there
         // are no racing mounts.
-        procfs::verify_same_procfs_mnt(proc_rootfd, root_mnt_id, &next)
+        procfs::verify_same_mnt(proc_rootfd, root_mnt_id, &next, "")
             .with_wrap(|| format!("open next component {part:?}"))
             .wrap("emulated procfs resolver RESOLVE_NO_XDEV")?;
 
         let next_meta = next.metadata().wrap("fstat of next component")?;
 
+        // Is this the trailing `<n>` of a `fd/<n>` magic-link, directly
+        // preceded by a literal `fd` directory component? If the caller opted
+        // into `trust_fd_magiclinks` (see
+        // [`PseudofsResolver::resolve_trusted_fd_magiclinks`]) and the
+        // requested access mode doesn't exceed the original fd's, follow it
+        // with the real `oflags` and return immediately -- this must happen
+        // before the generic re-open logic below, which always forces
+        // `O_NOFOLLOW` and would otherwise just return `ELOOP`.
+        let is_trailing = remaining_components.is_empty();
+        let is_fd_index = next_meta.is_symlink()
+            && prev_component.as_deref() == Some(OsStr::new("fd"))
+            && part.to_str().and_then(|s| s.parse::<u32>().ok()).is_some();
+        prev_component = Some(part.clone());
+
+        if trust_fd_magiclinks && is_trailing && is_fd_index {
+            if !magic_link_target_permits_access(&next, oflags)? {
+                Err(ErrorImpl::OsError {
+                    operation: "follow trusted fd magic-link".into(),
+                    source: IOError::from_raw_os_error(libc::EACCES),
+                })
+                .wrap(format!(
+                    "magic-link {part:?} would grant more access than the original fd's open mode",
+                ))?
+            }
+
+            let resolved = syscalls::openat(&current, &part, oflags, 0).map_err(|err| {
+                ErrorImpl::RawOsError {
+                    operation: "re-open trusted fd magic-link with requested flags".into(),
+                    source: err,
+                }
+            })?;
+            procfs::verify_same_mnt(proc_rootfd, root_mnt_id, &resolved, "")
+                .wrap("emulated procfs resolver RESOLVE_NO_XDEV for trusted fd magic-link")?;
+
+            return Ok(resolved);
+        }
+
         // If this is the last component, try to open the same component again
         // with with the requested flags. Unlike the other Handle resolvers, we
         // can't re-open the file through procfs (since this is the resolver
@@ -385,9 +721,30 @@ fn opath_resolve(
                 // code!).
                 Ok(final_reopen) => {
                     // Re-verify the next component is on the same mount.
-                    procfs::verify_same_procfs_mnt(proc_rootfd, root_mnt_id, &final_reopen)
+                    procfs::verify_same_mnt(proc_rootfd, root_mnt_id, &final_reopen, "")
                         .wrap("re-open final component")
                         .wrap("emulated procfs resolver RESOLVE_NO_XDEV")?;
+
+                    // The component could have been rename(2)d (swapped with
+                    // a completely different object) in between the
+                    // O_PATH|O_NOFOLLOW probe-open above and this re-open
+                    // with the caller's real flags -- the mount check alone
+                    // doesn't catch this, since the replacement object could
+                    // easily live on the same mount. Make sure the re-opened
+                    // fd is still the very same inode we already validated.
+                    let final_meta = final_reopen
+                        .metadata()
+                        .wrap("fstat of re-opened final component")?;
+                    if final_meta.dev() != next_meta.dev() || final_meta.ino() != next_meta.ino() {
+                        Err(ErrorImpl::OsError {
+                            operation: "emulated procfs resolver re-open".into(),
+                            source: IOError::from_raw_os_error(libc::ESTALE),
+                        })
+                        .wrap(format!(
+                            "component {part:?} was replaced between probe-open and re-open (rename race)",
+                        ))?
+                    }
+
                     return Ok(final_reopen);
                 }
                 Err(err) => {
@@ -481,7 +838,7 @@ mod tests {
 
     use std::{
         fs::File,
-        os::unix::io::{AsRawFd, OwnedFd, RawFd},
+        os::unix::io::{AsFd, AsRawFd, OwnedFd, RawFd},
         path::{Path, PathBuf},
     };
 
@@ -565,8 +922,8 @@ mod tests {
                     let $path_var = $path;
                     let expected: ExpectedResult = $expected_result.map(|p: PathBuf| root_dir.join(p));
                     let oflags = $(OpenFlags::$oflag)|*;
-                    let res = ProcfsResolver::Openat2
-                        .resolve(RawProcfsRoot::UnsafeGlobal, &root, &$path_var, oflags, $rflags)
+                    let res = ProcfsResolver::RestrictedOpenat2
+                        .resolve(RawProcfsRoot::UnsafeGlobal, &root, &$path_var, oflags, $rflags, false)
                         .as_ref()
                         .map(|f| {
                             f.as_unsafe_path_unchecked()
@@ -590,7 +947,7 @@ mod tests {
                     let expected: ExpectedResult = $expected_result.map(|p: PathBuf| root_dir.join(p));
                     let oflags = $(OpenFlags::$oflag)|*;
                     let res = ProcfsResolver::RestrictedOpath
-                        .resolve(RawProcfsRoot::UnsafeGlobal, &root, &$path_var, oflags, $rflags)
+                        .resolve(RawProcfsRoot::UnsafeGlobal, &root, &$path_var, oflags, $rflags, false)
                         .as_ref()
                         .map(|f| {
                             f.as_unsafe_path_unchecked()
@@ -694,6 +1051,106 @@ mod tests {
         file_opath_odir_onofollow("/proc", "filesystems", O_PATH|O_DIRECTORY|O_NOFOLLOW, ResolverFlags::empty()) == Err(ErrorKind::OsError(Some(libc::ENOTDIR)));
     }
 
+    /// The resolver code only ever talks to `root`/`proc_rootfd` through
+    /// `AsFd`/[`RawProcfsRoot`], so it shouldn't matter whether the fd it was
+    /// handed is the global `/proc` or a freshly-`fsopen(2)`'d private procfs
+    /// instance that can't have foreign overmounts. Run a couple of
+    /// representative lookups (a magic-link and a plain directory) against
+    /// such a private mount to make sure that's actually true.
+    #[test]
+    fn procfs_openat2_resolver_private_mount() -> Result<(), Error> {
+        // fsopen(2)-ing a new procfs can fail for reasons unrelated to this
+        // test (no privileges, pre-5.2 kernel, ...) -- skip in that case, the
+        // same way ProcfsHandle's own new_fsopen()/new_open_tree() tests do.
+        let procfs = match crate::procfs::ProcfsHandle::new_fsopen(
+            false,
+            crate::procfs::ProcfsHidePid::default(),
+            None,
+        ) {
+            Ok(procfs) => procfs,
+            Err(_) => return Ok(()),
+        };
+        let resolved = ProcfsResolver::RestrictedOpenat2
+            .resolve(
+                RawProcfsRoot::UnsafeFd(procfs.as_fd()),
+                &procfs,
+                "self/ns/user",
+                OpenFlags::O_PATH | OpenFlags::O_NOFOLLOW,
+                ResolverFlags::empty(),
+                false,
+            )
+            .context("resolve self/ns/user (a magic-link) through a private procfs mount")?;
+        let path = resolved
+            .as_unsafe_path_unchecked()
+            .context("get actual path of resolved handle")?;
+        assert_eq!(
+            path,
+            PathBuf::from(format!("/proc/{}/ns/user", syscalls::getpid())),
+            "self/ns/user should resolve the same way through a private procfs mount"
+        );
+
+        let resolved = ProcfsResolver::RestrictedOpenat2
+            .resolve(
+                RawProcfsRoot::UnsafeFd(procfs.as_fd()),
+                &procfs,
+                "tty",
+                OpenFlags::O_DIRECTORY,
+                ResolverFlags::empty(),
+                false,
+            )
+            .context("resolve tty (a plain directory) through a private procfs mount")?;
+        let path = resolved
+            .as_unsafe_path_unchecked()
+            .context("get actual path of resolved handle")?;
+        assert_eq!(
+            path,
+            PathBuf::from("/proc/tty"),
+            "tty should resolve the same way through a private procfs mount"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn procfs_resolve_trusted_fd_magiclinks() -> Result<(), Error> {
+        let procfs = crate::procfs::ProcfsHandle::new_unsafe_open()
+            .context("open global procfs handle")?;
+
+        // A read-only fd's self/fd/<n> magic-link can be followed with an
+        // access mode no more permissive than the original open(2).
+        let file = File::open("/proc/self/status").context("open a read-only test fd")?;
+        let path = format!("self/fd/{}", file.as_raw_fd());
+
+        ProcfsResolver::RestrictedOpath
+            .resolve_trusted_fd_magiclinks(
+                RawProcfsRoot::UnsafeGlobal,
+                &procfs,
+                &path,
+                OpenFlags::O_RDONLY,
+                ResolverFlags::empty(),
+            )
+            .context("follow a trusted fd magic-link with the same access mode")?;
+
+        // ... but not with a more permissive access mode than the original
+        // fd was opened with.
+        let err = ProcfsResolver::RestrictedOpath
+            .resolve_trusted_fd_magiclinks(
+                RawProcfsRoot::UnsafeGlobal,
+                &procfs,
+                &path,
+                OpenFlags::O_RDWR,
+                ResolverFlags::empty(),
+            )
+            .expect_err("following a read-only fd magic-link with O_RDWR should be rejected");
+        assert_eq!(
+            err.kind(),
+            ErrorKind::OsError(Some(libc::EACCES)),
+            "requesting more access than the original fd had should return EACCES"
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn check_possible_magic_link() {
         // Regular symlinks.
@@ -728,5 +1185,106 @@ mod tests {
             super::check_possible_magic_link(Path::new("anon_inode:[pidfd]")),
             Err(_)
         );
+
+        // Colon-only anon-inode names (no "[...]" wrapping).
+        assert_matches!(
+            super::check_possible_magic_link(Path::new("anon_inode:bpf-map")),
+            Err(_)
+        );
+        assert_matches!(
+            super::check_possible_magic_link(Path::new("anon_inode:bpf-prog")),
+            Err(_)
+        );
+        assert_matches!(
+            super::check_possible_magic_link(Path::new("anon_inode:inotify")),
+            Err(_)
+        );
+        assert_matches!(
+            super::check_possible_magic_link(Path::new("anon_inode:io_uring")),
+            Err(_)
+        );
+
+        // Bracket-only anon-inode names (no colon-separated prefix), used by
+        // some older anon-inodes.
+        assert_matches!(
+            super::check_possible_magic_link(Path::new("[eventpoll]")),
+            Err(_)
+        );
+        assert_matches!(
+            super::check_possible_magic_link(Path::new("[signalfd]")),
+            Err(_)
+        );
+        assert_matches!(
+            super::check_possible_magic_link(Path::new("[timerfd]")),
+            Err(_)
+        );
+        assert_matches!(
+            super::check_possible_magic_link(Path::new("[userfaultfd]")),
+            Err(_)
+        );
+
+        // A deleted-but-still-open file.
+        assert_matches!(
+            super::check_possible_magic_link(Path::new("foo/bar (deleted)")),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn classify_magic_link() {
+        use super::{classify_magic_link, MagicLinkKind};
+
+        assert_eq!(
+            classify_magic_link(Path::new("12345/foo/bar/baz")),
+            MagicLinkKind::Relative,
+            "a relative target is not a dangerous magic-link"
+        );
+
+        assert_eq!(
+            classify_magic_link(Path::new("/foo/bar")),
+            MagicLinkKind::Absolute,
+            "an absolute target is a d_path-style magic-link"
+        );
+
+        assert_eq!(
+            classify_magic_link(Path::new("pipe:[12345]")),
+            MagicLinkKind::AnonInode,
+            "pipe:[...] is an anon-inode-style magic-link"
+        );
+        assert_eq!(
+            classify_magic_link(Path::new("anon_inode:[pidfd]")),
+            MagicLinkKind::AnonInode,
+            "anon_inode:[...] is an anon-inode-style magic-link"
+        );
+    }
+
+    #[test]
+    fn resolve_bit_errno_means_unsupported() {
+        use super::resolve_bit_errno_means_unsupported as classify;
+
+        assert!(
+            !classify(None, false),
+            "a successful probe always means the bit is supported"
+        );
+        assert!(
+            classify(Some(libc::EINVAL), false),
+            "EINVAL always means the bit is unsupported"
+        );
+        assert!(
+            classify(Some(libc::EINVAL), true),
+            "EINVAL means the bit is unsupported regardless of allow_seccomp_fallback"
+        );
+        assert!(
+            !classify(Some(libc::EPERM), false),
+            "EPERM is not treated as unsupported unless allow_seccomp_fallback is set"
+        );
+        assert!(
+            classify(Some(libc::EPERM), true),
+            "EPERM is treated as unsupported (a seccomp shim) when allow_seccomp_fallback is set"
+        );
+        assert!(
+            !classify(Some(libc::EACCES), true),
+            "an unrelated errno never means the bit is unsupported"
+        );
     }
 }
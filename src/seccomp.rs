@@ -0,0 +1,538 @@
+// SPDX-License-Identifier: MPL-2.0 OR LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * == MPL-2.0 ==
+ *
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Alternatively, this Source Code Form may also (at your option) be used
+ * under the terms of the GNU Lesser General Public License Version 3, as
+ * described below:
+ *
+ * == LGPL-3.0-or-later ==
+ *
+ *  This program is free software: you can redistribute it and/or modify it
+ *  under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or (at
+ *  your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful, but
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY  or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General
+ * Public License  for more details.
+ *
+ *  You should have received a copy of the GNU Lesser General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Classic-BPF program construction for seccomp filters.
+//!
+//! > **NOTE**: This module contains a complete filter compiler
+//! > ([`SeccompFilterBuilder`]/[`compile_filter`]) and the tiny [`BpfVm`]
+//! > interpreter used to test it in isolation, but the code that actually
+//! > loads the resulting program with `seccomp(2)`/`prctl(2)` (the rest of
+//! > `bpfvm::seccomp`) isn't part of this checkout, so there is currently
+//! > nothing in the crate that calls [`compile_filter`]. Only the plumbing
+//! > that would thread a compiled filter into an actual running process is
+//! > missing.
+#![allow(dead_code)]
+
+use std::{collections::BTreeMap, fmt};
+
+/// Offsets (in bytes) of fields within the kernel's `struct seccomp_data`,
+/// for use with `BPF_LD+BPF_W+BPF_ABS` instructions.
+///
+/// ```c
+/// struct seccomp_data {
+///     int nr;                  // offset 0
+///     __u32 arch;               // offset 4
+///     __u64 instruction_pointer; // offset 8
+///     __u64 args[6];             // offset 16
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FieldOffset {
+    /// The syscall number being filtered (`seccomp_data.nr`).
+    Nr,
+    /// The audit architecture the syscall was made under
+    /// (`seccomp_data.arch`), compared against [`native_audit_arch`].
+    Arch,
+}
+
+impl FieldOffset {
+    pub(crate) const fn offset(self) -> u32 {
+        match self {
+            Self::Nr => 0,
+            Self::Arch => 4,
+        }
+    }
+}
+
+/// The `AUDIT_ARCH_*` constant (from `<linux/audit.h>`) identifying the
+/// compile-time-native architecture's syscall ABI, for comparison against
+/// [`FieldOffset::Arch`].
+// NOTE: AUDIT_ARCH_* values are `EM_* | __AUDIT_ARCH_64BIT` (and/or
+// `__AUDIT_ARCH_LE`), but we just hardcode the well-known resulting values
+// here rather than reconstructing them, since libc doesn't expose them.
+pub(crate) const fn native_audit_arch() -> u32 {
+    #[cfg(target_arch = "x86_64")]
+    {
+        0xC000_003E // AUDIT_ARCH_X86_64
+    }
+    #[cfg(target_arch = "x86")]
+    {
+        0x4000_0003 // AUDIT_ARCH_I386
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        0xC000_00B7 // AUDIT_ARCH_AARCH64
+    }
+    #[cfg(target_arch = "arm")]
+    {
+        0x4000_0028 // AUDIT_ARCH_ARM
+    }
+    #[cfg(not(any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "arm"
+    )))]
+    {
+        compile_error!("native_audit_arch() has no AUDIT_ARCH_* mapping for this target_arch");
+    }
+}
+
+/// A syscall number, as reported in `seccomp_data.nr`.
+///
+/// Stands in for the `Sysno` type from the (not a dependency of this
+/// checkout) `syscalls` crate -- just a thin wrapper around the raw number,
+/// since that's all a BPF dispatch table actually needs to compare against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct Sysno(pub(crate) u32);
+
+impl Sysno {
+    pub(crate) const fn new(nr: u32) -> Self {
+        Self(nr)
+    }
+}
+
+/// The action a seccomp filter should take for a particular syscall (or, as
+/// the default action, for every syscall that wasn't otherwise configured).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SeccompReturn {
+    /// Let the syscall through unmodified.
+    Allow,
+    /// Fail the syscall with the given `errno`, without killing the caller.
+    Errno(u16),
+    /// Send `SIGSYS` to the calling thread (`SECCOMP_RET_TRAP`).
+    Trap,
+    /// Allow the syscall, but emit an audit log record for it
+    /// (`SECCOMP_RET_LOG`).
+    Log,
+    /// Kill the entire process immediately (`SECCOMP_RET_KILL_PROCESS`).
+    /// Stricter than [`Self::Errno`]/[`Self::Trap`], but means the attempt
+    /// can never be retried.
+    KillProcess,
+}
+
+impl Default for SeccompReturn {
+    /// Defaults to `Errno(ENOSYS)`, matching the original "stub listed
+    /// syscalls to ENOSYS" behaviour of `compile_filter`.
+    fn default() -> Self {
+        Self::Errno(libc::ENOSYS as u16)
+    }
+}
+
+/// `struct sock_filter` (classic BPF), as consumed by `seccomp(2)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct BpfInstr {
+    pub(crate) code: u16,
+    pub(crate) jt: u8,
+    pub(crate) jf: u8,
+    pub(crate) k: u32,
+}
+
+// Classic BPF opcode components we actually use.
+const BPF_LD_W_ABS: u16 = 0x00 /* BPF_LD */ | 0x00 /* BPF_W */ | 0x20 /* BPF_ABS */;
+const BPF_JMP_JEQ_K: u16 = 0x05 /* BPF_JMP */ | 0x10 /* BPF_JEQ */ | 0x00 /* BPF_K */;
+const BPF_RET_K: u16 = 0x06 /* BPF_RET */ | 0x00 /* BPF_K */;
+
+const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+const SECCOMP_RET_TRAP: u32 = 0x0003_0000;
+const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+const SECCOMP_RET_LOG: u32 = 0x7ffc_0000;
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+const SECCOMP_RET_DATA_MASK: u32 = 0x0000_ffff;
+
+impl BpfInstr {
+    const fn stmt(code: u16, k: u32) -> Self {
+        Self {
+            code,
+            jt: 0,
+            jf: 0,
+            k,
+        }
+    }
+
+    const fn jump(code: u16, k: u32, jt: u8, jf: u8) -> Self {
+        Self { code, jt, jf, k }
+    }
+}
+
+impl SeccompReturn {
+    /// The `SECCOMP_RET_*` value (combined with any return data, such as an
+    /// errno) this action resolves to as a `BPF_RET+BPF_K` immediate.
+    const fn seccomp_ret(self) -> u32 {
+        match self {
+            Self::Allow => SECCOMP_RET_ALLOW,
+            Self::Errno(errno) => SECCOMP_RET_ERRNO | (errno as u32 & SECCOMP_RET_DATA_MASK),
+            Self::Trap => SECCOMP_RET_TRAP,
+            Self::Log => SECCOMP_RET_LOG,
+            Self::KillProcess => SECCOMP_RET_KILL_PROCESS,
+        }
+    }
+}
+
+/// Builds the instructions that must run *before* any syscall-number
+/// dispatch: load `seccomp_data.arch`, and if it doesn't match
+/// [`native_audit_arch`], immediately return `on_foreign_arch` without ever
+/// looking at the syscall number (which is only meaningful for the native
+/// arch's ABI).
+///
+/// The returned instructions always leave `seccomp_data.nr` loaded as the BPF
+/// accumulator on the "architecture matched" path, exactly as the dispatch
+/// table built by [`compile_filter`] expects as its first instruction, so
+/// callers can simply prepend this prologue to an existing syscall-dispatch
+/// program.
+pub(crate) fn arch_check_prologue(on_foreign_arch: SeccompReturn) -> Vec<BpfInstr> {
+    vec![
+        // A[0] = seccomp_data.arch
+        BpfInstr::stmt(BPF_LD_W_ABS, FieldOffset::Arch.offset()),
+        // if A[0] == native_audit_arch(): skip the foreign-arch return and
+        // fall through to loading seccomp_data.nr.
+        BpfInstr::jump(BPF_JMP_JEQ_K, native_audit_arch(), 1, 0),
+        // Foreign architecture: stop here, before any syscall dispatch runs.
+        BpfInstr::stmt(BPF_RET_K, on_foreign_arch.seccomp_ret()),
+        // Architecture matched: load the syscall number for whatever dispatch
+        // logic comes after this prologue.
+        BpfInstr::stmt(BPF_LD_W_ABS, FieldOffset::Nr.offset()),
+    ]
+}
+
+/// Builds a BPF dispatch table that compares the already-loaded
+/// `seccomp_data.nr` against each configured [`Sysno`] in turn, returning its
+/// associated [`SeccompReturn`] on a match, and falling through to
+/// `default_action` if nothing matched.
+///
+/// Iteration is over a [`BTreeMap`] so the emitted comparisons (and thus the
+/// resulting BPF program) are always in a deterministic, syscall-number
+/// order, regardless of the order `actions` was built up in.
+fn compile_dispatch(
+    actions: &BTreeMap<u32, SeccompReturn>,
+    default_action: SeccompReturn,
+) -> Vec<BpfInstr> {
+    let mut program = Vec::with_capacity(2 * actions.len() + 1);
+    for (&nr, &action) in actions {
+        // if A[0] == nr: fall through to the very next instruction (the
+        // return for this syscall); otherwise skip over it to keep checking.
+        program.push(BpfInstr::jump(BPF_JMP_JEQ_K, nr, 0, 1));
+        program.push(BpfInstr::stmt(BPF_RET_K, action.seccomp_ret()));
+    }
+    program.push(BpfInstr::stmt(BPF_RET_K, default_action.seccomp_ret()));
+    program
+}
+
+/// Compiles a full seccomp filter program from `policy`: the
+/// [`SeccompFilterBuilder::on_foreign_arch`] prologue, followed by a dispatch
+/// table comparing `seccomp_data.nr` against each configured [`Sysno`] (see
+/// [`SeccompFilterBuilder::syscall`]), falling back to
+/// [`SeccompFilterBuilder::default_action`] for anything else.
+pub(crate) fn compile_filter(policy: SeccompFilterBuilder) -> Vec<BpfInstr> {
+    let mut program = arch_check_prologue(policy.on_foreign_arch);
+    program.extend(compile_dispatch(&policy.actions, policy.default_action));
+    program
+}
+
+/// Builder for a seccomp filter's policy: which [`SeccompReturn`] action each
+/// [`Sysno`] should resolve to, what unconfigured syscalls should do, and
+/// what to do with syscalls made under a foreign (non-native)
+/// `seccomp_data.arch` (see [`arch_check_prologue`]). Call [`Self::build`] to
+/// compile the resulting [`BpfInstr`] program.
+#[derive(Debug, Clone)]
+pub(crate) struct SeccompFilterBuilder {
+    actions: BTreeMap<u32, SeccompReturn>,
+    default_action: SeccompReturn,
+    on_foreign_arch: SeccompReturn,
+}
+
+impl Default for SeccompFilterBuilder {
+    fn default() -> Self {
+        Self {
+            actions: BTreeMap::new(),
+            default_action: SeccompReturn::Allow,
+            on_foreign_arch: SeccompReturn::default(),
+        }
+    }
+}
+
+impl SeccompFilterBuilder {
+    /// Construct a new, empty [`SeccompFilterBuilder`]: every native-arch
+    /// syscall is allowed until configured otherwise, and every foreign-arch
+    /// syscall gets `Errno(ENOSYS)` (see [`Self::on_foreign_arch`]).
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Convenience constructor matching the original `compile_filter`
+    /// behaviour: stub every syscall in `syscalls` to return `ENOSYS`, and
+    /// allow everything else (the builder's `default_action`).
+    pub(crate) fn stub_enosys(syscalls: impl IntoIterator<Item = Sysno>) -> Self {
+        let mut builder = Self::new();
+        for nr in syscalls {
+            builder.set_syscall(nr, SeccompReturn::default());
+        }
+        builder
+    }
+
+    /// Set the action taken when `nr` is the syscall being filtered.
+    #[inline]
+    pub(crate) fn syscall(mut self, nr: Sysno, action: SeccompReturn) -> Self {
+        self.set_syscall(nr, action);
+        self
+    }
+
+    /// Setter form of [`Self::syscall`].
+    #[inline]
+    pub(crate) fn set_syscall(&mut self, nr: Sysno, action: SeccompReturn) -> &mut Self {
+        self.actions.insert(nr.0, action);
+        self
+    }
+
+    /// Set the action taken for any syscall that wasn't given its own action
+    /// via [`Self::syscall`]. Defaults to [`SeccompReturn::Allow`].
+    #[inline]
+    pub(crate) fn default_action(mut self, action: SeccompReturn) -> Self {
+        self.set_default_action(action);
+        self
+    }
+
+    /// Setter form of [`Self::default_action`].
+    #[inline]
+    pub(crate) fn set_default_action(&mut self, action: SeccompReturn) -> &mut Self {
+        self.default_action = action;
+        self
+    }
+
+    /// Set the action taken for a syscall made under a foreign (non-native)
+    /// `seccomp_data.arch`, before the dispatch table above ever runs. See
+    /// [`arch_check_prologue`]. Defaults to `Errno(ENOSYS)`.
+    #[inline]
+    pub(crate) fn on_foreign_arch(mut self, action: SeccompReturn) -> Self {
+        self.set_on_foreign_arch(action);
+        self
+    }
+
+    /// Setter form of [`Self::on_foreign_arch`].
+    #[inline]
+    pub(crate) fn set_on_foreign_arch(&mut self, action: SeccompReturn) -> &mut Self {
+        self.on_foreign_arch = action;
+        self
+    }
+
+    /// Compile this policy into a BPF program, as [`compile_filter`].
+    pub(crate) fn build(self) -> Vec<BpfInstr> {
+        compile_filter(self)
+    }
+}
+
+/// Mirrors `struct seccomp_data`, for feeding synthetic syscalls to
+/// [`BpfVm`] in tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SeccompData {
+    pub(crate) nr: u32,
+    pub(crate) arch: u32,
+}
+
+/// A minimal classic-BPF interpreter, supporting only the handful of
+/// instructions [`arch_check_prologue`]/[`compile_filter`] can emit --
+/// `BPF_LD+BPF_W+BPF_ABS`, `BPF_JMP+BPF_JEQ+BPF_K`, and `BPF_RET+BPF_K`. This
+/// is only meant for testing the programs built above in isolation, not as a
+/// general-purpose BPF VM.
+#[derive(Debug)]
+pub(crate) struct BpfVm<'a> {
+    program: &'a [BpfInstr],
+}
+
+/// The outcome of running a [`BpfVm`] program to completion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BpfVmResult {
+    /// The program returned via `BPF_RET+BPF_K`, with the given
+    /// `SECCOMP_RET_*`-style value.
+    Return(u32),
+}
+
+impl fmt::Display for BpfVmResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Return(k) => write!(f, "return 0x{k:08x}"),
+        }
+    }
+}
+
+impl<'a> BpfVm<'a> {
+    pub(crate) fn new(program: &'a [BpfInstr]) -> Self {
+        Self { program }
+    }
+
+    /// Runs the program against `data`, returning the eventual
+    /// `BPF_RET+BPF_K` value.
+    ///
+    /// Panics if the program runs off the end without a `BPF_RET`, or uses an
+    /// instruction this toy interpreter doesn't understand -- both are test
+    /// bugs, not something a real caller could hit.
+    pub(crate) fn run(&self, data: SeccompData) -> BpfVmResult {
+        let mut acc: u32 = 0;
+        let mut pc: usize = 0;
+
+        loop {
+            let instr = self
+                .program
+                .get(pc)
+                .expect("BPF program ran off the end without a BPF_RET");
+
+            match instr.code {
+                BPF_LD_W_ABS => {
+                    acc = match FieldOffset::Arch.offset() == instr.k {
+                        true => data.arch,
+                        false if FieldOffset::Nr.offset() == instr.k => data.nr,
+                        false => panic!("unsupported BPF_LD+BPF_W+BPF_ABS offset {}", instr.k),
+                    };
+                    pc += 1;
+                }
+                BPF_JMP_JEQ_K => {
+                    pc += 1 + usize::from(if acc == instr.k { instr.jt } else { instr.jf });
+                }
+                BPF_RET_K => return BpfVmResult::Return(instr.k),
+                code => panic!("unsupported BPF opcode {code:#06x}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    /// Any arch value other than `native_audit_arch()` is "foreign" -- using
+    /// a different well-known `AUDIT_ARCH_*` constant makes tests unambiguous
+    /// regardless of which arch they're compiled for.
+    fn foreign_arch() -> u32 {
+        match native_audit_arch() {
+            0xC000_003E => 0x4000_0003, // x86_64 -> pretend to be i386
+            _ => 0xC000_003E,           // otherwise -> pretend to be x86_64
+        }
+    }
+
+    #[test]
+    fn native_arch_reaches_dispatch() {
+        let program = SeccompFilterBuilder::new().build();
+        let result = BpfVm::new(&program).run(SeccompData {
+            nr: 0,
+            arch: native_audit_arch(),
+        });
+        assert_eq!(
+            result,
+            BpfVmResult::Return(SECCOMP_RET_ALLOW),
+            "an empty builder should allow every native-arch syscall by default"
+        );
+    }
+
+    #[test]
+    fn foreign_arch_is_rejected_before_dispatch() {
+        let program = SeccompFilterBuilder::new().build();
+        let result = BpfVm::new(&program).run(SeccompData {
+            nr: 0,
+            arch: foreign_arch(),
+        });
+        assert_eq!(
+            result,
+            BpfVmResult::Return(SECCOMP_RET_ERRNO | libc::ENOSYS as u32),
+            "foreign arch should get the default ENOSYS action, never reaching the dispatch table"
+        );
+    }
+
+    #[test]
+    fn foreign_arch_kill_process_action() {
+        let program = SeccompFilterBuilder::new()
+            .on_foreign_arch(SeccompReturn::KillProcess)
+            .build();
+        let result = BpfVm::new(&program).run(SeccompData {
+            nr: 0,
+            arch: foreign_arch(),
+        });
+        assert_eq!(result, BpfVmResult::Return(SECCOMP_RET_KILL_PROCESS));
+    }
+
+    #[test]
+    fn stub_enosys_convenience_constructor() {
+        let program = SeccompFilterBuilder::stub_enosys([Sysno::new(57), Sysno::new(58)]).build();
+        let vm = BpfVm::new(&program);
+
+        for &nr in &[57, 58] {
+            assert_eq!(
+                vm.run(SeccompData {
+                    nr,
+                    arch: native_audit_arch(),
+                }),
+                BpfVmResult::Return(SECCOMP_RET_ERRNO | libc::ENOSYS as u32),
+                "syscall {nr} should be stubbed to ENOSYS"
+            );
+        }
+        assert_eq!(
+            vm.run(SeccompData {
+                nr: 59,
+                arch: native_audit_arch(),
+            }),
+            BpfVmResult::Return(SECCOMP_RET_ALLOW),
+            "unlisted syscalls should fall through to the default Allow action"
+        );
+    }
+
+    #[test]
+    fn per_syscall_actions_and_default() {
+        let program = SeccompFilterBuilder::new()
+            .syscall(Sysno::new(257), SeccompReturn::Errno(libc::EPERM as u16))
+            .syscall(Sysno::new(258), SeccompReturn::Trap)
+            .syscall(Sysno::new(259), SeccompReturn::Log)
+            .default_action(SeccompReturn::KillProcess)
+            .build();
+        let vm = BpfVm::new(&program);
+
+        let run = |nr: u32| {
+            vm.run(SeccompData {
+                nr,
+                arch: native_audit_arch(),
+            })
+        };
+
+        assert_eq!(
+            run(257),
+            BpfVmResult::Return(SECCOMP_RET_ERRNO | libc::EPERM as u32),
+            "the EPERM-configured syscall should return EPERM, not ENOSYS"
+        );
+        assert_eq!(run(258), BpfVmResult::Return(SECCOMP_RET_TRAP));
+        assert_eq!(run(259), BpfVmResult::Return(SECCOMP_RET_LOG));
+        assert_eq!(
+            run(1),
+            BpfVmResult::Return(SECCOMP_RET_KILL_PROCESS),
+            "an unconfigured syscall should fall through to the configured default action"
+        );
+    }
+}
@@ -0,0 +1,463 @@
+// SPDX-License-Identifier: MPL-2.0 OR LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * == MPL-2.0 ==
+ *
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Alternatively, this Source Code Form may also (at your option) be used
+ * under the terms of the GNU Lesser General Public License Version 3, as
+ * described below:
+ *
+ * == LGPL-3.0-or-later ==
+ *
+ *  This program is free software: you can redistribute it and/or modify it
+ *  under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or (at
+ *  your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful, but
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY  or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General
+ * Public License  for more details.
+ *
+ *  You should have received a copy of the GNU Lesser General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#![forbid(unsafe_code)]
+
+//! Export a [`Root`] over the 9P2000.L protocol, for sandboxed file sharing
+//! with untrusted clients (such as a VM or container guest).
+//!
+//! Every 9P request is translated into the equivalent [`Root`] operation --
+//! `Twalk` into [`Root::resolve`]-style path resolution, `Tlopen` into
+//! [`Root::open_subpath`], `Tlcreate` into [`Root::create_file`], `Tsymlink`
+//! into [`Root::create_symlink`], `Tunlinkat` into [`Root::remove_file`],
+//! `Trenameat` into [`Root::rename`], `Treadlink` into [`Root::readlink`],
+//! and `Tgetattr` into [`Root::metadata_nofollow`] -- which means a client
+//! can be given access to the exported subtree without being able to escape
+//! it, even via a malicious `..` or symlink walk: the same race-free
+//! resolution core used by in-process callers is used for every client
+//! request.
+//!
+//! This is intentionally a small subset of 9P2000.L: just enough to attach,
+//! walk, open/create/remove/rename files, create and read symlinks, stat,
+//! and clunk fids. There is currently no support for `Tread`/`Twrite` (data
+//! transfer), `Treaddir`, `Tsetattr`, `Tstatfs`, `Tflush`, or extended
+//! attributes.
+//!
+//! [`Root`]: crate::Root
+//! [`Root::resolve`]: crate::Root::resolve
+//! [`Root::open_subpath`]: crate::Root::open_subpath
+//! [`Root::create_file`]: crate::Root::create_file
+//! [`Root::create_symlink`]: crate::Root::create_symlink
+//! [`Root::remove_file`]: crate::Root::remove_file
+//! [`Root::rename`]: crate::Root::rename
+//! [`Root::readlink`]: crate::Root::readlink
+//! [`Root::metadata_nofollow`]: crate::Root::metadata_nofollow
+
+mod proto;
+
+use crate::{
+    error::{Error, ErrorImpl},
+    flags::{OpenFlags, RenameFlags},
+    FileType, Metadata, Root,
+};
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs::{File, Permissions},
+    hash::{Hash, Hasher},
+    io::{Read, Write},
+    os::unix::{ffi::OsStrExt, fs::PermissionsExt},
+    path::{Path, PathBuf},
+};
+
+use proto::{getattr_mask, msg_type, qid_type, Message, Qid, Reader, Writer, NOFID};
+
+/// State tracked for each fid a client has walked to or attached.
+#[derive(Debug)]
+struct FidEntry {
+    /// Path of this fid, relative to the exported [`Root`].
+    path: PathBuf,
+    qid: Qid,
+    /// The open file backing this fid, if `Tlopen`/`Tlcreate` was called.
+    open: Option<File>,
+}
+
+/// A running 9P2000.L server, exporting a single [`Root`] to one client
+/// connection.
+///
+/// [`Root`]: crate::Root
+#[derive(Debug)]
+pub struct Server {
+    root: Root,
+    fids: HashMap<u32, FidEntry>,
+}
+
+fn qid_for(path: &Path, meta: &Metadata) -> Qid {
+    let qtype = match meta.file_type() {
+        FileType::Directory => qid_type::DIR,
+        FileType::Symlink => qid_type::SYMLINK,
+        _ => qid_type::FILE,
+    };
+
+    // statx(2) doesn't give us a stable "inode number" we can safely re-export
+    // to an untrusted 9P client (see Metadata's docs), so instead we derive
+    // the qid.path from the root-relative path itself -- unique enough for a
+    // client to tell files apart, which is all the protocol actually needs.
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    Qid {
+        qtype,
+        version: 0,
+        path: hasher.finish(),
+    }
+}
+
+impl Server {
+    /// Create a server exporting `root`. Nothing is served until
+    /// [`Server::serve`] is called with a client stream.
+    pub fn new(root: Root) -> Self {
+        Self {
+            root,
+            fids: HashMap::new(),
+        }
+    }
+
+    /// Run the server loop against `stream`, handling requests until the
+    /// client disconnects or sends a message we cannot parse.
+    pub fn serve<S: Read + Write>(&mut self, mut stream: S) -> Result<(), Error> {
+        while let Some(msg) = proto::read_message(&mut stream)? {
+            self.handle_message(&mut stream, msg)?;
+        }
+        Ok(())
+    }
+
+    fn handle_message<S: Write>(&mut self, stream: &mut S, msg: Message) -> Result<(), Error> {
+        let mut reader = Reader::new(&msg.body);
+        let result = match msg.mtype {
+            msg_type::TVERSION => self.tversion(&mut reader),
+            msg_type::TATTACH => self.tattach(&mut reader),
+            msg_type::TWALK => self.twalk(&mut reader),
+            msg_type::TLOPEN => self.tlopen(&mut reader),
+            msg_type::TLCREATE => self.tlcreate(&mut reader),
+            msg_type::TSYMLINK => self.tsymlink(&mut reader),
+            msg_type::TREADLINK => self.treadlink(&mut reader),
+            msg_type::TGETATTR => self.tgetattr(&mut reader),
+            msg_type::TUNLINKAT => self.tunlinkat(&mut reader),
+            msg_type::TRENAMEAT => self.trenameat(&mut reader),
+            msg_type::TCLUNK => self.tclunk(&mut reader),
+            other => Err(ErrorImpl::InvalidArgument {
+                name: "9P message type".into(),
+                description: format!("unsupported 9P message type {other}").into(),
+            }
+            .into()),
+        };
+
+        match result {
+            Ok((rtype, body)) => proto::write_message(stream, rtype, msg.tag, &body),
+            Err(err) => {
+                let mut body = Writer::new();
+                // ecode: a plain Linux errno, as used by Rlerror in 9P2000.L.
+                body.u32(err.kind().errno().unwrap_or(libc::EIO) as u32);
+                proto::write_message(stream, msg_type::RLERROR, msg.tag, &body.into_bytes())
+            }
+        }
+    }
+
+    fn get_fid(&self, fid: u32) -> Result<&FidEntry, Error> {
+        self.fids.get(&fid).ok_or_else(|| {
+            ErrorImpl::InvalidArgument {
+                name: "fid".into(),
+                description: format!("fid {fid} is not attached").into(),
+            }
+            .into()
+        })
+    }
+
+    fn tversion(&mut self, r: &mut Reader) -> Result<(u8, Vec<u8>), Error> {
+        let msize = r.u32()?;
+        let version = r.string()?;
+
+        // We only understand 9P2000.L; anything else is downgraded to
+        // "unknown" as the spec requires.
+        let version = if version == "9P2000.L" {
+            version
+        } else {
+            "unknown".to_string()
+        };
+
+        let mut body = Writer::new();
+        body.u32(msize).string(&version);
+        Ok((msg_type::RVERSION, body.into_bytes()))
+    }
+
+    fn tattach(&mut self, r: &mut Reader) -> Result<(u8, Vec<u8>), Error> {
+        let fid = r.u32()?;
+        let afid = r.u32()?;
+        let _uname = r.string()?;
+        let _aname = r.string()?;
+        let _n_uname = r.u32()?;
+
+        if afid != NOFID {
+            return Err(ErrorImpl::InvalidArgument {
+                name: "afid".into(),
+                description: "authentication is not supported by this 9P server".into(),
+            }
+            .into());
+        }
+
+        let path = PathBuf::from(".");
+        let meta = self.root.metadata_nofollow(&path)?;
+        let qid = qid_for(&path, &meta);
+        self.fids.insert(
+            fid,
+            FidEntry {
+                path,
+                qid,
+                open: None,
+            },
+        );
+
+        let mut body = Writer::new();
+        body.qid(&qid);
+        Ok((msg_type::RATTACH, body.into_bytes()))
+    }
+
+    fn twalk(&mut self, r: &mut Reader) -> Result<(u8, Vec<u8>), Error> {
+        let fid = r.u32()?;
+        let newfid = r.u32()?;
+        let nwname = r.u16()?;
+
+        let start = self.get_fid(fid)?;
+        let mut path = start.path.clone();
+        let mut last_qid = start.qid;
+        let mut qids = Vec::with_capacity(nwname as usize);
+        for _ in 0..nwname {
+            let name = r.string()?;
+            let candidate = path.join(&name);
+            // Each walk step is verified against the real filesystem via the
+            // root's race-free resolver, exactly like any other libpathrs
+            // caller -- a malicious "../../etc/passwd" walk can never escape
+            // the exported subtree.
+            let meta = self.root.metadata_nofollow(&candidate)?;
+            last_qid = qid_for(&candidate, &meta);
+            qids.push(last_qid);
+            path = candidate;
+        }
+
+        self.fids.insert(
+            newfid,
+            FidEntry {
+                path,
+                qid: last_qid,
+                open: None,
+            },
+        );
+
+        let mut body = Writer::new();
+        body.u16(qids.len() as u16);
+        for qid in &qids {
+            body.qid(qid);
+        }
+        Ok((msg_type::RWALK, body.into_bytes()))
+    }
+
+    fn tlopen(&mut self, r: &mut Reader) -> Result<(u8, Vec<u8>), Error> {
+        let fid = r.u32()?;
+        let flags = r.u32()?;
+
+        let entry = self.get_fid(fid)?;
+        let path = entry.path.clone();
+        let qid = entry.qid;
+
+        let file = self.root.open_subpath(&path, l9p_to_open_flags(flags))?;
+        self.fids.get_mut(&fid).expect("fid checked above").open = Some(file);
+
+        let mut body = Writer::new();
+        body.qid(&qid).u32(0); // iounit: 0 means "no preference".
+        Ok((msg_type::RLOPEN, body.into_bytes()))
+    }
+
+    fn tlcreate(&mut self, r: &mut Reader) -> Result<(u8, Vec<u8>), Error> {
+        let fid = r.u32()?;
+        let name = r.string()?;
+        let flags = r.u32()?;
+        let mode = r.u32()?;
+        let _gid = r.u32()?;
+
+        let dir_path = self.get_fid(fid)?.path.clone();
+        let path = dir_path.join(&name);
+
+        let file = self.root.create_file(
+            &path,
+            l9p_to_open_flags(flags),
+            &Permissions::from_mode(mode & 0o7777),
+        )?;
+        let meta = self.root.metadata_nofollow(&path)?;
+        let qid = qid_for(&path, &meta);
+
+        self.fids.insert(
+            fid,
+            FidEntry {
+                path,
+                qid,
+                open: Some(file),
+            },
+        );
+
+        let mut body = Writer::new();
+        body.qid(&qid).u32(0); // iounit: 0 means "no preference".
+        Ok((msg_type::RLCREATE, body.into_bytes()))
+    }
+
+    fn tsymlink(&mut self, r: &mut Reader) -> Result<(u8, Vec<u8>), Error> {
+        let fid = r.u32()?;
+        let name = r.string()?;
+        let symtgt = r.string()?;
+        let _gid = r.u32()?;
+
+        let dir_path = self.get_fid(fid)?.path.clone();
+        let path = dir_path.join(&name);
+
+        // The same absolute-target rejection `Root::create_symlink` always
+        // applies protects a guest from planting a symlink that would let a
+        // later walk escape the exported subtree.
+        self.root.create_symlink(&path, &symtgt)?;
+        let meta = self.root.metadata_nofollow(&path)?;
+        let qid = qid_for(&path, &meta);
+
+        let mut body = Writer::new();
+        body.qid(&qid);
+        Ok((msg_type::RSYMLINK, body.into_bytes()))
+    }
+
+    fn treadlink(&mut self, r: &mut Reader) -> Result<(u8, Vec<u8>), Error> {
+        let fid = r.u32()?;
+        let path = self.get_fid(fid)?.path.clone();
+
+        // Resolved through the same race-free, symlink-confined core as
+        // every other request -- the target is whatever the link inside the
+        // exported subtree actually points to, not something a guest-walked
+        // path could spoof.
+        let target = self.root.readlink(&path)?;
+
+        let mut body = Writer::new();
+        // Byte-preserving, like `pathrs_proc_readlink`: a symlink target
+        // isn't guaranteed to be UTF-8, so don't risk mangling (or panicking
+        // on) it by going through `str`.
+        body.bytes(target.as_os_str().as_bytes());
+        Ok((msg_type::RREADLINK, body.into_bytes()))
+    }
+
+    fn tgetattr(&mut self, r: &mut Reader) -> Result<(u8, Vec<u8>), Error> {
+        let fid = r.u32()?;
+        let _request_mask = r.u64()?; // Ignored: we return every field we can fill in.
+
+        let entry = self.get_fid(fid)?;
+        let path = entry.path.clone();
+        let qid = entry.qid;
+        let meta = self.root.metadata_nofollow(&path)?;
+
+        let mut valid = getattr_mask::MODE
+            | getattr_mask::NLINK
+            | getattr_mask::UID
+            | getattr_mask::GID
+            | getattr_mask::SIZE;
+        let (btime_sec, btime_nsec) = match meta.created() {
+            Some(btime) => {
+                valid |= getattr_mask::BTIME;
+                let since_epoch = btime
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default();
+                (since_epoch.as_secs(), since_epoch.subsec_nanos() as u64)
+            }
+            None => (0, 0),
+        };
+
+        let mut body = Writer::new();
+        body.u64(valid)
+            .qid(&qid)
+            .u32(l9p_mode_for(&meta))
+            .u32(meta.uid())
+            .u32(meta.gid())
+            .u64(1) // nlink: we don't track hardlink counts, report a single link.
+            .u64(0) // rdev: none of the exported files are device nodes.
+            .u64(meta.len())
+            .u64(4096) // blksize: arbitrary but fixed.
+            .u64((meta.len() + 511) / 512) // blocks
+            .u64(0)
+            .u64(0) // atime: not tracked.
+            .u64(0)
+            .u64(0) // mtime: not tracked.
+            .u64(0)
+            .u64(0) // ctime: not tracked.
+            .u64(btime_sec)
+            .u64(btime_nsec)
+            .u64(0) // gen: not tracked.
+            .u64(0); // data_version: not tracked.
+        Ok((msg_type::RGETATTR, body.into_bytes()))
+    }
+
+    fn tunlinkat(&mut self, r: &mut Reader) -> Result<(u8, Vec<u8>), Error> {
+        let dirfid = r.u32()?;
+        let name = r.string()?;
+        let _flags = r.u32()?;
+
+        let dir_path = self.get_fid(dirfid)?.path.clone();
+        self.root.remove_file(dir_path.join(&name))?;
+
+        Ok((msg_type::RUNLINKAT, Vec::new()))
+    }
+
+    fn trenameat(&mut self, r: &mut Reader) -> Result<(u8, Vec<u8>), Error> {
+        let olddirfid = r.u32()?;
+        let oldname = r.string()?;
+        let newdirfid = r.u32()?;
+        let newname = r.string()?;
+
+        let old_path = self.get_fid(olddirfid)?.path.clone().join(&oldname);
+        let new_path = self.get_fid(newdirfid)?.path.clone().join(&newname);
+        self.root.rename(old_path, new_path, RenameFlags::empty())?;
+
+        Ok((msg_type::RRENAMEAT, Vec::new()))
+    }
+
+    fn tclunk(&mut self, r: &mut Reader) -> Result<(u8, Vec<u8>), Error> {
+        let fid = r.u32()?;
+        // Dropping the FidEntry (and its `open: Option<File>`) closes the
+        // underlying fd, if any was open.
+        self.get_fid(fid)?;
+        self.fids.remove(&fid);
+        Ok((msg_type::RCLUNK, Vec::new()))
+    }
+}
+
+/// Build the `st_mode`-style value expected in `Rgetattr.mode`: the file type
+/// bits (`S_IFMT`) plus the permission bits from [`Metadata::mode`].
+fn l9p_mode_for(meta: &Metadata) -> u32 {
+    let ifmt = match meta.file_type() {
+        FileType::Directory => libc::S_IFDIR,
+        FileType::Symlink => libc::S_IFLNK,
+        FileType::Fifo => libc::S_IFIFO,
+        FileType::CharacterDevice => libc::S_IFCHR,
+        FileType::BlockDevice => libc::S_IFBLK,
+        FileType::Socket => libc::S_IFSOCK,
+        FileType::File | FileType::Unknown => libc::S_IFREG,
+    };
+    ifmt as u32 | meta.mode()
+}
+
+fn l9p_to_open_flags(l9p_flags: u32) -> OpenFlags {
+    // The low bits of the Linux-specific Tlopen/Tlcreate "flags" field match
+    // the host's O_* values directly (they are passed through from the
+    // client's open(2) call), so we can reuse OpenFlags::from_bits_truncate.
+    OpenFlags::from_bits_truncate(l9p_flags as i32)
+}
@@ -0,0 +1,300 @@
+// SPDX-License-Identifier: MPL-2.0 OR LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * == MPL-2.0 ==
+ *
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Alternatively, this Source Code Form may also (at your option) be used
+ * under the terms of the GNU Lesser General Public License Version 3, as
+ * described below:
+ *
+ * == LGPL-3.0-or-later ==
+ *
+ *  This program is free software: you can redistribute it and/or modify it
+ *  under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or (at
+ *  your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful, but
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY  or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General
+ * Public License  for more details.
+ *
+ *  You should have received a copy of the GNU Lesser General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#![forbid(unsafe_code)]
+
+//! Minimal 9P2000.L wire-format helpers, used internally by [`super::Server`].
+//!
+//! This only implements the bits of the framing and type system needed by the
+//! handful of message types [`super::Server`] actually speaks -- it is not a
+//! general-purpose 9P library.
+
+use crate::error::{Error, ErrorImpl};
+
+use std::io::{self, Read, Write};
+
+/// `msize`-independent message type tags, as defined by 9P2000.L.
+///
+/// Only the subset of the protocol that [`super::Server`] implements is
+/// listed here; unknown/unsupported message types are rejected with
+/// [`RLERROR`].
+#[allow(dead_code)]
+pub(super) mod msg_type {
+    pub(crate) const TLERROR: u8 = 6; // Illegal, not used by 9P2000.L.
+    pub(crate) const RLERROR: u8 = 7;
+    pub(crate) const TATTACH: u8 = 104;
+    pub(crate) const RATTACH: u8 = 105;
+    pub(crate) const TWALK: u8 = 110;
+    pub(crate) const RWALK: u8 = 111;
+    pub(crate) const TLOPEN: u8 = 12;
+    pub(crate) const RLOPEN: u8 = 13;
+    pub(crate) const TLCREATE: u8 = 14;
+    pub(crate) const RLCREATE: u8 = 15;
+    pub(crate) const TSYMLINK: u8 = 16;
+    pub(crate) const RSYMLINK: u8 = 17;
+    pub(crate) const TREADLINK: u8 = 22;
+    pub(crate) const RREADLINK: u8 = 23;
+    pub(crate) const TGETATTR: u8 = 24;
+    pub(crate) const RGETATTR: u8 = 25;
+    pub(crate) const TRENAMEAT: u8 = 74;
+    pub(crate) const RRENAMEAT: u8 = 75;
+    pub(crate) const TUNLINKAT: u8 = 76;
+    pub(crate) const RUNLINKAT: u8 = 77;
+    pub(crate) const TVERSION: u8 = 100;
+    pub(crate) const RVERSION: u8 = 101;
+    pub(crate) const TCLUNK: u8 = 120;
+    pub(crate) const RCLUNK: u8 = 121;
+}
+
+/// `Rgetattr.valid` mask bits (9P2000.L `getattr_flags`), indicating which of
+/// the fixed-size `Rgetattr` fields actually contain meaningful data.
+///
+/// Only the subset [`super::Server`] can actually populate from a
+/// [`crate::Metadata`] is listed here.
+pub(super) mod getattr_mask {
+    pub(crate) const MODE: u64 = 0x0000_0001;
+    pub(crate) const NLINK: u64 = 0x0000_0002;
+    pub(crate) const UID: u64 = 0x0000_0004;
+    pub(crate) const GID: u64 = 0x0000_0008;
+    pub(crate) const SIZE: u64 = 0x0000_0200;
+    pub(crate) const BTIME: u64 = 0x0000_0800;
+}
+
+/// `NOFID`, the sentinel fid value used by `Tattach` to indicate "no
+/// authentication fid".
+pub(super) const NOFID: u32 = 0xFFFF_FFFF;
+
+/// 9P2000.L `QID.type` bits (a small subset of `DMDIR`/friends).
+pub(super) mod qid_type {
+    pub(crate) const DIR: u8 = 0x80;
+    pub(crate) const SYMLINK: u8 = 0x02;
+    pub(crate) const FILE: u8 = 0x00;
+}
+
+/// A 9P `qid`: `(type, version, path)`, uniquely (for our purposes)
+/// identifying a file within the exported tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Qid {
+    pub(crate) qtype: u8,
+    pub(crate) version: u32,
+    pub(crate) path: u64,
+}
+
+impl Qid {
+    fn encode(&self, w: &mut impl Write) -> io::Result<()> {
+        w.write_all(&[self.qtype])?;
+        w.write_all(&self.version.to_le_bytes())?;
+        w.write_all(&self.path.to_le_bytes())
+    }
+}
+
+/// A 9P message, after the 4-byte `size` and `tag` framing has been stripped.
+#[derive(Debug)]
+pub(super) struct Message {
+    pub(crate) mtype: u8,
+    pub(crate) tag: u16,
+    pub(crate) body: Vec<u8>,
+}
+
+/// Read a single length-prefixed 9P message off `r`.
+///
+/// Returns `Ok(None)` on a clean EOF between messages (the client hung up),
+/// matching the `Read::read`-style "no more data" convention.
+pub(super) fn read_message(r: &mut impl Read) -> Result<Option<Message>, Error> {
+    let mut size_buf = [0u8; 4];
+    match r.read_exact(&mut size_buf) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => {
+            return Err(ErrorImpl::OsError {
+                operation: "read 9P message size".into(),
+                source: err,
+            }
+            .into())
+        }
+    };
+    let size = u32::from_le_bytes(size_buf) as usize;
+    if size < 7 {
+        return Err(ErrorImpl::InvalidArgument {
+            name: "9P message size".into(),
+            description: format!("message size {size} is smaller than the minimum header").into(),
+        }
+        .into());
+    }
+
+    let mut rest = vec![0u8; size - 4];
+    r.read_exact(&mut rest).map_err(|err| ErrorImpl::OsError {
+        operation: "read 9P message body".into(),
+        source: err,
+    })?;
+
+    let mtype = rest[0];
+    let tag = u16::from_le_bytes([rest[1], rest[2]]);
+    let body = rest[3..].to_vec();
+    Ok(Some(Message { mtype, tag, body }))
+}
+
+/// Write a single 9P message (`mtype`/`tag` header plus `body`) to `w`.
+pub(super) fn write_message(
+    w: &mut impl Write,
+    mtype: u8,
+    tag: u16,
+    body: &[u8],
+) -> Result<(), Error> {
+    let size = 4 + 1 + 2 + body.len();
+    let write = || -> io::Result<()> {
+        w.write_all(&(size as u32).to_le_bytes())?;
+        w.write_all(&[mtype])?;
+        w.write_all(&tag.to_le_bytes())?;
+        w.write_all(body)?;
+        w.flush()
+    };
+    write().map_err(|err| {
+        ErrorImpl::OsError {
+            operation: "write 9P message".into(),
+            source: err,
+        }
+        .into()
+    })
+}
+
+/// Cursor-based decoder for a message body, matching the primitive types used
+/// by the 9P2000.L messages [`super::Server`] handles.
+pub(super) struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.buf.len() < n {
+            return Err(ErrorImpl::InvalidArgument {
+                name: "9P message body".into(),
+                description: "message body is shorter than its fields require".into(),
+            }
+            .into());
+        }
+        let (head, tail) = self.buf.split_at(n);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().expect("2 bytes")))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().expect("4 bytes")))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, Error> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().expect("8 bytes")))
+    }
+
+    /// A 9P `string`: a `u16` byte length followed by (supposedly) UTF-8 data.
+    pub(crate) fn string(&mut self) -> Result<String, Error> {
+        let len = self.u16()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|err| {
+            ErrorImpl::InvalidArgument {
+                name: "9P string".into(),
+                description: format!("invalid utf-8 in 9P string: {err}").into(),
+            }
+            .into()
+        })
+    }
+}
+
+/// Cursor-based encoder for a message body, mirroring [`Reader`].
+#[derive(Debug, Default)]
+pub(super) struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub(crate) fn u8(&mut self, v: u8) -> &mut Self {
+        self.buf.push(v);
+        self
+    }
+
+    pub(crate) fn u16(&mut self, v: u16) -> &mut Self {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn u32(&mut self, v: u32) -> &mut Self {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn u64(&mut self, v: u64) -> &mut Self {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn string(&mut self, s: &str) -> &mut Self {
+        self.u16(s.len() as u16);
+        self.buf.extend_from_slice(s.as_bytes());
+        self
+    }
+
+    /// Like [`Writer::string`], but for raw, possibly-non-UTF-8 bytes (such
+    /// as a symlink target read off the host). 9P `string` fields are only a
+    /// `u16` length prefix plus raw bytes on the wire, so this is just as
+    /// valid a `string` as [`Writer::string`]'s -- it just skips the UTF-8
+    /// assumption that would otherwise force us to lossily mangle the target.
+    pub(crate) fn bytes(&mut self, data: &[u8]) -> &mut Self {
+        self.u16(data.len() as u16);
+        self.buf.extend_from_slice(data);
+        self
+    }
+
+    pub(crate) fn qid(&mut self, qid: &Qid) -> &mut Self {
+        qid.encode(&mut self.buf).expect("Vec<u8> writes are infallible");
+        self
+    }
+}
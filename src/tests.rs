@@ -76,9 +76,19 @@ pub(in crate::tests) mod traits {
     pub(in crate::tests) use error::*;
 }
 
+#[cfg(feature = "cap-std")]
+mod test_cap_std;
+mod test_create_symlink;
+mod test_dir;
+mod test_path_in_root;
 mod test_procfs;
+mod test_readlink;
 mod test_resolve;
+mod test_resolve_fuzz;
 mod test_resolve_partial;
+mod test_rename;
 mod test_root_ops;
+mod test_walk;
+mod test_xattr;
 
 mod test_race_resolve_partial;
@@ -20,6 +20,7 @@
 use crate::{
     capi::{self, procfs::CProcfsBase},
     flags::OpenFlags,
+    metadata::Metadata,
     procfs::ProcfsBase,
     tests::{
         capi::utils::{self as capi_utils, CapiError},
@@ -29,8 +30,13 @@ use crate::{
 
 use std::{
     fs::File,
-    os::unix::io::{AsFd, OwnedFd},
+    mem,
+    os::unix::{
+        ffi::OsStrExt,
+        io::{AsFd, OwnedFd},
+    },
     path::{Path, PathBuf},
+    time::{Duration, SystemTime},
 };
 
 #[derive(Debug)]
@@ -39,7 +45,7 @@ pub struct CapiProcfsHandle;
 impl CapiProcfsHandle {
     fn open_follow(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
         oflags: impl Into<OpenFlags>,
     ) -> Result<File, CapiError> {
@@ -55,7 +61,7 @@ impl CapiProcfsHandle {
 
     fn open(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
         oflags: impl Into<OpenFlags>,
     ) -> Result<File, CapiError> {
@@ -63,7 +69,7 @@ impl CapiProcfsHandle {
         self.open_follow(base, subpath, oflags.into() | OpenFlags::O_NOFOLLOW)
     }
 
-    fn readlink(&self, base: ProcfsBase, subpath: impl AsRef<Path>) -> Result<PathBuf, CapiError> {
+    fn readlink(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<PathBuf, CapiError> {
         let base: CProcfsBase = base.into();
         let subpath = capi_utils::path_to_cstring(subpath);
 
@@ -71,6 +77,46 @@ impl CapiProcfsHandle {
             capi::procfs::pathrs_proc_readlink(base, subpath.as_ptr(), linkbuf, linkbuf_size)
         })
     }
+
+    fn write(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>, data: &[u8]) -> Result<(), CapiError> {
+        let base: CProcfsBase = base.into();
+        let subpath = capi_utils::path_to_cstring(subpath);
+
+        capi_utils::call_capi_zst(|| unsafe {
+            capi::procfs::pathrs_proc_write(
+                base,
+                subpath.as_ptr(),
+                data.as_ptr() as *const _,
+                data.len(),
+            )
+        })
+    }
+
+    fn stat(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<Metadata, CapiError> {
+        let base: CProcfsBase = base.into();
+        let subpath = capi_utils::path_to_cstring(subpath);
+
+        let mut stat = capi::procfs::ProcfsStat::default();
+        capi_utils::call_capi_zst(|| unsafe {
+            capi::procfs::pathrs_proc_stat(
+                base,
+                subpath.as_ptr(),
+                &mut stat as *mut _,
+                mem::size_of_val(&stat),
+            )
+        })?;
+        Ok(capi_stat_to_metadata(stat))
+    }
+
+    fn read(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<Vec<u8>, CapiError> {
+        let base: CProcfsBase = base.into();
+        let subpath = capi_utils::path_to_cstring(subpath);
+
+        capi_utils::call_capi_readlink(|linkbuf, linkbuf_size| unsafe {
+            capi::procfs::pathrs_proc_readfile(base, subpath.as_ptr(), linkbuf, linkbuf_size)
+        })
+        .map(|path| path.into_os_string().as_bytes().to_vec())
+    }
 }
 
 impl ProcfsHandleImpl for CapiProcfsHandle {
@@ -78,7 +124,7 @@ impl ProcfsHandleImpl for CapiProcfsHandle {
 
     fn open_follow(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
         oflags: impl Into<OpenFlags>,
     ) -> Result<File, Self::Error> {
@@ -87,7 +133,7 @@ impl ProcfsHandleImpl for CapiProcfsHandle {
 
     fn open(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
         oflags: impl Into<OpenFlags>,
     ) -> Result<File, Self::Error> {
@@ -96,11 +142,48 @@ impl ProcfsHandleImpl for CapiProcfsHandle {
 
     fn readlink(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
     ) -> Result<PathBuf, Self::Error> {
         self.readlink(base, subpath)
     }
+
+    fn write(
+        &self,
+        base: ProcfsBase<'_>,
+        subpath: impl AsRef<Path>,
+        data: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.write(base, subpath, data)
+    }
+
+    fn read(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<Vec<u8>, Self::Error> {
+        self.read(base, subpath)
+    }
+
+    fn stat(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<Metadata, Self::Error> {
+        self.stat(base, subpath)
+    }
+}
+
+/// Reconstruct a [`Metadata`] from a `struct pathrs_proc_stat` returned
+/// across the C API.
+fn capi_stat_to_metadata(stat: capi::procfs::ProcfsStat) -> Metadata {
+    let file_type = rustix::fs::FileType::from_raw_mode(stat.mode).into();
+    let mount_id = (stat.mnt_id_valid != 0).then_some(stat.mnt_id);
+    let btime = (stat.btime_valid != 0).then(|| {
+        SystemTime::UNIX_EPOCH + Duration::new(stat.btime_sec as u64, stat.btime_nsec)
+    });
+
+    Metadata::from_parts(
+        file_type,
+        stat.mode,
+        stat.uid,
+        stat.gid,
+        stat.size,
+        mount_id,
+        btime,
+    )
 }
 
 #[derive(Debug)]
@@ -115,7 +198,7 @@ impl From<CapiProcfsHandleFd> for OwnedFd {
 impl CapiProcfsHandleFd {
     fn open_follow(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
         oflags: impl Into<OpenFlags>,
     ) -> Result<File, CapiError> {
@@ -136,7 +219,7 @@ impl CapiProcfsHandleFd {
 
     fn open(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
         oflags: impl Into<OpenFlags>,
     ) -> Result<File, CapiError> {
@@ -144,7 +227,7 @@ impl CapiProcfsHandleFd {
         self.open_follow(base, subpath, oflags.into() | OpenFlags::O_NOFOLLOW)
     }
 
-    fn readlink(&self, base: ProcfsBase, subpath: impl AsRef<Path>) -> Result<PathBuf, CapiError> {
+    fn readlink(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<PathBuf, CapiError> {
         let base: CProcfsBase = base.into();
         let subpath = capi_utils::path_to_cstring(subpath);
 
@@ -158,6 +241,54 @@ impl CapiProcfsHandleFd {
             )
         })
     }
+
+    fn write(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>, data: &[u8]) -> Result<(), CapiError> {
+        let base: CProcfsBase = base.into();
+        let subpath = capi_utils::path_to_cstring(subpath);
+
+        capi_utils::call_capi_zst(|| unsafe {
+            capi::procfs::pathrs_proc_writeat(
+                self.0.as_fd().into(),
+                base,
+                subpath.as_ptr(),
+                data.as_ptr() as *const _,
+                data.len(),
+            )
+        })
+    }
+
+    fn stat(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<Metadata, CapiError> {
+        let base: CProcfsBase = base.into();
+        let subpath = capi_utils::path_to_cstring(subpath);
+
+        let mut stat = capi::procfs::ProcfsStat::default();
+        capi_utils::call_capi_zst(|| unsafe {
+            capi::procfs::pathrs_proc_statat(
+                self.0.as_fd().into(),
+                base,
+                subpath.as_ptr(),
+                &mut stat as *mut _,
+                mem::size_of_val(&stat),
+            )
+        })?;
+        Ok(capi_stat_to_metadata(stat))
+    }
+
+    fn read(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<Vec<u8>, CapiError> {
+        let base: CProcfsBase = base.into();
+        let subpath = capi_utils::path_to_cstring(subpath);
+
+        capi_utils::call_capi_readlink(|linkbuf, linkbuf_size| unsafe {
+            capi::procfs::pathrs_proc_readfileat(
+                self.0.as_fd().into(),
+                base,
+                subpath.as_ptr(),
+                linkbuf,
+                linkbuf_size,
+            )
+        })
+        .map(|path| path.into_os_string().as_bytes().to_vec())
+    }
 }
 
 impl ProcfsHandleImpl for CapiProcfsHandleFd {
@@ -165,7 +296,7 @@ impl ProcfsHandleImpl for CapiProcfsHandleFd {
 
     fn open_follow(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
         oflags: impl Into<OpenFlags>,
     ) -> Result<File, Self::Error> {
@@ -174,7 +305,7 @@ impl ProcfsHandleImpl for CapiProcfsHandleFd {
 
     fn open(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
         oflags: impl Into<OpenFlags>,
     ) -> Result<File, Self::Error> {
@@ -183,9 +314,26 @@ impl ProcfsHandleImpl for CapiProcfsHandleFd {
 
     fn readlink(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
     ) -> Result<PathBuf, Self::Error> {
         self.readlink(base, subpath)
     }
+
+    fn write(
+        &self,
+        base: ProcfsBase<'_>,
+        subpath: impl AsRef<Path>,
+        data: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.write(base, subpath, data)
+    }
+
+    fn read(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<Vec<u8>, Self::Error> {
+        self.read(base, subpath)
+    }
+
+    fn stat(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<Metadata, Self::Error> {
+        self.stat(base, subpath)
+    }
 }
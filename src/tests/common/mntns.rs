@@ -20,15 +20,15 @@
 use std::{
     ffi::CString,
     fs::File,
-    io::Error as IOError,
-    os::unix::io::{AsRawFd, RawFd},
+    io::{Error as IOError, Read, Write},
+    os::unix::io::{AsRawFd, FromRawFd},
     path::{Path, PathBuf},
     ptr,
 };
 
 use crate::{syscalls, utils::ToCString};
 
-use anyhow::Error;
+use anyhow::{anyhow, Context, Error};
 use libc::c_int;
 
 unsafe fn unshare(flags: c_int) -> Result<(), IOError> {
@@ -42,17 +42,6 @@ unsafe fn unshare(flags: c_int) -> Result<(), IOError> {
     }
 }
 
-unsafe fn setns(fd: RawFd, flags: c_int) -> Result<(), IOError> {
-    // SAFETY: Caller guarantees that this setns operation is safe.
-    let ret = unsafe { libc::setns(fd, flags) };
-    let err = IOError::last_os_error();
-    if ret >= 0 {
-        Ok(())
-    } else {
-        Err(err)
-    }
-}
-
 fn make_slave<P: AsRef<Path>>(path: P) -> Result<(), IOError> {
     // SAFETY: Obviously safe syscall.
     let ret = unsafe {
@@ -119,24 +108,110 @@ pub(in crate::tests) fn mount<P: AsRef<Path>>(dst: P, ty: MountType) -> Result<(
     }
 }
 
+/// Run `func` inside a private mount namespace, in a forked subprocess.
+///
+/// We used to `unshare(CLONE_NEWNS)` in the current thread and `setns(2)`
+/// back afterwards, but `unshare(CLONE_NEWNS)` affects the whole process (all
+/// threads share the same mount namespace) which makes it unsafe to run
+/// alongside any other test that cares about the "host" mount namespace --
+/// Rust's test harness runs tests in parallel on separate threads of the same
+/// process. Forking gives each test its own process (and thus its own mount
+/// namespace) to play with, without disturbing the parent.
+///
+/// Because `func`'s result can't cross the `fork(2)` boundary, on success we
+/// only know *that* the child succeeded, not the actual `T` value -- so `T`
+/// must be constructible with [`Default`]. On failure, the child's error
+/// message is forwarded to the parent through a pipe.
 pub(in crate::tests) fn in_mnt_ns<F, T>(func: F) -> Result<T, Error>
 where
     F: FnOnce() -> Result<T, Error>,
+    T: Default,
 {
-    let old_ns = File::open("/proc/self/ns/mnt")?;
+    let (read_fd, write_fd) = {
+        let mut fds = [-1 as c_int; 2];
+        // SAFETY: Obviously safe syscall -- fds is a valid 2-element array.
+        let ret = unsafe { libc::pipe(fds.as_mut_ptr()) };
+        if ret < 0 {
+            return Err(IOError::last_os_error()).context("create pipe for child error reporting");
+        }
+        (fds[0], fds[1])
+    };
+
+    // SAFETY: fork(2) is safe to call, and we uniquely own read_fd/write_fd.
+    let child = unsafe { libc::fork() };
+    match child {
+        -1 => Err(IOError::last_os_error()).context("fork child for mount namespace"),
+        0 => {
+            // SAFETY: write_fd was just returned by pipe(2) above, and we
+            // (the child) are the only ones who will touch it from here on.
+            let mut write_end = unsafe { File::from_raw_fd(write_fd) };
+            // SAFETY: same as above, but for read_fd -- we just want it
+            // closed in the child, since we never read from the pipe here.
+            drop(unsafe { File::from_raw_fd(read_fd) });
+
+            // A panic in func (such as a failed assertion) must not unwind
+            // out of the child, so it is reported as a failure too.
+            let result =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| run_in_mnt_ns(func)));
+            let status = match result {
+                Ok(Ok(_)) => 0,
+                Ok(Err(err)) => {
+                    let _ = write_end.write_all(format!("{err:#}").as_bytes());
+                    1
+                }
+                Err(_) => {
+                    let _ = write_end.write_all(b"function panicked in mount namespace child");
+                    1
+                }
+            };
+            drop(write_end);
+            // SAFETY: we must not run any Rust destructors/unwinding past
+            // this fork()'d child -- _exit(2) skips atexit handlers and
+            // avoids double-flushing any buffered state shared with the
+            // parent.
+            unsafe { libc::_exit(status) };
+        }
+        pid => {
+            // SAFETY: write_fd was just returned by pipe(2) above, and we
+            // (the parent) never write to it -- only the child does.
+            drop(unsafe { File::from_raw_fd(write_fd) });
+            // SAFETY: read_fd was just returned by pipe(2) above, and we
+            // uniquely own it from here on.
+            let mut read_end = unsafe { File::from_raw_fd(read_fd) };
+
+            let mut child_err = String::new();
+            read_end.read_to_string(&mut child_err)?;
+
+            let mut wstatus: c_int = 0;
+            // SAFETY: pid is a valid child we just forked, and wstatus is a
+            // valid pointer to an in-scope c_int.
+            let ret = unsafe { libc::waitpid(pid, &mut wstatus, 0) };
+            if ret < 0 {
+                return Err(IOError::last_os_error()).context("waitpid on mount namespace child");
+            }
 
-    // TODO: Run this in a subprocess.
+            if libc::WIFEXITED(wstatus) && libc::WEXITSTATUS(wstatus) == 0 {
+                Ok(T::default())
+            } else if libc::WIFEXITED(wstatus) {
+                Err(anyhow!("mount namespace child exited with an error: {child_err}"))
+            } else {
+                Err(anyhow!(
+                    "mount namespace child did not exit cleanly (wstatus={wstatus:#x}): {child_err}"
+                ))
+            }
+        }
+    }
+}
 
+fn run_in_mnt_ns<F, T>(func: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error>,
+{
     unsafe { unshare(libc::CLONE_FS | libc::CLONE_NEWNS) }
-        .expect("unable to create a mount namespace");
+        .context("unable to create a mount namespace")?;
 
     // Mark / as MS_SLAVE to avoid DoSing the host.
     make_slave("/")?;
 
-    let ret = func();
-
-    unsafe { setns(old_ns.as_raw_fd(), libc::CLONE_NEWNS) }
-        .expect("unable to rejoin old namespace");
-
-    ret
+    func()
 }
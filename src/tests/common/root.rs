@@ -94,6 +94,23 @@ macro_rules! create_inode {
         .with_context(|| format!("chown <none>:{} {}", $gid, $path.display()))?;
     };
 
+    // "/foo/bar" @ xattr "user.foo" = "bar"
+    (@do $path:expr, xattr $name:expr => $value:expr) => {
+        rustix_fs::setxattr($path, $name, $value.as_bytes(), 0)
+            .with_context(|| format!("setxattr {}={:?} {}", $name, $value, $path.display()))?;
+    };
+
+    // "/foo/bar" @ selinux "unconfined_u:object_r:tmp_t:s0"
+    (@do $path:expr, selinux $context:expr) => {
+        rustix_fs::setxattr(
+            $path,
+            "security.selinux",
+            format!("{}\0", $context).as_bytes(),
+            0,
+        )
+        .with_context(|| format!("selinux {} {}", $context, $path.display()))?;
+    };
+
     // "/foo/bar" => dir
     ($path:expr => dir $(,{$($extra:tt)*})*) => {
         rustix_fs::mkdir($path, 0o755.into())
@@ -338,6 +355,21 @@ pub(crate) fn create_basic_tree() -> Result<TempDir, Error> {
     })
 }
 
+// Labelled inodes for the xattr/security-context tests. This is kept
+// separate from `create_basic_tree()` (unlike most fixtures) because
+// `user.*` xattrs and `security.selinux` aren't supported by every
+// filesystem/kernel a test might run under, and every other test in the
+// suite shares the basic tree -- callers of this tree are expected to treat
+// `Err` as "skip, this environment doesn't support it" rather than a hard
+// failure.
+pub(crate) fn create_xattr_tree() -> Result<TempDir, Error> {
+    Ok(create_tree! {
+        "file" => (file, {xattr "user.foo" => "bar"});
+        "labelled" => (file, {selinux "unconfined_u:object_r:tmp_t:s0"});
+        "link" => (symlink -> "file");
+    })
+}
+
 pub(crate) fn mask_nosymfollow(root: &Path) -> Result<(), Error> {
     // Apply NOSYMFOLLOW for some subpaths.
     let root_prefix = root.to_path_buf();
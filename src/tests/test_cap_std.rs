@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 Aleksa Sarai <cyphar@cyphar.com>
+ * Copyright (C) 2019-2025 SUSE LLC
+ *
+ * This program is free software: you can redistribute it and/or modify it
+ * under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or (at your
+ * option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY
+ * or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License
+ * for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Tests for the `Root`/`Handle` <-> cap-std conversions.
+
+use crate::{flags::OpenFlags, tests::common as tests_common, Root};
+
+use std::{fs, io::Read};
+
+use anyhow::Error;
+
+#[test]
+fn root_round_trips_through_cap_std_dir() -> Result<(), Error> {
+    let root_dir = tests_common::create_basic_tree()?;
+
+    let root = Root::open(root_dir.path())?;
+    let cap_dir = cap_std::fs::Dir::try_from(root)?;
+    let root = Root::from_cap_std_dir(cap_dir)?;
+
+    // The handed-off root must still resolve normally, and must still
+    // reject attempts to escape it via "..".
+    root.resolve("a")?;
+    assert!(root.resolve("../etc/passwd").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn handle_into_cap_std_file_reads_contents() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    fs::write(root_dir.path().join("file"), b"contents")?;
+
+    let root = Root::open(root_dir.path())?;
+    let handle = root.resolve("file")?;
+
+    let mut cap_file = handle.into_cap_std_file(OpenFlags::O_RDONLY)?;
+
+    let mut buf = Vec::new();
+    cap_file.read_to_end(&mut buf)?;
+    assert_eq!(buf, b"contents");
+
+    Ok(())
+}
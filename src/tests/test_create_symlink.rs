@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 Aleksa Sarai <cyphar@cyphar.com>
+ * Copyright (C) 2019-2025 SUSE LLC
+ *
+ * This program is free software: you can redistribute it and/or modify it
+ * under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or (at your
+ * option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY
+ * or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License
+ * for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Tests for [`Root::create_symlink`]/[`Root::create_symlink_strict`].
+
+use crate::{error::ErrorKind, Root};
+
+use std::{fs, path::Path};
+
+use anyhow::Error;
+
+#[test]
+fn create_symlink_basic_resolves_inside_root() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::File::create(p.join("target"))?;
+
+    let root = Root::open(p)?;
+    root.create_symlink("link", "target")?;
+
+    // resolve() follows the link and lands on the real target.
+    root.resolve("link")?;
+    // resolve_nofollow() returns a handle to the symlink itself.
+    let handle = root.resolve_nofollow("link")?;
+    assert_eq!(handle.readlink()?, Path::new("target"));
+
+    Ok(())
+}
+
+#[test]
+fn create_symlink_rejects_absolute_target() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let root = Root::open(root_dir.path())?;
+
+    let err = root
+        .create_symlink("link", "/etc/passwd")
+        .expect_err("absolute symlink target must be rejected");
+    assert_eq!(err.kind(), ErrorKind::SafetyViolation);
+
+    Ok(())
+}
+
+#[test]
+fn create_symlink_strict_rejects_climbing_target() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::create_dir(p.join("dir"))?;
+
+    let root = Root::open(p)?;
+
+    // "dir/link" -> "../../etc/passwd" climbs above the root even though it
+    // is a relative path.
+    let err = root
+        .create_symlink_strict("dir/link", "../../etc/passwd")
+        .expect_err("climbing symlink target must be rejected in strict mode");
+    assert_eq!(err.kind(), ErrorKind::SafetyViolation);
+
+    // The plain (non-strict) variant allows it, since the link still can't
+    // actually be used to escape the root via libpathrs.
+    root.create_symlink("dir/link", "../../etc/passwd")?;
+
+    Ok(())
+}
+
+#[test]
+fn create_symlink_strict_allows_non_climbing_target() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::create_dir(p.join("a"))?;
+    fs::create_dir(p.join("b"))?;
+    fs::File::create(p.join("b/file"))?;
+
+    let root = Root::open(p)?;
+    // "a/link" -> "../b/file" stays within the root.
+    root.create_symlink_strict("a/link", "../b/file")?;
+    root.resolve("a/link")?;
+
+    Ok(())
+}
@@ -0,0 +1,165 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 Aleksa Sarai <cyphar@cyphar.com>
+ * Copyright (C) 2019-2025 SUSE LLC
+ *
+ * This program is free software: you can redistribute it and/or modify it
+ * under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or (at your
+ * option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY
+ * or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License
+ * for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Tests for [`Root::read_dir`]/[`Handle::read_dir`] and [`DirEntry`].
+
+use crate::{flags::OpenFlags, tests::common as tests_common, FileType, Root};
+
+use std::{collections::HashSet, fs, io::Read, os::unix::fs as unixfs};
+
+use anyhow::Error;
+
+#[test]
+fn read_dir_yields_names_and_types() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::create_dir(p.join("dir"))?;
+    fs::File::create(p.join("file"))?;
+    unixfs::symlink("file", p.join("link"))?;
+
+    let root = Root::open(p)?;
+
+    let mut seen = Vec::new();
+    for entry in root.read_dir(".")? {
+        let entry = entry?;
+        seen.push((entry.file_name().to_owned(), entry.file_type()));
+    }
+    seen.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    assert_eq!(
+        seen,
+        [
+            ("dir".into(), FileType::Directory),
+            ("file".into(), FileType::File),
+            ("link".into(), FileType::Symlink),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn read_dir_skips_dot_and_dotdot() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    fs::create_dir(root_dir.path().join("dir"))?;
+
+    let root = Root::open(root_dir.path())?;
+
+    for entry in root.read_dir(".")? {
+        let name = entry?.file_name().to_owned();
+        assert_ne!(name, ".");
+        assert_ne!(name, "..");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn dir_entry_open_and_resolve_are_fd_relative() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::create_dir(p.join("dir"))?;
+    fs::write(p.join("dir/file"), b"contents")?;
+
+    let root = Root::open(p)?;
+    let dir_entry = root
+        .read_dir(".")?
+        .map(|entry| entry.unwrap())
+        .find(|entry| entry.file_name() == "dir")
+        .expect("\"dir\" missing from read_dir");
+
+    let sub_handle = dir_entry.resolve()?;
+    let inner = sub_handle
+        .read_dir()?
+        .map(|entry| entry.unwrap())
+        .find(|entry| entry.file_name() == "file")
+        .expect("\"file\" missing from read_dir of resolved subdirectory");
+
+    let mut contents = Vec::new();
+    inner.open(OpenFlags::O_RDONLY)?.read_to_end(&mut contents)?;
+    assert_eq!(contents, b"contents");
+
+    Ok(())
+}
+
+#[test]
+fn resolved_file_type_matches_known_d_type() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::create_dir(p.join("dir"))?;
+    fs::File::create(p.join("file"))?;
+
+    let root = Root::open(p)?;
+    for entry in root.read_dir(".")? {
+        let entry = entry?;
+        // Whatever the kernel told us (if anything), resolved_file_type()
+        // must agree with it rather than overriding it with a needless stat.
+        if entry.file_type() != FileType::Unknown {
+            assert_eq!(entry.resolved_file_type()?, entry.file_type());
+        }
+    }
+
+    Ok(())
+}
+
+// create_basic_tree()'s "deep-rmdir/aa" fan-out (25 "aa/XX/foo/bar/baz"
+// subtrees, "bb" through "zz") gives a directory wide enough to exercise
+// getdents64 buffer growth, while the top-level listing below compares two
+// sorted read_dir() passes to confirm it doesn't depend on any particular
+// kernel enumeration order.
+#[test]
+fn read_dir_handles_wide_fanout_directory() -> Result<(), Error> {
+    let root_dir = tests_common::create_basic_tree()?;
+    let root = Root::open(root_dir.path())?;
+
+    let names = root
+        .read_dir("deep-rmdir/aa")?
+        .map(|entry| Ok(entry?.file_name().to_owned()))
+        .collect::<Result<HashSet<_>, Error>>()?;
+
+    let expected = ('b'..='z')
+        .map(|c| format!("{c}{c}").into())
+        .collect::<HashSet<_>>();
+    assert_eq!(names, expected);
+
+    Ok(())
+}
+
+#[test]
+fn read_dir_top_level_is_order_independent() -> Result<(), Error> {
+    let root_dir = tests_common::create_basic_tree()?;
+    let root = Root::open(root_dir.path())?;
+
+    let mut first = root
+        .read_dir(".")?
+        .map(|entry| Ok(entry?.file_name().to_owned()))
+        .collect::<Result<Vec<_>, Error>>()?;
+    let mut second = root
+        .read_dir(".")?
+        .map(|entry| Ok(entry?.file_name().to_owned()))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    first.sort();
+    second.sort();
+    assert_eq!(first, second);
+    assert!(first.iter().any(|name| name == "deep-rmdir"));
+
+    Ok(())
+}
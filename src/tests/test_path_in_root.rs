@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 Aleksa Sarai <cyphar@cyphar.com>
+ * Copyright (C) 2019-2025 SUSE LLC
+ *
+ * This program is free software: you can redistribute it and/or modify it
+ * under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or (at your
+ * option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY
+ * or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License
+ * for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Tests for [`Handle::path_in_root`].
+
+use crate::{error::ErrorKind, Root};
+
+use std::{fs, path::Path};
+
+use anyhow::Error;
+
+#[test]
+fn path_in_root_basic() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::create_dir(p.join("a"))?;
+    fs::File::create(p.join("a/b"))?;
+
+    let root = Root::open(p)?;
+    let handle = root.resolve("a/b")?;
+
+    assert_eq!(handle.path_in_root(&root)?, Path::new("a/b"));
+
+    Ok(())
+}
+
+#[test]
+fn path_in_root_fails_after_move_out_of_root() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let other_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+
+    fs::File::create(p.join("file"))?;
+
+    let root = Root::open(p)?;
+    let handle = root.resolve("file")?;
+
+    // Move the file outside of the root entirely -- the handle still
+    // refers to the same inode, but it's no longer reachable (at its old
+    // name) from inside the root.
+    fs::rename(p.join("file"), other_dir.path().join("file"))?;
+
+    let err = handle
+        .path_in_root(&root)
+        .expect_err("path_in_root must fail once the handle has left the root");
+    assert_eq!(err.kind(), ErrorKind::SafetyViolation);
+
+    Ok(())
+}
+
+#[test]
+fn path_in_root_tracks_rename_within_root() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::File::create(p.join("old-name"))?;
+
+    let root = Root::open(p)?;
+    let handle = root.resolve("old-name")?;
+
+    fs::rename(p.join("old-name"), p.join("new-name"))?;
+
+    // The handle's /proc/self/fd magic-link reflects the live path, so
+    // path_in_root() reports the file's up-to-date location, not the stale
+    // name it was originally resolved under.
+    assert_eq!(handle.path_in_root(&root)?, Path::new("new-name"));
+
+    Ok(())
+}
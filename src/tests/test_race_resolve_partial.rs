@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 Aleksa Sarai <cyphar@cyphar.com>
+ * Copyright (C) 2019-2025 SUSE LLC
+ *
+ * This program is free software: you can redistribute it and/or modify it
+ * under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or (at your
+ * option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY
+ * or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License
+ * for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Stress-test the resolvers against a symlink-rename race, in the spirit of
+//! the `openat2(2)` selftests: one thread repeatedly swaps a path component
+//! between a real in-root directory and a symlink escaping outside of root,
+//! while another thread resolves through it in a loop. Whichever backend is
+//! handling the resolution, it must never hand back a handle outside of
+//! root -- every attempt has to either land on an in-root target or fail.
+//!
+//! This runs the same race against both the emulated and the kernel
+//! `openat2` backend (skipping the latter where unsupported), which is as
+//! close to a differential test as we can get without reaching into
+//! `do_resolve` directly: both backends are exercised through the same
+//! public `Root::resolve`, so any divergence between "the kernel rejects
+//! this race" and "our userspace emulation rejects this race" shows up as a
+//! failure here.
+
+use crate::{resolvers::ResolverBackend, tests::common as tests_common, Root};
+
+use std::{
+    fs, os as stdos,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Error;
+use rustix::fs::{self as rustix_fs, RenameFlags, CWD};
+
+// Long enough to exercise a good number of interleavings between the racer
+// and resolver loops without making the test suite noticeably slower.
+const RACE_DURATION: Duration = Duration::from_millis(300);
+
+fn race_resolve(backend: ResolverBackend, root_dir: &Path) -> Result<(), Error> {
+    let mut root = Root::open(root_dir)?;
+    root.set_resolver_backend(backend);
+    if !root.resolver_backend().supported() {
+        // Skip if this backend isn't supported on the current kernel.
+        return Ok(());
+    }
+
+    // "a/b/c" starts out as a real directory (already containing "d", from
+    // create_race_tree). "a/b/c-evil" is a symlink escaping root entirely,
+    // pointing at a sibling tmpdir that -- crucially -- also has a "d" entry
+    // of its own, so a resolver that forgets to re-verify after following
+    // the race would succeed with a handle to the *wrong* "d" instead of
+    // just failing.
+    let c_path = root_dir.join("a/b/c");
+    let evil_path = root_dir.join("a/b/c-evil");
+
+    let outside_dir = tempfile::TempDir::new()?;
+    fs::create_dir(outside_dir.path().join("d"))?;
+    stdos::unix::fs::symlink(outside_dir.path(), &evil_path)?;
+
+    let stop = AtomicBool::new(false);
+    let deadline = Instant::now() + RACE_DURATION;
+
+    thread::scope(|scope| -> Result<(), Error> {
+        scope.spawn(|| {
+            // RENAME_EXCHANGE swaps the two names atomically, so there is
+            // never a window where "a/b/c" doesn't exist at all -- only a
+            // window where it's the escaping symlink rather than the real
+            // directory. Any failure here is just the other side of the
+            // race and isn't interesting to report.
+            while !stop.load(Ordering::Relaxed) {
+                let _ = rustix_fs::renameat_with(CWD, &c_path, CWD, &evil_path, RenameFlags::EXCHANGE);
+            }
+        });
+
+        while Instant::now() < deadline {
+            match root.resolve("a/b/c/d") {
+                Ok(handle) => {
+                    use crate::utils::FdExt;
+                    let real_path = handle.as_unsafe_path_unchecked()?;
+                    assert!(
+                        real_path.starts_with(root_dir),
+                        "resolve(\"a/b/c/d\") raced its way to a handle outside root: {real_path:?}",
+                    );
+                }
+                Err(err) => {
+                    use crate::error::ErrorKind;
+                    assert!(
+                        err.is_safety_violation()
+                            || matches!(
+                                err.kind(),
+                                ErrorKind::OsError(Some(libc::ENOENT))
+                                    | ErrorKind::OsError(Some(libc::ENOTDIR))
+                                    | ErrorKind::OsError(Some(libc::ELOOP))
+                            ),
+                        "resolve(\"a/b/c/d\") failed in an unexpected way during the race: {err:?}",
+                    );
+                }
+            }
+        }
+
+        stop.store(true, Ordering::Relaxed);
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+fn race_resolve_opath() -> Result<(), Error> {
+    let (_tmpdir, root_dir) = tests_common::create_race_tree()?;
+    race_resolve(ResolverBackend::EmulatedOpath, &root_dir)
+}
+
+#[test]
+fn race_resolve_openat2() -> Result<(), Error> {
+    let (_tmpdir, root_dir) = tests_common::create_race_tree()?;
+    race_resolve(ResolverBackend::KernelOpenat2, &root_dir)
+}
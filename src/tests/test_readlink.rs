@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 Aleksa Sarai <cyphar@cyphar.com>
+ * Copyright (C) 2019-2025 SUSE LLC
+ *
+ * This program is free software: you can redistribute it and/or modify it
+ * under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or (at your
+ * option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY
+ * or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License
+ * for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Tests for [`Root::readlink`] and [`Handle::readlink`].
+
+use crate::{error::ErrorKind, Root};
+
+use std::{fs, os::unix::fs as unixfs, path::Path};
+
+use anyhow::Error;
+
+#[test]
+fn readlink_root_and_handle_agree() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+
+    fs::File::create(p.join("target"))?;
+    unixfs::symlink("target", p.join("link"))?;
+
+    let root = Root::open(p)?;
+
+    // The path-based variant reads the link without following it.
+    assert_eq!(root.readlink("link")?, Path::new("target"));
+
+    // A Handle obtained via resolve_nofollow() must report the same target.
+    let handle = root.resolve_nofollow("link")?;
+    assert_eq!(handle.readlink()?, Path::new("target"));
+
+    Ok(())
+}
+
+#[test]
+fn readlink_non_symlink_is_einval() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::File::create(p.join("regular"))?;
+
+    let root = Root::open(p)?;
+    let handle = root.resolve_nofollow("regular")?;
+
+    let err = handle.readlink().expect_err("readlink of a regular file must fail");
+    assert_eq!(err.kind(), ErrorKind::OsError(Some(libc::EINVAL)));
+
+    Ok(())
+}
+
+#[test]
+fn readlink_survives_path_rename() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+
+    unixfs::symlink("original-target", p.join("link"))?;
+
+    let root = Root::open(p)?;
+    let handle = root.resolve_nofollow("link")?;
+
+    // Renaming the path out from under the handle must not affect a
+    // Handle::readlink() call -- it reads straight off the open fd.
+    fs::rename(p.join("link"), p.join("moved-link"))?;
+
+    assert_eq!(handle.readlink()?, Path::new("original-target"));
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 Aleksa Sarai <cyphar@cyphar.com>
+ * Copyright (C) 2019-2025 SUSE LLC
+ *
+ * This program is free software: you can redistribute it and/or modify it
+ * under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or (at your
+ * option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY
+ * or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License
+ * for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Tests for [`Root::rename`].
+
+use crate::{flags::RenameFlags, tests::common as tests_common, Root};
+
+use std::{
+    fs, os as stdos,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Error;
+use rustix::fs::{self as rustix_fs, CWD};
+
+// Long enough to exercise a good number of interleavings between the racer
+// and rename loops without making the test suite noticeably slower.
+const RACE_DURATION: Duration = Duration::from_millis(300);
+
+#[test]
+fn rename_basic() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::write(p.join("a"), b"a-contents")?;
+
+    let root = Root::open(p)?;
+    root.rename("a", "b", RenameFlags::empty())?;
+
+    assert!(!p.join("a").exists());
+    assert_eq!(fs::read(p.join("b"))?, b"a-contents");
+
+    Ok(())
+}
+
+#[test]
+fn rename_noreplace_refuses_existing_target() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::write(p.join("a"), b"a-contents")?;
+    fs::write(p.join("b"), b"b-contents")?;
+
+    let root = Root::open(p)?;
+    let err = root
+        .rename("a", "b", RenameFlags::RENAME_NOREPLACE)
+        .expect_err("RENAME_NOREPLACE must fail if the target already exists");
+    eprintln!("got expected error: {err}");
+
+    // Neither file should have been touched.
+    assert_eq!(fs::read(p.join("a"))?, b"a-contents");
+    assert_eq!(fs::read(p.join("b"))?, b"b-contents");
+
+    Ok(())
+}
+
+#[test]
+fn rename_exchange_swaps_both_inodes() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::write(p.join("a"), b"a-contents")?;
+    fs::write(p.join("b"), b"b-contents")?;
+
+    let root = Root::open(p)?;
+    root.rename("a", "b", RenameFlags::RENAME_EXCHANGE)?;
+
+    assert_eq!(fs::read(p.join("a"))?, b"b-contents");
+    assert_eq!(fs::read(p.join("b"))?, b"a-contents");
+
+    Ok(())
+}
+
+#[test]
+fn rename_exchange_requires_both_sides_to_exist() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::write(p.join("a"), b"a-contents")?;
+
+    let root = Root::open(p)?;
+    let err = root
+        .rename("a", "nonexistent", RenameFlags::RENAME_EXCHANGE)
+        .expect_err("RENAME_EXCHANGE must fail if either side is missing");
+    eprintln!("got expected error: {err}");
+
+    Ok(())
+}
+
+#[test]
+fn rename_rejects_trailing_dotdot() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::create_dir(p.join("a"))?;
+
+    let root = Root::open(p)?;
+    let err = root
+        .rename("a/..", "b", RenameFlags::empty())
+        .expect_err("rename of a path ending in \"..\" must be rejected");
+    eprintln!("got expected error: {err}");
+
+    Ok(())
+}
+
+// One thread repeatedly swaps "a/b/c" between a real in-root directory and a
+// symlink escaping outside of root (as `create_race_tree()` stages), while
+// another thread renames through it in a loop. The rename must either
+// operate on the real in-root inode (whichever parent directory it resolved
+// at the time) or fail outright -- it must never be able to use the
+// escaping symlink as a rename target.
+#[test]
+fn race_rename_escape() -> Result<(), Error> {
+    let (_tmpdir, root_dir) = tests_common::create_race_tree()?;
+    let root = Root::open(&root_dir)?;
+
+    let c_path = root_dir.join("a/b/c");
+    let evil_path = root_dir.join("a/b/c-evil");
+
+    let outside_dir = tempfile::TempDir::new()?;
+    fs::create_dir(outside_dir.path().join("d"))?;
+    stdos::unix::fs::symlink(outside_dir.path(), &evil_path)?;
+
+    let stop = AtomicBool::new(false);
+    let deadline = Instant::now() + RACE_DURATION;
+
+    thread::scope(|scope| -> Result<(), Error> {
+        scope.spawn(|| {
+            // Any failure here is just the other side of the race and isn't
+            // interesting to report.
+            while !stop.load(Ordering::Relaxed) {
+                let _ = rustix_fs::renameat_with(
+                    CWD,
+                    &c_path,
+                    CWD,
+                    &evil_path,
+                    rustix_fs::RenameFlags::EXCHANGE,
+                );
+            }
+        });
+
+        while Instant::now() < deadline {
+            match root.rename("a/b/c/d", "a/b/c/d-renamed", RenameFlags::empty()) {
+                Ok(()) => {
+                    // Put it back (best-effort -- "c" may currently be the
+                    // escaping symlink, in which case this just fails and
+                    // the next forward rename attempt will too).
+                    let _ = root.rename("a/b/c/d-renamed", "a/b/c/d", RenameFlags::empty());
+                }
+                Err(err) => {
+                    use crate::error::ErrorKind;
+                    assert!(
+                        err.is_safety_violation()
+                            || matches!(
+                                err.kind(),
+                                ErrorKind::OsError(Some(libc::ENOENT))
+                                    | ErrorKind::OsError(Some(libc::ENOTDIR))
+                                    | ErrorKind::OsError(Some(libc::ELOOP))
+                            ),
+                        "rename(\"a/b/c/d\", ...) failed in an unexpected way during the race: {err:?}",
+                    );
+                }
+            }
+        }
+
+        stop.store(true, Ordering::Relaxed);
+        Ok(())
+    })?;
+
+    Ok(())
+}
@@ -501,19 +501,29 @@ resolve_tests! {
     }
 }
 
+resolve_tests! {
+    // Verify RESOLVE_NO_XDEV / ResolverFlags::NO_XDEV behaviour.
+    [with_mount_tree] {
+        mount_xdev_allowed_dir: resolve("mount-xdev/inside") => Ok(("mount-xdev/inside", libc::S_IFDIR));
+        mount_xdev_allowed_file: resolve("mount-xdev/file") => Ok(("mount-xdev/file", libc::S_IFREG));
+        mount_xdev_forbidden_dir: resolve("mount-xdev/inside", rflags = NO_XDEV) => Err(ErrorKind::OsError(Some(libc::EXDEV)));
+        mount_xdev_forbidden_file: resolve("mount-xdev/file", rflags = NO_XDEV) => Err(ErrorKind::OsError(Some(libc::EXDEV)));
+    }
+}
+
 mod utils {
     use crate::{
         error::ErrorKind,
         flags::OpenFlags,
         syscalls,
         tests::{
-            common::{self as tests_common, LookupResult},
+            common::{self as tests_common, LookupResult, MountType},
             traits::{HandleImpl, RootImpl},
         },
         utils::FdExt,
     };
 
-    use std::{os::unix::fs::MetadataExt, path::Path};
+    use std::{fs, os::unix::fs::MetadataExt, path::Path};
 
     use anyhow::{Context, Error};
     use pretty_assertions::assert_eq;
@@ -553,6 +563,30 @@ mod utils {
         })
     }
 
+    /// Like [`with_basic_tree`], but additionally mounts a fresh tmpfs over
+    /// `mount-xdev/` so that descending into it crosses a device boundary --
+    /// used to validate `ResolverFlags::NO_XDEV` against `RESOLVE_NO_XDEV` on
+    /// both backends.
+    pub(super) fn with_mount_tree<F>(func: F) -> Result<(), Error>
+    where
+        F: FnOnce(&Path) -> Result<(), Error>,
+    {
+        tests_common::in_mnt_ns(|| {
+            let root_dir = tests_common::create_basic_tree()?;
+
+            let submount = root_dir.path().join("mount-xdev");
+            fs::create_dir(&submount).context("mkdir mount-xdev")?;
+            tests_common::mount(&submount, MountType::Tmpfs).context("mount tmpfs on mount-xdev")?;
+            fs::create_dir(submount.join("inside")).context("mkdir mount-xdev/inside")?;
+            fs::File::create(submount.join("file")).context("create mount-xdev/file")?;
+
+            let res = func(root_dir.path());
+
+            let _root_dir = root_dir; // make sure tmpdir is kept alive
+            res
+        })
+    }
+
     pub(super) fn check_root_resolve<R, H>(
         root: R,
         unsafe_path: impl AsRef<Path>,
@@ -0,0 +1,403 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 Aleksa Sarai <cyphar@cyphar.com>
+ * Copyright (C) 2019-2025 SUSE LLC
+ *
+ * This program is free software: you can redistribute it and/or modify it
+ * under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or (at your
+ * option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY
+ * or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License
+ * for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Differential fuzzing of [`ResolverBackend::KernelOpenat2`] against
+//! [`ResolverBackend::EmulatedOpath`].
+//!
+//! [`test_resolve`](super::test_resolve) pins down a static matrix of
+//! hand-picked trees and lookup paths (in particular `create_basic_tree`'s
+//! `nonlexical_*` / `dangling*` fixtures). That matrix is valuable as a
+//! regression suite, but it can only ever cover the cases we thought to
+//! write down. This module instead generates random trees and lookups and
+//! asserts that the two backends *agree* on every one of them -- if they
+//! ever disagree, one of them has a bug, since both are meant to implement
+//! the same resolution semantics.
+//!
+//! NOTE: This is a hand-rolled stand-in for a "real" property-based test
+//! (the obvious tools would be `proptest` or `arbitrary`). This checkout has
+//! no `Cargo.toml` anywhere, so there is nowhere to add such a dependency --
+//! what follows is a minimal seeded PRNG generator plus a linear shrink
+//! loop, good enough to pin a failing case down to a small reproducible
+//! tree+path without pulling in an external crate.
+
+use crate::{
+    error::ErrorKind,
+    flags::ResolverFlags,
+    resolvers::ResolverBackend,
+    utils::FdExt,
+    Root,
+};
+
+use std::{
+    fs,
+    os::unix::fs::{self as unixfs, MetadataExt},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+use tempfile::TempDir;
+
+/// Number of random (tree, path) samples checked by each `#[test]` in this
+/// module. Chosen to keep the suite fast enough for `cargo test` while still
+/// giving the generator a realistic chance of hitting an edge case.
+const NUM_SAMPLES: u32 = 512;
+
+/// Bound on the number of filesystem entries in a single generated tree --
+/// these are meant to be small enough that a failing case is already close
+/// to minimal, not to stress-test large directory trees.
+const MAX_NODES: usize = 12;
+
+/// Bound on the number of path components in a single generated lookup.
+const MAX_PATH_COMPONENTS: usize = 8;
+
+/// A tiny xorshift64* PRNG.
+///
+/// This is not meant to be a good general-purpose PRNG -- it only needs to
+/// be cheap, deterministic given a seed, and to not get stuck in short
+/// cycles for the handful of values we draw per sample.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // xorshift64* is undefined for a zero state.
+        Self(seed | 1)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a value in `0..bound`. `bound` must be non-zero.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Returns `true` with probability `1/n`.
+    fn one_in(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+}
+
+/// A single planned filesystem entry, relative to the tree root.
+#[derive(Clone, Debug)]
+enum PlannedNode {
+    Dir,
+    File,
+    /// Symlink with the given (possibly bogus, possibly absolute, possibly
+    /// self-referential) target.
+    Symlink(String),
+}
+
+/// A randomly-generated filesystem tree, described before it is actually
+/// created on-disk.
+#[derive(Clone, Debug, Default)]
+struct TreePlan {
+    /// `(relative path, node)`, in creation order (parents always precede
+    /// their children).
+    nodes: Vec<(PathBuf, PlannedNode)>,
+}
+
+impl TreePlan {
+    fn component_names(&self) -> Vec<String> {
+        self.nodes
+            .iter()
+            .filter_map(|(path, _)| path.file_name()?.to_str().map(str::to_string))
+            .collect()
+    }
+}
+
+const NAME_POOL: &[&str] = &["a", "b", "c", "foo", "bar", ".hidden"];
+
+/// Generates a small random tree. Directories are created eagerly so that
+/// later entries can be nested inside them; symlinks may dangle, point
+/// outside the tree via `..`/absolute paths, or point at themselves/each
+/// other to force `ELOOP`.
+fn gen_tree(rng: &mut Rng) -> TreePlan {
+    let mut plan = TreePlan::default();
+    let mut dirs = vec![PathBuf::new()]; // "" == the tree root.
+
+    let num_nodes = 1 + rng.below(MAX_NODES);
+    for _ in 0..num_nodes {
+        let parent = dirs[rng.below(dirs.len())].clone();
+        let name = NAME_POOL[rng.below(NAME_POOL.len())];
+        let path = parent.join(name);
+
+        // Skip accidental duplicate paths rather than erroring out -- a
+        // smaller tree is a perfectly fine sample.
+        if plan.nodes.iter().any(|(p, _)| *p == path) {
+            continue;
+        }
+
+        let node = match rng.below(4) {
+            0 => {
+                dirs.push(path.clone());
+                PlannedNode::Dir
+            }
+            1 => PlannedNode::File,
+            2 => {
+                // A dangling or bogus-component target.
+                PlannedNode::Symlink(format!("{}-missing", NAME_POOL[rng.below(NAME_POOL.len())]))
+            }
+            _ => {
+                // A target built from real/bogus components and "..", to
+                // exercise both non-lexical resolution and root-escape
+                // attempts.
+                let mut target = String::new();
+                if rng.one_in(2) {
+                    target.push('/'); // Absolute target.
+                }
+                let hops = 1 + rng.below(3);
+                for hop in 0..hops {
+                    if hop > 0 {
+                        target.push('/');
+                    }
+                    match rng.below(3) {
+                        0 => target.push_str(".."),
+                        1 => target.push_str(name), // Self-referential.
+                        _ => target.push_str(NAME_POOL[rng.below(NAME_POOL.len())]),
+                    }
+                }
+                PlannedNode::Symlink(target)
+            }
+        };
+        plan.nodes.push((path, node));
+    }
+
+    plan
+}
+
+/// Creates `plan` under a fresh temporary directory.
+fn materialize(plan: &TreePlan) -> Result<TempDir, Error> {
+    let root = TempDir::new().context("create fuzz tree tmpdir")?;
+
+    for (path, node) in &plan.nodes {
+        let full_path = root.path().join(path);
+        match node {
+            PlannedNode::Dir => {
+                fs::create_dir(&full_path)
+                    .with_context(|| format!("mkdir {full_path:?}"))?;
+            }
+            PlannedNode::File => {
+                fs::File::create(&full_path).with_context(|| format!("create {full_path:?}"))?;
+            }
+            PlannedNode::Symlink(target) => {
+                unixfs::symlink(target, &full_path)
+                    .with_context(|| format!("symlink {full_path:?} -> {target:?}"))?;
+            }
+        }
+    }
+
+    Ok(root)
+}
+
+/// Builds a random lookup path out of real tree components, bogus
+/// components, and `.`/`..`/repeated-`/` noise.
+fn gen_lookup_path(rng: &mut Rng, plan: &TreePlan) -> String {
+    let names = plan.component_names();
+    let num_components = 1 + rng.below(MAX_PATH_COMPONENTS);
+
+    let mut path = String::new();
+    if rng.one_in(8) {
+        path.push('/'); // Leading-slash lookups are still root-relative.
+    }
+    for i in 0..num_components {
+        if i > 0 {
+            // Occasionally double up the separator.
+            path.push('/');
+            if rng.one_in(6) {
+                path.push('/');
+            }
+        }
+        match rng.below(4) {
+            0 => path.push('.'),
+            1 => path.push_str(".."),
+            _ if !names.is_empty() && rng.one_in(2) => {
+                path.push_str(&names[rng.below(names.len())]);
+            }
+            _ => path.push_str(NAME_POOL[rng.below(NAME_POOL.len())]),
+        }
+    }
+    if rng.one_in(8) {
+        path.push('/'); // Trailing slash.
+    }
+
+    path
+}
+
+/// A resolution outcome, normalized enough to compare across backends
+/// without caring about incidental differences (e.g. which exact handle
+/// type was returned).
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    Ok {
+        /// Root-relative path of the resolved handle.
+        path: PathBuf,
+        /// `S_IF*` file type bits.
+        file_type: u32,
+    },
+    /// `errno`, normalized to the handful of values the differential check
+    /// cares about distinguishing -- anything else is kept as `Other` with
+    /// its full [`ErrorKind`], so the backends must agree on it exactly.
+    Err(NormalizedErrno),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum NormalizedErrno {
+    NotFound,
+    Loop,
+    NotADirectory,
+    CrossDevice,
+    Other(ErrorKind),
+}
+
+fn normalize_errno(kind: ErrorKind) -> NormalizedErrno {
+    match kind {
+        ErrorKind::OsError(Some(libc::ENOENT)) => NormalizedErrno::NotFound,
+        ErrorKind::OsError(Some(libc::ELOOP)) => NormalizedErrno::Loop,
+        ErrorKind::OsError(Some(libc::ENOTDIR)) => NormalizedErrno::NotADirectory,
+        ErrorKind::OsError(Some(libc::EXDEV)) => NormalizedErrno::CrossDevice,
+        other => NormalizedErrno::Other(other),
+    }
+}
+
+/// Resolves `path` against `root_dir` using the given backend and flags,
+/// returning a normalized [`Outcome`].
+fn resolve_outcome(
+    root_dir: &Path,
+    backend: ResolverBackend,
+    rflags: ResolverFlags,
+    no_follow_trailing: bool,
+    path: &str,
+) -> Result<Outcome, Error> {
+    let mut root = Root::open(root_dir).context("open fuzz tree root")?;
+    root.set_resolver_backend(backend);
+    root.set_resolver_flags(rflags);
+
+    let result = if no_follow_trailing {
+        root.resolve_nofollow(path)
+    } else {
+        root.resolve(path)
+    };
+
+    match result {
+        Ok(handle) => {
+            let real_path = handle.as_unsafe_path_unchecked()?;
+            let rel_path = real_path
+                .strip_prefix(root_dir)
+                .unwrap_or(&real_path)
+                .to_path_buf();
+            let file_type = handle.metadata()?.mode() & libc::S_IFMT;
+            Ok(Outcome::Ok {
+                path: rel_path,
+                file_type,
+            })
+        }
+        Err(err) => Ok(Outcome::Err(normalize_errno(err.kind()))),
+    }
+}
+
+/// Checks that both resolver backends agree on `path` against the tree
+/// rooted at `root_dir`.
+fn check_backends_agree(
+    root_dir: &Path,
+    rflags: ResolverFlags,
+    no_follow_trailing: bool,
+    path: &str,
+) -> Result<(), Error> {
+    let openat2 = resolve_outcome(
+        root_dir,
+        ResolverBackend::KernelOpenat2,
+        rflags,
+        no_follow_trailing,
+        path,
+    )?;
+    if !ResolverBackend::KernelOpenat2.supported() {
+        // Nothing to cross-check against on kernels without openat2.
+        return Ok(());
+    }
+    let opath = resolve_outcome(
+        root_dir,
+        ResolverBackend::EmulatedOpath,
+        rflags,
+        no_follow_trailing,
+        path,
+    )?;
+
+    anyhow::ensure!(
+        openat2 == opath,
+        "resolver backends disagree on {path:?} (no_follow_trailing={no_follow_trailing}): \
+         openat2={openat2:?} opath={opath:?}",
+    );
+    Ok(())
+}
+
+/// Runs one random (tree, path) sample under the given `seed`.
+fn run_sample(seed: u64, rflags: ResolverFlags, no_follow_trailing: bool) -> Result<(), Error> {
+    let mut rng = Rng::new(seed);
+    let plan = gen_tree(&mut rng);
+    let lookup_path = gen_lookup_path(&mut rng, &plan);
+
+    let tree = materialize(&plan)?;
+    let result = check_backends_agree(tree.path(), rflags, no_follow_trailing, &lookup_path);
+    let _tree = tree; // Keep the tmpdir alive until after resolution.
+
+    result.with_context(|| format!("fuzz seed {seed} (tree={plan:?}, path={lookup_path:?})"))
+}
+
+/// If `seed` fails, looks for a smaller failing seed nearby so the failure
+/// message points at something closer to minimal. This is a linear search,
+/// not real shrinking (there is no structured "smaller" relation on a raw
+/// seed), but in practice nearby seeds tend to generate smaller trees and
+/// shorter paths, which is enough to make triage easier.
+fn shrink_and_panic(seed: u64, rflags: ResolverFlags, no_follow_trailing: bool, err: Error) -> ! {
+    let mut smallest = (seed, err);
+    for candidate in 0..seed.min(64) {
+        if let Err(err) = run_sample(candidate, rflags, no_follow_trailing) {
+            smallest = (candidate, err);
+            break;
+        }
+    }
+    let (seed, err) = smallest;
+    panic!("fuzz failure, minimal known-bad seed {seed}: {err:?}");
+}
+
+fn fuzz_resolve(rflags: ResolverFlags, no_follow_trailing: bool) -> Result<(), Error> {
+    for seed in 0..u64::from(NUM_SAMPLES) {
+        if let Err(err) = run_sample(seed, rflags, no_follow_trailing) {
+            shrink_and_panic(seed, rflags, no_follow_trailing, err);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn fuzz_resolve_default() -> Result<(), Error> {
+    fuzz_resolve(ResolverFlags::empty(), false)
+}
+
+#[test]
+fn fuzz_resolve_nofollow_trailing() -> Result<(), Error> {
+    fuzz_resolve(ResolverFlags::empty(), true)
+}
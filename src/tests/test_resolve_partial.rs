@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 Aleksa Sarai <cyphar@cyphar.com>
+ * Copyright (C) 2019-2025 SUSE LLC
+ *
+ * This program is free software: you can redistribute it and/or modify it
+ * under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or (at your
+ * option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY
+ * or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License
+ * for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Tests for [`Root::resolve_partial`], checked against both resolver
+//! backends.
+
+use crate::{resolvers::ResolverBackend, tests::common as tests_common, utils::FdExt, Root};
+
+use std::path::Path;
+
+use anyhow::Error;
+use pretty_assertions::assert_eq;
+
+fn check_resolve_partial(
+    root: &Root,
+    unsafe_path: &str,
+    expected_path: &str,
+    expected_remaining: &str,
+) -> Result<(), Error> {
+    let root_dir = root.as_unsafe_path_unchecked()?;
+    let partial = root.resolve_partial(unsafe_path)?;
+
+    let real_handle_path = partial.handle.as_unsafe_path_unchecked()?;
+    assert_eq!(
+        real_handle_path,
+        root_dir.join(expected_path.trim_start_matches('/')),
+        "resolve_partial({unsafe_path:?}) handle mismatch",
+    );
+    assert_eq!(
+        partial.remaining,
+        Path::new(expected_remaining),
+        "resolve_partial({unsafe_path:?}) remaining mismatch",
+    );
+    Ok(())
+}
+
+fn test_resolve_partial_with(backend: ResolverBackend) -> Result<(), Error> {
+    let root_dir = tests_common::create_basic_tree()?;
+
+    let mut root = Root::open(root_dir.path())?;
+    root.set_resolver_backend(backend);
+    if !root.resolver_backend().supported() {
+        // Skip if this backend isn't supported on the current kernel.
+        return Ok(());
+    }
+
+    // A path that exists in full resolves with no remainder.
+    check_resolve_partial(&root, "b/c/d/e/f", "b/c/d/e/f", "")?;
+
+    // A path that doesn't exist past an existing directory.
+    check_resolve_partial(&root, "b/c/d/e/f/g/h", "b/c/d/e/f", "g/h")?;
+    check_resolve_partial(&root, "a/b/c/d/e/f/g/h", "a", "b/c/d/e/f/g/h")?;
+
+    // Symlinks encountered along the resolved prefix are still followed
+    // (resolve_partial has the same "follow" semantics as resolve()), and
+    // the remainder doesn't include any of the symlink's own target.
+    check_resolve_partial(&root, "link3/target_abs/foo/bar", "target", "foo/bar")?;
+
+    // A totally nonexistent top-level path resolves to the root itself.
+    check_resolve_partial(&root, "nonexistent/foo/bar", ".", "nonexistent/foo/bar")?;
+
+    Ok(())
+}
+
+#[test]
+fn resolve_partial_openat2() -> Result<(), Error> {
+    test_resolve_partial_with(ResolverBackend::KernelOpenat2)
+}
+
+#[test]
+fn resolve_partial_opath() -> Result<(), Error> {
+    test_resolve_partial_with(ResolverBackend::EmulatedOpath)
+}
@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 Aleksa Sarai <cyphar@cyphar.com>
+ * Copyright (C) 2019-2025 SUSE LLC
+ *
+ * This program is free software: you can redistribute it and/or modify it
+ * under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or (at your
+ * option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY
+ * or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License
+ * for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Tests for [`Root::walk`].
+
+use crate::{error::ErrorKind, FileType, Root};
+
+use std::{collections::HashSet, fs, os::unix::fs as unixfs, path::PathBuf};
+
+use anyhow::Error;
+
+fn build_tree() -> Result<tempfile::TempDir, Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+
+    fs::create_dir(p.join("a"))?;
+    fs::File::create(p.join("a/x"))?;
+    fs::File::create(p.join("a/y"))?;
+    fs::create_dir(p.join("a/b"))?;
+    fs::File::create(p.join("a/b/z"))?;
+    fs::File::create(p.join("top-file"))?;
+    unixfs::symlink("a", p.join("link-to-a"))?;
+
+    Ok(root_dir)
+}
+
+#[test]
+fn walk_preorder_yields_dir_before_children() -> Result<(), Error> {
+    let root_dir = build_tree()?;
+    let root = Root::open(root_dir.path())?;
+
+    let mut seen = Vec::new();
+    for entry in root.walk("a")? {
+        let entry = entry?;
+        seen.push((entry.path.clone(), entry.file_type));
+    }
+
+    let pos = |path: &str| {
+        seen.iter()
+            .position(|(p, _)| p == &PathBuf::from(path))
+            .unwrap_or_else(|| panic!("{path:?} missing from walk: {seen:?}"))
+    };
+
+    assert_eq!(seen.len(), 5, "unexpected walk entries: {seen:?}");
+    // Pre-order: every directory comes before its children.
+    assert!(pos("a") < pos("a/x"));
+    assert!(pos("a") < pos("a/b"));
+    assert!(pos("a/b") < pos("a/b/z"));
+
+    Ok(())
+}
+
+#[test]
+fn walk_contents_first_yields_dir_after_children() -> Result<(), Error> {
+    let root_dir = build_tree()?;
+    let root = Root::open(root_dir.path())?;
+
+    let mut seen = Vec::new();
+    for entry in root.walk("a")?.contents_first(true) {
+        let entry = entry?;
+        seen.push(entry.path);
+    }
+
+    let pos = |path: &str| {
+        seen.iter()
+            .position(|p| p == &PathBuf::from(path))
+            .unwrap_or_else(|| panic!("{path:?} missing from walk: {seen:?}"))
+    };
+
+    // Post-order: every directory comes after its children.
+    assert!(pos("a/x") < pos("a"));
+    assert!(pos("a/b/z") < pos("a/b"));
+    assert!(pos("a/b") < pos("a"));
+
+    Ok(())
+}
+
+#[test]
+fn walk_max_depth_stops_descent() -> Result<(), Error> {
+    let root_dir = build_tree()?;
+    let root = Root::open(root_dir.path())?;
+
+    let seen = root
+        .walk("a")?
+        .max_depth(1)
+        .collect::<Result<Vec<_>, _>>()?;
+    let paths: HashSet<_> = seen.into_iter().map(|entry| entry.path).collect();
+
+    // Depth 0 is "a" itself, depth 1 is its direct children -- "a/b"'s own
+    // contents ("a/b/z") are one level too deep and must not appear.
+    assert!(paths.contains(&PathBuf::from("a")));
+    assert!(paths.contains(&PathBuf::from("a/x")));
+    assert!(paths.contains(&PathBuf::from("a/b")));
+    assert!(!paths.contains(&PathBuf::from("a/b/z")));
+
+    Ok(())
+}
+
+#[test]
+fn walk_does_not_follow_symlinks_by_default() -> Result<(), Error> {
+    let root_dir = build_tree()?;
+    let root = Root::open(root_dir.path())?;
+
+    let seen = root.walk(".")?.collect::<Result<Vec<_>, _>>()?;
+    let link = seen
+        .iter()
+        .find(|entry| entry.path == PathBuf::from("link-to-a"))
+        .expect("link-to-a missing from walk");
+    assert_eq!(link.file_type, FileType::Symlink);
+
+    // The symlink was not descended into, so its target's contents must not
+    // show up under the symlink's own path.
+    assert!(!seen
+        .iter()
+        .any(|entry| entry.path == PathBuf::from("link-to-a/x")));
+
+    Ok(())
+}
+
+#[test]
+fn walk_follow_links_detects_symlink_loop() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::create_dir(p.join("a"))?;
+    unixfs::symlink("..", p.join("a/up"))?;
+
+    let root = Root::open(p)?;
+
+    let results = root
+        .walk(".")?
+        .follow_links(true)
+        .collect::<Vec<_>>();
+
+    let has_loop_error = results.iter().any(|entry| match entry {
+        Err(err) => err.kind() == ErrorKind::OsError(Some(libc::ELOOP)),
+        Ok(_) => false,
+    });
+    assert!(
+        has_loop_error,
+        "expected an ELOOP entry from the a/up -> .. cycle: {results:?}"
+    );
+
+    Ok(())
+}
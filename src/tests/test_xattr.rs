@@ -0,0 +1,265 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 Aleksa Sarai <cyphar@cyphar.com>
+ * Copyright (C) 2019-2025 SUSE LLC
+ *
+ * This program is free software: you can redistribute it and/or modify it
+ * under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or (at your
+ * option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY
+ * or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License
+ * for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Tests for [`Handle`]'s extended attribute methods.
+
+use crate::{tests::common as tests_common, Root, XattrFlags};
+
+use std::{
+    fs,
+    os::unix::fs as unixfs,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Error;
+use rustix::fs as rustix_fs;
+
+// Long enough to exercise a good number of interleavings between the racer
+// and the handle-based xattr reads without making the test suite noticeably
+// slower.
+const RACE_DURATION: Duration = Duration::from_millis(300);
+
+#[test]
+fn xattr_roundtrip_on_regular_file() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::File::create(p.join("file"))?;
+
+    let root = Root::open(p)?;
+    let handle = root.resolve("file")?;
+
+    // tmpfs may not support user.* xattrs in every test environment -- skip
+    // rather than fail if so.
+    if let Err(err) = handle.set_xattr("user.foo", b"bar", XattrFlags::empty()) {
+        eprintln!("skipping xattr test, filesystem doesn't support it: {err}");
+        return Ok(());
+    }
+
+    assert_eq!(handle.get_xattr("user.foo")?, b"bar");
+    assert!(handle
+        .list_xattrs()?
+        .iter()
+        .any(|name| name == "user.foo"));
+
+    Ok(())
+}
+
+#[test]
+fn xattr_create_flag_fails_if_already_set() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::File::create(p.join("file"))?;
+
+    let root = Root::open(p)?;
+    let handle = root.resolve("file")?;
+
+    if handle
+        .set_xattr("user.foo", b"bar", XattrFlags::empty())
+        .is_err()
+    {
+        eprintln!("skipping xattr test, filesystem doesn't support it");
+        return Ok(());
+    }
+
+    let err = handle
+        .set_xattr("user.foo", b"baz", XattrFlags::CREATE)
+        .expect_err("XATTR_CREATE must fail if the attribute already exists");
+    eprintln!("got expected error: {err}");
+
+    Ok(())
+}
+
+#[test]
+fn xattr_on_symlink_handle_does_not_touch_target() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::File::create(p.join("target"))?;
+    unixfs::symlink("target", p.join("link"))?;
+
+    let root = Root::open(p)?;
+    let link_handle = root.resolve_nofollow("link")?;
+    let target_handle = root.resolve("target")?;
+
+    if target_handle
+        .set_xattr("user.bar", b"probe", XattrFlags::empty())
+        .is_err()
+    {
+        eprintln!("skipping xattr test, filesystem doesn't support it");
+        return Ok(());
+    }
+
+    // Linux refuses user.* xattrs on a symlink itself (EPERM), so this must
+    // fail rather than land on the target.
+    assert!(link_handle
+        .set_xattr("user.foo", b"on-link", XattrFlags::empty())
+        .is_err());
+    // The target must be unaffected by the symlink handle's xattr.
+    assert!(target_handle.get_xattr("user.foo").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn xattr_remove() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::File::create(p.join("file"))?;
+
+    let root = Root::open(p)?;
+    let handle = root.resolve("file")?;
+
+    if handle
+        .set_xattr("user.foo", b"bar", XattrFlags::empty())
+        .is_err()
+    {
+        eprintln!("skipping xattr test, filesystem doesn't support it");
+        return Ok(());
+    }
+
+    handle.remove_xattr("user.foo")?;
+    assert!(handle.get_xattr("user.foo").is_err());
+
+    let err = handle
+        .remove_xattr("user.foo")
+        .expect_err("removing an already-removed xattr must fail");
+    eprintln!("got expected error: {err}");
+
+    Ok(())
+}
+
+#[test]
+fn root_xattr_convenience_wrappers_match_handle() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    fs::File::create(p.join("file"))?;
+    unixfs::symlink("file", p.join("link"))?;
+
+    let root = Root::open(p)?;
+
+    if root
+        .set_xattr("file", "user.foo", b"bar", XattrFlags::empty())
+        .is_err()
+    {
+        eprintln!("skipping xattr test, filesystem doesn't support it");
+        return Ok(());
+    }
+
+    assert_eq!(root.get_xattr("file", "user.foo")?, b"bar");
+    assert!(root
+        .list_xattrs("file")?
+        .iter()
+        .any(|name| name == "user.foo"));
+
+    // The nofollow wrappers must operate on the symlink itself, not "file".
+    // Linux refuses user.* xattrs on a symlink (EPERM), so both fail on
+    // "link" and "file" keeps its own value.
+    assert!(root
+        .set_xattr_nofollow("link", "user.foo", b"on-link", XattrFlags::empty())
+        .is_err());
+    assert!(root.get_xattr_nofollow("link", "user.foo").is_err());
+    assert_eq!(root.get_xattr("file", "user.foo")?, b"bar");
+
+    root.remove_xattr("file", "user.foo")?;
+    assert!(root.get_xattr("file", "user.foo").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn xattr_tree_fixture_labels() -> Result<(), Error> {
+    let root_dir = match tests_common::create_xattr_tree() {
+        Ok(root_dir) => root_dir,
+        Err(err) => {
+            eprintln!("skipping xattr test, filesystem doesn't support it: {err}");
+            return Ok(());
+        }
+    };
+
+    let root = Root::open(root_dir.path())?;
+
+    assert_eq!(root.get_xattr("file", "user.foo")?, b"bar");
+    assert_eq!(
+        root.get_security_context("labelled")?,
+        "unconfined_u:object_r:tmp_t:s0",
+    );
+    // The symlink must see "file"'s xattr through the default follow-links
+    // resolution, without needing any of its own.
+    assert_eq!(root.get_xattr("link", "user.foo")?, b"bar");
+
+    Ok(())
+}
+
+#[test]
+fn xattr_handle_immune_to_path_swap_race() -> Result<(), Error> {
+    let root_dir = tempfile::TempDir::new()?;
+    let p = root_dir.path();
+    let victim_path = p.join("victim");
+    fs::File::create(&victim_path)?;
+
+    let root = Root::open(p)?;
+    let handle = root.resolve("victim")?;
+
+    if handle
+        .set_xattr("user.race", b"original", XattrFlags::empty())
+        .is_err()
+    {
+        eprintln!("skipping xattr test, filesystem doesn't support it");
+        return Ok(());
+    }
+
+    let stop = AtomicBool::new(false);
+    let deadline = Instant::now() + RACE_DURATION;
+
+    thread::scope(|scope| -> Result<(), Error> {
+        scope.spawn(|| {
+            // Keep unlinking and recreating "victim" as a brand-new inode
+            // with a conflicting xattr value -- an attacker racing to swap
+            // the path our handle was originally resolved from. Any failure
+            // here is just the other side of the race and isn't interesting
+            // to report.
+            while !stop.load(Ordering::Relaxed) {
+                let _ = fs::remove_file(&victim_path);
+                if let Ok(attacker) = fs::File::create(&victim_path) {
+                    let _ = rustix_fs::fsetxattr(&attacker, "user.race", b"attacker", 0);
+                }
+            }
+        });
+
+        while Instant::now() < deadline {
+            // The handle was already resolved, so every read below goes
+            // through /proc/self/fd straight to the original inode --
+            // the attacker swapping "victim" on disk must never be able to
+            // redirect it.
+            assert_eq!(
+                handle.get_xattr("user.race")?,
+                b"original",
+                "handle.get_xattr() must always read the original inode's xattr, \
+                 never a path-swapped attacker's",
+            );
+        }
+
+        stop.store(true, Ordering::Relaxed);
+        Ok(())
+    })?;
+
+    Ok(())
+}
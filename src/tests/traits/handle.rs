@@ -30,11 +30,12 @@
  *  along with this program. If not, see <https://www.gnu.org/licenses/>.
  */
 
-use crate::{error::Error, flags::OpenFlags, tests::traits::ErrorImpl, Handle, HandleRef};
+use crate::{error::Error, flags::OpenFlags, tests::traits::ErrorImpl, Directory, Handle, HandleRef};
 
 use std::{
     fs::File,
     os::unix::io::{AsFd, OwnedFd},
+    path::PathBuf,
 };
 
 pub(in crate::tests) trait HandleImpl: AsFd + std::fmt::Debug + Sized {
@@ -47,6 +48,10 @@ pub(in crate::tests) trait HandleImpl: AsFd + std::fmt::Debug + Sized {
     fn try_clone(&self) -> Result<Self::Cloned, anyhow::Error>;
 
     fn reopen(&self, flags: impl Into<OpenFlags>) -> Result<File, Self::Error>;
+
+    fn read_dir(&self) -> Result<Directory, Self::Error>;
+
+    fn readlink(&self) -> Result<PathBuf, Self::Error>;
 }
 
 impl HandleImpl for Handle {
@@ -64,6 +69,14 @@ impl HandleImpl for Handle {
     fn reopen(&self, flags: impl Into<OpenFlags>) -> Result<File, Self::Error> {
         self.as_ref().reopen(flags)
     }
+
+    fn read_dir(&self) -> Result<Directory, Self::Error> {
+        self.as_ref().read_dir()
+    }
+
+    fn readlink(&self) -> Result<PathBuf, Self::Error> {
+        self.as_ref().readlink()
+    }
 }
 
 impl HandleImpl for &Handle {
@@ -81,6 +94,14 @@ impl HandleImpl for &Handle {
     fn reopen(&self, flags: impl Into<OpenFlags>) -> Result<File, Self::Error> {
         Handle::reopen(self, flags)
     }
+
+    fn read_dir(&self) -> Result<Directory, Self::Error> {
+        Handle::read_dir(self)
+    }
+
+    fn readlink(&self) -> Result<PathBuf, Self::Error> {
+        Handle::readlink(self)
+    }
 }
 
 impl HandleImpl for HandleRef<'_> {
@@ -98,6 +119,14 @@ impl HandleImpl for HandleRef<'_> {
     fn reopen(&self, flags: impl Into<OpenFlags>) -> Result<File, Self::Error> {
         self.reopen(flags)
     }
+
+    fn read_dir(&self) -> Result<Directory, Self::Error> {
+        self.read_dir()
+    }
+
+    fn readlink(&self) -> Result<PathBuf, Self::Error> {
+        self.readlink()
+    }
 }
 
 impl HandleImpl for &HandleRef<'_> {
@@ -115,4 +144,12 @@ impl HandleImpl for &HandleRef<'_> {
     fn reopen(&self, flags: impl Into<OpenFlags>) -> Result<File, Self::Error> {
         HandleRef::reopen(self, flags)
     }
+
+    fn read_dir(&self) -> Result<Directory, Self::Error> {
+        HandleRef::read_dir(self)
+    }
+
+    fn readlink(&self) -> Result<PathBuf, Self::Error> {
+        HandleRef::readlink(self)
+    }
 }
@@ -33,6 +33,7 @@
 use crate::{
     error::Error,
     flags::OpenFlags,
+    metadata::Metadata,
     procfs::{ProcfsBase, ProcfsHandleRef},
     tests::traits::ErrorImpl,
 };
@@ -47,20 +48,31 @@ pub(in crate::tests) trait ProcfsHandleImpl: std::fmt::Debug {
 
     fn open_follow(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
         flags: impl Into<OpenFlags>,
     ) -> Result<File, Self::Error>;
 
     fn open(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
         flags: impl Into<OpenFlags>,
     ) -> Result<File, Self::Error>;
 
-    fn readlink(&self, base: ProcfsBase, subpath: impl AsRef<Path>)
+    fn readlink(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>)
         -> Result<PathBuf, Self::Error>;
+
+    fn write(
+        &self,
+        base: ProcfsBase<'_>,
+        subpath: impl AsRef<Path>,
+        data: &[u8],
+    ) -> Result<(), Self::Error>;
+
+    fn read(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<Vec<u8>, Self::Error>;
+
+    fn stat(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<Metadata, Self::Error>;
 }
 
 impl<'fd> ProcfsHandleImpl for ProcfsHandleRef<'fd> {
@@ -68,7 +80,7 @@ impl<'fd> ProcfsHandleImpl for ProcfsHandleRef<'fd> {
 
     fn open_follow(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
         flags: impl Into<OpenFlags>,
     ) -> Result<File, Self::Error> {
@@ -77,7 +89,7 @@ impl<'fd> ProcfsHandleImpl for ProcfsHandleRef<'fd> {
 
     fn open(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
         flags: impl Into<OpenFlags>,
     ) -> Result<File, Self::Error> {
@@ -86,9 +98,26 @@ impl<'fd> ProcfsHandleImpl for ProcfsHandleRef<'fd> {
 
     fn readlink(
         &self,
-        base: ProcfsBase,
+        base: ProcfsBase<'_>,
         subpath: impl AsRef<Path>,
     ) -> Result<PathBuf, Self::Error> {
         self.readlink(base, subpath)
     }
+
+    fn write(
+        &self,
+        base: ProcfsBase<'_>,
+        subpath: impl AsRef<Path>,
+        data: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.write(base, subpath, data)
+    }
+
+    fn read(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<Vec<u8>, Self::Error> {
+        self.read(base, subpath)
+    }
+
+    fn stat(&self, base: ProcfsBase<'_>, subpath: impl AsRef<Path>) -> Result<Metadata, Self::Error> {
+        self.stat(base, subpath)
+    }
 }
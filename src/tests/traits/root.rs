@@ -23,7 +23,7 @@ use crate::{
     flags::{OpenFlags, RenameFlags},
     resolvers::Resolver,
     tests::traits::{ErrorImpl, HandleImpl},
-    Handle, InodeType, Root, RootRef,
+    Directory, Handle, InodeType, Metadata, Root, RootRef,
 };
 
 use std::{
@@ -49,6 +49,13 @@ pub(in crate::tests) trait RootImpl: AsFd + std::fmt::Debug + Sized {
 
     fn resolve_nofollow(&self, path: impl AsRef<Path>) -> Result<Self::Handle, Self::Error>;
 
+    // NOTE: Returned as (handle, remaining) rather than the real crate's
+    // PartialLookup, since Self::Handle differs from the real Handle type.
+    fn resolve_partial(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<(Self::Handle, PathBuf), Self::Error>;
+
     fn open_subpath(
         &self,
         path: impl AsRef<Path>,
@@ -57,6 +64,12 @@ pub(in crate::tests) trait RootImpl: AsFd + std::fmt::Debug + Sized {
 
     fn readlink(&self, path: impl AsRef<Path>) -> Result<PathBuf, Self::Error>;
 
+    fn read_dir(&self, path: impl AsRef<Path>) -> Result<Directory, Self::Error>;
+
+    fn metadata(&self, path: impl AsRef<Path>) -> Result<Metadata, Self::Error>;
+
+    fn metadata_nofollow(&self, path: impl AsRef<Path>) -> Result<Metadata, Self::Error>;
+
     fn create(&self, path: impl AsRef<Path>, inode_type: &InodeType) -> Result<(), Self::Error>;
 
     fn create_file(
@@ -78,6 +91,8 @@ pub(in crate::tests) trait RootImpl: AsFd + std::fmt::Debug + Sized {
 
     fn remove_all(&self, path: impl AsRef<Path>) -> Result<(), Self::Error>;
 
+    fn remove(&self, path: impl AsRef<Path>) -> Result<(), Self::Error>;
+
     fn rename(
         &self,
         source: impl AsRef<Path>,
@@ -116,6 +131,14 @@ impl RootImpl for Root {
         self.resolve_nofollow(path)
     }
 
+    fn resolve_partial(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<(Self::Handle, PathBuf), Self::Error> {
+        let partial = self.resolve_partial(path)?;
+        Ok((partial.handle, partial.remaining))
+    }
+
     fn open_subpath(
         &self,
         path: impl AsRef<Path>,
@@ -128,6 +151,18 @@ impl RootImpl for Root {
         self.readlink(path)
     }
 
+    fn read_dir(&self, path: impl AsRef<Path>) -> Result<Directory, Self::Error> {
+        self.read_dir(path)
+    }
+
+    fn metadata(&self, path: impl AsRef<Path>) -> Result<Metadata, Self::Error> {
+        self.metadata(path)
+    }
+
+    fn metadata_nofollow(&self, path: impl AsRef<Path>) -> Result<Metadata, Self::Error> {
+        self.metadata_nofollow(path)
+    }
+
     fn create(&self, path: impl AsRef<Path>, inode_type: &InodeType) -> Result<(), Self::Error> {
         self.create(path, inode_type)
     }
@@ -161,6 +196,10 @@ impl RootImpl for Root {
         self.remove_all(path)
     }
 
+    fn remove(&self, path: impl AsRef<Path>) -> Result<(), Self::Error> {
+        self.remove(path)
+    }
+
     fn rename(
         &self,
         source: impl AsRef<Path>,
@@ -201,6 +240,14 @@ impl RootImpl for &Root {
         Root::resolve_nofollow(self, path)
     }
 
+    fn resolve_partial(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<(Self::Handle, PathBuf), Self::Error> {
+        let partial = Root::resolve_partial(self, path)?;
+        Ok((partial.handle, partial.remaining))
+    }
+
     fn open_subpath(
         &self,
         path: impl AsRef<Path>,
@@ -213,6 +260,18 @@ impl RootImpl for &Root {
         Root::readlink(self, path)
     }
 
+    fn read_dir(&self, path: impl AsRef<Path>) -> Result<Directory, Self::Error> {
+        Root::read_dir(self, path)
+    }
+
+    fn metadata(&self, path: impl AsRef<Path>) -> Result<Metadata, Self::Error> {
+        Root::metadata(self, path)
+    }
+
+    fn metadata_nofollow(&self, path: impl AsRef<Path>) -> Result<Metadata, Self::Error> {
+        Root::metadata_nofollow(self, path)
+    }
+
     fn create(&self, path: impl AsRef<Path>, inode_type: &InodeType) -> Result<(), Self::Error> {
         Root::create(self, path, inode_type)
     }
@@ -246,6 +305,10 @@ impl RootImpl for &Root {
         Root::remove_all(self, path)
     }
 
+    fn remove(&self, path: impl AsRef<Path>) -> Result<(), Self::Error> {
+        Root::remove(self, path)
+    }
+
     fn rename(
         &self,
         source: impl AsRef<Path>,
@@ -286,6 +349,14 @@ impl RootImpl for RootRef<'_> {
         self.resolve_nofollow(path)
     }
 
+    fn resolve_partial(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<(Self::Handle, PathBuf), Self::Error> {
+        let partial = self.resolve_partial(path)?;
+        Ok((partial.handle, partial.remaining))
+    }
+
     fn open_subpath(
         &self,
         path: impl AsRef<Path>,
@@ -298,6 +369,18 @@ impl RootImpl for RootRef<'_> {
         self.readlink(path)
     }
 
+    fn read_dir(&self, path: impl AsRef<Path>) -> Result<Directory, Self::Error> {
+        self.read_dir(path)
+    }
+
+    fn metadata(&self, path: impl AsRef<Path>) -> Result<Metadata, Self::Error> {
+        self.metadata(path)
+    }
+
+    fn metadata_nofollow(&self, path: impl AsRef<Path>) -> Result<Metadata, Self::Error> {
+        self.metadata_nofollow(path)
+    }
+
     fn create(&self, path: impl AsRef<Path>, inode_type: &InodeType) -> Result<(), Self::Error> {
         self.create(path, inode_type)
     }
@@ -331,6 +414,10 @@ impl RootImpl for RootRef<'_> {
         self.remove_all(path)
     }
 
+    fn remove(&self, path: impl AsRef<Path>) -> Result<(), Self::Error> {
+        self.remove(path)
+    }
+
     fn rename(
         &self,
         source: impl AsRef<Path>,
@@ -371,6 +458,14 @@ impl RootImpl for &RootRef<'_> {
         RootRef::resolve_nofollow(self, path)
     }
 
+    fn resolve_partial(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<(Self::Handle, PathBuf), Self::Error> {
+        let partial = RootRef::resolve_partial(self, path)?;
+        Ok((partial.handle, partial.remaining))
+    }
+
     fn open_subpath(
         &self,
         path: impl AsRef<Path>,
@@ -383,6 +478,18 @@ impl RootImpl for &RootRef<'_> {
         RootRef::readlink(self, path)
     }
 
+    fn read_dir(&self, path: impl AsRef<Path>) -> Result<Directory, Self::Error> {
+        RootRef::read_dir(self, path)
+    }
+
+    fn metadata(&self, path: impl AsRef<Path>) -> Result<Metadata, Self::Error> {
+        RootRef::metadata(self, path)
+    }
+
+    fn metadata_nofollow(&self, path: impl AsRef<Path>) -> Result<Metadata, Self::Error> {
+        RootRef::metadata_nofollow(self, path)
+    }
+
     fn create(&self, path: impl AsRef<Path>, inode_type: &InodeType) -> Result<(), Self::Error> {
         RootRef::create(self, path, inode_type)
     }
@@ -416,6 +523,10 @@ impl RootImpl for &RootRef<'_> {
         RootRef::remove_all(self, path)
     }
 
+    fn remove(&self, path: impl AsRef<Path>) -> Result<(), Self::Error> {
+        RootRef::remove(self, path)
+    }
+
     fn rename(
         &self,
         source: impl AsRef<Path>,
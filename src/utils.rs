@@ -40,3 +40,7 @@ pub(crate) use maybe_owned::*;
 
 mod raw_procfs;
 pub(crate) use raw_procfs::*;
+
+pub(crate) mod kernel_version;
+
+pub(crate) mod feature_probe;
@@ -24,12 +24,45 @@ use crate::{
 };
 
 use std::{
-    ffi::OsStr,
-    os::unix::{ffi::OsStrExt, io::AsFd},
+    ffi::{OsStr, OsString},
+    os::unix::{
+        ffi::OsStrExt,
+        io::{AsFd, BorrowedFd, OwnedFd},
+    },
     path::Path,
 };
 
-use rustix::fs::{AtFlags, Dir};
+use rustix::fs::{self as rustix_fs, AtFlags, Dir};
+
+/// Default cap on `remove_all`'s work-stack depth (i.e. how many nested
+/// directories it will hold open at once). This exists purely to bound
+/// memory/fd usage on pathologically deep trees -- it is not expected to be
+/// hit in practice.
+const DEFAULT_MAX_DEPTH: usize = 4096;
+
+/// Default cap on the number of times `remove_all` will re-scan a single
+/// directory looking for leftover entries before giving up. Without this, an
+/// attacker who can keep creating new entries in a directory we are trying to
+/// empty could force us to loop forever.
+const DEFAULT_MAX_RESCANS: usize = 4096;
+
+/// By default, `remove_all` refuses to descend into a directory that turns
+/// out to be a different mount than its parent (see [`MountPointPolicy`]).
+const DEFAULT_MOUNT_POINT_POLICY: MountPointPolicy = MountPointPolicy::Refuse;
+
+/// What `remove_all` should do when it finds that a subdirectory it is about
+/// to recurse into is actually on a different filesystem than its parent
+/// (i.e. it is a mount point, possibly one an attacker bind-mounted there
+/// mid-deletion).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MountPointPolicy {
+    /// Refuse to cross into the other filesystem, and fail the whole
+    /// `remove_all` with a [`SafetyViolation`](ErrorImpl::SafetyViolation).
+    Refuse,
+    /// Leave the mount point (and everything on it) alone, and just skip
+    /// over it as if it could not be removed.
+    Skip,
+}
 
 trait RmdirResultExt {
     // ENOENT from a removal function should be treated the same as an Ok(()).
@@ -45,33 +78,178 @@ impl RmdirResultExt for Result<(), Error> {
     }
 }
 
-fn remove_inode(dirfd: impl AsFd, name: impl AsRef<Path>) -> Result<(), Error> {
+fn unlink_inode(dirfd: impl AsFd, name: impl AsRef<Path>) -> Result<(), Error> {
     let dirfd = dirfd.as_fd();
     let name = name.as_ref();
 
-    // To ensure we return a useful error, we try both unlink and rmdir and
-    // try to avoid returning EISDIR/ENOTDIR if both failed.
-    syscalls::unlinkat(dirfd, name, AtFlags::empty())
-        .or_else(|unlink_err| {
-            syscalls::unlinkat(dirfd, name, AtFlags::REMOVEDIR).map_err(|rmdir_err| {
-                if rmdir_err.root_cause().raw_os_error() == Some(libc::ENOTDIR) {
-                    unlink_err
-                } else {
-                    rmdir_err
-                }
-            })
+    syscalls::unlinkat(dirfd, name, AtFlags::empty()).map_err(|err| {
+        ErrorImpl::RawOsError {
+            operation: "unlink file".into(),
+            source: err,
+        }
+        .into()
+    })
+}
+
+fn rmdir_inode(dirfd: impl AsFd, name: impl AsRef<Path>) -> Result<(), Error> {
+    let dirfd = dirfd.as_fd();
+    let name = name.as_ref();
+
+    syscalls::unlinkat(dirfd, name, AtFlags::REMOVEDIR).map_err(|err| {
+        ErrorImpl::RawOsError {
+            operation: "rmdir directory".into(),
+            source: err,
+        }
+        .into()
+    })
+}
+
+/// Remove a single directory entry `name` (relative to `dirfd`), regardless
+/// of whether it is a directory or not.
+///
+/// Unlike [`remove_all`], this never descends into `name` -- if it turns out
+/// to be a non-empty directory, the underlying `rmdir(2)` simply fails with
+/// `ENOTEMPTY`, just as a plain `rmdir(1)`/`unlink(1)` would.
+pub(crate) fn remove(dirfd: impl AsFd, name: impl AsRef<Path>) -> Result<(), Error> {
+    let dirfd = dirfd.as_fd();
+    let name = name.as_ref();
+
+    let stat = rustix_fs::statat(dirfd, name, AtFlags::SYMLINK_NOFOLLOW).map_err(|err| {
+        ErrorImpl::RawOsError {
+            operation: "stat entry to decide removal method".into(),
+            source: err,
+        }
+    })?;
+
+    if rustix_fs::FileType::from_raw_mode(stat.st_mode) == rustix_fs::FileType::Directory {
+        rmdir_inode(dirfd, name)
+    } else {
+        unlink_inode(dirfd, name)
+    }
+}
+
+/// Open `name` (relative to `dirfd`) as a directory, verifying it really is
+/// one, and return the opened fd along with its `st_dev` (so callers can
+/// detect mount point crossings).
+///
+/// Open with `O_NOFOLLOW` so that an attacker who swaps this entry for a
+/// symlink between our caller's `readdir(2)` and this `openat(2)` gets
+/// `ELOOP`/`ENOTDIR` rather than us silently following them outside of the
+/// tree we were asked to remove (the fix used for CVE-2022-21658 in
+/// `std::fs::remove_dir_all`).
+fn open_subdir(dirfd: BorrowedFd<'_>, name: &OsStr) -> Result<(OwnedFd, u64), Error> {
+    let subdir = syscalls::openat(
+        dirfd,
+        name,
+        OpenFlags::O_DIRECTORY | OpenFlags::O_NOFOLLOW,
+        0,
+    )
+    .map_err(|err| ErrorImpl::RawOsError {
+        operation: "open directory to scan entries".into(),
+        source: err,
+    })?;
+
+    // Belt-and-braces: confirm the fd we just opened really is the directory
+    // we listed, not something an attacker swapped in. O_DIRECTORY above
+    // already guarantees this at the open(2) level, but since this is a
+    // security-critical invariant we double-check it explicitly rather than
+    // relying solely on kernel flag semantics.
+    let stat = rustix_fs::fstat(&subdir).map_err(|err| ErrorImpl::OsError {
+        operation: "verify opened subdirectory is still a directory".into(),
+        source: err.into(),
+    })?;
+    if rustix_fs::FileType::from_raw_mode(stat.st_mode) != rustix_fs::FileType::Directory {
+        Err(ErrorImpl::SafetyViolation {
+            description: format!("{name:?} was replaced with a non-directory during remove_all")
+                .into(),
+        })?;
+    }
+
+    Ok((subdir, stat.st_dev))
+}
+
+/// A single `readdir(2)` pass over `dirfd`, returning the (name, d_type) of
+/// every entry other than `.`/`..`.
+fn scan_directory(
+    dirfd: BorrowedFd<'_>,
+    name: &OsStr,
+) -> Result<Vec<(OsString, rustix_fs::FileType)>, Error> {
+    // TODO: Dir creates a new file descriptor rather than reusing the one we
+    //       have, and RawDir can't be used as an Iterator yet (rustix needs
+    //       GAT to make that work). But this is okay for now...
+    Dir::read_from(dirfd)
+        .map_err(|err| ErrorImpl::OsError {
+            operation: "create directory iterator".into(),
+            source: err.into(),
         })
-        .map_err(|err| {
-            ErrorImpl::RawOsError {
-                operation: "remove inode".into(),
-                source: err,
-            }
-            .into()
+        .with_wrap(|| format!("scan directory {name:?} for deletion"))?
+        .filter(|res| {
+            !matches!(
+                res.as_ref().map(|dentry| dentry.file_name().to_bytes()),
+                Ok(b".") | Ok(b"..")
+            )
         })
+        .map(|dentry| {
+            let dentry = dentry.map_err(|err| ErrorImpl::OsError {
+                operation: format!("scan directory {name:?}").into(),
+                source: err.into(),
+            })?;
+            Ok((
+                OsStr::from_bytes(dentry.file_name().to_bytes()).to_os_string(),
+                dentry.file_type(),
+            ))
+        })
+        .collect()
+}
+
+/// A directory we have open and are in the process of draining, as part of
+/// [`remove_all`]'s explicit work-stack.
+struct Frame {
+    dirfd: OwnedFd,
+    name: OsString,
+    /// The `st_dev` of `dirfd`, so children can be checked for mount point
+    /// crossings before we recurse into them.
+    dev: u64,
+    /// How many times we've done a full `readdir(2)` pass looking for
+    /// leftover entries -- capped by `max_rescans` to stop an attacker who
+    /// keeps recreating entries from making us loop forever.
+    rescans: usize,
+    /// Entries from the most recent scan that still need to be processed.
+    pending: Vec<(OsString, rustix_fs::FileType)>,
 }
 
 pub(crate) fn remove_all(dirfd: impl AsFd, name: impl AsRef<Path>) -> Result<(), Error> {
-    let dirfd = dirfd.as_fd();
+    remove_all_with_limits(
+        dirfd,
+        name,
+        DEFAULT_MAX_DEPTH,
+        DEFAULT_MAX_RESCANS,
+        DEFAULT_MOUNT_POINT_POLICY,
+    )
+}
+
+/// Recursively remove `name` (relative to `dirfd`), iteratively rather than
+/// via native recursion.
+///
+/// This holds every ancestor directory fd open on an explicit heap-allocated
+/// work stack (rather than recursing once per directory level), so all
+/// `unlinkat(2)` calls stay relative to a verified, still-open fd, and deeply
+/// nested trees can't exhaust the native call stack. `max_depth` bounds how
+/// many directory levels we'll hold open at once, and `max_rescans` bounds
+/// how many times we'll re-scan a single directory for leftover entries --
+/// both exist to turn what would otherwise be unbounded recursion/looping
+/// into a well-defined [`ErrorKind::ResourceExhausted`](crate::error::ErrorKind::ResourceExhausted)
+/// error. `mount_point_policy` controls what happens if a directory we are
+/// about to recurse into turns out to be on a different filesystem than its
+/// parent (see [`MountPointPolicy`]).
+fn remove_all_with_limits(
+    dirfd: impl AsFd,
+    name: impl AsRef<Path>,
+    max_depth: usize,
+    max_rescans: usize,
+    mount_point_policy: MountPointPolicy,
+) -> Result<(), Error> {
+    let root_dirfd = dirfd.as_fd();
     let name = name.as_ref();
 
     if name.as_os_str().as_bytes().contains(&b'/') {
@@ -79,96 +257,164 @@ pub(crate) fn remove_all(dirfd: impl AsFd, name: impl AsRef<Path>) -> Result<(),
             description: "remove_all reached a component containing '/'".into(),
         })?;
     }
+    let name = name.as_os_str();
 
-    // Fast path -- try to remove it with unlink/rmdir.
-    if remove_inode(dirfd, name).ignore_enoent().is_ok() {
-        return Ok(());
-    }
+    let root_dirfd_dev = rustix_fs::fstat(root_dirfd)
+        .map_err(|err| ErrorImpl::OsError {
+            operation: "stat anchor directory to detect mount point crossings".into(),
+            source: err.into(),
+        })?
+        .st_dev;
 
-    // Try to delete all children. We need to re-do the iteration until there
-    // are no components left because deleting entries while iterating over a
-    // directory can lead to the iterator skipping components. An attacker could
-    // try to make this loop forever by consistently creating inodes, but
-    // there's not much we can do about it and I suspect they would eventually
-    // lose the race.
-    let subdir = match syscalls::openat(dirfd, name, OpenFlags::O_DIRECTORY, 0).map_err(|err| {
-        ErrorImpl::RawOsError {
-            operation: "open directory to scan entries".into(),
-            source: err,
-        }
-    }) {
+    // We have no dirent d_type for the top-level path the caller asked us to
+    // remove, so classify it the same way we would a DT_UNKNOWN child: try
+    // to open it as a directory, and fall back to a plain unlink if it turns
+    // out not to be one.
+    let (root_fd, root_dev) = match open_subdir(root_dirfd, name) {
         Ok(fd) => fd,
         Err(err) => match err.kind().errno() {
-            // The path was deleted between us trying to with remove_inode() and
-            // now -- just return as if we were the ones that deleted it.
             Some(libc::ENOENT) => return Ok(()),
-            _ => Err(err)?,
+            Some(libc::ENOTDIR) | Some(libc::ELOOP) => {
+                return unlink_inode(root_dirfd, name).ignore_enoent()
+            }
+            _ => return Err(err),
         },
     };
-    loop {
-        // TODO: Dir creates a new file descriptor rather than reusing the one
-        //       we have, and RawDir can't be used as an Iterator yet (rustix
-        //       needs GAT to make that work). But this is okay for now...
-        let mut iter = match Dir::read_from(&subdir)
-            .map_err(|err| ErrorImpl::OsError {
-                operation: "create directory iterator".into(),
-                source: err.into(),
-            })
-            .with_wrap(|| format!("scan directory {name:?} for deletion"))
-        {
-            Ok(iter) => iter,
-            Err(err) => match err.kind().errno() {
-                // If we got ENOENT that means the directory got deleted after
-                // we opened it, so stop iterating (maybe another thread did "rm
-                // -rf"). An attacker might've also replaced the directory but
-                // we're not going retry opening it because that could lead to a
-                // DoS. remove_inode will error out in that case, and that's
-                // fine.
-                Some(libc::ENOENT) => break,
-                // TODO: Maybe we want to just break out of the loop here as
-                //       well, rather than return an error? If remove_inode()
-                //       again succeeds we're golden.
-                _ => Err(err)?,
-            },
+
+    if root_dev != root_dirfd_dev {
+        match mount_point_policy {
+            MountPointPolicy::Refuse => {
+                return Err(ErrorImpl::SafetyViolation {
+                    description: format!("{name:?} is a mount point, refusing to remove_all it")
+                        .into(),
+                }
+                .into())
+            }
+            MountPointPolicy::Skip => return Ok(()),
         }
-        .filter(|res| {
-            !matches!(
-                res.as_ref().map(|dentry| dentry.file_name().to_bytes()),
-                Ok(b".") | Ok(b"..")
-            )
-        })
-        .peekable();
+    }
+
+    let mut stack = vec![Frame {
+        dirfd: root_fd,
+        name: name.to_os_string(),
+        dev: root_dev,
+        rescans: 0,
+        pending: Vec::new(),
+    }];
 
-        // We can stop iterating when a fresh directory iterator is empty.
-        if iter.peek().is_none() {
-            break;
+    while let Some(frame) = stack.last_mut() {
+        if frame.pending.is_empty() {
+            let entries = match scan_directory(frame.dirfd.as_fd(), &frame.name) {
+                Ok(entries) => entries,
+                // The directory got deleted after we opened it (maybe
+                // another thread did "rm -rf") -- treat it as already-empty
+                // so we fall through to (trying to) remove it below.
+                Err(err) if err.kind().errno() == Some(libc::ENOENT) => Vec::new(),
+                Err(err) => return Err(err),
+            };
+
+            frame.rescans += 1;
+            if frame.rescans > max_rescans {
+                return Err(ErrorImpl::ResourceExhausted {
+                    description: format!(
+                        "exceeded the limit of {max_rescans} re-scans while deleting {:?}",
+                        frame.name
+                    )
+                    .into(),
+                }
+                .into());
+            }
+
+            if entries.is_empty() {
+                // This directory is (now) empty -- pop it off the stack and
+                // remove it relative to its parent (the new top of the
+                // stack, or the caller's dirfd if this was the last frame).
+                let done = stack.pop().expect("stack is non-empty");
+                let parent_dirfd = stack.last().map_or(root_dirfd, |f| f.dirfd.as_fd());
+                rmdir_inode(parent_dirfd, &done.name)
+                    .ignore_enoent()
+                    .with_wrap(|| format!("deleting emptied directory {:?}", done.name))?;
+            } else {
+                frame.pending = entries;
+            }
+            continue;
         }
 
-        // Recurse into all of the children and try to delete them.
-        for child in iter {
-            // TODO: We probably want to break out of the scan loop here if this
-            //       is an error as well.
-            let child = child.map_err(|err| ErrorImpl::OsError {
-                operation: format!("scan directory {name:?}").into(),
-                source: err.into(),
-            })?;
-            let name: &Path = OsStr::from_bytes(child.file_name().to_bytes()).as_ref();
-            remove_all(&subdir, name).ignore_enoent()?
+        let (child_name, child_type) = frame.pending.pop().expect("checked non-empty above");
+        let dirfd = frame.dirfd.as_fd();
+
+        // If the dirent already told us this definitely isn't a directory
+        // (DT_REG, DT_LNK, DT_FIFO, DT_SOCK, DT_CHR, DT_BLK), just unlink(2)
+        // it directly. We must never try rmdir(2)/recursion first and fall
+        // back to unlink(2) afterwards, since unlink(2) on a directory can
+        // *succeed* (and leave behind a dangling inode requiring an fsck) on
+        // some filesystems, notably illumos/UFS.
+        if !matches!(
+            child_type,
+            rustix_fs::FileType::Directory | rustix_fs::FileType::Unknown
+        ) {
+            unlink_inode(dirfd, &child_name).ignore_enoent()?;
+            continue;
+        }
+
+        match open_subdir(dirfd, &child_name) {
+            Ok((child_fd, child_dev)) => {
+                if child_dev != frame.dev {
+                    match mount_point_policy {
+                        MountPointPolicy::Refuse => {
+                            return Err(ErrorImpl::SafetyViolation {
+                                description: format!(
+                                    "{child_name:?} is a mount point, refusing to remove_all it"
+                                )
+                                .into(),
+                            }
+                            .into())
+                        }
+                        MountPointPolicy::Skip => continue,
+                    }
+                }
+
+                if stack.len() >= max_depth {
+                    return Err(ErrorImpl::ResourceExhausted {
+                        description: format!(
+                            "exceeded the maximum remove_all depth of {max_depth} at {child_name:?}"
+                        )
+                        .into(),
+                    }
+                    .into());
+                }
+                stack.push(Frame {
+                    dirfd: child_fd,
+                    name: child_name,
+                    dev: child_dev,
+                    rescans: 0,
+                    pending: Vec::new(),
+                });
+            }
+            Err(err) => match err.kind().errno() {
+                // Already gone -- nothing to do.
+                Some(libc::ENOENT) => {}
+                // We only get here for DT_UNKNOWN children: it turns out
+                // this wasn't a directory after all, so fall back to a plain
+                // unlink. If d_type told us it was DT_DIR and we still got
+                // this, something raced us -- don't paper over that by
+                // guessing, just propagate the error.
+                Some(libc::ENOTDIR) | Some(libc::ELOOP)
+                    if child_type == rustix_fs::FileType::Unknown =>
+                {
+                    unlink_inode(dirfd, &child_name).ignore_enoent()?;
+                }
+                _ => return Err(err),
+            },
         }
     }
 
-    // We have deleted all of the children of the directory, let's try to delete
-    // the inode again (it should be empty now -- an attacker could add things
-    // but we can just error out in that case, and if they swapped it to a file
-    // then remove_inode will take care of that).
-    remove_inode(dirfd, name)
-        .ignore_enoent()
-        .with_wrap(|| format!("deleting emptied directory {name:?}"))
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::remove_all;
+    use super::{remove, remove_all};
     use crate::{error::ErrorKind, tests::common as tests_common, Root};
 
     use std::{os::unix::io::OwnedFd, path::Path};
@@ -176,6 +422,31 @@ mod tests {
     use anyhow::Error;
     use pretty_assertions::assert_eq;
 
+    #[test]
+    fn remove_basic() -> Result<(), Error> {
+        let dir = tests_common::create_basic_tree()?;
+        let dirfd: OwnedFd = Root::open(&dir)?.into();
+
+        assert_eq!(
+            remove(&dirfd, Path::new("b/c/file")).map_err(|err| err.kind()),
+            Ok(()),
+            "remove(root, 'b/c/file') should unlink a regular file",
+        );
+        assert_eq!(
+            remove(&dirfd, Path::new("a")).map_err(|err| err.kind()),
+            Ok(()),
+            "remove(root, 'a') should rmdir an empty directory",
+        );
+        assert_eq!(
+            remove(&dirfd, Path::new("b")).map_err(|err| err.kind()),
+            Err(ErrorKind::OsError(Some(libc::ENOTEMPTY))),
+            "remove(root, 'b') should refuse to remove a non-empty directory",
+        );
+
+        let _dir = dir; // make sure the tempdir is not dropped early
+        Ok(())
+    }
+
     #[test]
     fn remove_all_basic() -> Result<(), Error> {
         let dir = tests_common::create_basic_tree()?;
@@ -0,0 +1,184 @@
+// SPDX-License-Identifier: MPL-2.0 OR LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * == MPL-2.0 ==
+ *
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Alternatively, this Source Code Form may also (at your option) be used
+ * under the terms of the GNU Lesser General Public License Version 3, as
+ * described below:
+ *
+ * == LGPL-3.0-or-later ==
+ *
+ *  This program is free software: you can redistribute it and/or modify it
+ *  under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or (at
+ *  your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful, but
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY  or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General
+ * Public License  for more details.
+ *
+ *  You should have received a copy of the GNU Lesser General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Distributions backport kernel features heavily, so comparing
+//! [`HOST_KERNEL_VERSION`](super::kernel_version::HOST_KERNEL_VERSION) against
+//! the upstream version a feature landed in (via
+//! [`is_gte!`](super::kernel_version::is_gte)) can easily be wrong in both
+//! directions. This module takes the libbpf-style "probe, don't guess"
+//! approach instead: the first time a [`Feature`] is queried we perform a
+//! cheap, side-effect-free syscall that can only fail with `ENOSYS` if the
+//! kernel genuinely lacks support, and cache the result for the lifetime of
+//! the process -- a feature cannot appear or disappear at runtime.
+
+use crate::{flags::OpenFlags, syscalls, syscalls::OpenHow};
+
+use std::{collections::HashMap, sync::Mutex};
+
+use once_cell::sync::Lazy;
+use rustix::fs::{self as rustix_fs, AtFlags, StatxFlags};
+
+/// A kernel feature (usually a particular syscall or syscall flag) that can
+/// be probed for at runtime, rather than inferred from
+/// [`HOST_KERNEL_VERSION`](super::kernel_version::HOST_KERNEL_VERSION).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Feature {
+    /// Whether `openat2(2)` (and thus all `RESOLVE_*` flags it supports) is
+    /// implemented by the running kernel.
+    Openat2,
+    /// Whether `statx(2)` is implemented by the running kernel.
+    Statx,
+}
+
+/// Probe results are cached for the lifetime of the process.
+// MSRV(1.80): Use LazyLock.
+static PROBE_CACHE: Lazy<Mutex<HashMap<Feature, bool>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// `openat2(2)` was added in Linux 5.6 with the full set of `RESOLVE_*` flags
+/// it still supports today, so a single no-op call against `/` tells us
+/// whether the kernel (or a backport) understands the syscall at all.
+fn probe_openat2() -> bool {
+    // Any O_PATH directory fd works as a probe target -- we never actually
+    // look anything up with it, we just want to see whether the kernel
+    // understands openat2(2) in the first place.
+    let probe_root = match syscalls::openat(syscalls::AT_FDCWD, "/", OpenFlags::O_PATH, 0) {
+        Ok(fd) => fd,
+        // If we can't even open "/", assume openat2(2) doesn't work either --
+        // whatever is going on, it's not something this probe can fix.
+        Err(_) => return false,
+    };
+
+    match syscalls::openat2_follow(
+        &probe_root,
+        ".",
+        OpenHow {
+            flags: OpenFlags::O_PATH.bits() as u64,
+            ..Default::default()
+        },
+    ) {
+        // Whether or not the dummy lookup itself succeeded, the syscall was
+        // at least understood.
+        Ok(_) => true,
+        Err(err) => err.root_cause().raw_os_error() != Some(libc::ENOSYS),
+    }
+}
+
+/// `statx(2)` was added in Linux 4.11.
+fn probe_statx() -> bool {
+    match rustix_fs::statx(
+        rustix_fs::CWD,
+        ".",
+        AtFlags::empty(),
+        StatxFlags::BASIC_STATS,
+    ) {
+        Ok(_stx) => true,
+        Err(rustix::io::Errno::NOSYS) => false,
+        Err(_) => true,
+    }
+}
+
+fn probe(feature: Feature) -> bool {
+    match feature {
+        Feature::Openat2 => probe_openat2(),
+        Feature::Statx => probe_statx(),
+    }
+}
+
+/// Returns whether `feature` is supported by the running kernel, probing (and
+/// caching the result) on first use. Prefer the [`is_supported!`] macro over
+/// calling this directly.
+pub(crate) fn is_supported(feature: Feature) -> bool {
+    *PROBE_CACHE
+        .lock()
+        .expect("feature probe cache lock should never be poisoned")
+        .entry(feature)
+        .or_insert_with(|| probe(feature))
+}
+
+/// Returns whether the given [`Feature`] is supported by the running kernel.
+///
+/// Unlike [`is_gte!`](super::kernel_version::is_gte), this actually probes
+/// the kernel for the feature rather than guessing from the reported kernel
+/// version, so a positive probe result is trusted even when
+/// [`HOST_KERNEL_VERSION`](super::kernel_version::HOST_KERNEL_VERSION) would
+/// suggest the feature shouldn't exist yet (as happens on distributions that
+/// backport features to older-looking kernel versions).
+macro_rules! is_supported {
+    ($feature:expr) => {
+        $crate::utils::feature_probe::is_supported($feature)
+    };
+}
+pub(crate) use is_supported;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn probe_openat2_is_stable() {
+        // Probing twice must agree with itself (and with the cache) -- a
+        // feature cannot appear and disappear within a single process.
+        let first = probe_openat2();
+        let second = probe_openat2();
+        assert_eq!(first, second, "probing openat2(2) twice should agree");
+        assert_eq!(
+            is_supported!(Feature::Openat2),
+            first,
+            "cached result should match a fresh probe"
+        );
+    }
+
+    #[test]
+    fn probe_statx_is_stable() {
+        let first = probe_statx();
+        let second = probe_statx();
+        assert_eq!(first, second, "probing statx(2) twice should agree");
+        assert_eq!(
+            is_supported!(Feature::Statx),
+            first,
+            "cached result should match a fresh probe"
+        );
+    }
+
+    #[test]
+    fn is_supported_caches_result() {
+        // Calling is_supported! repeatedly for the same feature must not
+        // panic or deadlock, and must always agree.
+        for _ in 0..3 {
+            assert_eq!(
+                is_supported!(Feature::Openat2),
+                is_supported!(Feature::Openat2)
+            );
+        }
+    }
+}
@@ -31,7 +31,7 @@
 
 use std::{
     cmp::{self, Ordering},
-    fmt,
+    fmt, fs,
 };
 
 use once_cell::sync::Lazy;
@@ -132,12 +132,167 @@ fn parse_kernel_version(kver_str: &str) -> Option<KernelVersion> {
     }
 }
 
+/// A single comparison operator used by [`KernelVersionReq`], such as the
+/// `>=` in `">=5.6"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparator {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Comparator {
+    fn matches(self, kver: &KernelVersion, req: &KernelVersion) -> bool {
+        match self {
+            Self::Eq => kver == req,
+            Self::Ne => kver != req,
+            Self::Gt => kver > req,
+            Self::Ge => kver >= req,
+            Self::Lt => kver < req,
+            Self::Le => kver <= req,
+        }
+    }
+}
+
+/// A single `<comparator><version>` predicate, such as `">=5.6"` or the bare
+/// (implicitly `=`) `"5.10"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Predicate {
+    comparator: Comparator,
+    version: KernelVersion,
+}
+
+impl Predicate {
+    /// Parse a single predicate like `">=5.6"`. A bare version string (no
+    /// operator) is accepted and means `=`.
+    fn parse(predicate_str: &str) -> Option<Self> {
+        let (comparator, version_str) = if let Some(rest) = predicate_str.strip_prefix(">=") {
+            (Comparator::Ge, rest)
+        } else if let Some(rest) = predicate_str.strip_prefix("<=") {
+            (Comparator::Le, rest)
+        } else if let Some(rest) = predicate_str.strip_prefix("==") {
+            (Comparator::Eq, rest)
+        } else if let Some(rest) = predicate_str.strip_prefix("!=") {
+            (Comparator::Ne, rest)
+        } else if let Some(rest) = predicate_str.strip_prefix('>') {
+            (Comparator::Gt, rest)
+        } else if let Some(rest) = predicate_str.strip_prefix('<') {
+            (Comparator::Lt, rest)
+        } else if let Some(rest) = predicate_str.strip_prefix('=') {
+            (Comparator::Eq, rest)
+        } else {
+            (Comparator::Eq, predicate_str)
+        };
+
+        Some(Self {
+            comparator,
+            version: parse_kernel_version(version_str.trim())?,
+        })
+    }
+
+    fn matches(&self, kver: &KernelVersion) -> bool {
+        self.comparator.matches(kver, &self.version)
+    }
+}
+
+/// A kernel version requirement, such as `">=5.6, <6.0"` or
+/// `"5.10 || >=5.15"`, for expressing feature windows (introduced in one
+/// version, regressed in another, fixed again later) that a single
+/// [`is_gte!`]/[`is_lt!`] comparison can't express.
+///
+/// Comma-separated predicates are combined with logical AND (all must match),
+/// and `||`-separated groups of those are combined with logical OR (at least
+/// one group must match) -- e.g. `">=5.6, <6.0"` matches any `5.6 <= v < 6.0`,
+/// while `"5.10 || >=5.15"` matches either exactly `5.10` or anything
+/// `>=5.15`.
+///
+/// Supported comparators are `=`, `==`, `!=`, `>`, `>=`, `<`, and `<=`; a bare
+/// version with no comparator (e.g. `"5.10"`) is treated as `=`. Matching
+/// respects the same trailing-zero-extension semantics as
+/// [`KernelVersion::cmp`] (so a `"5.10"` requirement matches a parsed
+/// `"5.10.0"` [`KernelVersion`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct KernelVersionReq {
+    /// Disjunction (OR) of conjunctions (AND) of predicates.
+    groups: Vec<Vec<Predicate>>,
+}
+
+impl KernelVersionReq {
+    /// Parse a requirement string like `">=5.6, <6.0"`. Returns `None` if
+    /// `req_str` is empty, or if any individual comparator (or the version it
+    /// applies to) is malformed, mirroring [`parse_kernel_version`].
+    pub(crate) fn parse(req_str: &str) -> Option<Self> {
+        if req_str.trim().is_empty() {
+            return None;
+        }
+
+        let groups = req_str
+            .split("||")
+            .map(|group_str| {
+                group_str
+                    .split(',')
+                    .map(|predicate_str| {
+                        let predicate_str = predicate_str.trim();
+                        if predicate_str.is_empty() {
+                            None // comparators must be non-empty
+                        } else {
+                            Predicate::parse(predicate_str)
+                        }
+                    })
+                    .collect::<Option<Vec<_>>>()
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self { groups })
+    }
+
+    /// Returns whether `kver` satisfies this requirement.
+    pub(crate) fn matches(&self, kver: &KernelVersion) -> bool {
+        self.groups
+            .iter()
+            .any(|group| group.iter().all(|predicate| predicate.matches(kver)))
+    }
+}
+
+/// Path to the Debian/Ubuntu-specific file containing the true upstream
+/// kernel version, for kernels whose `uname(2)` release string has been
+/// mangled by the distribution's ABI-tracking package (see
+/// [`parse_version_signature`]).
+const VERSION_SIGNATURE_PATH: &str = "/proc/version_signature";
+
+/// Parse the contents of [`VERSION_SIGNATURE_PATH`] (`/proc/version_signature`),
+/// such as `"Ubuntu 5.4.0-42.46-generic 5.4.125"`, returning the true upstream
+/// kernel version. The last whitespace-separated token is always the upstream
+/// `major.minor.patch` version, regardless of how many fields precede it.
+///
+/// Returns `None` if `contents` is empty or the last token is not a valid
+/// kernel version.
+fn parse_version_signature(contents: &str) -> Option<KernelVersion> {
+    let upstream_kver = contents.split_whitespace().last()?;
+    parse_kernel_version(upstream_kver)
+}
+
+/// Returns the true upstream kernel version from [`VERSION_SIGNATURE_PATH`]
+/// (on distributions such as Ubuntu and WSL that mangle the `uname(2)`
+/// release string with downstream ABI numbers), or `None` if the file doesn't
+/// exist, can't be read, or doesn't contain a valid kernel version
+/// ([`host_kernel_version`] then falls back to `uname(2)`).
+fn distro_kernel_version() -> Option<KernelVersion> {
+    let contents = fs::read_to_string(VERSION_SIGNATURE_PATH).ok()?;
+    parse_version_signature(&contents)
+}
+
 // MSRV(1.80): Use LazyLock.
 pub(crate) static HOST_KERNEL_VERSION: Lazy<KernelVersion> = Lazy::new(host_kernel_version);
 
 pub(crate) fn host_kernel_version() -> KernelVersion {
-    parse_kernel_version(&rustix_system::uname().release().to_string_lossy())
-        .expect("uname kernel release must be a valid KernelVersion string")
+    distro_kernel_version().unwrap_or_else(|| {
+        parse_kernel_version(&rustix_system::uname().release().to_string_lossy())
+            .expect("uname kernel release must be a valid KernelVersion string")
+    })
 }
 
 /// Returns the result of comparing the kernel version of the running system
@@ -285,6 +440,48 @@ mod tests {
         assert_eq!(parse_kernel_version("3.8-4"), Some(kver![3, 8]));
     }
 
+    #[test]
+    fn parse_kernel_version_wsl() {
+        // WSL releases look like "4.4.0-19041-Microsoft", which is already
+        // handled by the regular suffix-stripping logic.
+        assert_eq!(
+            parse_kernel_version("4.4.0-19041-Microsoft"),
+            Some(kver![4, 4, 0])
+        );
+        assert_eq!(
+            parse_kernel_version("5.10.102.1-microsoft-standard-WSL2"),
+            Some(kver![5, 10, 102, 1])
+        );
+    }
+
+    #[test]
+    fn parse_version_signature_good() {
+        assert_eq!(
+            parse_version_signature("Ubuntu 5.4.0-42.46-generic 5.4.125"),
+            Some(kver![5, 4, 125])
+        );
+        assert_eq!(
+            parse_version_signature("Ubuntu 6.8.0-45.45-generic 6.8.12"),
+            Some(kver![6, 8, 12])
+        );
+        // Only the last whitespace-separated token matters.
+        assert_eq!(
+            parse_version_signature("a b c 3.8.12"),
+            Some(kver![3, 8, 12])
+        );
+    }
+
+    #[test]
+    fn parse_version_signature_bad() {
+        assert_eq!(parse_version_signature(""), None);
+        assert_eq!(parse_version_signature("   "), None);
+        // Last token has fewer than the minimum 2 version components.
+        assert_eq!(parse_version_signature("Ubuntu 5.4.0-42.46-generic 5"), None);
+        // Last token isn't a version at all.
+        assert_eq!(parse_version_signature("Ubuntu"), None);
+        assert_eq!(parse_version_signature("not-a-kernel-version"), None);
+    }
+
     #[test]
     fn kernel_version_eq_same_length() {
         assert!(kver![3, 8] == kver![3, 8], "3.8 == 3.8");
@@ -438,4 +635,107 @@ mod tests {
             "UNAME26 personality should always result in a <3.0 kernel version: is_kver!(!= 4, 0) failed"
         );
     }
+
+    #[test]
+    fn kernel_version_req_bad() {
+        assert!(KernelVersionReq::parse("").is_none());
+        assert!(KernelVersionReq::parse("   ").is_none());
+        assert!(KernelVersionReq::parse(">=").is_none(), "comparator with no version");
+        assert!(KernelVersionReq::parse(">=5.6,").is_none(), "trailing empty comparator");
+        assert!(KernelVersionReq::parse(",>=5.6").is_none(), "leading empty comparator");
+        assert!(KernelVersionReq::parse(">=5.6 || ").is_none(), "trailing empty group");
+        assert!(KernelVersionReq::parse(">=foo").is_none(), "malformed version");
+        assert!(KernelVersionReq::parse(">=5.6, <").is_none(), "malformed second comparator");
+    }
+
+    #[test]
+    fn kernel_version_req_single_comparator() {
+        let req = KernelVersionReq::parse(">=5.6").expect("parse '>=5.6'");
+        assert!(req.matches(&kver![5, 6]), "5.6 >= 5.6");
+        assert!(req.matches(&kver![5, 7]), "5.7 >= 5.6");
+        assert!(req.matches(&kver![6, 0]), "6.0 >= 5.6");
+        assert!(!req.matches(&kver![5, 5]), "5.5 !>= 5.6");
+    }
+
+    #[test]
+    fn kernel_version_req_bare_version_is_eq() {
+        let req = KernelVersionReq::parse("5.10").expect("parse '5.10'");
+        assert!(req.matches(&kver![5, 10]), "5.10 == 5.10");
+        // Trailing-zero equivalence should still apply, same as KernelVersion::cmp.
+        assert!(req.matches(&kver![5, 10, 0]), "5.10.0 == 5.10");
+        assert!(!req.matches(&kver![5, 10, 1]), "5.10.1 != 5.10");
+        assert!(!req.matches(&kver![5, 11]), "5.11 != 5.10");
+    }
+
+    #[test]
+    fn kernel_version_req_all_comparators() {
+        assert!(KernelVersionReq::parse("=5.6")
+            .expect("parse '=5.6'")
+            .matches(&kver![5, 6]));
+        assert!(KernelVersionReq::parse("==5.6")
+            .expect("parse '==5.6'")
+            .matches(&kver![5, 6]));
+        assert!(KernelVersionReq::parse("!=5.6")
+            .expect("parse '!=5.6'")
+            .matches(&kver![5, 7]));
+        assert!(!KernelVersionReq::parse("!=5.6")
+            .expect("parse '!=5.6'")
+            .matches(&kver![5, 6]));
+        assert!(KernelVersionReq::parse(">5.6")
+            .expect("parse '>5.6'")
+            .matches(&kver![5, 7]));
+        assert!(!KernelVersionReq::parse(">5.6")
+            .expect("parse '>5.6'")
+            .matches(&kver![5, 6]));
+        assert!(KernelVersionReq::parse("<=5.6")
+            .expect("parse '<=5.6'")
+            .matches(&kver![5, 6]));
+        assert!(KernelVersionReq::parse("<5.6")
+            .expect("parse '<5.6'")
+            .matches(&kver![5, 5]));
+        assert!(!KernelVersionReq::parse("<5.6")
+            .expect("parse '<5.6'")
+            .matches(&kver![5, 6]));
+    }
+
+    #[test]
+    fn kernel_version_req_and_conjunction() {
+        // A feature window: introduced in 5.6, regressed again at 6.0.
+        let req = KernelVersionReq::parse(">=5.6, <6.0").expect("parse '>=5.6, <6.0'");
+        assert!(!req.matches(&kver![5, 5]), "5.5 is before the window");
+        assert!(req.matches(&kver![5, 6]), "5.6 is the start of the window");
+        assert!(req.matches(&kver![5, 19]), "5.19 is inside the window");
+        assert!(!req.matches(&kver![6, 0]), "6.0 is after the window");
+        assert!(!req.matches(&kver![6, 1]), "6.1 is after the window");
+    }
+
+    #[test]
+    fn kernel_version_req_or_disjunction() {
+        // Fixed in 5.10, or re-fixed starting at 5.15 after a regression.
+        let req = KernelVersionReq::parse("5.10 || >=5.15").expect("parse '5.10 || >=5.15'");
+        assert!(req.matches(&kver![5, 10]), "5.10 matches the exact group");
+        assert!(!req.matches(&kver![5, 10, 1]), "5.10.1 matches neither group");
+        assert!(!req.matches(&kver![5, 12]), "5.12 matches neither group");
+        assert!(req.matches(&kver![5, 15]), "5.15 matches the >=5.15 group");
+        assert!(req.matches(&kver![6, 0]), "6.0 matches the >=5.15 group");
+    }
+
+    #[test]
+    fn kernel_version_req_multi_component() {
+        let req =
+            KernelVersionReq::parse(">=5.4.125, <5.4.200").expect("parse '>=5.4.125, <5.4.200'");
+        assert!(!req.matches(&kver![5, 4, 124]));
+        assert!(req.matches(&kver![5, 4, 125]));
+        assert!(req.matches(&kver![5, 4, 199]));
+        assert!(!req.matches(&kver![5, 4, 200]));
+    }
+
+    #[test]
+    fn kernel_version_req_whitespace_tolerant() {
+        let req =
+            KernelVersionReq::parse("  >= 5.6 ,  < 6.0  ||  >= 7.0  ").expect("whitespace-heavy");
+        assert!(req.matches(&kver![5, 8]));
+        assert!(req.matches(&kver![7, 2]));
+        assert!(!req.matches(&kver![6, 5]));
+    }
 }
@@ -17,7 +17,13 @@
  * along with this program. If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::os::unix::io::{AsFd, BorrowedFd, OwnedFd};
+use crate::error::{Error, ErrorImpl};
+
+use std::{
+    fs::File,
+    io,
+    os::unix::io::{AsFd, BorrowedFd, OwnedFd},
+};
 
 /// Like [`std::borrow::Cow`] but without the [`ToOwned`] requirement, and only
 /// for file descriptors.
@@ -54,8 +60,15 @@ where
     BorrowedFd(BorrowedFd<'fd>),
 }
 
-impl<'fd> From<OwnedFd> for MaybeOwnedFd<'fd, OwnedFd> {
-    fn from(fd: OwnedFd) -> Self {
+// A blanket impl (rather than one hard-wired to `OwnedFd`) so that any owning
+// fd type from the wider `AsFd` ecosystem (`tokio::net` sockets, `socket2`,
+// `os_pipe`, ...) can be handed to a `MaybeOwnedFd`-accepting entry point
+// without the caller first converting it to `OwnedFd` themselves.
+impl<'fd, Fd> From<Fd> for MaybeOwnedFd<'fd, Fd>
+where
+    Fd: AsFd + Into<OwnedFd>,
+{
+    fn from(fd: Fd) -> Self {
         Self::OwnedFd(fd)
     }
 }
@@ -96,6 +109,43 @@ where
     }
 }
 
+impl<'fd, Fd> MaybeOwnedFd<'fd, Fd>
+where
+    Fd: AsFd + Into<OwnedFd>,
+{
+    /// Unwrap this `MaybeOwnedFd` into an owned file descriptor, cloning the
+    /// underlying fd (via `fcntl(F_DUPFD_CLOEXEC)`) if we only had a borrow.
+    ///
+    /// Unlike [`MaybeOwnedFd::into_owned`], this never returns `None` --
+    /// callers that need an owned fd to store somewhere that can outlive the
+    /// original borrow (but don't care whether it's the "original" owned fd
+    /// or a dup of a borrowed one) should use this instead.
+    pub(crate) fn into_owned_or_clone(self) -> Result<OwnedFd, Error> {
+        match self {
+            Self::OwnedFd(fd) => Ok(fd.into()),
+            Self::BorrowedFd(fd) => fd.try_clone_to_owned().map_err(|err| {
+                ErrorImpl::OsError {
+                    operation: "clone borrowed fd to outlive its borrow".into(),
+                    source: err,
+                }
+                .into()
+            }),
+        }
+    }
+
+    /// Like [`MaybeOwnedFd::into_owned_or_clone`], but returns a [`File`] and
+    /// a plain [`io::Result`] rather than this crate's own [`Error`] type --
+    /// useful when plumbing a fd through an ecosystem entry point (such as a
+    /// `From`/`TryFrom` impl for a non-libpathrs type) that only deals in
+    /// `std::io`.
+    pub(crate) fn try_into_file(self) -> io::Result<File> {
+        match self {
+            Self::OwnedFd(fd) => Ok(File::from(fd.into())),
+            Self::BorrowedFd(fd) => fd.try_clone_to_owned().map(File::from),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,7 +156,7 @@ mod tests {
     };
 
     use anyhow::Error;
-    use pretty_assertions::{assert_eq, assert_matches};
+    use pretty_assertions::{assert_eq, assert_matches, assert_ne};
 
     #[test]
     fn as_fd() -> Result<(), Error> {
@@ -155,4 +205,74 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn into_owned_or_clone() -> Result<(), Error> {
+        let f: OwnedFd = File::open(".")?.into();
+        let fd = f.as_raw_fd();
+        let owned: MaybeOwnedFd<OwnedFd> = f.into();
+        let cloned = owned
+            .into_owned_or_clone()
+            .expect("OwnedFd variant should always succeed");
+        assert_eq!(
+            cloned.as_raw_fd(),
+            fd,
+            "OwnedFd variant should be returned unchanged"
+        );
+
+        let f = File::open(".")?;
+        let borrowed: MaybeOwnedFd<OwnedFd> = f.as_fd().into();
+        let cloned = borrowed
+            .into_owned_or_clone()
+            .expect("BorrowedFd variant should be clonable");
+        assert_ne!(
+            cloned.as_raw_fd(),
+            f.as_raw_fd(),
+            "BorrowedFd variant should be cloned to a new fd number"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_ecosystem_fd() -> Result<(), Error> {
+        // The blanket `From<Fd>` impl should accept any owning `AsFd` type,
+        // not just `OwnedFd` -- a `File` is a convenient stand-in here since
+        // we can't pull in an extra ecosystem crate just for this test.
+        let f = File::open(".")?;
+        let fd = f.as_raw_fd();
+        let owned: MaybeOwnedFd<File> = f.into();
+        assert_matches!(owned, MaybeOwnedFd::OwnedFd(_));
+        assert_eq!(owned.as_fd().as_raw_fd(), fd);
+
+        Ok(())
+    }
+
+    #[test]
+    fn try_into_file() -> Result<(), Error> {
+        let f: OwnedFd = File::open(".")?.into();
+        let fd = f.as_raw_fd();
+        let owned: MaybeOwnedFd<OwnedFd> = f.into();
+        let file = owned
+            .try_into_file()
+            .expect("OwnedFd variant should always succeed");
+        assert_eq!(
+            file.as_fd().as_raw_fd(),
+            fd,
+            "OwnedFd variant should be returned unchanged"
+        );
+
+        let f = File::open(".")?;
+        let borrowed: MaybeOwnedFd<OwnedFd> = f.as_fd().into();
+        let file = borrowed
+            .try_into_file()
+            .expect("BorrowedFd variant should be clonable");
+        assert_ne!(
+            file.as_fd().as_raw_fd(),
+            f.as_raw_fd(),
+            "BorrowedFd variant should be cloned to a new fd number"
+        );
+
+        Ok(())
+    }
 }
@@ -37,22 +37,28 @@ use crate::{
 };
 
 use std::{
-    io::{BufRead, BufReader},
+    fmt::Display,
+    io::{BufRead, BufReader, Write},
     path::PathBuf,
     str::FromStr,
 };
 
-pub(crate) fn sysctl_read_parse<T>(procfs: &ProcfsHandle, sysctl: &str) -> Result<T, Error>
-where
-    T: FromStr,
-    T::Err: Into<ErrorImpl> + Into<Error>,
-{
+/// Convert a `"foo.bar.baz"`-style sysctl name into the `"foo/bar/baz"`-style
+/// path used under `/proc/sys`.
+fn sysctl_path(sysctl: &str) -> PathBuf {
     // "/proc/sys"
     let mut sysctl_path = PathBuf::from("sys");
     // Convert "foo.bar.baz" to "foo/bar/baz".
     sysctl_path.push(sysctl.replace(".", "/"));
+    sysctl_path
+}
 
-    let sysctl_file = procfs.open(ProcfsBase::ProcRoot, sysctl_path, OpenFlags::O_RDONLY)?;
+pub(crate) fn sysctl_read_parse<T>(procfs: &ProcfsHandle, sysctl: &str) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: Into<ErrorImpl> + Into<Error>,
+{
+    let sysctl_file = procfs.open(ProcfsBase::ProcRoot, sysctl_path(sysctl), OpenFlags::O_RDONLY)?;
 
     // Just read the first line.
     let mut reader = BufReader::new(sysctl_file);
@@ -76,6 +82,44 @@ where
         })
 }
 
+/// Write `value` to the given `/proc/sys` sysctl, through the hardened
+/// [`ProcfsHandle`] (just like [`sysctl_read_parse`], but for writes).
+pub(crate) fn sysctl_write<T>(procfs: &ProcfsHandle, sysctl: &str, value: T) -> Result<(), Error>
+where
+    T: Display,
+{
+    let mut sysctl_file =
+        procfs.open(ProcfsBase::ProcRoot, sysctl_path(sysctl), OpenFlags::O_WRONLY)?;
+
+    sysctl_file
+        .write_all(value.to_string().as_bytes())
+        .map_err(|err| ErrorImpl::OsError {
+            operation: format!("write {sysctl:?} sysctl").into(),
+            source: err,
+        })?;
+    Ok(())
+}
+
+/// Read-modify-write a sysctl: parse its current value with
+/// [`sysctl_read_parse`], pass it through `modify`, and write the result back
+/// with [`sysctl_write`].
+///
+/// Note that this is not atomic -- another process could write to the same
+/// sysctl between the read and the write.
+pub(crate) fn sysctl_write_parse<T, F>(
+    procfs: &ProcfsHandle,
+    sysctl: &str,
+    modify: F,
+) -> Result<(), Error>
+where
+    T: FromStr + Display,
+    T::Err: Into<ErrorImpl> + Into<Error>,
+    F: FnOnce(T) -> T,
+{
+    let old_value = sysctl_read_parse(procfs, sysctl)?;
+    sysctl_write(procfs, sysctl, modify(old_value))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -127,6 +171,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn bad_sysctl_write_noexist() {
+        assert_eq!(
+            sysctl_write(&TEST_PROCFS_HANDLE, "nonexistent.dummy.sysctl.path", "1")
+                .as_ref()
+                .map_err(Error::kind),
+            Err(ErrorKind::OsError(Some(libc::ENOENT))),
+            "writing to non-existent sysctl",
+        );
+    }
+
+    #[test]
+    fn bad_sysctl_write_nowrite() {
+        // kernel.random.uuid is a read-only (generate-on-read) sysctl, so any
+        // write to it must fail -- the exact errno depends on kernel version,
+        // so we only check that it's surfaced as an OsError.
+        assert!(matches!(
+            sysctl_write(&TEST_PROCFS_HANDLE, "kernel.random.uuid", "not-a-uuid")
+                .as_ref()
+                .map_err(Error::kind),
+            Err(ErrorKind::OsError(_))
+        ));
+    }
+
     #[test]
     fn bad_sysctl_parse_invalid_multinumber() {
         assert!(sysctl_read_parse::<String>(&TEST_PROCFS_HANDLE, "kernel.printk").is_ok());
@@ -0,0 +1,459 @@
+// SPDX-License-Identifier: MPL-2.0 OR LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * == MPL-2.0 ==
+ *
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Alternatively, this Source Code Form may also (at your option) be used
+ * under the terms of the GNU Lesser General Public License Version 3, as
+ * described below:
+ *
+ * == LGPL-3.0-or-later ==
+ *
+ *  This program is free software: you can redistribute it and/or modify it
+ *  under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or (at
+ *  your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful, but
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY  or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General
+ * Public License  for more details.
+ *
+ *  You should have received a copy of the GNU Lesser General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#![forbid(unsafe_code)]
+
+//! Recursive, race-free directory walking, returned by [`Root::walk`].
+//!
+//! Built on top of [`Root::read_dir`]/[`Handle::read_dir`] -- every
+//! directory is listed through a [`Directory`] iterator and every child is
+//! re-opened relative to the directory fd it was listed from
+//! ([`DirEntry::resolve`]), so a concurrent rename of an ancestor component
+//! can never redirect the walk outside of where it started. The exception is
+//! a symlink followed under [`RootWalk::follow_links`], which is re-resolved
+//! by its path through the [`Root`].
+//!
+//! Not yet mirrored: [`RootWalk`] is not wired into the `RootImpl` test
+//! trait or `CapiRoot`, and there is no `pathrs_inroot_walk` C API entry
+//! point. `CapiRoot`'s `RootImpl` mirror is already missing several other
+//! methods (`read_dir`, `resolve_partial`, `metadata`, ...) because the
+//! `capi::core` surface backing it doesn't exist in this tree yet, and
+//! [`RootWalk`] is concretely tied to [`Root`]/[`Handle`] rather than the
+//! trait's associated `Handle` type -- so cross-checking it against the
+//! capi backend needs that groundwork first, not a one-off shim here.
+//!
+//! [`Root::walk`]: crate::Root::walk
+//! [`Root::read_dir`]: crate::Root::read_dir
+//! [`Handle::read_dir`]: crate::Handle::read_dir
+
+use crate::{
+    error::{Error, ErrorImpl},
+    utils::FdExt,
+    Directory, FileType, Handle, Root,
+};
+
+use std::{
+    collections::HashSet,
+    io::Error as IOError,
+    mem,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
+
+/// A single entry yielded by a [`RootWalk`].
+#[derive(Debug)]
+pub struct WalkEntry {
+    /// The path of this entry, relative to the root of the walk (not the
+    /// root of the [`Root`] it was opened from).
+    pub path: PathBuf,
+    /// An `O_PATH` [`Handle`] to this entry.
+    pub handle: Handle,
+    /// The type of this entry, as reported by the directory it was listed
+    /// from.
+    pub file_type: FileType,
+}
+
+#[derive(Debug)]
+struct Frame {
+    path: PathBuf,
+    depth: usize,
+    dir: Directory,
+    // Queued until the directory has been fully drained, for `contents_first`.
+    pending_self: Option<WalkEntry>,
+}
+
+#[derive(Debug)]
+enum State {
+    Pending(PathBuf),
+    Walking {
+        base_dev: Option<u64>,
+        visited: HashSet<(u64, u64)>,
+        stack: Vec<Frame>,
+    },
+    Done,
+}
+
+/// A race-free, recursive directory walker, returned by [`Root::walk`].
+///
+/// Configure it with the builder methods before iterating -- the starting
+/// path is opened like [`Root::resolve_nofollow`], and symlinks found during
+/// the walk are never followed unless [`RootWalk::follow_links`] is
+/// explicitly enabled.
+///
+/// [`Root::resolve_nofollow`]: crate::Root::resolve_nofollow
+#[derive(Debug)]
+pub struct RootWalk {
+    root: Root,
+    follow_links: bool,
+    max_depth: usize,
+    same_file_system: bool,
+    contents_first: bool,
+    state: State,
+}
+
+impl RootWalk {
+    pub(crate) fn new(root: &Root, path: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(Self {
+            root: root.try_clone()?,
+            follow_links: false,
+            max_depth: usize::MAX,
+            same_file_system: false,
+            contents_first: false,
+            state: State::Pending(path.as_ref().to_path_buf()),
+        })
+    }
+
+    /// Follow symlinks encountered during the walk, yielding the handle of
+    /// their target rather than the symlink itself.
+    ///
+    /// When this is disabled (the default), symlinks are yielded as
+    /// `S_IFLNK` handles -- exactly like [`Root::resolve_nofollow`] -- and
+    /// are never descended into.
+    ///
+    /// [`Root::resolve_nofollow`]: crate::Root::resolve_nofollow
+    pub fn follow_links(mut self, follow_links: bool) -> Self {
+        self.follow_links = follow_links;
+        self
+    }
+
+    /// Limit how many directory levels below the starting path (depth 0)
+    /// are yielded: entries deeper than `max_depth` are not listed.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Refuse to descend into directories on a different filesystem than
+    /// the starting path.
+    pub fn same_file_system(mut self, same_file_system: bool) -> Self {
+        self.same_file_system = same_file_system;
+        self
+    }
+
+    /// Yield a directory's contents before the directory itself
+    /// (post-order), rather than the default pre-order traversal.
+    ///
+    /// This is what you want for a safe recursive removal: every entry is
+    /// yielded before its parent directory, so the parent is always empty
+    /// by the time it is yielded.
+    pub fn contents_first(mut self, contents_first: bool) -> Self {
+        self.contents_first = contents_first;
+        self
+    }
+
+    fn dev_ino(handle: &Handle) -> Result<(u64, u64), Error> {
+        let meta = handle.metadata()?;
+        Ok((meta.dev(), meta.ino()))
+    }
+
+    /// Open `path` (relative to the walk's root) as a directory frame,
+    /// recording it in `visited` to detect symlink/bind-mount loops.
+    fn enter_dir(
+        &self,
+        path: PathBuf,
+        handle: &Handle,
+        depth: usize,
+        visited: &mut HashSet<(u64, u64)>,
+    ) -> Result<Frame, Error> {
+        let dev_ino = Self::dev_ino(handle)?;
+        if !visited.insert(dev_ino) {
+            return Err(ErrorImpl::OsError {
+                operation: "walk directory".into(),
+                source: IOError::from_raw_os_error(libc::ELOOP),
+            }
+            .into());
+        }
+
+        let dir = handle.read_dir()?;
+        Ok(Frame {
+            path,
+            depth,
+            dir,
+            pending_self: None,
+        })
+    }
+}
+
+impl Iterator for RootWalk {
+    type Item = Result<WalkEntry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match mem::replace(&mut self.state, State::Done) {
+                State::Done => return None,
+
+                State::Pending(path) => {
+                    let handle = match self.root.resolve_nofollow(&path) {
+                        Ok(handle) => handle,
+                        Err(err) => return Some(Err(err)),
+                    };
+
+                    let file_type = match handle.metadata() {
+                        Ok(meta) => FileType::from(rustix::fs::FileType::from_raw_mode(
+                            meta.mode(),
+                        )),
+                        Err(err) => return Some(Err(err.into())),
+                    };
+
+                    let base_dev = if self.same_file_system {
+                        match Self::dev_ino(&handle) {
+                            Ok((dev, _)) => Some(dev),
+                            Err(err) => return Some(Err(err)),
+                        }
+                    } else {
+                        None
+                    };
+
+                    if file_type != FileType::Directory || self.max_depth == 0 {
+                        self.state = State::Done;
+                        return Some(Ok(WalkEntry {
+                            path,
+                            handle,
+                            file_type,
+                        }));
+                    }
+
+                    let mut visited = HashSet::new();
+                    let frame = match self.enter_dir(path.clone(), &handle, 0, &mut visited) {
+                        Ok(frame) => frame,
+                        Err(err) => return Some(Err(err)),
+                    };
+
+                    let root_entry = WalkEntry {
+                        path,
+                        handle,
+                        file_type,
+                    };
+
+                    self.state = State::Walking {
+                        base_dev,
+                        visited,
+                        stack: vec![frame],
+                    };
+
+                    if self.contents_first {
+                        // Queue the root directory's own entry to be emitted
+                        // once its contents are drained.
+                        if let State::Walking { stack, .. } = &mut self.state {
+                            stack[0].pending_self = Some(root_entry);
+                        }
+                        continue;
+                    }
+                    return Some(Ok(root_entry));
+                }
+
+                State::Walking {
+                    base_dev,
+                    mut visited,
+                    mut stack,
+                } => {
+                    let Some(frame) = stack.last_mut() else {
+                        self.state = State::Done;
+                        return None;
+                    };
+
+                    let Some(entry) = frame.dir.next() else {
+                        // This directory is exhausted -- pop it and (for
+                        // contents_first) yield its own entry now.
+                        let frame = stack.pop().expect("frame just borrowed from stack");
+                        self.state = State::Walking {
+                            base_dev,
+                            visited,
+                            stack,
+                        };
+                        if let Some(pending) = frame.pending_self {
+                            return Some(Ok(pending));
+                        }
+                        continue;
+                    };
+
+                    let dirent = match entry {
+                        Ok(dirent) => dirent,
+                        Err(err) => {
+                            self.state = State::Walking {
+                                base_dev,
+                                visited,
+                                stack,
+                            };
+                            return Some(Err(err));
+                        }
+                    };
+
+                    let child_path = frame.path.join(dirent.file_name());
+                    let child_depth = frame.depth + 1;
+
+                    let handle = match dirent.resolve() {
+                        Ok(handle) => handle,
+                        Err(err) => {
+                            self.state = State::Walking {
+                                base_dev,
+                                visited,
+                                stack,
+                            };
+                            return Some(Err(err));
+                        }
+                    };
+
+                    let mut file_type = dirent.file_type();
+                    if file_type == FileType::Unknown {
+                        file_type = match handle.metadata() {
+                            Ok(meta) => FileType::from(rustix::fs::FileType::from_raw_mode(
+                                meta.mode(),
+                            )),
+                            Err(err) => {
+                                self.state = State::Walking {
+                                    base_dev,
+                                    visited,
+                                    stack,
+                                };
+                                return Some(Err(err.into()));
+                            }
+                        };
+                    }
+
+                    // Follow a trailing symlink if requested, by re-resolving
+                    // it through the root (which still enforces the same
+                    // escape-proof guarantees as any other `resolve()` call).
+                    let (handle, file_type) = if file_type == FileType::Symlink && self.follow_links
+                    {
+                        let handle = match self.root.resolve(&child_path) {
+                            Ok(handle) => handle,
+                            Err(err) => {
+                                self.state = State::Walking {
+                                    base_dev,
+                                    visited,
+                                    stack,
+                                };
+                                return Some(Err(err));
+                            }
+                        };
+                        let file_type = match handle.metadata() {
+                            Ok(meta) => FileType::from(rustix::fs::FileType::from_raw_mode(
+                                meta.mode(),
+                            )),
+                            Err(err) => {
+                                self.state = State::Walking {
+                                    base_dev,
+                                    visited,
+                                    stack,
+                                };
+                                return Some(Err(err.into()));
+                            }
+                        };
+                        (handle, file_type)
+                    } else {
+                        (handle, file_type)
+                    };
+
+                    let descend = file_type == FileType::Directory
+                        && child_depth < self.max_depth
+                        && base_dev
+                            .map(|base_dev| match Self::dev_ino(&handle) {
+                                Ok((dev, _)) => dev == base_dev,
+                                Err(_) => true, // let the stat error surface via enter_dir below
+                            })
+                            .unwrap_or(true);
+
+                    if !descend {
+                        self.state = State::Walking {
+                            base_dev,
+                            visited,
+                            stack,
+                        };
+                        return Some(Ok(WalkEntry {
+                            path: child_path,
+                            handle,
+                            file_type,
+                        }));
+                    }
+
+                    let child_frame =
+                        match self.enter_dir(child_path.clone(), &handle, child_depth, &mut visited)
+                        {
+                            Ok(frame) => frame,
+                            Err(err) => {
+                                self.state = State::Walking {
+                                    base_dev,
+                                    visited,
+                                    stack,
+                                };
+                                return Some(Err(err));
+                            }
+                        };
+
+                    let child_entry = WalkEntry {
+                        path: child_path,
+                        handle,
+                        file_type,
+                    };
+
+                    stack.push(child_frame);
+                    if self.contents_first {
+                        stack.last_mut().expect("just pushed").pending_self = Some(child_entry);
+                        self.state = State::Walking {
+                            base_dev,
+                            visited,
+                            stack,
+                        };
+                        continue;
+                    }
+
+                    self.state = State::Walking {
+                        base_dev,
+                        visited,
+                        stack,
+                    };
+                    return Some(Ok(child_entry));
+                }
+            }
+        }
+    }
+}
+
+impl Root {
+    /// Recursively walk every entry beneath `path`, returning a builder-style
+    /// [`RootWalk`] iterator.
+    ///
+    /// Like [`Root::resolve`], every step of the walk is confined to the
+    /// root: each child is re-opened relative to the directory fd it was
+    /// listed from (never by re-resolving a path string, except for a
+    /// symlink followed under [`RootWalk::follow_links`], which is resolved
+    /// by its path through the root), and directory
+    /// loops (via a symlink or bind-mount cycle) are detected by tracking
+    /// the `(st_dev, st_ino)` of every directory entered, surfacing `ELOOP`
+    /// for the offending branch rather than aborting the whole walk.
+    ///
+    /// [`Root::resolve`]: crate::Root::resolve
+    pub fn walk(&self, path: impl AsRef<Path>) -> Result<RootWalk, Error> {
+        RootWalk::new(self, path)
+    }
+}
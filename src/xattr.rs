@@ -0,0 +1,347 @@
+// SPDX-License-Identifier: MPL-2.0 OR LGPL-3.0-or-later
+/*
+ * libpathrs: safe path resolution on Linux
+ * Copyright (C) 2019-2025 SUSE LLC
+ * Copyright (C) 2026 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * == MPL-2.0 ==
+ *
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Alternatively, this Source Code Form may also (at your option) be used
+ * under the terms of the GNU Lesser General Public License Version 3, as
+ * described below:
+ *
+ * == LGPL-3.0-or-later ==
+ *
+ *  This program is free software: you can redistribute it and/or modify it
+ *  under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or (at
+ *  your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful, but
+ *  WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY  or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General
+ * Public License  for more details.
+ *
+ *  You should have received a copy of the GNU Lesser General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#![forbid(unsafe_code)]
+
+//! Extended attribute (and SELinux security context) access on a resolved
+//! [`Handle`], operating through `/proc/self/fd` so the name is never
+//! re-resolved through a path an attacker could have swapped.
+//!
+//! `f*xattr(2)` is not reliably usable on an `O_PATH` fd (the kind every
+//! [`Handle`] is), so these methods instead build the `/proc/self/fd/<n>`
+//! magic-link path for the handle and operate on *that* -- which resolves to
+//! exactly the same inode the handle was opened on, with no window for a
+//! concurrent rename to redirect it. For a [`Handle`] obtained via
+//! [`Root::resolve_nofollow`] on a symlink, the magic-link itself names the
+//! symlink (not its target), so this module uses the `l*xattr` family in
+//! that case -- mirroring coreutils' split between `getfilecon` and
+//! `lgetfilecon`.
+//!
+//! [`Root`] also gets `get_xattr`/`set_xattr`/`list_xattrs`/`remove_xattr`
+//! (and the security-context helpers) as plain path-based convenience
+//! wrappers that resolve the path and then call straight through to the
+//! [`Handle`] methods above.
+//!
+//! [`Handle`]: crate::Handle
+//! [`Root`]: crate::Root
+//! [`Root::resolve_nofollow`]: crate::Root::resolve_nofollow
+
+use crate::{
+    error::{Error, ErrorImpl},
+    utils::FdExt,
+    Handle, Root,
+};
+
+use std::{
+    ffi::{CString, OsStr, OsString},
+    io::Error as IOError,
+    os::unix::{
+        ffi::{OsStrExt, OsStringExt},
+        io::AsRawFd,
+    },
+    path::{Path, PathBuf},
+};
+
+use rustix::fs as rustix_fs;
+
+/// The SELinux security context xattr name, as used by `getfilecon(3)`.
+const SELINUX_XATTR: &str = "security.selinux";
+
+bitflags! {
+    /// Flags for [`Handle::set_xattr`], mirroring the `XATTR_CREATE` and
+    /// `XATTR_REPLACE` flags accepted by [`setxattr(2)`].
+    ///
+    /// [`setxattr(2)`]: https://www.man7.org/linux/man-pages/man2/setxattr.2.html
+    pub struct XattrFlags: u32 {
+        /// Fail with `EEXIST` if the attribute already exists.
+        const CREATE = libc::XATTR_CREATE as u32;
+        /// Fail with `ENODATA` if the attribute does not already exist.
+        const REPLACE = libc::XATTR_REPLACE as u32;
+    }
+}
+
+fn xattr_error(operation: &'static str, err: rustix::io::Errno) -> Error {
+    ErrorImpl::OsError {
+        operation: operation.into(),
+        source: IOError::from_raw_os_error(err.raw_os_error()),
+    }
+    .into()
+}
+
+fn to_cstring(name: impl AsRef<OsStr>) -> Result<CString, Error> {
+    CString::new(name.as_ref().as_bytes()).map_err(|_| {
+        ErrorImpl::InvalidArgument {
+            name: "name".into(),
+            description: "xattr name must not contain a NUL byte".into(),
+        }
+        .into()
+    })
+}
+
+impl Handle {
+    /// The `/proc/self/fd/<n>` magic-link path for this handle.
+    fn procfs_self_fd(&self) -> PathBuf {
+        PathBuf::from(format!("/proc/self/fd/{}", self.as_raw_fd()))
+    }
+
+    /// Whether this handle's own `l*xattr` semantics should be used (i.e.
+    /// this handle refers to a symlink itself, rather than something a
+    /// magic-link traversal would otherwise follow).
+    fn wants_lxattr(&self) -> Result<bool, Error> {
+        Ok(self.metadata()?.file_type().is_symlink())
+    }
+
+    /// Read the value of extended attribute `name` on this handle.
+    ///
+    /// For a symlink handle (e.g. from [`Root::resolve_nofollow`]), this
+    /// reads the symlink's own attribute rather than its target's.
+    ///
+    /// [`Root::resolve_nofollow`]: crate::Root::resolve_nofollow
+    pub fn get_xattr(&self, name: impl AsRef<OsStr>) -> Result<Vec<u8>, Error> {
+        let path = self.procfs_self_fd();
+        let name = to_cstring(name)?;
+        let lxattr = self.wants_lxattr()?;
+
+        let mut buf = vec![0u8; 256];
+        loop {
+            let result = if lxattr {
+                rustix_fs::lgetxattr(&path, &name, &mut buf)
+            } else {
+                rustix_fs::getxattr(&path, &name, &mut buf)
+            };
+            match result {
+                Ok(len) => {
+                    buf.truncate(len);
+                    return Ok(buf);
+                }
+                Err(rustix::io::Errno::RANGE) => {
+                    buf.resize(buf.len() * 2, 0);
+                }
+                Err(err) => return Err(xattr_error("getxattr", err)),
+            }
+        }
+    }
+
+    /// Set extended attribute `name` to `value` on this handle.
+    ///
+    /// For a symlink handle, this sets the symlink's own attribute rather
+    /// than its target's.
+    pub fn set_xattr(
+        &self,
+        name: impl AsRef<OsStr>,
+        value: &[u8],
+        flags: XattrFlags,
+    ) -> Result<(), Error> {
+        let path = self.procfs_self_fd();
+        let name = to_cstring(name)?;
+        let lxattr = self.wants_lxattr()?;
+
+        let result = if lxattr {
+            rustix_fs::lsetxattr(&path, &name, value, flags.bits() as i32)
+        } else {
+            rustix_fs::setxattr(&path, &name, value, flags.bits() as i32)
+        };
+        result.map_err(|err| xattr_error("setxattr", err))
+    }
+
+    /// List the names of all extended attributes set on this handle.
+    pub fn list_xattrs(&self) -> Result<Vec<OsString>, Error> {
+        let path = self.procfs_self_fd();
+        let lxattr = self.wants_lxattr()?;
+
+        let mut buf = vec![0u8; 256];
+        let len = loop {
+            let result = if lxattr {
+                rustix_fs::llistxattr(&path, &mut buf)
+            } else {
+                rustix_fs::listxattr(&path, &mut buf)
+            };
+            match result {
+                Ok(len) => break len,
+                Err(rustix::io::Errno::RANGE) => buf.resize(buf.len() * 2, 0),
+                Err(err) => return Err(xattr_error("listxattr", err)),
+            }
+        };
+
+        // The kernel packs a NUL-separated list of names into the buffer.
+        Ok(buf[..len]
+            .split(|&byte| byte == 0)
+            .filter(|name| !name.is_empty())
+            .map(|name| OsString::from_vec(name.to_vec()))
+            .collect())
+    }
+
+    /// Remove extended attribute `name` from this handle.
+    ///
+    /// For a symlink handle, this removes the symlink's own attribute rather
+    /// than its target's.
+    pub fn remove_xattr(&self, name: impl AsRef<OsStr>) -> Result<(), Error> {
+        let path = self.procfs_self_fd();
+        let name = to_cstring(name)?;
+        let lxattr = self.wants_lxattr()?;
+
+        let result = if lxattr {
+            rustix_fs::lremovexattr(&path, &name)
+        } else {
+            rustix_fs::removexattr(&path, &name)
+        };
+        result.map_err(|err| xattr_error("removexattr", err))
+    }
+
+    /// Get the SELinux security context of this handle (the
+    /// `security.selinux` xattr), mirroring `getfilecon(3)`/`lgetfilecon(3)`.
+    pub fn get_security_context(&self) -> Result<String, Error> {
+        let value = self.get_xattr(SELINUX_XATTR)?;
+        // Security contexts are NUL-terminated C strings on-disk.
+        let value = value.strip_suffix(&[0]).unwrap_or(&value);
+        String::from_utf8(value.to_vec()).map_err(|_| {
+            ErrorImpl::InvalidArgument {
+                name: "security.selinux".into(),
+                description: "security context is not valid UTF-8".into(),
+            }
+            .into()
+        })
+    }
+
+    /// Set the SELinux security context of this handle (the
+    /// `security.selinux` xattr), mirroring `setfilecon(3)`.
+    pub fn set_security_context(&self, context: impl AsRef<str>) -> Result<(), Error> {
+        let mut value = context.as_ref().as_bytes().to_vec();
+        value.push(0); // NUL-terminated, like setfilecon(3).
+        self.set_xattr(SELINUX_XATTR, &value, XattrFlags::empty())
+    }
+}
+
+impl Root {
+    /// Resolve `path` and read the value of extended attribute `name` on
+    /// it. See [`Handle::get_xattr`].
+    pub fn get_xattr(&self, path: impl AsRef<Path>, name: impl AsRef<OsStr>) -> Result<Vec<u8>, Error> {
+        self.resolve(path)?.get_xattr(name)
+    }
+
+    /// Resolve `path` without following a trailing symlink and read the
+    /// value of extended attribute `name` on it. See [`Handle::get_xattr`].
+    ///
+    /// [`Root::resolve_nofollow`]: crate::Root::resolve_nofollow
+    pub fn get_xattr_nofollow(
+        &self,
+        path: impl AsRef<Path>,
+        name: impl AsRef<OsStr>,
+    ) -> Result<Vec<u8>, Error> {
+        self.resolve_nofollow(path)?.get_xattr(name)
+    }
+
+    /// Resolve `path` and set extended attribute `name` to `value` on it.
+    /// See [`Handle::set_xattr`].
+    pub fn set_xattr(
+        &self,
+        path: impl AsRef<Path>,
+        name: impl AsRef<OsStr>,
+        value: &[u8],
+        flags: XattrFlags,
+    ) -> Result<(), Error> {
+        self.resolve(path)?.set_xattr(name, value, flags)
+    }
+
+    /// Resolve `path` without following a trailing symlink and set extended
+    /// attribute `name` to `value` on it. See [`Handle::set_xattr`].
+    pub fn set_xattr_nofollow(
+        &self,
+        path: impl AsRef<Path>,
+        name: impl AsRef<OsStr>,
+        value: &[u8],
+        flags: XattrFlags,
+    ) -> Result<(), Error> {
+        self.resolve_nofollow(path)?.set_xattr(name, value, flags)
+    }
+
+    /// Resolve `path` and list the names of all extended attributes set on
+    /// it. See [`Handle::list_xattrs`].
+    pub fn list_xattrs(&self, path: impl AsRef<Path>) -> Result<Vec<OsString>, Error> {
+        self.resolve(path)?.list_xattrs()
+    }
+
+    /// Resolve `path` without following a trailing symlink and list the
+    /// names of all extended attributes set on it. See
+    /// [`Handle::list_xattrs`].
+    pub fn list_xattrs_nofollow(&self, path: impl AsRef<Path>) -> Result<Vec<OsString>, Error> {
+        self.resolve_nofollow(path)?.list_xattrs()
+    }
+
+    /// Resolve `path` and remove extended attribute `name` from it. See
+    /// [`Handle::remove_xattr`].
+    pub fn remove_xattr(&self, path: impl AsRef<Path>, name: impl AsRef<OsStr>) -> Result<(), Error> {
+        self.resolve(path)?.remove_xattr(name)
+    }
+
+    /// Resolve `path` without following a trailing symlink and remove
+    /// extended attribute `name` from it. See [`Handle::remove_xattr`].
+    pub fn remove_xattr_nofollow(
+        &self,
+        path: impl AsRef<Path>,
+        name: impl AsRef<OsStr>,
+    ) -> Result<(), Error> {
+        self.resolve_nofollow(path)?.remove_xattr(name)
+    }
+
+    /// Resolve `path` and get its SELinux security context. See
+    /// [`Handle::get_security_context`].
+    pub fn get_security_context(&self, path: impl AsRef<Path>) -> Result<String, Error> {
+        self.resolve(path)?.get_security_context()
+    }
+
+    /// Resolve `path` without following a trailing symlink and get its
+    /// SELinux security context. See [`Handle::get_security_context`].
+    pub fn get_security_context_nofollow(&self, path: impl AsRef<Path>) -> Result<String, Error> {
+        self.resolve_nofollow(path)?.get_security_context()
+    }
+
+    /// Resolve `path` and set its SELinux security context. See
+    /// [`Handle::set_security_context`].
+    pub fn set_security_context(
+        &self,
+        path: impl AsRef<Path>,
+        context: impl AsRef<str>,
+    ) -> Result<(), Error> {
+        self.resolve(path)?.set_security_context(context)
+    }
+
+    /// Resolve `path` without following a trailing symlink and set its
+    /// SELinux security context. See [`Handle::set_security_context`].
+    pub fn set_security_context_nofollow(
+        &self,
+        path: impl AsRef<Path>,
+        context: impl AsRef<str>,
+    ) -> Result<(), Error> {
+        self.resolve_nofollow(path)?.set_security_context(context)
+    }
+}